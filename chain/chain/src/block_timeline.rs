@@ -0,0 +1,185 @@
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::{BlockHeight, ShardId};
+use near_primitives::views::{BlockTimelineEventView, BlockTimelineStage, BlockTimelineView};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/// Number of heights for which the timeline is kept.
+const BLOCK_TIMELINE_HEIGHTS: usize = 64;
+
+/// Ring buffer with the events of the most recent heights, used only for debugging purposes.
+///
+/// Owned by the `Chain`, and shared with the shards manager of the same node which runs on a
+/// different thread, so cloning it returns a handle to the same timeline.
+#[derive(Clone, Default)]
+pub struct BlockTimeline(Arc<Mutex<BlockTimelines>>);
+
+#[derive(Default)]
+struct BlockTimelines {
+    heights: BTreeMap<BlockHeight, Vec<BlockTimelineEventView>>,
+}
+
+impl BlockTimelines {
+    fn events_mut(&mut self, height: BlockHeight) -> Option<&mut Vec<BlockTimelineEventView>> {
+        if !self.heights.contains_key(&height) && self.heights.len() >= BLOCK_TIMELINE_HEIGHTS {
+            let lowest_height = *self.heights.keys().next().unwrap();
+            if height < lowest_height {
+                return None;
+            }
+            self.heights.remove(&lowest_height);
+        }
+        Some(self.heights.entry(height).or_default())
+    }
+}
+
+impl BlockTimeline {
+    /// Records an approval for the height, only the first and the last ones are kept.
+    pub fn record_approval(&self, height: BlockHeight) {
+        let now = chrono::Utc::now();
+        let mut timelines = self.0.lock().unwrap();
+        let Some(events) = timelines.events_mut(height) else { return };
+        let approval = |stage| BlockTimelineEventView {
+            stage,
+            block_hash: None,
+            shard_id: None,
+            part_ord: None,
+            start: now,
+            end: None,
+        };
+        if !events.iter().any(|event| event.stage == BlockTimelineStage::FirstApproval) {
+            events.push(approval(BlockTimelineStage::FirstApproval));
+        }
+        events.retain(|event| event.stage != BlockTimelineStage::LastApproval);
+        events.push(approval(BlockTimelineStage::LastApproval));
+    }
+
+    /// Returns the events recorded for the height, if the height is still in the ring buffer.
+    pub fn get_timeline(&self, height: BlockHeight) -> Option<BlockTimelineView> {
+        let timelines = self.0.lock().unwrap();
+        let mut events = timelines.heights.get(&height)?.clone();
+        events.sort_by_key(|event| event.start);
+        Some(BlockTimelineView { height, events })
+    }
+}
+
+/// Event of the processing of a block or a chunk at a given height, see `BlockTimelineView`.
+///
+/// The timestamps are taken with `chrono::Utc::now()` rather than the `StaticClock`, so that the
+/// timeline doesn't interfere with tests which mock the clock.
+///
+/// Example use:
+/// ```rust, ignore
+/// BlockTimelineEvent::new(height, BlockTimelineStage::ChunkPartReceived)
+///     .shard_id(shard_id)
+///     .part_ord(part_ord)
+///     .record(&block_timeline);
+/// {
+///     let _span = BlockTimelineEvent::new(height, BlockTimelineStage::ApplyChunk)
+///         .start_span(block_timeline.clone());
+///     // Apply the chunk, the end of the event is recorded when the span is dropped.
+/// }
+/// ```
+#[must_use]
+pub struct BlockTimelineEvent {
+    height: BlockHeight,
+    event: BlockTimelineEventView,
+}
+
+impl BlockTimelineEvent {
+    pub fn new(height: BlockHeight, stage: BlockTimelineStage) -> Self {
+        let event = BlockTimelineEventView {
+            stage,
+            block_hash: None,
+            shard_id: None,
+            part_ord: None,
+            start: chrono::Utc::now(),
+            end: None,
+        };
+        Self { height, event }
+    }
+
+    pub fn block_hash(mut self, block_hash: CryptoHash) -> Self {
+        self.event.block_hash = Some(block_hash);
+        self
+    }
+
+    pub fn shard_id(mut self, shard_id: ShardId) -> Self {
+        self.event.shard_id = Some(shard_id);
+        self
+    }
+
+    pub fn part_ord(mut self, part_ord: u64) -> Self {
+        self.event.part_ord = Some(part_ord);
+        self
+    }
+
+    pub fn record(self, block_timeline: &BlockTimeline) {
+        if let Some(events) = block_timeline.0.lock().unwrap().events_mut(self.height) {
+            events.push(self.event);
+        }
+    }
+
+    /// Records the event once the returned span is dropped, with the time of the drop as its end.
+    pub fn start_span(self, block_timeline: BlockTimeline) -> BlockTimelineSpan {
+        BlockTimelineSpan(Some((self, block_timeline)))
+    }
+}
+
+#[must_use]
+pub struct BlockTimelineSpan(Option<(BlockTimelineEvent, BlockTimeline)>);
+
+impl Drop for BlockTimelineSpan {
+    fn drop(&mut self) {
+        if let Some((mut event, block_timeline)) = self.0.take() {
+            event.event.end = Some(chrono::Utc::now());
+            event.record(&block_timeline);
+        }
+    }
+}
+
+#[test]
+fn test_block_timeline() {
+    let block_timeline = BlockTimeline::default();
+    let height = 10;
+    assert_eq!(block_timeline.get_timeline(height), None);
+    BlockTimelineEvent::new(height, BlockTimelineStage::ChunkPartReceived)
+        .shard_id(1)
+        .part_ord(3)
+        .record(&block_timeline);
+    {
+        let _span = BlockTimelineEvent::new(height, BlockTimelineStage::ApplyChunk)
+            .block_hash(CryptoHash::default())
+            .shard_id(1)
+            .start_span(block_timeline.clone());
+    }
+    block_timeline.record_approval(height);
+    block_timeline.record_approval(height);
+
+    let timeline = block_timeline.get_timeline(height).unwrap();
+    let stages: Vec<_> = timeline.events.iter().map(|event| event.stage).collect();
+    assert_eq!(
+        stages,
+        vec![
+            BlockTimelineStage::ChunkPartReceived,
+            BlockTimelineStage::ApplyChunk,
+            BlockTimelineStage::FirstApproval,
+            BlockTimelineStage::LastApproval,
+        ]
+    );
+    assert_eq!(timeline.events[0].part_ord, Some(3));
+    assert!(timeline.events[0].end.is_none());
+    assert!(timeline.events[1].end.is_some());
+    assert!(timeline.events[2].start <= timeline.events[3].start);
+
+    // Timelines of different nodes are independent.
+    assert_eq!(BlockTimeline::default().get_timeline(height), None);
+
+    // Once the ring buffer is full, the lowest heights are dropped.
+    for other_height in height + 1..=height + BLOCK_TIMELINE_HEIGHTS as BlockHeight {
+        block_timeline.record_approval(other_height);
+    }
+    assert_eq!(block_timeline.get_timeline(height), None);
+    block_timeline.record_approval(height);
+    assert_eq!(block_timeline.get_timeline(height), None);
+    assert!(block_timeline.get_timeline(height + 1).is_some());
+}
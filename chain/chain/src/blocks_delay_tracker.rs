@@ -7,14 +7,15 @@ use near_primitives::sharding::{ChunkHash, ShardChunkHeader};
 use near_primitives::static_clock::StaticClock;
 use near_primitives::types::{BlockHeight, ShardId};
 use near_primitives::views::{
-    BlockProcessingInfo, BlockProcessingStatus, ChainProcessingInfo, ChunkProcessingInfo,
-    ChunkProcessingStatus, DroppedReason,
+    BlockProcessingInfo, BlockProcessingStatus, BlockTimelineStage, ChainProcessingInfo,
+    ChunkProcessingInfo, ChunkProcessingStatus, DroppedReason,
 };
 use std::collections::{BTreeMap, HashMap};
 use std::mem;
 use std::time::Instant;
 use tracing::error;
 
+use crate::block_timeline::{BlockTimeline, BlockTimelineEvent};
 use crate::{metrics, Chain, ChainStoreAccess};
 
 const BLOCK_DELAY_TRACKING_COUNT: u64 = 50;
@@ -138,6 +139,7 @@ impl BlocksDelayTracker {
         block: &Block,
         timestamp: Instant,
         utc_timestamp: DateTime<chrono::Utc>,
+        block_timeline: &BlockTimeline,
     ) {
         let block_hash = block.header().hash();
 
@@ -172,6 +174,9 @@ impl BlocksDelayTracker {
                 chunks,
             });
             self.blocks_height_map.entry(height).or_insert(vec![]).push(*block_hash);
+            BlockTimelineEvent::new(height, BlockTimelineStage::HeaderReceived)
+                .block_hash(*block_hash)
+                .record(block_timeline);
         }
     }
 
@@ -1,6 +1,7 @@
 use crate::block_processing_utils::{
     BlockPreprocessInfo, BlockProcessingArtifact, BlocksInProcessing, DoneApplyChunkCallback,
 };
+use crate::block_timeline::{BlockTimeline, BlockTimelineEvent};
 use crate::blocks_delay_tracker::BlocksDelayTracker;
 use crate::crypto_hash_timer::CryptoHashTimer;
 use crate::lightclient::get_epoch_block_producers_view;
@@ -67,9 +68,11 @@ use near_primitives::unwrap_or_return;
 use near_primitives::utils::MaybeValidated;
 use near_primitives::version::PROTOCOL_VERSION;
 use near_primitives::views::{
-    BlockStatusView, DroppedReason, ExecutionOutcomeWithIdView, ExecutionStatusView,
-    FinalExecutionOutcomeView, FinalExecutionOutcomeWithReceiptView, FinalExecutionStatus,
-    LightClientBlockView, SignedTransactionView,
+    BlockStatusView, BlockTimelineStage, DroppedReason, ExecutionOutcomeView,
+    ExecutionOutcomeWithIdView, ExecutionStatusView, FinalExecutionOutcomeView,
+    FinalExecutionOutcomeWithReceiptView, FinalExecutionStatus, LightClientBlockView,
+    ReceiptDataEdgeView, ReceiptEnumView, ReceiptExecutionView, ReceiptTreeNodeView,
+    ReceiptTreeView, ReceiptView, SignedTransactionView,
 };
 use near_store::flat::{
     store_helper, FlatStateChanges, FlatStateDelta, FlatStateDeltaMetadata, FlatStorageError,
@@ -438,6 +441,8 @@ pub struct Chain {
     pub block_economics_config: BlockEconomicsConfig,
     pub doomslug_threshold_mode: DoomslugThresholdMode,
     pub blocks_delay_tracker: BlocksDelayTracker,
+    /// Events of the processing of the recent heights, for debugging purposes.
+    pub block_timeline: BlockTimeline,
     /// Processing a block is done in three stages: preprocess_block, async_apply_chunks and
     /// postprocess_block. The async_apply_chunks is done asynchronously from the ClientActor thread.
     /// `blocks_in_processing` keeps track of all the blocks that have been preprocessed but are
@@ -558,6 +563,7 @@ impl Chain {
             block_economics_config: BlockEconomicsConfig::from(chain_genesis),
             doomslug_threshold_mode,
             blocks_delay_tracker: BlocksDelayTracker::default(),
+            block_timeline: BlockTimeline::default(),
             apply_chunks_sender: sc,
             apply_chunks_receiver: rc,
             last_time_head_updated: StaticClock::instant(),
@@ -722,6 +728,7 @@ impl Chain {
             block_economics_config: BlockEconomicsConfig::from(chain_genesis),
             doomslug_threshold_mode,
             blocks_delay_tracker: BlocksDelayTracker::default(),
+            block_timeline: BlockTimeline::default(),
             apply_chunks_sender: sc,
             apply_chunks_receiver: rc,
             last_time_head_updated: StaticClock::instant(),
@@ -2006,6 +2013,10 @@ impl Chain {
         //    No chain updates are applied at this step.
         let state_patch = self.pending_state_patch.take();
         let preprocess_timer = metrics::BLOCK_PREPROCESSING_TIME.start_timer();
+        let preprocess_span =
+            BlockTimelineEvent::new(block_height, BlockTimelineStage::Preprocessing)
+                .block_hash(*block.hash())
+                .start_span(self.block_timeline.clone());
         let preprocess_res = self.preprocess_block(
             me,
             &block,
@@ -2015,6 +2026,7 @@ impl Chain {
             block_received_time,
             state_patch,
         );
+        drop(preprocess_span);
         let preprocess_res = match preprocess_res {
             Ok(preprocess_res) => {
                 preprocess_timer.observe_duration();
@@ -2244,6 +2256,10 @@ impl Chain {
             "postprocess_block",
             height = block.header().height())
         .entered();
+        let _timeline_span =
+            BlockTimelineEvent::new(block.header().height(), BlockTimelineStage::Postprocessing)
+                .block_hash(block_hash)
+                .start_span(self.block_timeline.clone());
 
         let prev_head = self.store.head()?;
         let is_caught_up = block_preprocess_info.is_caught_up;
@@ -3625,6 +3641,109 @@ impl Chain {
         Ok(FinalExecutionOutcomeView { status, transaction, transaction_outcome, receipts_outcome })
     }
 
+    /// Returns the receipts of the transaction with their outcomes and the data dependencies
+    /// between them, or None if the transaction or its outcome isn't known.
+    pub fn get_receipt_tree(
+        &self,
+        transaction_hash: &CryptoHash,
+    ) -> Result<Option<ReceiptTreeView>, Error> {
+        let Some(transaction) = self.store.get_transaction(transaction_hash)? else {
+            return Ok(None);
+        };
+        let transaction_outcome = match self.get_execution_outcome(transaction_hash) {
+            Ok(outcome) => ExecutionOutcomeWithIdView::from(outcome),
+            Err(Error::DBNotFoundErr(_)) => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        // Receipts to visit, with the id and the executor of their parent.
+        let mut stack: Vec<_> = transaction_outcome
+            .outcome
+            .receipt_ids
+            .iter()
+            .rev()
+            .map(|receipt_id| {
+                (*receipt_id, *transaction_hash, transaction.transaction.signer_id.clone())
+            })
+            .collect();
+        let mut receipts = vec![];
+        while let Some((receipt_id, parent_id, parent_executor_id)) = stack.pop() {
+            let execution = match self.get_execution_outcome(&receipt_id) {
+                Ok(outcome) => {
+                    let header = self.get_block_header(&outcome.block_hash)?;
+                    let outcome = ExecutionOutcomeView::from(outcome.outcome_with_id.outcome);
+                    let shard_id = self
+                        .epoch_manager
+                        .account_id_to_shard_id(&outcome.executor_id, header.epoch_id())?;
+                    Some(ReceiptExecutionView {
+                        block_hash: *header.hash(),
+                        block_height: header.height(),
+                        shard_id,
+                        outcome,
+                    })
+                }
+                Err(Error::DBNotFoundErr(_)) => None,
+                Err(err) => return Err(err),
+            };
+            let (predecessor_id, receiver_id, receipt) =
+                match self.store.get_receipt(&receipt_id)? {
+                    Some(receipt) => {
+                        let receipt = ReceiptView::from(Receipt::clone(&receipt));
+                        (receipt.predecessor_id, receipt.receiver_id, Some(receipt.receipt))
+                    }
+                    // Local receipts aren't stored, their predecessor is the executor of the parent.
+                    None => match &execution {
+                        Some(execution) => {
+                            (parent_executor_id, execution.outcome.executor_id.clone(), None)
+                        }
+                        None => continue,
+                    },
+                };
+            if let Some(execution) = &execution {
+                stack.extend(execution.outcome.receipt_ids.iter().rev().map(|child_id| {
+                    (*child_id, receipt_id, execution.outcome.executor_id.clone())
+                }));
+            }
+            receipts.push(ReceiptTreeNodeView {
+                receipt_id,
+                parent_id,
+                is_refund: predecessor_id.is_system(),
+                predecessor_id,
+                receiver_id,
+                receipt,
+                execution,
+            });
+        }
+
+        let mut data_producers = HashMap::new();
+        for node in &receipts {
+            if let Some(ReceiptEnumView::Action { output_data_receivers, .. }) = &node.receipt {
+                for data_receiver in output_data_receivers {
+                    data_producers.insert(data_receiver.data_id, node.receipt_id);
+                }
+            }
+        }
+        let mut data_edges = vec![];
+        for node in &receipts {
+            if let Some(ReceiptEnumView::Action { input_data_ids, .. }) = &node.receipt {
+                for data_id in input_data_ids {
+                    if let Some(from) = data_producers.get(data_id) {
+                        data_edges.push(ReceiptDataEdgeView {
+                            from: *from,
+                            to: node.receipt_id,
+                            data_id: *data_id,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(Some(ReceiptTreeView {
+            transaction_hash: *transaction_hash,
+            transaction_outcome,
+            receipts,
+            data_edges,
+        }))
+    }
+
     pub fn get_final_transaction_result_with_receipt(
         &self,
         final_outcome: FinalExecutionOutcomeView,
@@ -3962,6 +4081,7 @@ impl Chain {
                     let random_seed = *block.header().random_value();
                     let height = chunk_header.height_included();
                     let prev_block_hash = *chunk_header.prev_block_hash();
+                    let block_timeline = self.block_timeline.clone();
 
                     Ok(Some(Box::new(move |parent_span| -> Result<ApplyChunkResult, Error> {
                         let _span = tracing::debug_span!(
@@ -3970,6 +4090,11 @@ impl Chain {
                             "new_chunk",
                             shard_id)
                         .entered();
+                        let _timeline_span =
+                            BlockTimelineEvent::new(height, BlockTimelineStage::ApplyChunk)
+                                .block_hash(block_hash)
+                                .shard_id(shard_id)
+                                .start_span(block_timeline);
                         let _timer = CryptoHashTimer::new(chunk.chunk_hash().0);
                         match runtime.apply_transactions(
                             shard_id,
@@ -4024,6 +4149,7 @@ impl Chain {
                     let random_seed = *block.header().random_value();
                     let height = block.header().height();
                     let prev_block_hash = *prev_block.hash();
+                    let block_timeline = self.block_timeline.clone();
 
                     Ok(Some(Box::new(move |parent_span| -> Result<ApplyChunkResult, Error> {
                         let _span = tracing::debug_span!(
@@ -4032,6 +4158,11 @@ impl Chain {
                             "existing_chunk",
                             shard_id)
                         .entered();
+                        let _timeline_span =
+                            BlockTimelineEvent::new(height, BlockTimelineStage::ApplyChunk)
+                                .block_hash(block_hash)
+                                .shard_id(shard_id)
+                                .start_span(block_timeline);
                         match runtime.apply_transactions(
                             shard_id,
                             new_extra.state_root(),
@@ -4757,6 +4888,17 @@ impl Chain {
     pub fn patch_state_in_progress(&self) -> bool {
         !self.pending_state_patch.is_empty()
     }
+
+    /// Rolls the head and the final head back to the given tips, which were the heads when a
+    /// sandbox snapshot was taken.  Blocks above the head aren't removed, they are on a fork
+    /// now.  Fails if the head block has been garbage collected in the meantime.
+    pub fn sandbox_restore_heads(&mut self, head: &Tip, final_head: &Tip) -> Result<(), Error> {
+        self.get_block(&head.last_block_hash)?;
+        let mut chain_store_update = self.mut_store().store_update();
+        chain_store_update.save_head(head)?;
+        chain_store_update.save_final_head(final_head)?;
+        chain_store_update.commit()
+    }
 }
 
 /// Chain update helper, contains information that is needed to process block
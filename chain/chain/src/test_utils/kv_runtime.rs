@@ -34,8 +34,9 @@ use near_primitives::transaction::{
 };
 use near_primitives::types::validator_stake::{ValidatorStake, ValidatorStakeIter};
 use near_primitives::types::{
-    AccountId, ApprovalStake, Balance, BlockHeight, EpochHeight, EpochId, Gas, Nonce, NumShards,
-    ShardId, StateChangesForSplitStates, StateRoot, StateRootNode, ValidatorInfoIdentifier,
+    AccountId, ApprovalStake, Balance, BlockHeight, EpochHeight, EpochId, Gas, Nonce, NumBlocks,
+    NumShards, ShardId, StateChangesForSplitStates, StateRoot, StateRootNode,
+    ValidatorInfoIdentifier,
 };
 use near_primitives::version::{ProtocolVersion, PROTOCOL_VERSION};
 use near_primitives::views::{
@@ -46,7 +47,9 @@ use near_store::{
     DBCol, PartialStorage, ShardTries, Store, StoreUpdate, Trie, TrieChanges, WrappedTrieChanges,
 };
 
-use crate::types::{ApplySplitStateResult, ApplyTransactionResult, RuntimeAdapter};
+use crate::types::{
+    ApplySplitStateResult, ApplyTransactionResult, DryRunResult, DryRunShard, RuntimeAdapter,
+};
 use crate::BlockHeader;
 
 use near_primitives::epoch_manager::ShardConfig;
@@ -1274,6 +1277,18 @@ impl RuntimeAdapter for KeyValueRuntime {
         }
     }
 
+    fn dry_run_transaction(
+        &self,
+        _prev_header: &BlockHeader,
+        _shards: &HashMap<ShardId, DryRunShard>,
+        _transaction: &SignedTransaction,
+        _verify_signature: bool,
+        _max_blocks: NumBlocks,
+        _max_gas: Gas,
+    ) -> Result<Result<DryRunResult, InvalidTxError>, Error> {
+        Err(Error::Other("dry run is not supported by KeyValueRuntime".into()))
+    }
+
     fn obtain_state_part(
         &self,
         _shard_id: ShardId,
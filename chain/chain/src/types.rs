@@ -24,8 +24,8 @@ use near_primitives::state_part::PartId;
 use near_primitives::transaction::{ExecutionOutcomeWithId, SignedTransaction};
 use near_primitives::types::validator_stake::{ValidatorStake, ValidatorStakeIter};
 use near_primitives::types::{
-    Balance, BlockHeight, BlockHeightDelta, EpochId, Gas, MerkleHash, NumBlocks, ShardId,
-    StateChangesForSplitStates, StateRoot, StateRootNode,
+    AccountId, Balance, BlockHeight, BlockHeightDelta, EpochId, Gas, MerkleHash, NumBlocks,
+    ShardId, StateChanges, StateChangesForSplitStates, StateRoot, StateRootNode,
 };
 use near_primitives::version::{
     ProtocolVersion, MIN_GAS_PRICE_NEP_92, MIN_GAS_PRICE_NEP_92_FIX, MIN_PROTOCOL_VERSION_NEP_92,
@@ -105,6 +105,35 @@ pub struct ApplyTransactionResult {
     pub processed_delayed_receipts: Vec<Receipt>,
 }
 
+/// State of a shard a transaction is dry run on.
+pub struct DryRunShard {
+    pub state_root: StateRoot,
+    pub gas_limit: Gas,
+}
+
+/// Balance of an account before and after a dry run.  The balance of an account which doesn't
+/// exist is zero.
+#[derive(Debug, PartialEq, Eq)]
+pub struct AccountBalanceChange {
+    pub account_id: AccountId,
+    pub amount_before: Balance,
+    pub amount_after: Balance,
+    pub locked_before: Balance,
+    pub locked_after: Balance,
+}
+
+/// Result of `RuntimeAdapter::dry_run_transaction`.
+pub struct DryRunResult {
+    /// Outcomes of the transaction and of the receipts it generated, in execution order.
+    pub outcomes: Vec<ExecutionOutcomeWithId>,
+    /// Changes of the state made by the transaction and its receipts.
+    pub state_changes: StateChanges,
+    pub balance_changes: Vec<AccountBalanceChange>,
+    /// Receipts generated by the transaction which weren't executed, because the dry run ran
+    /// out of blocks or gas or because their shard isn't tracked.
+    pub pending_receipts: Vec<Receipt>,
+}
+
 impl ApplyTransactionResult {
     /// Returns root and paths for all the outcomes in the result.
     pub fn compute_outcomes_proof(
@@ -414,6 +443,21 @@ pub trait RuntimeAdapter: Send + Sync {
         request: &QueryRequest,
     ) -> Result<QueryResponse, near_chain_primitives::error::QueryError>;
 
+    /// Applies the transaction on top of the post state of the block `prev_header` and follows
+    /// the receipts it generates for at most `max_blocks` made up blocks, without committing
+    /// anything.  No further block is made up once the blocks burnt `max_gas` in total.  `shards` holds the state of the shards the receipts can be executed on and
+    /// must include the shard of the signer.
+    /// Returns `Ok(Err(InvalidTxError))` if the transaction is invalid.
+    fn dry_run_transaction(
+        &self,
+        prev_header: &BlockHeader,
+        shards: &HashMap<ShardId, DryRunShard>,
+        transaction: &SignedTransaction,
+        verify_signature: bool,
+        max_blocks: NumBlocks,
+        max_gas: Gas,
+    ) -> Result<Result<DryRunResult, InvalidTxError>, Error>;
+
     /// Get part of the state corresponding to the given state root.
     /// `prev_hash` is a block whose post state root is `state_root`.
     /// Returns error when storage is inconsistent.
@@ -47,6 +47,14 @@ impl ShardedTransactionPool {
         Self { tx_pools: HashMap::new(), rng_seed, pool_size_limit }
     }
 
+    /// Changes the size limit of the pools of all shards.
+    pub fn set_pool_size_limit(&mut self, pool_size_limit: Option<u64>) {
+        self.pool_size_limit = pool_size_limit;
+        for pool in self.tx_pools.values_mut() {
+            pool.set_total_transaction_size_limit(pool_size_limit);
+        }
+    }
+
     pub fn get_pool_iterator(&mut self, shard_id: ShardId) -> Option<PoolIteratorWrapper<'_>> {
         self.tx_pools.get_mut(&shard_id).map(|pool| pool.pool_iterator())
     }
@@ -66,6 +74,22 @@ impl ShardedTransactionPool {
         }
     }
 
+    /// Returns the transactions in the pools of all shards together with their shard ids.
+    pub fn transactions(&self) -> Vec<(ShardId, SignedTransaction)> {
+        self.tx_pools
+            .iter()
+            .flat_map(|(shard_id, pool)| pool.transactions().map(|tx| (*shard_id, tx.clone())))
+            .collect()
+    }
+
+    /// Removes all the transactions from the pools of all shards.
+    pub fn clear(&mut self) {
+        for pool in self.tx_pools.values_mut() {
+            let transactions: Vec<_> = pool.transactions().cloned().collect();
+            pool.remove_transactions(&transactions);
+        }
+    }
+
     /// Computes a deterministic random seed for given `shard_id`.
     /// This seed is used to randomize the transaction pool.
     /// For better security we want the seed to different in each shard.
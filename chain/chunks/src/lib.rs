@@ -92,6 +92,7 @@ use metrics::{
 };
 use near_async::messaging::Sender;
 use near_async::time;
+use near_chain::block_timeline::{BlockTimeline, BlockTimelineEvent};
 use near_chain::byzantine_assert;
 use near_chain::chunks_store::ReadOnlyChunksStore;
 use near_chain::near_chain_primitives::error::Error::DBNotFoundErr;
@@ -105,6 +106,7 @@ use near_network::types::{
 };
 use near_network::types::{NetworkRequests, PeerManagerMessageRequest};
 use near_primitives::block::Tip;
+use near_primitives::epoch_manager::RngSeed;
 use near_primitives::errors::EpochError;
 use near_primitives::hash::CryptoHash;
 use near_primitives::merkle::{verify_path, MerklePath};
@@ -123,9 +125,11 @@ use near_primitives::unwrap_or_return;
 use near_primitives::utils::MaybeValidated;
 use near_primitives::validator_signer::ValidatorSigner;
 use near_primitives::version::ProtocolVersion;
+use near_primitives::views::BlockTimelineStage;
+use rand::rngs::StdRng;
 use rand::seq::IteratorRandom;
-use rand::Rng;
-use std::collections::{HashMap, HashSet};
+use rand::{Rng, SeedableRng};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
 use tracing::{debug, error, warn};
 
@@ -266,6 +270,10 @@ pub struct ShardsManager {
     // header_head is new, but we would only know that the older chunks are old because
     // header_head is much newer.
     chain_header_head: Tip,
+    // Chunk events of the timeline of recent heights, shared with the chain of this node.
+    block_timeline: BlockTimeline,
+    // Picks the nodes to request chunk parts from.
+    rng: StdRng,
 }
 
 impl ShardsManager {
@@ -302,9 +310,24 @@ impl ShardsManager {
             chunk_forwards_cache: lru::LruCache::new(CHUNK_FORWARD_CACHE_SIZE),
             chain_head: initial_chain_head,
             chain_header_head: initial_chain_header_head,
+            block_timeline: BlockTimeline::default(),
+            rng: StdRng::from_entropy(),
         }
     }
 
+    /// Records the chunk events in the given timeline, which is usually the one of the `Chain`
+    /// of the same node, instead of a timeline of its own.
+    pub fn with_block_timeline(mut self, block_timeline: BlockTimeline) -> Self {
+        self.block_timeline = block_timeline;
+        self
+    }
+
+    /// Seeds the choice of the nodes to request chunk parts from, so that tests can replay it.
+    pub fn with_rng_seed(mut self, rng_seed: RngSeed) -> Self {
+        self.rng = StdRng::from_seed(rng_seed);
+        self
+    }
+
     pub fn update_chain_heads(&mut self, head: Tip, header_head: Tip) {
         self.encoded_chunks.update_largest_seen_height(
             head.height,
@@ -332,7 +355,7 @@ impl ShardsManager {
             ?shard_id,
             ?request_from_archival)
         .entered();
-        let mut bp_to_parts = HashMap::<_, Vec<u64>>::new();
+        let mut bp_to_parts = BTreeMap::<_, Vec<u64>>::new();
 
         let cache_entry = self.encoded_chunks.get(chunk_hash);
 
@@ -375,7 +398,9 @@ impl ShardsManager {
         {
             Some(chunk_producer_account_id)
         } else {
-            self.get_random_target_tracking_shard(ancestor_hash, shard_id)?
+            self.get_targets_tracking_shard(ancestor_hash, shard_id)?
+                .into_iter()
+                .choose(&mut self.rng)
         };
 
         let epoch_id = self.epoch_manager.get_epoch_id_from_prev_block(ancestor_hash)?;
@@ -422,12 +447,17 @@ impl ShardsManager {
             bp_to_parts.entry(shard_representative_target.clone()).or_default();
         }
 
+        if !bp_to_parts.is_empty() {
+            BlockTimelineEvent::new(height, BlockTimelineStage::ChunkRequested)
+                .shard_id(shard_id)
+                .record(&self.block_timeline);
+        }
         let no_account_id = me.is_none();
         debug!(target: "chunks", "Will send {} requests to fetch chunk parts.", bp_to_parts.len());
         for (target_account, part_ords) in bp_to_parts {
             // extra check that we are not sending request to ourselves.
             if no_account_id || me != target_account.as_ref() {
-                let prefer_peer = request_from_archival || rand::thread_rng().gen::<bool>();
+                let prefer_peer = request_from_archival || self.rng.gen::<bool>();
                 debug!(
                     target: "chunks",
                     ?part_ords,
@@ -471,12 +501,12 @@ impl ShardsManager {
         Ok(())
     }
 
-    /// Get a random shard block producer that is not me.
-    fn get_random_target_tracking_shard(
+    /// Get the shard block producers that are not me.
+    fn get_targets_tracking_shard(
         &self,
         parent_hash: &CryptoHash,
         shard_id: ShardId,
-    ) -> Result<Option<AccountId>, near_chain::Error> {
+    ) -> Result<Vec<AccountId>, near_chain::Error> {
         let epoch_id = self.epoch_manager.get_epoch_id_from_prev_block(parent_hash).unwrap();
         let block_producers = self
             .epoch_manager
@@ -498,9 +528,9 @@ impl ShardsManager {
                 } else {
                     None
                 }
-            });
-
-        Ok(block_producers.choose(&mut rand::thread_rng()))
+            })
+            .collect();
+        Ok(block_producers)
     }
 
     fn get_tracking_shards(&self, parent_hash: &CryptoHash) -> HashSet<ShardId> {
@@ -1426,6 +1456,12 @@ impl ShardsManager {
         // into chunk cache
         let new_part_ords =
             self.encoded_chunks.merge_in_partial_encoded_chunk(partial_encoded_chunk);
+        for part_ord in &new_part_ords {
+            BlockTimelineEvent::new(header.height_created(), BlockTimelineStage::ChunkPartReceived)
+                .shard_id(header.shard_id())
+                .part_ord(*part_ord)
+                .record(&self.block_timeline);
+        }
 
         // 3. Forward my parts to others tracking this chunk's shard
         // It's possible that the previous block has not been processed yet. We will want to
@@ -1609,9 +1645,16 @@ impl ShardsManager {
                     Some(part_entry.part.clone());
             }
 
+            let reconstruct_span = BlockTimelineEvent::new(
+                header.height_created(),
+                BlockTimelineStage::ChunkReconstructed,
+            )
+            .shard_id(header.shard_id())
+            .start_span(self.block_timeline.clone());
             let (shard_chunk, partial_chunk) = self
                 .decode_encoded_chunk_if_complete(encoded_chunk)?
                 .expect("decoding shouldn't fail");
+            drop(reconstruct_span);
 
             // For consistency, only persist shard_chunk if we actually care about the shard.
             // Don't persist if we don't care about the shard, even if we accidentally got enough
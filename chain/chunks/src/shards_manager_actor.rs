@@ -3,6 +3,7 @@ use std::{sync::Arc, time::Duration};
 use actix::{Actor, Addr, Arbiter, ArbiterHandle, Context, Handler};
 use near_async::messaging::Sender;
 use near_async::time;
+use near_chain::block_timeline::BlockTimeline;
 use near_chain::{chunks_store::ReadOnlyChunksStore, types::Tip};
 use near_epoch_manager::{shard_tracker::ShardTracker, EpochManagerAdapter};
 use near_network::{
@@ -70,6 +71,7 @@ pub fn start_shards_manager(
     me: Option<AccountId>,
     store: Store,
     chunk_request_retry_period: Duration,
+    block_timeline: BlockTimeline,
 ) -> (Addr<ShardsManagerActor>, ArbiterHandle) {
     let shards_manager_arbiter = Arbiter::new();
     let shards_manager_arbiter_handle = shards_manager_arbiter.handle();
@@ -93,7 +95,8 @@ pub fn start_shards_manager(
         chunks_store,
         chain_head,
         chain_header_head,
-    );
+    )
+    .with_block_timeline(block_timeline);
     let shards_manager_addr =
         ShardsManagerActor::start_in_arbiter(&shards_manager_arbiter_handle, move |_| {
             ShardsManagerActor::new(shards_manager, chunk_request_retry_period)
@@ -3,9 +3,10 @@
 use crate::types::StatusError;
 use chrono::DateTime;
 use near_primitives::types::EpochId;
+pub use near_primitives::views::KickoutRisk;
 use near_primitives::views::{
-    CatchupStatusView, ChainProcessingInfo, EpochValidatorInfo, RequestedStatePartsView,
-    SyncStatusView,
+    BlockTimelineView, CatchupStatusView, ChainProcessingInfo, EpochValidatorInfo,
+    RequestedStatePartsView, SyncStatusView, TrackedShardsChangeView,
 };
 use near_primitives::{
     block_header::ApprovalInner,
@@ -22,6 +23,13 @@ pub struct TrackedShardsView {
     pub shards_tracked_next_epoch: Vec<bool>,
 }
 
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct CatchupInfoView {
+    pub catchup_status: Vec<CatchupStatusView>,
+    // Changes of the tracked shards requested while the node is running.
+    pub tracked_shards_changes: Vec<TrackedShardsChangeView>,
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
 pub struct EpochInfoView {
     pub epoch_id: CryptoHash,
@@ -166,6 +174,56 @@ pub struct ValidatorStatus {
     pub banned_chunk_producers: Vec<(EpochId, Vec<AccountId>)>,
 }
 
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct ProductionHealthView {
+    pub produced: u64,
+    pub expected: u64,
+    // Kickout threshold of the epoch in percent.
+    pub threshold_percent: u8,
+    // Estimated number of blocks (or chunks) expected from the validator by the end of the epoch,
+    // assuming the validator is assigned at the same rate as so far.
+    pub projected_expected: u64,
+    pub kickout_risk: KickoutRisk,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidatorRole {
+    None,
+    BlockProducer,
+    ChunkOnlyProducer,
+}
+
+// Self-monitoring of the validator run by this node.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct ValidatorHealthView {
+    pub validator_name: Option<AccountId>,
+    pub head_height: BlockHeight,
+    pub epoch_start_height: BlockHeight,
+    pub epoch_length: u64,
+    pub current_role: ValidatorRole,
+    pub next_role: ValidatorRole,
+    // None if the validator doesn't produce blocks (or chunks) in the current epoch.
+    pub block_production: Option<ProductionHealthView>,
+    pub chunk_production: Option<ProductionHealthView>,
+    // Mismatches between the validator key of the node and the keys staked for the current and
+    // the next epoch. Nothing the node signs is valid, so they fail the health check.
+    pub key_mismatches: Vec<String>,
+    // Human readable description of every detected problem, including the key mismatches.
+    pub issues: Vec<String>,
+}
+
+impl ValidatorHealthView {
+    /// The highest kickout risk of the block and chunk production.
+    pub fn kickout_risk(&self) -> KickoutRisk {
+        [&self.block_production, &self.chunk_production]
+            .into_iter()
+            .flatten()
+            .map(|production| production.kickout_risk)
+            .max()
+            .unwrap_or(KickoutRisk::None)
+    }
+}
+
 // Different debug requests that can be sent by HTML pages, via GET.
 pub enum DebugStatus {
     // Request for the current sync status
@@ -178,12 +236,16 @@ pub enum DebugStatus {
     BlockStatus(Option<BlockHeight>),
     // Consensus related information.
     ValidatorStatus,
-    // Request for the current catchup status
+    // Request for the current catchup status and changes of the tracked shards
     CatchupStatus,
     // Request for the current state of chain processing (blocks in progress etc).
     ChainProcessingStatus,
     // The state parts already requested.
     RequestedStateParts,
+    // Projected kickouts and key mismatches of the validator run by this node.
+    ValidatorHealth,
+    // Timeline of the processing of the blocks and chunks at the given height.
+    BlockTimeline(BlockHeight),
 }
 
 impl actix::Message for DebugStatus {
@@ -193,7 +255,7 @@ impl actix::Message for DebugStatus {
 #[derive(serde::Serialize, Debug)]
 pub enum DebugStatusResponse {
     SyncStatus(SyncStatusView),
-    CatchupStatus(Vec<CatchupStatusView>),
+    CatchupStatus(CatchupInfoView),
     TrackedShards(TrackedShardsView),
     // List of epochs - in descending order (next epoch is first).
     EpochInfo(Vec<EpochInfoView>),
@@ -205,4 +267,8 @@ pub enum DebugStatusResponse {
     ChainProcessingStatus(ChainProcessingInfo),
     // The state parts already requested.
     RequestedStateParts(Vec<RequestedStatePartsView>),
+    // Projected kickouts and key mismatches of the validator run by this node.
+    ValidatorHealth(ValidatorHealthView),
+    // Timeline of the processing of the blocks and chunks at a height.
+    BlockTimeline(BlockTimelineView),
 }
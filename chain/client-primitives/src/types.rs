@@ -3,22 +3,24 @@ use ansi_term::Color::{Purple, Yellow};
 use ansi_term::Style;
 use chrono::DateTime;
 use chrono::Utc;
-use near_chain_configs::{ClientConfig, ProtocolConfigView};
+use near_chain_configs::{ClientConfig, ConfigValueChange, ProtocolConfigView};
+use near_primitives::errors::InvalidTxError;
 use near_primitives::hash::CryptoHash;
 use near_primitives::merkle::{MerklePath, PartialMerkleTree};
 use near_primitives::network::PeerId;
 use near_primitives::sharding::ChunkHash;
+use near_primitives::transaction::SignedTransaction;
 use near_primitives::types::{
-    AccountId, BlockHeight, BlockReference, EpochId, EpochReference, MaybeBlockId, ShardId,
-    TransactionOrReceiptId,
+    AccountId, BlockHeight, BlockReference, EpochId, EpochReference, MaybeBlockId, NumBlocks,
+    ShardId, StateChangeValue, TransactionOrReceiptId,
 };
 use near_primitives::views::validator_stake_view::ValidatorStakeView;
 use near_primitives::views::{
     BlockView, ChunkView, DownloadStatusView, EpochValidatorInfo, ExecutionOutcomeWithIdView,
     FinalExecutionOutcomeViewEnum, GasPriceView, LightClientBlockLiteView, LightClientBlockView,
-    MaintenanceWindowsView, QueryRequest, QueryResponse, ReceiptView, ShardSyncDownloadView,
-    SplitStorageInfoView, StateChangesKindsView, StateChangesRequestView, StateChangesView,
-    SyncStatusView,
+    MaintenanceWindowsView, QueryRequest, QueryResponse, ReceiptTreeView, ReceiptView,
+    ShardSyncDownloadView, SplitStorageInfoView, StateChangesKindsView, StateChangesRequestView,
+    StateChangesView, SyncStatusView, TrackedShardsChangeView, TransactionDryRunView,
 };
 pub use near_primitives::views::{StatusResponse, StatusSyncInfo};
 use once_cell::sync::OnceCell;
@@ -590,6 +592,8 @@ pub enum StatusError {
     NodeIsSyncing,
     #[error("No blocks for {elapsed:?}")]
     NoNewBlocks { elapsed: std::time::Duration },
+    #[error("Validator is unhealthy: {}", issues.join("; "))]
+    ValidatorUnhealthy { issues: Vec<String> },
     #[error("Epoch Out Of Bounds {epoch_id:?}")]
     EpochOutOfBounds { epoch_id: near_primitives::types::EpochId },
     #[error("The node reached its limits. Try again later. More details: {error_message}")]
@@ -860,6 +864,103 @@ impl Message for GetStateChangesWithCauseInBlock {
 pub struct GetStateChangesWithCauseInBlockForTrackedShards {
     pub block_hash: CryptoHash,
     pub epoch_id: EpochId,
+    /// Only the changes matching the filter are returned.
+    pub filter: StateChangesFilter,
+}
+
+/// Account id pattern in which `*` matches any sequence of characters, e.g. `*.sweat`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccountPattern(String);
+
+impl std::str::FromStr for AccountPattern {
+    type Err = String;
+
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        if pattern.is_empty() {
+            return Err("account pattern can't be empty".to_string());
+        }
+        Ok(Self(pattern.to_string()))
+    }
+}
+
+impl std::fmt::Display for AccountPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AccountPattern {
+    pub fn matches(&self, account_id: &AccountId) -> bool {
+        let mut parts = self.0.split('*');
+        let mut rest = account_id.as_str();
+        // There is always at least one part, and the first one has to be a prefix unless the
+        // pattern starts with `*`.
+        let first = parts.next().unwrap_or_default();
+        let Some(after_first) = rest.strip_prefix(first) else { return false };
+        rest = after_first;
+        let parts: Vec<&str> = parts.collect();
+        let Some((last, middle)) = parts.split_last() else {
+            // No `*` in the pattern.
+            return rest.is_empty();
+        };
+        for part in middle {
+            match rest.find(part) {
+                Some(pos) => rest = &rest[pos + part.len()..],
+                None => return false,
+            }
+        }
+        rest.ends_with(last)
+    }
+}
+
+/// Kind of a [`StateChangeValue`], used to select state changes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StateChangeValueKind {
+    AccountUpdate,
+    AccountDeletion,
+    AccessKeyUpdate,
+    AccessKeyDeletion,
+    DataUpdate,
+    DataDeletion,
+    ContractCodeUpdate,
+    ContractCodeDeletion,
+}
+
+impl From<&StateChangeValue> for StateChangeValueKind {
+    fn from(value: &StateChangeValue) -> Self {
+        match value {
+            StateChangeValue::AccountUpdate { .. } => Self::AccountUpdate,
+            StateChangeValue::AccountDeletion { .. } => Self::AccountDeletion,
+            StateChangeValue::AccessKeyUpdate { .. } => Self::AccessKeyUpdate,
+            StateChangeValue::AccessKeyDeletion { .. } => Self::AccessKeyDeletion,
+            StateChangeValue::DataUpdate { .. } => Self::DataUpdate,
+            StateChangeValue::DataDeletion { .. } => Self::DataDeletion,
+            StateChangeValue::ContractCodeUpdate { .. } => Self::ContractCodeUpdate,
+            StateChangeValue::ContractCodeDeletion { .. } => Self::ContractCodeDeletion,
+        }
+    }
+}
+
+/// Selects state changes by the affected account and by kind.  `None` doesn't restrict the
+/// changes.
+#[derive(Clone, Debug, Default)]
+pub struct StateChangesFilter {
+    pub accounts: Option<Vec<AccountPattern>>,
+    pub kinds: Option<Vec<StateChangeValueKind>>,
+}
+
+impl StateChangesFilter {
+    pub fn matches(&self, value: &StateChangeValue) -> bool {
+        let account_id = value.affected_account_id();
+        self.accounts
+            .as_ref()
+            .map_or(true, |accounts| accounts.iter().any(|pattern| pattern.matches(account_id)))
+            && self
+                .kinds
+                .as_ref()
+                .map_or(true, |kinds| kinds.contains(&StateChangeValueKind::from(value)))
+    }
 }
 
 impl Message for GetStateChangesWithCauseInBlockForTrackedShards {
@@ -1013,6 +1114,81 @@ impl Message for GetReceipt {
     type Result = Result<Option<ReceiptView>, GetReceiptError>;
 }
 
+/// Applies a transaction and the receipts it generates on top of the state of the head of the
+/// chain without committing anything.
+pub struct DryRunTransaction {
+    pub transaction: SignedTransaction,
+    pub verify_signature: bool,
+    /// Number of blocks in which the receipts generated by the transaction are followed.
+    pub max_blocks: NumBlocks,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum DryRunTransactionError {
+    #[error("Invalid transaction: {0}")]
+    InvalidTransaction(InvalidTxError),
+    #[error("Node doesn't track the shard of the signer {0}")]
+    DoesNotTrackShard(AccountId),
+    #[error("There are no fully synchronized blocks yet")]
+    NoSyncedBlocks,
+    #[error("IO Error: {0}")]
+    IOError(String),
+    // NOTE: Currently, the underlying errors are too broad, and while we tried to handle
+    // expected cases, we cannot statically guarantee that no other errors will be returned
+    // in the future.
+    // TODO #3851: Remove this variant once we can exhaustively match all the underlying errors
+    #[error("It is a bug if you receive this error type, please, report this incident: https://github.com/near/nearcore/issues/new/choose. Details: {0}")]
+    Unreachable(String),
+}
+
+impl From<near_chain_primitives::Error> for DryRunTransactionError {
+    fn from(error: near_chain_primitives::Error) -> Self {
+        match error {
+            near_chain_primitives::Error::IOErr(error) => Self::IOError(error.to_string()),
+            _ => Self::Unreachable(error.to_string()),
+        }
+    }
+}
+
+impl Message for DryRunTransaction {
+    type Result = Result<TransactionDryRunView, DryRunTransactionError>;
+}
+
+/// Returns all the receipts of a transaction, see `Chain::get_receipt_tree`.
+pub struct GetReceiptTree {
+    pub transaction_hash: CryptoHash,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GetReceiptTreeError {
+    #[error("IO Error: {0}")]
+    IOError(String),
+    #[error("Transaction {0} has never been observed on this node")]
+    UnknownTransaction(near_primitives::hash::CryptoHash),
+    #[error("Data needed to build the receipt tree is missing: {0}")]
+    IncompleteTree(String),
+    // NOTE: Currently, the underlying errors are too broad, and while we tried to handle
+    // expected cases, we cannot statically guarantee that no other errors will be returned
+    // in the future.
+    // TODO #3851: Remove this variant once we can exhaustively match all the underlying errors
+    #[error("It is a bug if you receive this error type, please, report this incident: https://github.com/near/nearcore/issues/new/choose. Details: {0}")]
+    Unreachable(String),
+}
+
+impl From<near_chain_primitives::Error> for GetReceiptTreeError {
+    fn from(error: near_chain_primitives::Error) -> Self {
+        match error {
+            near_chain_primitives::Error::IOErr(error) => Self::IOError(error.to_string()),
+            near_chain_primitives::Error::DBNotFoundErr(error) => Self::IncompleteTree(error),
+            _ => Self::Unreachable(error.to_string()),
+        }
+    }
+}
+
+impl Message for GetReceiptTree {
+    type Result = Result<ReceiptTreeView, GetReceiptTreeError>;
+}
+
 pub struct GetProtocolConfig(pub BlockReference);
 
 impl Message for GetProtocolConfig {
@@ -1071,7 +1247,15 @@ impl From<near_chain_primitives::Error> for GetMaintenanceWindowsError {
 pub struct GetClientConfig {}
 
 impl Message for GetClientConfig {
-    type Result = Result<ClientConfig, GetClientConfigError>;
+    type Result = Result<GetClientConfigResponse, GetClientConfigError>;
+}
+
+pub struct GetClientConfigResponse {
+    pub client_config: ClientConfig,
+    /// Latest changes of the config applied while the node is running, the oldest first.
+    pub applied_changes: Vec<ConfigValueChange>,
+    /// Reason why the latest change of the config was rejected, if it was.
+    pub rejected_change: Option<String>,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -1128,6 +1312,50 @@ impl From<std::io::Error> for GetSplitStorageInfoError {
     }
 }
 
+/// Starts or stops tracking shards while the node is running.
+/// Accounts stand for the shards they belong to.
+#[derive(Debug)]
+pub struct UpdateTrackedShards {
+    pub track_shards: Vec<ShardId>,
+    pub untrack_shards: Vec<ShardId>,
+    pub track_accounts: Vec<AccountId>,
+    pub untrack_accounts: Vec<AccountId>,
+}
+
+impl Message for UpdateTrackedShards {
+    /// All changes of the tracked shards requested while the node is running, the oldest first.
+    type Result = Result<Vec<TrackedShardsChangeView>, UpdateTrackedShardsError>;
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum UpdateTrackedShardsError {
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+    #[error("IO Error: {0}")]
+    IOError(String),
+    // NOTE: Currently, the underlying errors are too broad, and while we tried to handle
+    // expected cases, we cannot statically guarantee that no other errors will be returned
+    // in the future.
+    // TODO #3851: Remove this variant once we can exhaustively match all the underlying errors
+    #[error("It is a bug if you receive this error type, please, report this incident: https://github.com/near/nearcore/issues/new/choose. Details: {0}")]
+    Unreachable(String),
+}
+
+impl From<near_chain_primitives::Error> for UpdateTrackedShardsError {
+    fn from(error: near_chain_primitives::Error) -> Self {
+        match error {
+            near_chain_primitives::Error::IOErr(error) => Self::IOError(error.to_string()),
+            _ => Self::Unreachable(error.to_string()),
+        }
+    }
+}
+
+impl From<near_primitives::errors::EpochError> for UpdateTrackedShardsError {
+    fn from(error: near_primitives::errors::EpochError) -> Self {
+        Self::Unreachable(error.to_string())
+    }
+}
+
 #[cfg(feature = "sandbox")]
 #[derive(Debug)]
 pub enum SandboxMessage {
@@ -1135,6 +1363,12 @@ pub enum SandboxMessage {
     SandboxPatchStateStatus,
     SandboxFastForward(near_primitives::types::BlockHeightDelta),
     SandboxFastForwardStatus,
+    SandboxSnapshot,
+    SandboxRestore(u64),
+    SandboxRestoreStatus,
+    SandboxDropSnapshot(u64),
+    SandboxSetTimestamp(u64),
+    SandboxOffsetTime(i64),
 }
 
 #[cfg(feature = "sandbox")]
@@ -1143,6 +1377,13 @@ pub enum SandboxResponse {
     SandboxPatchStateFinished(bool),
     SandboxFastForwardFinished(bool),
     SandboxFastForwardFailed(String),
+    SandboxSnapshotCreated(u64),
+    SandboxSnapshotFailed(String),
+    SandboxRestoreFinished(bool),
+    SandboxRestoreFailed(String),
+    SandboxSnapshotDropped,
+    SandboxDropSnapshotFailed(String),
+    SandboxSetTimeFailed(String),
     SandboxNoResponse,
 }
 #[cfg(feature = "sandbox")]
@@ -2,6 +2,7 @@
 //! This client works completely synchronously and must be operated by some async actor outside.
 
 use crate::adapter::ProcessTxResponse;
+use crate::config_updater::ClientConfigUpdateError;
 use crate::debug::BlockProductionTracker;
 use crate::debug::PRODUCTION_TIMES_CACHE_SIZE;
 use crate::sync::block::BlockSync;
@@ -25,7 +26,10 @@ use near_chain::{
     BlockProcessingArtifact, BlockStatus, Chain, ChainGenesis, ChainStoreAccess,
     DoneApplyChunkCallback, Doomslug, DoomslugThresholdMode, Provenance,
 };
-use near_chain_configs::{ClientConfig, LogSummaryStyle, UpdateableClientConfig};
+use near_chain_configs::{
+    ClientConfig, ConfigValueChange, LogSummaryStyle, UpdateableClientConfig,
+    UpdateableClientConfigError,
+};
 use near_chunks::adapter::ShardsManagerRequestFromClient;
 use near_chunks::client::ShardedTransactionPool;
 use near_chunks::logic::{
@@ -35,8 +39,9 @@ use near_chunks::ShardsManager;
 use near_client_primitives::debug::ChunkProduction;
 use near_client_primitives::types::{
     format_shard_sync_phase_per_shard, Error, ShardSyncDownload, ShardSyncStatus,
+    UpdateTrackedShards, UpdateTrackedShardsError,
 };
-use near_epoch_manager::shard_tracker::ShardTracker;
+use near_epoch_manager::shard_tracker::{ShardTracker, TrackingChange, TrackingTarget};
 use near_epoch_manager::EpochManagerAdapter;
 use near_network::types::{AccountKeys, ChainInfo, PeerManagerMessageRequest, SetChainInfo};
 use near_network::types::{
@@ -53,6 +58,7 @@ use near_primitives::hash::CryptoHash;
 use near_primitives::merkle::{merklize, MerklePath, PartialMerkleTree};
 use near_primitives::network::PeerId;
 use near_primitives::receipt::Receipt;
+use near_primitives::shard_layout::{account_id_to_shard_id, ShardLayout, ShardUId};
 use near_primitives::sharding::StateSyncInfo;
 use near_primitives::sharding::{
     ChunkHash, EncodedShardChunk, PartialEncodedChunk, ReedSolomonWrapper, ShardChunk,
@@ -61,13 +67,16 @@ use near_primitives::sharding::{
 use near_primitives::static_clock::StaticClock;
 use near_primitives::transaction::SignedTransaction;
 use near_primitives::types::chunk_extra::ChunkExtra;
-use near_primitives::types::{AccountId, ApprovalStake, BlockHeight, EpochId, NumBlocks, ShardId};
+use near_primitives::types::{
+    AccountId, ApprovalStake, BlockHeight, EpochHeight, EpochId, NumBlocks, ShardId,
+};
 use near_primitives::unwrap_or_return;
 use near_primitives::utils::MaybeValidated;
 use near_primitives::validator_signer::ValidatorSigner;
 use near_primitives::version::PROTOCOL_VERSION;
-use near_primitives::views::{CatchupStatusView, DroppedReason};
+use near_primitives::views::{CatchupStatusView, DroppedReason, TrackedShardsChangeView};
 use near_store::metadata::DbKind;
+use near_store::DBCol;
 use std::cmp::max;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
@@ -90,6 +99,19 @@ const BLOCK_HORIZON: u64 = 500;
 /// number of blocks at the epoch start for which we will log more detailed info
 pub const EPOCH_START_INFO_BLOCKS: u64 = 500;
 
+/// State of the client saved by a sandbox snapshot.  The trie state and the flat storage are
+/// not copied: the state of the head block stays in the database and the flat head is locked
+/// while snapshots exist.
+#[cfg(feature = "sandbox")]
+#[derive(Clone)]
+struct SandboxSnapshot {
+    head: Tip,
+    final_head: Tip,
+    transactions: Vec<(ShardId, SignedTransaction)>,
+    chunk_headers_ready_for_inclusion:
+        Option<HashMap<ShardId, (ShardChunkHeader, chrono::DateTime<chrono::Utc>, AccountId)>>,
+}
+
 pub struct Client {
     /// Adversarial controls
     #[cfg(feature = "test_features")]
@@ -104,6 +126,14 @@ pub struct Client {
     /// Fast Forward accrued delta height used to calculate fast forwarded timestamps for each block.
     #[cfg(feature = "sandbox")]
     pub(crate) accrued_fastforward_delta: near_primitives::types::BlockHeightDelta,
+    /// Offset of the timestamps of the produced blocks set by `sandbox_set_timestamp` and
+    /// `sandbox_offset_time`, on top of the fast forwarded time.
+    #[cfg(feature = "sandbox")]
+    sandbox_time_offset: chrono::Duration,
+    /// Snapshots taken by `sandbox_snapshot`, the id of a snapshot is its index.  Dropped
+    /// snapshots are `None`.
+    #[cfg(feature = "sandbox")]
+    sandbox_snapshots: Vec<Option<SandboxSnapshot>>,
 
     pub config: ClientConfig,
     pub sync_status: SyncStatus,
@@ -159,11 +189,117 @@ pub struct Client {
     tier1_accounts_cache: Option<(EpochId, Arc<AccountKeys>)>,
     /// Used when it is needed to create flat storage in background for some shards.
     flat_storage_creator: Option<FlatStorageCreator>,
+    /// Deletions of the state of shards that stopped being tracked while the node is running.
+    state_deletions: Vec<StateDeletion>,
+}
+
+/// Deletion of the state of a shard that stopped being tracked while the node is running.
+struct StateDeletion {
+    /// Index of the change in `ShardTracker::tracking_changes()` which caused the deletion.
+    change_index: usize,
+    shard_uid: ShardUId,
+    /// The state is deleted once the head reaches this epoch height, so that garbage
+    /// collection of the older blocks doesn't need it anymore.
+    epoch_height: EpochHeight,
+    status: StateDeletionStatus,
+}
+
+enum StateDeletionStatus {
+    Scheduled,
+    Deleted,
+    /// The state is still needed, for the given reason.
+    Kept(&'static str),
 }
 
 impl Client {
-    pub(crate) fn update_client_config(&self, update_client_config: UpdateableClientConfig) {
-        self.config.expected_shutdown.update(update_client_config.expected_shutdown);
+    /// Applies the config values changed while the node is running.
+    /// The update is rejected as a whole if any of the changes is unsafe.
+    pub(crate) fn update_client_config(
+        &mut self,
+        update_client_config: UpdateableClientConfig,
+    ) -> Result<Vec<ConfigValueChange>, ClientConfigUpdateError> {
+        self.config.check_update(&update_client_config)?;
+        let tracked_accounts_changed =
+            self.config.tracked_accounts.get() != update_client_config.tracked_accounts;
+        if tracked_accounts_changed {
+            self.check_tracked_accounts_update(&update_client_config.tracked_accounts)?;
+        }
+        let state_sync_config_changed =
+            self.config.state_sync.get().sync != update_client_config.state_sync.sync;
+        if state_sync_config_changed && matches!(self.sync_status, SyncStatus::StateSync(..)) {
+            return Err(UpdateableClientConfigError::UnsafeChange {
+                field_name: "state_sync.sync",
+                reason: "the node is running state sync".to_string(),
+            }
+            .into());
+        }
+
+        // Nothing is changed before this point, and the shard tracker is the only update which
+        // can still fail, so that a rejected update doesn't leave the node half-updated.
+        if tracked_accounts_changed {
+            self.shard_tracker
+                .update_tracked_accounts(update_client_config.tracked_accounts.clone())
+                .map_err(|reason| UpdateableClientConfigError::UnsafeChange {
+                    field_name: "tracked_accounts",
+                    reason,
+                })?;
+        }
+        let tries = self.runtime_adapter.get_tries();
+        let mut changes = tries.update_cache_size_limits(&update_client_config.trie_cache, false);
+        changes.extend(tries.update_cache_size_limits(&update_client_config.view_trie_cache, true));
+        changes.extend(self.config.apply_update(update_client_config));
+
+        self.sharded_tx_pool.set_pool_size_limit(self.config.transaction_pool_size_limit.get());
+        self.header_sync.set_timeouts(
+            self.config.header_sync_initial_timeout.get(),
+            self.config.header_sync_progress_timeout.get(),
+            self.config.header_sync_stall_ban_timeout.get(),
+        );
+        self.state_sync.set_timeout(self.config.state_sync_timeout.get());
+        if state_sync_config_changed {
+            self.state_sync = StateSync::new(
+                self.network_adapter.clone(),
+                self.config.state_sync_timeout.get(),
+                &self.config.chain_id,
+                &self.config.state_sync.get().sync,
+            );
+        }
+        Ok(changes)
+    }
+
+    /// Checks that all the shards of the new tracked accounts are already tracked, because
+    /// tracking a new shard requires syncing its state.
+    fn check_tracked_accounts_update(
+        &self,
+        tracked_accounts: &[AccountId],
+    ) -> Result<(), ClientConfigUpdateError> {
+        if !self.config.tracked_shards.is_empty() || !self.config.tracked_shard_schedule.is_empty()
+        {
+            return Err(UpdateableClientConfigError::UnsafeChange {
+                field_name: "tracked_accounts",
+                reason: "the node tracks shards by 'tracked_shards' or 'tracked_shard_schedule'"
+                    .to_string(),
+            }
+            .into());
+        }
+        let head = self.chain.head()?;
+        for epoch_id in [&head.epoch_id, &head.next_epoch_id] {
+            let shard_layout = self.epoch_manager.get_shard_layout(epoch_id)?;
+            for account_id in tracked_accounts {
+                let shard_id = account_id_to_shard_id(account_id, &shard_layout);
+                if !self.shard_tracker.tracks_shard_at_epoch(shard_id, epoch_id)? {
+                    return Err(UpdateableClientConfigError::UnsafeChange {
+                        field_name: "tracked_accounts",
+                        reason: format!(
+                            "account {} belongs to shard {} which is not tracked and needs to be synced first",
+                            account_id, shard_id
+                        ),
+                    }
+                    .into());
+                }
+            }
+        }
+        Ok(())
     }
 }
 
@@ -235,7 +371,7 @@ impl Client {
             chain_config.background_migration_threads,
         )?;
         let sharded_tx_pool =
-            ShardedTransactionPool::new(rng_seed, config.transaction_pool_size_limit);
+            ShardedTransactionPool::new(rng_seed, config.transaction_pool_size_limit.get());
         let sync_status = SyncStatus::AwaitingPeers;
         let genesis_block = chain.genesis_block();
         let epoch_sync = EpochSync::new(
@@ -255,9 +391,9 @@ impl Client {
         );
         let header_sync = HeaderSync::new(
             network_adapter.clone(),
-            config.header_sync_initial_timeout,
-            config.header_sync_progress_timeout,
-            config.header_sync_stall_ban_timeout,
+            config.header_sync_initial_timeout.get(),
+            config.header_sync_progress_timeout.get(),
+            config.header_sync_stall_ban_timeout.get(),
             config.header_sync_expected_height_per_second,
         );
         let block_sync = BlockSync::new(
@@ -268,9 +404,9 @@ impl Client {
         );
         let state_sync = StateSync::new(
             network_adapter.clone(),
-            config.state_sync_timeout,
+            config.state_sync_timeout.get(),
             &config.chain_id,
-            &config.state_sync.sync,
+            &config.state_sync.get().sync,
         );
         let num_block_producer_seats = config.num_block_producer_seats as usize;
         let data_parts = epoch_manager.num_data_parts();
@@ -285,7 +421,7 @@ impl Client {
             validator_signer.clone(),
             doomslug_threshold_mode,
         );
-        Ok(Self {
+        let mut client = Self {
             #[cfg(feature = "test_features")]
             adv_produce_blocks: false,
             #[cfg(feature = "test_features")]
@@ -296,6 +432,10 @@ impl Client {
             produce_invalid_tx_in_chunks: false,
             #[cfg(feature = "sandbox")]
             accrued_fastforward_delta: 0,
+            #[cfg(feature = "sandbox")]
+            sandbox_time_offset: chrono::Duration::zero(),
+            #[cfg(feature = "sandbox")]
+            sandbox_snapshots: vec![],
             config,
             sync_status,
             chain,
@@ -327,7 +467,17 @@ impl Client {
             chunk_production_info: lru::LruCache::new(PRODUCTION_TIMES_CACHE_SIZE),
             tier1_accounts_cache: None,
             flat_storage_creator,
-        })
+            state_deletions: vec![],
+        };
+        // The tracking changes requested before a restart are persisted, but the deletions of
+        // the state they scheduled are not, so they are scheduled again.
+        let tracking_changes = client.shard_tracker.tracking_changes();
+        if !tracking_changes.is_empty() {
+            let shard_layout =
+                client.epoch_manager.get_shard_layout(&client.chain.head()?.epoch_id)?;
+            client.schedule_state_deletions(0, tracking_changes, &shard_layout);
+        }
+        Ok(client)
     }
 
     // Checks if it's been at least `stall_timeout` since the last time the head was updated, or
@@ -642,7 +792,9 @@ impl Client {
         };
 
         #[cfg(feature = "sandbox")]
-        let timestamp_override = Some(StaticClock::utc() + self.sandbox_delta_time());
+        let timestamp_override = Some(self.sandbox_time().ok_or_else(|| {
+            Error::Other("The sandbox clock is out of the range of block timestamps".to_string())
+        })?);
         #[cfg(not(feature = "sandbox"))]
         let timestamp_override = None;
 
@@ -729,6 +881,24 @@ impl Client {
             block_merkle_root,
             timestamp_override,
         );
+        // A remote validator signer which refused to sign, or couldn't be reached, leaves an
+        // invalid signature or VRF in the block, which must not be sent.
+        if !block.header().verify_block_producer(&validator_signer.public_key())
+            || self
+                .epoch_manager
+                .verify_block_vrf(
+                    block.header().epoch_id(),
+                    next_height,
+                    prev_header.random_value(),
+                    block.vrf_value(),
+                    block.vrf_proof(),
+                )
+                .is_err()
+        {
+            warn!(target: "client", next_height, "Not producing a block the validator signer didn't sign");
+            metrics::VALIDATOR_SIGNER_FAILURES.with_label_values(&["block"]).inc();
+            return Ok(None);
+        }
 
         // Update latest known even before returning block out, to prevent race conditions.
         self.chain.mut_store().save_latest_known(LatestKnown {
@@ -848,11 +1018,21 @@ impl Client {
             &mut self.rs_for_chunk_production,
             protocol_version,
         )?;
+        let chunk_hash = encoded_chunk.chunk_hash();
+        if !encoded_chunk
+            .cloned_header()
+            .signature()
+            .verify(chunk_hash.as_ref(), &validator_signer.public_key())
+        {
+            warn!(target: "client", next_height, shard_id, "Not producing a chunk the validator signer didn't sign");
+            metrics::VALIDATOR_SIGNER_FAILURES.with_label_values(&["chunk"]).inc();
+            return Ok(None);
+        }
 
         debug!(
             target: "client",
             me=%validator_signer.validator_id(),
-            chunk_hash=%encoded_chunk.chunk_hash().0,
+            chunk_hash=%chunk_hash.0,
             %prev_block_hash,
             "Produced chunk with {} txs and {} receipts",
             num_filtered_transactions,
@@ -1016,6 +1196,7 @@ impl Client {
             &block,
             StaticClock::instant(),
             StaticClock::utc(),
+            &self.chain.block_timeline,
         );
         // To protect ourselves from spamming, we do some pre-check on block height before we do any
         // real processing.
@@ -1387,9 +1568,153 @@ impl Client {
         Ok(())
     }
 
+    /// Saves the head of the chain, the transaction pool and the chunks ready for inclusion
+    /// on top of the head, and returns the id of the snapshot.
+    ///
+    /// The first snapshot locks the flat storage head so that the flat state of the snapshot
+    /// head stays reachable through flat storage deltas.  While any snapshot is kept, the
+    /// deltas of every new block pile up in memory and flat state reads get slower with every
+    /// block, so snapshots which aren't needed anymore should be dropped with
+    /// `sandbox_drop_snapshot`.
+    #[cfg(feature = "sandbox")]
+    pub fn sandbox_snapshot(&mut self) -> Result<u64, Error> {
+        if self.sandbox_snapshots.iter().all(Option::is_none) {
+            if let Some(flat_storage_manager) = self.runtime_adapter.get_flat_storage_manager() {
+                if !flat_storage_manager.set_flat_state_updates_mode(false) {
+                    return Err(Error::Other("Failed to lock the flat storage head".to_string()));
+                }
+            }
+        }
+        let head = self.chain.head()?;
+        let snapshot = SandboxSnapshot {
+            final_head: self.chain.final_head()?,
+            transactions: self.sharded_tx_pool.transactions(),
+            chunk_headers_ready_for_inclusion: self
+                .prev_block_to_chunk_headers_ready_for_inclusion
+                .peek(&head.last_block_hash)
+                .cloned(),
+            head,
+        };
+        self.sandbox_snapshots.push(Some(snapshot));
+        Ok(self.sandbox_snapshots.len() as u64 - 1)
+    }
+
+    /// Drops the snapshot, it can't be restored anymore.  Once the last snapshot is dropped,
+    /// the flat storage head is unlocked.  Ids of the dropped snapshots aren't reused.
+    #[cfg(feature = "sandbox")]
+    pub fn sandbox_drop_snapshot(&mut self, snapshot_id: u64) -> Result<(), Error> {
+        self.sandbox_snapshots
+            .get_mut(snapshot_id as usize)
+            .and_then(Option::take)
+            .ok_or_else(|| Error::Other(format!("Unknown snapshot {}", snapshot_id)))?;
+        if self.sandbox_snapshots.iter().all(Option::is_none) {
+            if let Some(flat_storage_manager) = self.runtime_adapter.get_flat_storage_manager() {
+                if !flat_storage_manager.set_flat_state_updates_mode(true) {
+                    return Err(Error::Other("Failed to unlock the flat storage head".to_string()));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Rolls the chain, the transaction pool and the chunks ready for inclusion back to the
+    /// snapshot.  New blocks are produced on top of the head of the snapshot at heights above
+    /// the latest known height, so they don't conflict with the blocks produced after the
+    /// snapshot.  Must not be called while blocks are being processed.
+    #[cfg(feature = "sandbox")]
+    pub fn sandbox_restore(&mut self, snapshot_id: u64) -> Result<(), Error> {
+        let snapshot = self
+            .sandbox_snapshots
+            .get(snapshot_id as usize)
+            .and_then(Option::as_ref)
+            .cloned()
+            .ok_or_else(|| Error::Other(format!("Unknown snapshot {}", snapshot_id)))?;
+        self.chain.sandbox_restore_heads(&snapshot.head, &snapshot.final_head).map_err(|err| {
+            Error::Other(format!("Snapshot {} can't be restored: {}", snapshot_id, err))
+        })?;
+
+        self.sharded_tx_pool.clear();
+        for (shard_id, transaction) in snapshot.transactions {
+            let _ = self.sharded_tx_pool.insert_transaction(shard_id, transaction);
+        }
+        if let Some(chunk_headers) = snapshot.chunk_headers_ready_for_inclusion {
+            self.prev_block_to_chunk_headers_ready_for_inclusion
+                .put(snapshot.head.last_block_hash, chunk_headers);
+        }
+
+        let latest_known_height = self.chain.store().get_latest_known()?.height;
+        self.sandbox_update_tip(latest_known_height)
+    }
+
+    /// Sets the clock of the sandbox so that the next produced block has the given timestamp in
+    /// nanoseconds, the following blocks are timestamped from there on.  The timestamp of a
+    /// block is still always greater than the timestamp of its previous block.
+    #[cfg(feature = "sandbox")]
+    pub fn sandbox_set_timestamp(&mut self, timestamp: u64) -> Result<(), Error> {
+        let out_of_range = || {
+            Error::Other(format!("Timestamp {} is out of the range of block timestamps", timestamp))
+        };
+        if i64::try_from(timestamp).is_err() {
+            return Err(out_of_range());
+        }
+        let now = StaticClock::utc()
+            .checked_add_signed(self.sandbox_fastforward_time())
+            .ok_or_else(out_of_range)?;
+        let offset = near_primitives::utils::from_timestamp(timestamp) - now;
+        self.sandbox_set_time_offset(offset).map_err(|_| out_of_range())
+    }
+
+    /// Moves the clock of the sandbox by the given offset in nanoseconds, which may be
+    /// negative.
+    #[cfg(feature = "sandbox")]
+    pub fn sandbox_offset_time(&mut self, offset: i64) -> Result<(), Error> {
+        let offset = self
+            .sandbox_time_offset
+            .checked_add(&chrono::Duration::nanoseconds(offset))
+            .ok_or_else(|| {
+            Error::Other(format!("Offset {} overflows the sandbox clock", offset))
+        })?;
+        self.sandbox_set_time_offset(offset)
+    }
+
+    /// Sets the offset of the clock of the sandbox, unless the clock would leave the range of
+    /// block timestamps.
+    #[cfg(feature = "sandbox")]
+    fn sandbox_set_time_offset(&mut self, offset: chrono::Duration) -> Result<(), Error> {
+        if self.sandbox_time_with_offset(offset).is_none() {
+            return Err(Error::Other(
+                "The sandbox clock would be out of the range of block timestamps".to_string(),
+            ));
+        }
+        self.sandbox_time_offset = offset;
+        Ok(())
+    }
+
+    /// Gets the time of the clock of the sandbox, which is the real time plus the time
+    /// fast-forwarded and the offset set by `sandbox_set_timestamp` or `sandbox_offset_time`.
+    /// None if a block timestamp in nanoseconds can't represent it.
+    #[cfg(feature = "sandbox")]
+    pub fn sandbox_time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.sandbox_time_with_offset(self.sandbox_time_offset)
+    }
+
+    #[cfg(feature = "sandbox")]
+    fn sandbox_time_with_offset(
+        &self,
+        offset: chrono::Duration,
+    ) -> Option<chrono::DateTime<chrono::Utc>> {
+        let time = self
+            .sandbox_fastforward_time()
+            .checked_add(&offset)
+            .and_then(|delta| StaticClock::utc().checked_add_signed(delta))?;
+        let range = near_primitives::utils::from_timestamp(0)
+            ..=near_primitives::utils::from_timestamp(i64::MAX as u64);
+        range.contains(&time).then_some(time)
+    }
+
     /// Gets the advanced timestamp delta in nanoseconds for sandbox once it has been fast-forwarded
     #[cfg(feature = "sandbox")]
-    pub fn sandbox_delta_time(&self) -> chrono::Duration {
+    fn sandbox_fastforward_time(&self) -> chrono::Duration {
         let avg_block_prod_time = (self.config.min_block_production_delay.as_nanos()
             + self.config.max_block_production_delay.as_nanos())
             / 2;
@@ -1408,6 +1733,14 @@ impl Client {
         parent_hash: &CryptoHash,
         approval: Approval,
     ) -> Result<(), Error> {
+        let data = Approval::get_data_for_sig(&approval.inner, approval.target_height);
+        if let Some(signer) = &self.validator_signer {
+            if !approval.signature.verify(&data, &signer.public_key()) {
+                warn!(target: "client", target_height = approval.target_height, "Not sending an approval the validator signer didn't sign");
+                metrics::VALIDATOR_SIGNER_FAILURES.with_label_values(&["approval"]).inc();
+                return Ok(());
+            }
+        }
         let next_epoch_id = self.epoch_manager.get_epoch_id_from_prev_block(parent_hash)?;
         let next_block_producer =
             self.epoch_manager.get_block_producer(&next_epoch_id, approval.target_height)?;
@@ -1863,6 +2196,7 @@ impl Client {
                     return;
                 }
             };
+        self.chain.block_timeline.record_approval(*target_height);
         self.doomslug.on_approval_message(StaticClock::instant(), approval, &block_producer_stakes);
     }
 
@@ -2125,7 +2459,7 @@ impl Client {
             let network_adapter = self.network_adapter.clone();
 
             let shards_to_split = self.get_shards_to_split(sync_hash, &state_sync_info, me)?;
-            let state_sync_timeout = self.config.state_sync_timeout;
+            let state_sync_timeout = self.config.state_sync_timeout.get();
             let epoch_id = self.chain.get_block(&sync_hash)?.header().epoch_id().clone();
 
             // TODO(resharding) what happens to the shards_to_split here when
@@ -2139,7 +2473,7 @@ impl Client {
                             network_adapter,
                             state_sync_timeout,
                             &self.config.chain_id,
-                            &self.config.state_sync.sync,
+                            &self.config.state_sync.get().sync,
                         ),
                         shards_to_split,
                         BlocksCatchUpState::new(sync_hash, epoch_id),
@@ -2297,7 +2631,8 @@ impl Client {
         // A RPC node should do regular garbage collection.
         if !self.config.archive {
             let tries = self.runtime_adapter.get_tries();
-            return self.chain.clear_data(tries, &self.config.gc);
+            self.chain.clear_data(tries, &self.config.gc.get())?;
+            return self.delete_untracked_shards_state();
         }
 
         // An archival node with split storage should perform garbage collection
@@ -2308,12 +2643,12 @@ impl Client {
         let kind = store.get_db_kind()?;
         if kind == Some(DbKind::Hot) {
             let tries = self.runtime_adapter.get_tries();
-            return self.chain.clear_data(tries, &self.config.gc);
+            return self.chain.clear_data(tries, &self.config.gc.get());
         }
 
         // An archival node with legacy storage or in the midst of migration to split
         // storage should do the legacy clear_archive_data.
-        self.chain.clear_archive_data(self.config.gc.gc_blocks_limit)
+        self.chain.clear_archive_data(self.config.gc.get().gc_blocks_limit)
     }
 }
 
@@ -2467,4 +2802,217 @@ impl Client {
         }
         Ok(ret)
     }
+
+    /// Starts or stops tracking shards while the node is running.
+    ///
+    /// A shard is tracked starting from the epoch after the next one, so that the node
+    /// catches up with its state during the next epoch. A shard stops being tracked
+    /// starting from the next epoch, and its state is deleted once garbage collection
+    /// doesn't need it anymore.
+    pub fn update_tracked_shards(
+        &mut self,
+        update: UpdateTrackedShards,
+    ) -> Result<Vec<TrackedShardsChangeView>, UpdateTrackedShardsError> {
+        let head = self.chain.head()?;
+        let epoch_height = self.epoch_manager.get_epoch_info(&head.epoch_id)?.epoch_height();
+        let shard_layout = self.epoch_manager.get_shard_layout(&head.epoch_id)?;
+        for shard_id in update.track_shards.iter().chain(update.untrack_shards.iter()) {
+            if *shard_id >= shard_layout.num_shards() {
+                return Err(UpdateTrackedShardsError::InvalidRequest(format!(
+                    "shard {} doesn't exist, the number of shards is {}",
+                    shard_id,
+                    shard_layout.num_shards()
+                )));
+            }
+        }
+
+        let track_targets = update
+            .track_shards
+            .into_iter()
+            .map(TrackingTarget::Shard)
+            .chain(update.track_accounts.into_iter().map(TrackingTarget::Account));
+        let untrack_targets = update
+            .untrack_shards
+            .into_iter()
+            .map(TrackingTarget::Shard)
+            .chain(update.untrack_accounts.into_iter().map(TrackingTarget::Account));
+        let changes: Vec<_> = track_targets
+            .map(|target| TrackingChange {
+                target,
+                track: true,
+                from_epoch_height: epoch_height + 2,
+            })
+            .chain(untrack_targets.map(|target| TrackingChange {
+                target,
+                track: false,
+                from_epoch_height: epoch_height + 1,
+            }))
+            .collect();
+        let first_change_index = self.shard_tracker.tracking_changes().len();
+        self.shard_tracker
+            .add_tracking_changes(changes.clone())
+            .map_err(UpdateTrackedShardsError::InvalidRequest)?;
+        info!(target: "client", ?changes, "Updated tracked shards");
+        self.schedule_state_deletions(first_change_index, changes, &shard_layout);
+        Ok(self.get_tracked_shards_changes()?)
+    }
+
+    /// Schedules the deletion of the state of the shards which stop being tracked, and cancels
+    /// the scheduled deletions of the shards which are tracked again.
+    fn schedule_state_deletions(
+        &mut self,
+        first_change_index: usize,
+        changes: Vec<TrackingChange>,
+        shard_layout: &ShardLayout,
+    ) {
+        let gc_num_epochs_to_keep = self.config.gc.get().gc_num_epochs_to_keep;
+        for (index, change) in changes.into_iter().enumerate() {
+            let shard_uid = ShardUId::from_shard_id_and_layout(
+                change.target.shard_id(shard_layout),
+                shard_layout,
+            );
+            if change.track {
+                for deletion in self.state_deletions.iter_mut() {
+                    if deletion.shard_uid == shard_uid
+                        && matches!(deletion.status, StateDeletionStatus::Scheduled)
+                    {
+                        deletion.status = StateDeletionStatus::Kept("the shard is tracked again");
+                    }
+                }
+            } else if !self.config.archive {
+                self.state_deletions.push(StateDeletion {
+                    change_index: first_change_index + index,
+                    shard_uid,
+                    epoch_height: change.from_epoch_height + gc_num_epochs_to_keep,
+                    status: StateDeletionStatus::Scheduled,
+                });
+            }
+        }
+    }
+
+    /// Deletes the state of the shards which stopped being tracked while the node is running,
+    /// once garbage collection doesn't need it anymore.
+    fn delete_untracked_shards_state(&mut self) -> Result<(), near_chain::Error> {
+        if !self
+            .state_deletions
+            .iter()
+            .any(|deletion| matches!(deletion.status, StateDeletionStatus::Scheduled))
+        {
+            return Ok(());
+        }
+        let head = self.chain.head()?;
+        let epoch_height = self.epoch_manager.get_epoch_info(&head.epoch_id)?.epoch_height();
+        let me = self.validator_signer.as_ref().map(|signer| signer.validator_id().clone());
+        for deletion in self.state_deletions.iter_mut() {
+            if !matches!(deletion.status, StateDeletionStatus::Scheduled)
+                || deletion.epoch_height > epoch_height
+            {
+                continue;
+            }
+            let shard_id = deletion.shard_uid.shard_id as ShardId;
+            if self.epoch_manager.shard_id_to_uid(shard_id, &head.epoch_id)? != deletion.shard_uid {
+                deletion.status = StateDeletionStatus::Kept("the shard layout has changed");
+                continue;
+            }
+            if self.shard_tracker.care_about_shard(
+                me.as_ref(),
+                &head.last_block_hash,
+                shard_id,
+                true,
+            ) || self.shard_tracker.will_care_about_shard(
+                me.as_ref(),
+                &head.last_block_hash,
+                shard_id,
+                true,
+            ) {
+                deletion.status = StateDeletionStatus::Kept("the shard is tracked");
+                continue;
+            }
+
+            info!(target: "client", shard_uid = ?deletion.shard_uid, "Deleting state of untracked shard");
+            if let Some(flat_storage_manager) = self.runtime_adapter.get_flat_storage_manager() {
+                flat_storage_manager.remove_flat_storage_for_shard(deletion.shard_uid)?;
+            }
+            let mut store_update = self.chain.store().store().store_update();
+            let key_from = deletion.shard_uid.to_bytes();
+            let key_to = ShardUId::next_shard_prefix(&key_from);
+            store_update.delete_range(DBCol::State, &key_from, &key_to);
+            store_update.commit()?;
+            deletion.status = StateDeletionStatus::Deleted;
+        }
+        Ok(())
+    }
+
+    /// Changes of the tracked shards requested while the node is running, with their progress.
+    pub fn get_tracked_shards_changes(
+        &self,
+    ) -> Result<Vec<TrackedShardsChangeView>, near_chain::Error> {
+        let changes = self.shard_tracker.tracking_changes();
+        if changes.is_empty() {
+            return Ok(vec![]);
+        }
+        let head = self.chain.head()?;
+        let epoch_height = self.epoch_manager.get_epoch_info(&head.epoch_id)?.epoch_height();
+        let shard_layout = self.epoch_manager.get_shard_layout(&head.epoch_id)?;
+        let state_sync_infos = self.chain.store().iterate_state_sync_infos()?;
+
+        let mut ret = vec![];
+        for (index, change) in changes.into_iter().enumerate() {
+            let shard_id = change.target.shard_id(&shard_layout);
+            let status = if change.track {
+                let shard_sync_status = self
+                    .catchup_state_syncs
+                    .values()
+                    .find_map(|(_, shard_sync_state, _)| shard_sync_state.get(&shard_id));
+                let is_waiting_for_catchup = state_sync_infos
+                    .iter()
+                    .any(|(_, info)| info.shards.iter().any(|shard| shard.0 == shard_id));
+                if epoch_height >= change.from_epoch_height {
+                    "Tracked".to_string()
+                } else if let Some(shard_sync_status) = shard_sync_status {
+                    format!("Catching up: {}", shard_sync_status.status)
+                } else if is_waiting_for_catchup {
+                    "Waiting for catchup".to_string()
+                } else if epoch_height + 1 == change.from_epoch_height {
+                    format!("Caught up, tracked from epoch height {}", change.from_epoch_height)
+                } else {
+                    format!("Catchup starts at epoch height {}", change.from_epoch_height - 1)
+                }
+            } else if epoch_height < change.from_epoch_height {
+                format!("Tracked until epoch height {}", change.from_epoch_height)
+            } else {
+                match self.state_deletions.iter().find(|deletion| deletion.change_index == index) {
+                    Some(StateDeletion {
+                        status: StateDeletionStatus::Scheduled,
+                        epoch_height,
+                        ..
+                    }) => {
+                        format!(
+                            "Not tracked, state deletion scheduled at epoch height {}",
+                            epoch_height
+                        )
+                    }
+                    Some(StateDeletion { status: StateDeletionStatus::Deleted, .. }) => {
+                        "Not tracked, state deleted".to_string()
+                    }
+                    Some(StateDeletion { status: StateDeletionStatus::Kept(reason), .. }) => {
+                        format!("State kept: {}", reason)
+                    }
+                    None => "Not tracked".to_string(),
+                }
+            };
+            let (shard_id, account_id) = match change.target {
+                TrackingTarget::Shard(shard_id) => (Some(shard_id), None),
+                TrackingTarget::Account(account_id) => (None, Some(account_id)),
+            };
+            ret.push(TrackedShardsChangeView {
+                shard_id,
+                account_id,
+                track: change.track,
+                from_epoch_height: change.from_epoch_height,
+                status,
+            });
+        }
+        Ok(ret)
+    }
 }
@@ -15,11 +15,12 @@ use crate::debug::new_network_info_view;
 use crate::info::{display_sync_status, InfoHelper};
 use crate::sync::state::{StateSync, StateSyncResult};
 use crate::sync_jobs_actor::{create_sync_job_scheduler, SyncJobsActor};
-use crate::{metrics, StatusResponse};
+use crate::{metrics, validator_health, StatusResponse};
 use actix::{Actor, Addr, Arbiter, AsyncContext, Context, Handler};
 use actix_rt::ArbiterHandle;
 use chrono::{DateTime, Utc};
 use near_async::messaging::{CanSend, Sender};
+use near_chain::block_timeline::BlockTimeline;
 use near_chain::chain::{
     ApplyStatePartsRequest, ApplyStatePartsResponse, BlockCatchUpRequest, BlockCatchUpResponse,
     StateSplitRequest, StateSplitResponse,
@@ -38,9 +39,11 @@ use near_chain_primitives::error::EpochErrorResultToChainError;
 use near_chunks::adapter::ShardsManagerRequestFromClient;
 use near_chunks::client::ShardsManagerResponse;
 use near_chunks::logic::cares_about_shard_this_or_next_epoch;
+use near_client_primitives::debug::ValidatorHealthView;
 use near_client_primitives::types::{
-    Error, GetClientConfig, GetClientConfigError, GetNetworkInfo, NetworkInfoResponse,
-    StateSyncStatus, Status, StatusError, StatusSyncInfo, SyncStatus,
+    Error, GetClientConfig, GetClientConfigError, GetClientConfigResponse, GetNetworkInfo,
+    NetworkInfoResponse, StateSyncStatus, Status, StatusError, StatusSyncInfo, SyncStatus,
+    UpdateTrackedShards, UpdateTrackedShardsError,
 };
 use near_epoch_manager::shard_tracker::ShardTracker;
 use near_epoch_manager::EpochManagerAdapter;
@@ -62,7 +65,7 @@ use near_primitives::unwrap_or_return;
 use near_primitives::utils::{from_timestamp, MaybeValidated};
 use near_primitives::validator_signer::ValidatorSigner;
 use near_primitives::version::PROTOCOL_VERSION;
-use near_primitives::views::{DetailedDebugStatus, ValidatorInfo};
+use near_primitives::views::{DetailedDebugStatus, TrackedShardsChangeView, ValidatorInfo};
 #[cfg(feature = "test_features")]
 use near_store::DBCol;
 use near_telemetry::TelemetryActor;
@@ -81,6 +84,8 @@ const STATUS_WAIT_TIME_MULTIPLIER: u64 = 10;
 /// `max_block_production_time` times this multiplier is how long we wait before rebroadcasting
 /// the current `head`
 const HEAD_STALL_MULTIPLIER: u32 = 4;
+/// How often the validator of this node checks its own health.
+const VALIDATOR_HEALTH_CHECK_PERIOD: Duration = Duration::from_secs(10);
 
 pub struct ClientActor {
     /// Adversarial controls
@@ -104,6 +109,10 @@ pub struct ClientActor {
 
     // Last time when log_summary method was called.
     log_summary_timer_next_attempt: DateTime<Utc>,
+    validator_health_timer_next_attempt: DateTime<Utc>,
+    /// Health of the validator of this node at the last check, None if the node doesn't run a
+    /// validator or is syncing.
+    validator_health: Option<ValidatorHealthView>,
 
     block_production_started: bool,
     doomslug_timer_next_attempt: DateTime<Utc>,
@@ -116,6 +125,12 @@ pub struct ClientActor {
 
     #[cfg(feature = "sandbox")]
     fastforward_delta: near_primitives::types::BlockHeightDelta,
+    /// Snapshot to restore once no block is being processed.
+    #[cfg(feature = "sandbox")]
+    pending_restore: Option<u64>,
+    /// Error of the last restore, reported by the next restore status request.
+    #[cfg(feature = "sandbox")]
+    restore_error: Option<String>,
 
     /// Synchronization measure to allow graceful shutdown.
     /// Informs the system when a ClientActor gets dropped.
@@ -197,6 +212,8 @@ impl ClientActor {
             info_helper,
             block_production_next_attempt: now,
             log_summary_timer_next_attempt: now,
+            validator_health_timer_next_attempt: now,
+            validator_health: None,
             block_production_started: false,
             doomslug_timer_next_attempt: now,
             sync_timer_next_attempt: now,
@@ -214,6 +231,10 @@ impl ClientActor {
 
             #[cfg(feature = "sandbox")]
             fastforward_delta: 0,
+            #[cfg(feature = "sandbox")]
+            pending_restore: None,
+            #[cfg(feature = "sandbox")]
+            restore_error: None,
             shutdown_signal,
             config_updater,
         })
@@ -600,6 +621,72 @@ impl Handler<WithSpanContext<near_client_primitives::types::SandboxMessage>> for
                     self.fastforward_delta == 0,
                 )
             }
+            near_client_primitives::types::SandboxMessage::SandboxSnapshot => {
+                match self.client.sandbox_snapshot() {
+                    Ok(snapshot_id) => {
+                        near_client_primitives::types::SandboxResponse::SandboxSnapshotCreated(
+                            snapshot_id,
+                        )
+                    }
+                    Err(err) => {
+                        near_client_primitives::types::SandboxResponse::SandboxSnapshotFailed(
+                            err.to_string(),
+                        )
+                    }
+                }
+            }
+            near_client_primitives::types::SandboxMessage::SandboxRestore(snapshot_id) => {
+                if self.pending_restore.is_some() {
+                    return near_client_primitives::types::SandboxResponse::SandboxRestoreFailed(
+                        "Consecutive restore requests cannot be made while a current one is going on.".to_string());
+                }
+                self.restore_error = None;
+                self.pending_restore = Some(snapshot_id);
+                self.sandbox_process_restore();
+                near_client_primitives::types::SandboxResponse::SandboxNoResponse
+            }
+            near_client_primitives::types::SandboxMessage::SandboxRestoreStatus => {
+                match self.restore_error.take() {
+                    Some(err) => {
+                        near_client_primitives::types::SandboxResponse::SandboxRestoreFailed(err)
+                    }
+                    None => near_client_primitives::types::SandboxResponse::SandboxRestoreFinished(
+                        self.pending_restore.is_none(),
+                    ),
+                }
+            }
+            near_client_primitives::types::SandboxMessage::SandboxDropSnapshot(snapshot_id) => {
+                match self.client.sandbox_drop_snapshot(snapshot_id) {
+                    Ok(()) => {
+                        near_client_primitives::types::SandboxResponse::SandboxSnapshotDropped
+                    }
+                    Err(err) => {
+                        near_client_primitives::types::SandboxResponse::SandboxDropSnapshotFailed(
+                            err.to_string(),
+                        )
+                    }
+                }
+            }
+            near_client_primitives::types::SandboxMessage::SandboxSetTimestamp(timestamp) => {
+                match self.client.sandbox_set_timestamp(timestamp) {
+                    Ok(()) => near_client_primitives::types::SandboxResponse::SandboxNoResponse,
+                    Err(err) => {
+                        near_client_primitives::types::SandboxResponse::SandboxSetTimeFailed(
+                            err.to_string(),
+                        )
+                    }
+                }
+            }
+            near_client_primitives::types::SandboxMessage::SandboxOffsetTime(offset) => {
+                match self.client.sandbox_offset_time(offset) {
+                    Ok(()) => near_client_primitives::types::SandboxResponse::SandboxNoResponse,
+                    Err(err) => {
+                        near_client_primitives::types::SandboxResponse::SandboxSetTimeFailed(
+                            err.to_string(),
+                        )
+                    }
+                }
+            }
         }
     }
 }
@@ -635,6 +722,9 @@ impl Handler<WithSpanContext<Status>> for ClientActor {
             if self.client.sync_status.is_syncing() {
                 return Err(StatusError::NodeIsSyncing);
             }
+            if let Some(health) = &self.validator_health {
+                validator_health::check_validator_health(health)?;
+            }
         }
         let validators: Vec<ValidatorInfo> = self
             .client
@@ -687,7 +777,7 @@ impl Handler<WithSpanContext<Status>> for ClientActor {
                     display_sync_status(
                         &self.client.sync_status,
                         &self.client.chain.head()?,
-                        &self.client.config.state_sync.sync,
+                        &self.client.config.state_sync.get().sync,
                     ),
                 ),
                 catchup_status: self.client.get_catchup_status()?,
@@ -727,6 +817,7 @@ impl Handler<WithSpanContext<Status>> for ClientActor {
             node_public_key,
             node_key,
             uptime_sec,
+            validator_kickout_risk: self.validator_health.as_ref().map(|it| it.kickout_risk()),
             detailed_debug_status,
         })
     }
@@ -953,9 +1044,27 @@ impl ClientActor {
         Ok(Some(new_latest_known))
     }
 
+    /// Restores the pending sandbox snapshot unless blocks are being processed, since their
+    /// postprocessing would move the head again.
+    #[cfg(feature = "sandbox")]
+    fn sandbox_process_restore(&mut self) {
+        let snapshot_id = match self.pending_restore {
+            Some(snapshot_id) => snapshot_id,
+            None => return,
+        };
+        if self.client.chain.blocks_in_processing_len() > 0 {
+            return;
+        }
+        self.pending_restore = None;
+        if let Err(err) = self.client.sandbox_restore(snapshot_id) {
+            self.restore_error = Some(err.to_string());
+        }
+    }
+
     fn pre_block_production(&mut self) -> Result<(), Error> {
         #[cfg(feature = "sandbox")]
         {
+            self.sandbox_process_restore();
             let latest_known = self.client.chain.mut_store().get_latest_known()?;
             if let Some(new_latest_known) =
                 self.sandbox_process_fast_forward(latest_known.height)?
@@ -1094,7 +1203,7 @@ impl ClientActor {
     /// min(time until the closest trigger, 1 second).
     fn check_triggers(&mut self, ctx: &mut Context<ClientActor>) -> Duration {
         if let Some(config_updater) = &mut self.config_updater {
-            config_updater.try_update(&|updateable_client_config| {
+            config_updater.try_update(&mut |updateable_client_config| {
                 self.client.update_client_config(updateable_client_config)
             });
         }
@@ -1184,6 +1293,21 @@ impl ClientActor {
                 .to_std()
                 .unwrap_or(delay),
         );
+
+        self.validator_health_timer_next_attempt = self.run_timer(
+            VALIDATOR_HEALTH_CHECK_PERIOD,
+            self.validator_health_timer_next_attempt,
+            ctx,
+            |act, _ctx| act.check_validator_health(),
+            "validator_health",
+        );
+        delay = core::cmp::min(
+            delay,
+            self.validator_health_timer_next_attempt
+                .signed_duration_since(now)
+                .to_std()
+                .unwrap_or(delay),
+        );
         timer.observe_duration();
         delay
     }
@@ -1725,6 +1849,22 @@ impl ClientActor {
         }
     }
 
+    /// Computes the health of the validator of this node, exports it as metrics and keeps it for
+    /// the status and health requests.
+    fn check_validator_health(&mut self) {
+        self.validator_health = None;
+        if self.client.validator_signer.is_none() || self.client.sync_status.is_syncing() {
+            return;
+        }
+        match validator_health::get_validator_health(&self.client) {
+            Ok(health) => {
+                validator_health::report_validator_health(&health);
+                self.validator_health = Some(health);
+            }
+            Err(err) => debug!(target: "client", ?err, "Failed to compute validator health"),
+        }
+    }
+
     /// Print current summary.
     fn log_summary(&mut self) {
         let _span = tracing::debug_span!(target: "client", "log_summary").entered();
@@ -1832,7 +1972,7 @@ impl Handler<WithSpanContext<ShardsManagerResponse>> for ClientActor {
 }
 
 impl Handler<WithSpanContext<GetClientConfig>> for ClientActor {
-    type Result = Result<ClientConfig, GetClientConfigError>;
+    type Result = Result<GetClientConfigResponse, GetClientConfigError>;
 
     fn handle(
         &mut self,
@@ -1842,7 +1982,33 @@ impl Handler<WithSpanContext<GetClientConfig>> for ClientActor {
         let (_span, _msg) = handler_debug_span!(target: "client", msg);
         let _d = delay_detector::DelayDetector::new(|| "client get client config".into());
 
-        Ok(self.client.config.clone())
+        Ok(GetClientConfigResponse {
+            client_config: self.client.config.clone(),
+            applied_changes: self
+                .config_updater
+                .as_ref()
+                .map(|config_updater| config_updater.applied_changes())
+                .unwrap_or_default(),
+            rejected_change: self
+                .config_updater
+                .as_ref()
+                .and_then(|config_updater| config_updater.client_config_update_error()),
+        })
+    }
+}
+
+impl Handler<WithSpanContext<UpdateTrackedShards>> for ClientActor {
+    type Result = Result<Vec<TrackedShardsChangeView>, UpdateTrackedShardsError>;
+
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<UpdateTrackedShards>,
+        _: &mut Context<Self>,
+    ) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        let _d = delay_detector::DelayDetector::new(|| "client update tracked shards".into());
+
+        self.client.update_tracked_shards(msg)
     }
 }
 
@@ -1869,12 +2035,13 @@ pub fn start_client(
     sender: Option<broadcast::Sender<()>>,
     adv: crate::adversarial::Controls,
     config_updater: Option<ConfigUpdater>,
+    block_timeline: BlockTimeline,
 ) -> (Addr<ClientActor>, ArbiterHandle) {
     let client_arbiter = Arbiter::new();
     let client_arbiter_handle = client_arbiter.handle();
 
     wait_until_genesis(&chain_genesis.time);
-    let client = Client::new(
+    let mut client = Client::new(
         client_config.clone(),
         chain_genesis,
         epoch_manager,
@@ -1888,6 +2055,7 @@ pub fn start_client(
         make_state_snapshot_callback,
     )
     .unwrap();
+    client.chain.block_timeline = block_timeline;
     let client_addr = ClientActor::start_in_arbiter(&client_arbiter_handle, move |ctx| {
         ClientActor::new(
             client,
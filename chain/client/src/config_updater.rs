@@ -1,10 +1,22 @@
-use near_chain_configs::UpdateableClientConfig;
+use near_chain_configs::{ConfigValueChange, UpdateableClientConfig, UpdateableClientConfigError};
 use near_dyn_configs::{UpdateableConfigLoaderError, UpdateableConfigs};
+use near_primitives::errors::EpochError;
+use std::collections::VecDeque;
 use std::sync::Arc;
 use tokio::sync::broadcast::Receiver;
 
-#[derive(Debug)]
-pub enum ClientConfigUpdateError {}
+/// How many applied changes of the config are kept for the debug page.
+const MAX_APPLIED_CHANGES: usize = 100;
+
+#[derive(thiserror::Error, Debug)]
+pub enum ClientConfigUpdateError {
+    #[error(transparent)]
+    InvalidConfig(#[from] UpdateableClientConfigError),
+    #[error("Failed to check the config update against the chain state: {0}")]
+    Chain(#[from] near_chain::Error),
+    #[error("Failed to check the config update against the epoch info: {0}")]
+    Epoch(#[from] EpochError),
+}
 
 /// Manages updating the config encapsulating.
 pub struct ConfigUpdater {
@@ -13,24 +25,52 @@ pub struct ConfigUpdater {
 
     /// Represents the latest Error of reading the dynamically reloadable configs.
     updateable_configs_error: Option<Arc<UpdateableConfigLoaderError>>,
+
+    /// Represents the latest rejected update of the client config.
+    client_config_update_error: Option<String>,
+
+    /// Latest changes of the client config, the oldest first.
+    applied_changes: VecDeque<ConfigValueChange>,
 }
 
 impl ConfigUpdater {
     pub fn new(
         rx_config_update: Receiver<Result<UpdateableConfigs, Arc<UpdateableConfigLoaderError>>>,
     ) -> Self {
-        Self { rx_config_update, updateable_configs_error: None }
+        Self {
+            rx_config_update,
+            updateable_configs_error: None,
+            client_config_update_error: None,
+            applied_changes: VecDeque::new(),
+        }
     }
 
     /// Check if any of the configs were updated.
     /// If they did, the receiver (rx_config_update) will contain a clone of the new configs.
-    pub fn try_update(&mut self, update_client_config_fn: &dyn Fn(UpdateableClientConfig)) {
+    pub fn try_update(
+        &mut self,
+        update_client_config_fn: &mut dyn FnMut(
+            UpdateableClientConfig,
+        ) -> Result<
+            Vec<ConfigValueChange>,
+            ClientConfigUpdateError,
+        >,
+    ) {
         while let Ok(maybe_updateable_configs) = self.rx_config_update.try_recv() {
             match maybe_updateable_configs {
                 Ok(updateable_configs) => {
                     if let Some(client_config) = updateable_configs.client_config {
-                        update_client_config_fn(client_config);
-                        tracing::info!(target: "config", "Updated ClientConfig");
+                        match update_client_config_fn(client_config) {
+                            Ok(changes) => {
+                                tracing::info!(target: "config", num_changes = changes.len(), "Updated ClientConfig");
+                                self.record_changes(changes);
+                                self.client_config_update_error = None;
+                            }
+                            Err(err) => {
+                                tracing::error!(target: "config", %err, "Rejected ClientConfig update");
+                                self.client_config_update_error = Some(err.to_string());
+                            }
+                        }
                     }
                     self.updateable_configs_error = None;
                 }
@@ -41,6 +81,23 @@ impl ConfigUpdater {
         }
     }
 
+    fn record_changes(&mut self, changes: Vec<ConfigValueChange>) {
+        self.applied_changes.extend(changes);
+        while self.applied_changes.len() > MAX_APPLIED_CHANGES {
+            self.applied_changes.pop_front();
+        }
+    }
+
+    /// Latest changes of the client config, the oldest first.
+    pub fn applied_changes(&self) -> Vec<ConfigValueChange> {
+        self.applied_changes.iter().cloned().collect()
+    }
+
+    /// Reason why the latest update of the client config was rejected, if it was.
+    pub fn client_config_update_error(&self) -> Option<String> {
+        self.client_config_update_error.clone()
+    }
+
     /// Prints an error if it's present.
     pub fn report_status(&self) {
         if let Some(updateable_configs_error) = &self.updateable_configs_error {
@@ -49,5 +106,11 @@ impl ConfigUpdater {
                 "Dynamically updateable configs are not valid. Please fix this ASAP otherwise the node will probably crash after restart: {}",
                 *updateable_configs_error);
         }
+        if let Some(client_config_update_error) = &self.client_config_update_error {
+            tracing::warn!(
+                target: "stats",
+                "The latest change of config.json was not applied: {}",
+                client_config_update_error);
+        }
     }
 }
@@ -7,8 +7,9 @@ use itertools::Itertools;
 use near_chain::crypto_hash_timer::CryptoHashTimer;
 use near_chain::{near_chain_primitives, Chain, ChainStoreAccess};
 use near_client_primitives::debug::{
-    ApprovalAtHeightStatus, BlockProduction, ChunkCollection, DebugBlockStatusData, DebugStatus,
-    DebugStatusResponse, MissedHeightInfo, ProductionAtHeight, ValidatorStatus,
+    ApprovalAtHeightStatus, BlockProduction, CatchupInfoView, ChunkCollection,
+    DebugBlockStatusData, DebugStatus, DebugStatusResponse, MissedHeightInfo, ProductionAtHeight,
+    ValidatorStatus,
 };
 use near_client_primitives::types::Error;
 use near_client_primitives::{
@@ -35,7 +36,8 @@ use near_network::types::{ConnectedPeerInfo, NetworkInfo, PeerType};
 use near_primitives::sharding::ShardChunkHeader;
 use near_primitives::static_clock::StaticClock;
 use near_primitives::views::{
-    AccountDataView, KnownProducerView, NetworkInfoView, PeerInfoView, Tier1ProxyView,
+    AccountDataView, BlockTimelineView, KnownProducerView, NetworkInfoView, PeerInfoView,
+    Tier1ProxyView,
 };
 
 // Constants for debug requests.
@@ -170,15 +172,26 @@ impl Handler<WithSpanContext<DebugStatus>> for ClientActor {
             DebugStatus::ValidatorStatus => {
                 Ok(DebugStatusResponse::ValidatorStatus(self.get_validator_status()?))
             }
-            DebugStatus::CatchupStatus => {
-                Ok(DebugStatusResponse::CatchupStatus(self.client.get_catchup_status()?))
-            }
+            DebugStatus::CatchupStatus => Ok(DebugStatusResponse::CatchupStatus(CatchupInfoView {
+                catchup_status: self.client.get_catchup_status()?,
+                tracked_shards_changes: self.client.get_tracked_shards_changes()?,
+            })),
             DebugStatus::RequestedStateParts => Ok(DebugStatusResponse::RequestedStateParts(
                 self.client.chain.get_requested_state_parts(),
             )),
             DebugStatus::ChainProcessingStatus => Ok(DebugStatusResponse::ChainProcessingStatus(
                 self.client.chain.get_chain_processing_info(),
             )),
+            DebugStatus::ValidatorHealth => Ok(DebugStatusResponse::ValidatorHealth(
+                crate::validator_health::get_validator_health(&self.client)?,
+            )),
+            DebugStatus::BlockTimeline(height) => Ok(DebugStatusResponse::BlockTimeline(
+                self.client
+                    .chain
+                    .block_timeline
+                    .get_timeline(height)
+                    .unwrap_or(BlockTimelineView { height, events: vec![] }),
+            )),
         }
     }
 }
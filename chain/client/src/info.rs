@@ -373,7 +373,7 @@ impl InfoHelper {
         let s = |num| if num == 1 { "" } else { "s" };
 
         let sync_status_log =
-            Some(display_sync_status(sync_status, head, &client_config.state_sync.sync));
+            Some(display_sync_status(sync_status, head, &client_config.state_sync.get().sync));
         let catchup_status_log = display_catchup_status(catchup_status);
         let validator_info_log = validator_info.as_ref().map(|info| {
             format!(
@@ -514,6 +514,7 @@ impl InfoHelper {
             chain: TelemetryChainInfo {
                 node_id: node_id.to_string(),
                 account_id: self.validator_signer.as_ref().map(|bp| bp.validator_id().clone()),
+                validator_public_key: self.validator_signer.as_ref().map(|bp| bp.public_key()),
                 is_validator,
                 status: sync_status.as_variant_name().to_string(),
                 latest_block_hash: head.last_block_hash,
@@ -1,11 +1,12 @@
 pub use near_client_primitives::types::{
-    Error, GetBlock, GetBlockProof, GetBlockProofResponse, GetBlockWithMerkleTree, GetChunk,
-    GetClientConfig, GetExecutionOutcome, GetExecutionOutcomeResponse,
+    AccountPattern, Error, GetBlock, GetBlockProof, GetBlockProofResponse, GetBlockWithMerkleTree,
+    GetChunk, GetClientConfig, GetExecutionOutcome, GetExecutionOutcomeResponse,
     GetExecutionOutcomesForBlock, GetGasPrice, GetMaintenanceWindows, GetNetworkInfo,
-    GetNextLightClientBlock, GetProtocolConfig, GetReceipt, GetSplitStorageInfo, GetStateChanges,
-    GetStateChangesInBlock, GetStateChangesWithCauseInBlock,
+    GetNextLightClientBlock, GetProtocolConfig, GetReceipt, GetReceiptTree, GetSplitStorageInfo,
+    GetStateChanges, GetStateChangesInBlock, GetStateChangesWithCauseInBlock,
     GetStateChangesWithCauseInBlockForTrackedShards, GetValidatorInfo, GetValidatorOrdered, Query,
-    QueryError, Status, StatusResponse, SyncStatus, TxStatus, TxStatusError,
+    QueryError, StateChangeValueKind, StateChangesFilter, Status, StatusResponse, SyncStatus,
+    TxStatus, TxStatusError, UpdateTrackedShards,
 };
 
 pub use near_client_primitives::debug::DebugStatus;
@@ -30,7 +31,9 @@ mod info;
 mod metrics;
 pub mod sync;
 mod sync_jobs_actor;
+pub mod test_loop;
 pub mod test_utils;
 #[cfg(test)]
 mod tests;
+mod validator_health;
 mod view_client;
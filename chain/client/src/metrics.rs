@@ -105,6 +105,23 @@ pub(crate) static VALIDATORS_CHUNKS_EXPECTED: Lazy<IntGaugeVec> = Lazy::new(|| {
     .unwrap()
 });
 
+pub(crate) static VALIDATOR_PROJECTED_KICKOUT: Lazy<IntGaugeVec> = Lazy::new(|| {
+    try_create_int_gauge_vec(
+        "near_validator_projected_kickout",
+        "Bool to denote if the validator of this node produces too few blocks or chunks to stay a validator in the next epoch",
+        &["kind"],
+    )
+    .unwrap()
+});
+
+pub(crate) static VALIDATOR_HEALTH_ISSUES: Lazy<IntGauge> = Lazy::new(|| {
+    try_create_int_gauge(
+        "near_validator_health_issues",
+        "Number of problems detected by the self-monitoring of the validator of this node, 0 if it is healthy",
+    )
+    .unwrap()
+});
+
 pub(crate) static VALIDATORS_CHUNKS_PRODUCED_BY_SHARD: Lazy<IntGaugeVec> = Lazy::new(|| {
     try_create_int_gauge_vec(
         "near_validators_chunks_produced_by_shard",
@@ -210,6 +227,15 @@ pub(crate) static CHUNK_SKIPPED_TOTAL: Lazy<IntCounterVec> = Lazy::new(|| {
     .unwrap()
 });
 
+pub(crate) static VALIDATOR_SIGNER_FAILURES: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "near_validator_signer_failures_total",
+        "Number of blocks, chunks and approvals not sent because the validator signer didn't sign them",
+        &["kind"],
+    )
+    .unwrap()
+});
+
 pub(crate) static CHUNK_PRODUCER_BANNED_FOR_EPOCH: Lazy<IntCounter> = Lazy::new(|| {
     try_create_int_counter(
         "near_chunk_producer_banned_for_epoch",
@@ -58,6 +58,18 @@ impl HeaderSync {
         }
     }
 
+    /// Updates the timeouts, which can be changed while the node is running.
+    pub fn set_timeouts(
+        &mut self,
+        initial_timeout: TimeDuration,
+        progress_timeout: TimeDuration,
+        stall_ban_timeout: TimeDuration,
+    ) {
+        self.initial_timeout = Duration::from_std(initial_timeout).unwrap();
+        self.progress_timeout = Duration::from_std(progress_timeout).unwrap();
+        self.stall_ban_timeout = Duration::from_std(stall_ban_timeout).unwrap();
+    }
+
     pub fn run(
         &mut self,
         sync_status: &mut SyncStatus,
@@ -211,6 +211,11 @@ impl StateSync {
         }
     }
 
+    /// Updates the timeout, which can be changed while the node is running.
+    pub fn set_timeout(&mut self, timeout: TimeDuration) {
+        self.timeout = Duration::from_std(timeout).unwrap();
+    }
+
     fn sync_block_status(
         &mut self,
         prev_hash: &CryptoHash,
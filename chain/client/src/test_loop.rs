@@ -0,0 +1,495 @@
+//! Runs the client stack of multiple nodes (Client, ShardsManager and ViewClient) on the
+//! `near_async` TestLoop, connected by a simulated network with latency and partitions.
+//!
+//! Everything happens in response to events of the loop, in virtual time, and all the randomness
+//! comes from seeds, so a failing test replays exactly, down to the block hashes. The genesis and
+//! the `StaticClock` the client reads its timestamps from follow the virtual clock too. Use
+//! `TestEnvBuilder::build_test_loop` to set up the nodes.
+//!
+//! Block production follows slots of the virtual clock instead of doomslug timers: half a slot
+//! before each height the validators send their approvals, and at the height the block producer
+//! produces the block. Header, block and state sync are not simulated; nodes that fall behind
+//! catch up by requesting the orphan blocks they receive.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use derive_enum_from_into::{EnumFrom, EnumTryInto};
+use near_async::messaging::{CanSend, IntoSender, Sender};
+use near_async::test_loop::adhoc::{handle_adhoc_events, AdhocEvent};
+use near_async::test_loop::event_handler::{
+    ignore_events_on_drop, interval, LoopEventHandler, LoopHandlerContext,
+};
+use near_async::test_loop::futures::MessageExpectingResponse;
+use near_async::test_loop::TestLoop;
+use near_async::time;
+
+use near_chain::Provenance;
+use near_chunks::adapter::ShardsManagerRequestFromClient;
+use near_chunks::client::ShardsManagerResponse;
+use near_chunks::test_loop::{
+    forward_client_request_to_shards_manager, forward_network_request_to_shards_manager,
+    periodically_resend_chunk_requests, ShardsManagerResendChunkRequests,
+};
+use near_chunks::{ShardsManager, CHUNK_REQUEST_RETRY};
+use near_network::shards_manager::ShardsManagerRequestFromNetwork;
+use near_network::test_loop::SupportsRoutingLookup;
+use near_network::types::{
+    NetworkRequests, NetworkResponses, PeerManagerMessageRequest, PeerManagerMessageResponse,
+};
+use near_primitives::block::{Approval, Block};
+use near_primitives::block_header::ApprovalType;
+use near_primitives::hash::CryptoHash;
+use near_primitives::network::PeerId;
+use near_primitives::static_clock::MockClockGuard;
+use near_primitives::transaction::SignedTransaction;
+use near_primitives::types::{AccountId, BlockHeight};
+use near_primitives::utils::MaybeValidated;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use tracing::{debug, error};
+
+use crate::test_utils::run_catchup;
+use crate::{Client, ViewClientActor};
+
+/// The components of one node of the simulation.
+#[derive(derive_more::AsMut, derive_more::AsRef)]
+pub struct TestLoopNode {
+    pub account_id: AccountId,
+    pub peer_id: PeerId,
+    pub client: Client,
+    pub shards_manager: ShardsManager,
+    pub view_client: ViewClientActor,
+}
+
+/// Messages the client receives from other nodes.
+#[derive(Debug)]
+pub enum ClientRequestFromNetwork {
+    Block { block: Block, peer_id: PeerId, was_requested: bool },
+    Approval { approval: Approval, peer_id: PeerId },
+    Transaction { transaction: SignedTransaction, is_forwarded: bool },
+}
+
+#[derive(EnumTryInto, EnumFrom, Debug)]
+pub enum TestLoopEvent {
+    Adhoc(AdhocEvent<TestLoopNode>),
+    ClientToShardsManager(ShardsManagerRequestFromClient),
+    NetworkToShardsManager(ShardsManagerRequestFromNetwork),
+    ShardsManagerToClient(ShardsManagerResponse),
+    NetworkToClient(ClientRequestFromNetwork),
+    OutboundNetwork(PeerManagerMessageRequest),
+    OutboundNetworkWithResponse(
+        MessageExpectingResponse<PeerManagerMessageRequest, Result<PeerManagerMessageResponse, ()>>,
+    ),
+    BlockProduction(BlockProductionTrigger),
+    ResendChunkRequests(ShardsManagerResendChunkRequests),
+    Catchup(RunCatchup),
+}
+
+pub type ClientTestLoop = TestLoop<Vec<TestLoopNode>, (usize, TestLoopEvent)>;
+
+pub fn forward_network_requests_to_client() -> LoopEventHandler<Client, ClientRequestFromNetwork> {
+    LoopEventHandler::new_simple(|request, client: &mut Client| match request {
+        ClientRequestFromNetwork::Block { block, peer_id, was_requested } => {
+            client.receive_block(block, peer_id, was_requested, Arc::new(|_| {}));
+            client.finish_blocks_in_processing();
+        }
+        ClientRequestFromNetwork::Approval { approval, peer_id } => {
+            client.collect_block_approval(&approval, ApprovalType::PeerApproval(peer_id));
+        }
+        ClientRequestFromNetwork::Transaction { transaction, is_forwarded } => {
+            client.process_tx(transaction, is_forwarded, false);
+        }
+    })
+}
+
+pub fn forward_shards_manager_responses_to_client(
+) -> LoopEventHandler<Client, ShardsManagerResponse> {
+    LoopEventHandler::new_simple(|response, client: &mut Client| {
+        match response {
+            ShardsManagerResponse::ChunkCompleted { partial_chunk, shard_chunk } => {
+                client.on_chunk_completed(partial_chunk, shard_chunk, Arc::new(|_| {}));
+            }
+            ShardsManagerResponse::InvalidChunk(encoded_chunk) => {
+                client.on_invalid_chunk(encoded_chunk);
+            }
+            ShardsManagerResponse::ChunkHeaderReadyForInclusion {
+                chunk_header,
+                chunk_producer,
+            } => {
+                client.on_chunk_header_ready_for_inclusion(chunk_header, chunk_producer);
+            }
+        }
+        client.finish_blocks_in_processing();
+    })
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BlockProductionTrigger {
+    SendApproval(BlockHeight),
+    ProduceBlock(BlockHeight),
+}
+
+/// Produces a block every `slot`, starting at `first_height` one slot after the start of the
+/// test. Half a slot before each height, the validators send their approvals for it.
+pub fn produce_blocks(
+    first_height: BlockHeight,
+    slot: time::Duration,
+    network_adapter: Sender<PeerManagerMessageRequest>,
+) -> LoopEventHandler<Client, BlockProductionTrigger> {
+    LoopEventHandler::new_with_initial_event(
+        BlockProductionTrigger::SendApproval(first_height),
+        slot / 2,
+        move |trigger, client: &mut Client, context: &LoopHandlerContext<_>| {
+            match trigger {
+                BlockProductionTrigger::SendApproval(height) => {
+                    send_approval(client, height);
+                    context
+                        .sender
+                        .send_with_delay(BlockProductionTrigger::ProduceBlock(height), slot / 2);
+                }
+                BlockProductionTrigger::ProduceBlock(height) => {
+                    produce_block(client, height, &network_adapter);
+                    context.sender.send_with_delay(
+                        BlockProductionTrigger::SendApproval(height + 1),
+                        slot / 2,
+                    );
+                }
+            }
+            Ok(())
+        },
+        |_| true,
+    )
+}
+
+fn send_approval(client: &mut Client, height: BlockHeight) {
+    let Some(signer) = client.validator_signer.clone() else { return };
+    let head = client.chain.head().unwrap();
+    if head.height >= height
+        || !(client.is_validator(&head.epoch_id, &head.last_block_hash)
+            || client.is_validator(&head.next_epoch_id, &head.last_block_hash))
+    {
+        return;
+    }
+    let approval = Approval::new(head.last_block_hash, head.height, height, &*signer);
+    if let Err(err) = client.send_approval(&head.last_block_hash, approval) {
+        error!(target: "test_loop", ?err, "failed to send an approval");
+    }
+}
+
+fn produce_block(
+    client: &mut Client,
+    height: BlockHeight,
+    network_adapter: &Sender<PeerManagerMessageRequest>,
+) {
+    let Some(signer) = client.validator_signer.clone() else { return };
+    let head = client.chain.head().unwrap();
+    let epoch_id =
+        client.epoch_manager.get_epoch_id_from_prev_block(&head.last_block_hash).unwrap();
+    if client.epoch_manager.get_block_producer(&epoch_id, height).unwrap() != *signer.validator_id()
+    {
+        return;
+    }
+    let block = match client.produce_block(height) {
+        Ok(Some(block)) => block,
+        Ok(None) => return,
+        Err(err) => {
+            error!(target: "test_loop", ?err, height, "failed to produce a block");
+            return;
+        }
+    };
+    network_adapter.send(PeerManagerMessageRequest::NetworkRequests(NetworkRequests::Block {
+        block: block.clone(),
+    }));
+    let result = client.start_process_block(
+        MaybeValidated::from_validated(block),
+        Provenance::PRODUCED,
+        Arc::new(|_| {}),
+    );
+    match result {
+        Ok(()) | Err(near_chain::Error::ChunksMissing(_)) => {}
+        Err(err) => error!(target: "test_loop", ?err, "failed to process a produced block"),
+    }
+    client.finish_blocks_in_processing();
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RunCatchup;
+
+/// Periodically catches up the shards the node will track in the next epoch.
+pub fn periodically_run_catchup(every: time::Duration) -> LoopEventHandler<Client, RunCatchup> {
+    interval(every, RunCatchup, |client: &mut Client| {
+        if client.chain.store().iterate_state_sync_infos().unwrap().is_empty() {
+            return;
+        }
+        run_catchup(client, &[]).unwrap();
+        client.finish_blocks_in_processing();
+    })
+}
+
+/// Latencies and partitions of the simulated network. Cloned handles share the state, so that a
+/// test can partition or heal the network while the loop is running.
+#[derive(Clone)]
+pub struct SimulatedNetwork(Arc<Mutex<SimulatedNetworkInner>>);
+
+struct SimulatedNetworkInner {
+    seed: u64,
+    min_latency: time::Duration,
+    max_latency: time::Duration,
+    /// Every directed link draws its latencies from its own generator, so that the latencies of a
+    /// link don't depend on the traffic of the others.
+    links: HashMap<(usize, usize), StdRng>,
+    /// Group of every node while the network is partitioned.
+    partitions: Option<HashMap<usize, usize>>,
+}
+
+impl SimulatedNetwork {
+    /// Network delivering every message after a latency uniformly drawn from
+    /// `[min_latency, max_latency]`.
+    pub fn new(seed: u64, min_latency: time::Duration, max_latency: time::Duration) -> Self {
+        assert!(min_latency <= max_latency);
+        Self(Arc::new(Mutex::new(SimulatedNetworkInner {
+            seed,
+            min_latency,
+            max_latency,
+            links: HashMap::new(),
+            partitions: None,
+        })))
+    }
+
+    /// Drops the messages between nodes of different groups. Nodes not in any group can't reach
+    /// anyone.
+    pub fn partition(&self, groups: &[&[usize]]) {
+        let partitions = groups
+            .iter()
+            .enumerate()
+            .flat_map(|(group, nodes)| nodes.iter().map(move |node| (*node, group)))
+            .collect();
+        self.0.lock().unwrap().partitions = Some(partitions);
+    }
+
+    pub fn heal(&self) {
+        self.0.lock().unwrap().partitions = None;
+    }
+
+    /// Latency of the next message from `from` to `to`, or None if the message is dropped.
+    fn latency(&self, from: usize, to: usize) -> Option<time::Duration> {
+        let mut inner = self.0.lock().unwrap();
+        if let Some(partitions) = &inner.partitions {
+            match (partitions.get(&from), partitions.get(&to)) {
+                (Some(a), Some(b)) if a == b => {}
+                _ => return None,
+            }
+        }
+        let seed = inner.seed;
+        let (min, max) =
+            (inner.min_latency.whole_milliseconds(), inner.max_latency.whole_milliseconds());
+        let rng = inner.links.entry((from, to)).or_insert_with(|| {
+            StdRng::from_seed(CryptoHash::hash_borsh((seed, from as u64, to as u64)).0)
+        });
+        Some(time::Duration::milliseconds(rng.gen_range(min..=max) as i64))
+    }
+}
+
+/// Delivers the network messages of the nodes to each other over the simulated network.
+///
+/// TODO: Like `route_shards_manager_network_messages`, this duplicates the routing of the real
+/// PeerManagerActor.
+pub fn route_network_messages(
+    network: SimulatedNetwork,
+) -> LoopEventHandler<Vec<TestLoopNode>, (usize, TestLoopEvent)> {
+    let mut route_back_lookup: HashMap<CryptoHash, usize> = HashMap::new();
+    let mut next_hash: u64 = 0;
+    LoopEventHandler::new(
+        move |(idx, event): (usize, TestLoopEvent),
+              data: &mut Vec<TestLoopNode>,
+              context: &LoopHandlerContext<(usize, TestLoopEvent)>| {
+            let request = match event {
+                TestLoopEvent::OutboundNetwork(request) => request,
+                TestLoopEvent::OutboundNetworkWithResponse(MessageExpectingResponse {
+                    message,
+                    responder,
+                }) => {
+                    responder(Ok(PeerManagerMessageResponse::NetworkResponses(
+                        NetworkResponses::NoResponse,
+                    )));
+                    message
+                }
+                event => return Err((idx, event)),
+            };
+            let PeerManagerMessageRequest::NetworkRequests(request) = request else {
+                return Ok(());
+            };
+            let send = |to: usize, event: TestLoopEvent| match network.latency(idx, to) {
+                Some(latency) => context.sender.send_with_delay((to, event), latency),
+                None => debug!(target: "test_loop", from = idx, to, "dropped a message"),
+            };
+            match request {
+                NetworkRequests::Block { block } => {
+                    for to in (0..data.len()).filter(|to| *to != idx) {
+                        send(
+                            to,
+                            ClientRequestFromNetwork::Block {
+                                block: block.clone(),
+                                peer_id: data[idx].peer_id.clone(),
+                                was_requested: false,
+                            }
+                            .into(),
+                        );
+                    }
+                }
+                NetworkRequests::Approval { approval_message } => {
+                    let to = data.index_for_account(&approval_message.target);
+                    send(
+                        to,
+                        ClientRequestFromNetwork::Approval {
+                            approval: approval_message.approval,
+                            peer_id: data[idx].peer_id.clone(),
+                        }
+                        .into(),
+                    );
+                }
+                NetworkRequests::BlockRequest { hash, peer_id } => {
+                    let Some(to) = data.iter().position(|node| node.peer_id == peer_id) else {
+                        return Ok(());
+                    };
+                    let (Some(request_latency), Some(response_latency)) =
+                        (network.latency(idx, to), network.latency(to, idx))
+                    else {
+                        debug!(target: "test_loop", from = idx, to, "dropped a block request");
+                        return Ok(());
+                    };
+                    if let Some(block) = data[to].view_client.handle_block_request(hash) {
+                        context.sender.send_with_delay(
+                            (
+                                idx,
+                                ClientRequestFromNetwork::Block {
+                                    block: *block,
+                                    peer_id,
+                                    was_requested: true,
+                                }
+                                .into(),
+                            ),
+                            request_latency + response_latency,
+                        );
+                    }
+                }
+                NetworkRequests::ForwardTx(account_id, transaction) => {
+                    let to = data.index_for_account(&account_id);
+                    send(
+                        to,
+                        ClientRequestFromNetwork::Transaction { transaction, is_forwarded: true }
+                            .into(),
+                    );
+                }
+                NetworkRequests::PartialEncodedChunkRequest { target, request, .. } => {
+                    let to = match &target.account_id {
+                        Some(account_id) => Some(data.index_for_account(account_id)),
+                        None => (0..data.len()).find(|to| {
+                            let node = &data[*to];
+                            let head = node.client.chain.head().unwrap();
+                            *to != idx
+                                && node.client.shard_tracker.care_about_shard(
+                                    Some(&node.account_id),
+                                    &head.last_block_hash,
+                                    target.shard_id,
+                                    true,
+                                )
+                        }),
+                    };
+                    let Some(to) = to else { return Ok(()) };
+                    let route_back = CryptoHash::hash_borsh(next_hash);
+                    next_hash += 1;
+                    route_back_lookup.insert(route_back, idx);
+                    send(
+                        to,
+                        ShardsManagerRequestFromNetwork::ProcessPartialEncodedChunkRequest {
+                            partial_encoded_chunk_request: request,
+                            route_back,
+                        }
+                        .into(),
+                    );
+                }
+                NetworkRequests::PartialEncodedChunkResponse { route_back, response } => {
+                    let Some(to) = route_back_lookup.remove(&route_back) else {
+                        return Ok(());
+                    };
+                    send(
+                        to,
+                        ShardsManagerRequestFromNetwork::ProcessPartialEncodedChunkResponse {
+                            partial_encoded_chunk_response: response,
+                            received_time: context.clock.now().into(),
+                        }
+                        .into(),
+                    );
+                }
+                NetworkRequests::PartialEncodedChunkMessage {
+                    account_id,
+                    partial_encoded_chunk,
+                } => {
+                    let to = data.index_for_account(&account_id);
+                    send(
+                        to,
+                        ShardsManagerRequestFromNetwork::ProcessPartialEncodedChunk(
+                            partial_encoded_chunk.into(),
+                        )
+                        .into(),
+                    );
+                }
+                NetworkRequests::PartialEncodedChunkForward { account_id, forward } => {
+                    let to = data.index_for_account(&account_id);
+                    send(
+                        to,
+                        ShardsManagerRequestFromNetwork::ProcessPartialEncodedChunkForward(forward)
+                            .into(),
+                    );
+                }
+                request => {
+                    debug!(target: "test_loop", ?request, "dropped an unsupported network request")
+                }
+            }
+            Ok(())
+        },
+    )
+}
+
+/// Makes `StaticClock` follow the given virtual clock on this thread until the returned guard is
+/// dropped.
+pub fn follow_virtual_clock(clock: time::Clock) -> MockClockGuard {
+    let guard = MockClockGuard::default();
+    let start = (clock.now(), std::time::Instant::now());
+    let utc_clock = clock.clone();
+    guard.follow(
+        move || start.1 + std::time::Duration::try_from(clock.now() - start.0).unwrap(),
+        move || SystemTime::from(utc_clock.now_utc()).into(),
+    );
+    guard
+}
+
+/// Registers the handlers of all the nodes of the loop.
+pub fn register_handlers(
+    test: &mut ClientTestLoop,
+    network: SimulatedNetwork,
+    first_height: BlockHeight,
+    slot: time::Duration,
+) {
+    for idx in 0..test.data.len() {
+        let network_adapter = test.sender().for_index(idx).into_sender();
+        test.register_handler(handle_adhoc_events().for_index(idx));
+        test.register_handler(forward_client_request_to_shards_manager().widen().for_index(idx));
+        test.register_handler(forward_network_request_to_shards_manager().widen().for_index(idx));
+        test.register_handler(
+            periodically_resend_chunk_requests(CHUNK_REQUEST_RETRY).widen().for_index(idx),
+        );
+        test.register_handler(forward_network_requests_to_client().widen().for_index(idx));
+        test.register_handler(forward_shards_manager_responses_to_client().widen().for_index(idx));
+        test.register_handler(
+            produce_blocks(first_height, slot, network_adapter).widen().for_index(idx),
+        );
+        test.register_handler(periodically_run_catchup(slot).widen().for_index(idx));
+    }
+    test.register_handler(route_network_messages(network));
+    // Network messages and timers are always in flight, so the test can end at any time.
+    test.register_handler(ignore_events_on_drop());
+}
@@ -10,7 +10,8 @@ use actix_rt::{Arbiter, System};
 use chrono::DateTime;
 use futures::{future, FutureExt};
 use near_async::actix::AddrWithAutoSpanContextExt;
-use near_async::messaging::{CanSend, IntoSender, LateBoundSender, Sender};
+use near_async::messaging::{CanSend, IntoAsyncSender, IntoSender, LateBoundSender, Sender};
+use near_async::test_loop::TestLoopBuilder;
 use near_async::time;
 use near_chunks::shards_manager_actor::start_shards_manager;
 use near_chunks::ShardsManager;
@@ -24,8 +25,14 @@ use once_cell::sync::OnceCell;
 use rand::{thread_rng, Rng};
 use tracing::info;
 
+use crate::test_loop::{
+    follow_virtual_clock, register_handlers, ClientTestLoop, SimulatedNetwork, TestLoopEvent,
+    TestLoopNode,
+};
+use crate::view_client::ViewClientRequestManager;
 use crate::{start_view_client, Client, ClientActor, SyncStatus, ViewClientActor};
 use chrono::Utc;
+use near_chain::block_timeline::BlockTimeline;
 use near_chain::chain::{do_apply_chunks, BlockCatchUpRequest, StateSplitRequest};
 use near_chain::state_snapshot_actor::MakeSnapshotCallback;
 use near_chain::test_utils::{
@@ -64,7 +71,7 @@ use near_primitives::receipt::Receipt;
 use near_primitives::runtime::config::RuntimeConfig;
 use near_primitives::shard_layout::ShardUId;
 use near_primitives::sharding::{EncodedShardChunk, PartialEncodedChunk, ReedSolomonWrapper};
-use near_primitives::static_clock::StaticClock;
+use near_primitives::static_clock::{MockClockGuard, StaticClock};
 use near_primitives::transaction::{Action, FunctionCallAction, SignedTransaction};
 
 use near_primitives::types::{
@@ -270,6 +277,7 @@ pub fn setup(
         adv.clone(),
     );
 
+    let block_timeline = BlockTimeline::default();
     let (shards_manager_addr, _) = start_shards_manager(
         epoch_manager.clone(),
         shard_tracker.clone(),
@@ -278,10 +286,11 @@ pub fn setup(
         Some(account_id),
         store,
         config.chunk_request_retry_period,
+        block_timeline.clone(),
     );
     let shards_manager_adapter = Arc::new(shards_manager_addr);
 
-    let client = Client::new(
+    let mut client = Client::new(
         config.clone(),
         chain_genesis,
         epoch_manager,
@@ -295,6 +304,7 @@ pub fn setup(
         None,
     )
     .unwrap();
+    client.chain.block_timeline = block_timeline;
     let client_actor = ClientActor::new(
         client,
         ctx.address(),
@@ -1718,6 +1728,98 @@ impl TestEnvBuilder {
         self.ensure_shard_trackers().ensure_runtimes().ensure_network_adapters().build_impl()
     }
 
+    /// Constructs the clients on a `TestLoop` instead of a `TestEnv`, connected by the given
+    /// simulated network, with a block produced every `slot`. See [`crate::test_loop`].
+    ///
+    /// The genesis starts at the time of the virtual clock, which `StaticClock` follows on this
+    /// thread as long as the returned guard is alive.
+    pub fn build_test_loop(
+        self,
+        network: SimulatedNetwork,
+        slot: time::Duration,
+    ) -> (ClientTestLoop, MockClockGuard) {
+        let mut builder = self.ensure_shard_trackers().ensure_runtimes();
+        let loop_builder = TestLoopBuilder::<(usize, TestLoopEvent)>::new();
+        let clock_guard = follow_virtual_clock(loop_builder.clock());
+        builder.chain_genesis.time = StaticClock::utc();
+        let num_validators = builder.validators.len();
+        let epoch_managers = builder.epoch_managers.unwrap();
+        let shard_trackers = builder.shard_trackers.unwrap();
+        let runtimes = builder.runtimes.unwrap();
+        let nodes = builder
+            .clients
+            .iter()
+            .enumerate()
+            .map(|(i, account_id)| {
+                let sender = loop_builder.sender().for_index(i);
+                let network_adapter = PeerManagerAdapter {
+                    async_request_sender: sender.clone().into_async_sender(),
+                    request_sender: sender.clone().into_sender(),
+                    set_chain_info_sender: Sender::noop(),
+                };
+                let epoch_manager = epoch_managers[i].clone().into_adapter();
+                let rng_seed = builder.seeds.get(account_id).copied().unwrap_or(TEST_SEED);
+                let client = setup_client_with_runtime(
+                    u64::try_from(num_validators).unwrap(),
+                    Some(account_id.clone()),
+                    false,
+                    network_adapter.clone(),
+                    ShardsManagerAdapterForTest {
+                        client: sender.clone().into_sender(),
+                        network: sender.clone().into_sender(),
+                    },
+                    builder.chain_genesis.clone(),
+                    epoch_manager.clone(),
+                    shard_trackers[i].clone(),
+                    runtimes[i].clone(),
+                    rng_seed,
+                    builder.archive,
+                    builder.save_trie_changes,
+                    None,
+                );
+                let shards_manager = ShardsManager::new(
+                    loop_builder.clock(),
+                    Some(account_id.clone()),
+                    epoch_manager.clone(),
+                    shard_trackers[i].clone(),
+                    network_adapter.request_sender.clone(),
+                    sender.into_sender(),
+                    client.chain.store().new_read_only_chunks_store(),
+                    client.chain.head().unwrap(),
+                    client.chain.header_head().unwrap(),
+                )
+                .with_rng_seed(rng_seed);
+                let view_client = ViewClientActor::new(
+                    Some(account_id.clone()),
+                    &builder.chain_genesis,
+                    epoch_manager,
+                    shard_trackers[i].clone(),
+                    runtimes[i].clone(),
+                    network_adapter,
+                    client.config.clone(),
+                    Arc::new(RwLock::new(ViewClientRequestManager::new())),
+                    crate::adversarial::Controls::default(),
+                )
+                .unwrap();
+                let signer = InMemorySigner::from_seed(
+                    account_id.clone(),
+                    KeyType::ED25519,
+                    account_id.as_ref(),
+                );
+                TestLoopNode {
+                    account_id: account_id.clone(),
+                    peer_id: PeerId::new(signer.public_key()),
+                    client,
+                    shards_manager,
+                    view_client,
+                }
+            })
+            .collect();
+        let mut test = loop_builder.build(nodes);
+        register_handlers(&mut test, network, builder.chain_genesis.height + 1, slot);
+        (test, clock_guard)
+    }
+
     fn build_impl(self) -> TestEnv {
         let chain_genesis = self.chain_genesis;
         let clients = self.clients.clone();
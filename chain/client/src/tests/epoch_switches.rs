@@ -0,0 +1,167 @@
+//! Multi-node tests of epoch switches on the simulated network of `crate::test_loop`.
+
+use std::collections::HashSet;
+
+use near_async::time;
+use near_chain::test_utils::{MockEpochManager, ValidatorSchedule};
+use near_chain::ChainGenesis;
+use near_o11y::testonly::init_test_logger;
+use near_primitives::hash::CryptoHash;
+use near_primitives::static_clock::MockClockGuard;
+use near_primitives::types::{AccountId, BlockHeight, BlockHeightDelta, NumShards};
+use near_store::test_utils::create_test_store;
+
+use crate::test_loop::{ClientTestLoop, SimulatedNetwork};
+use crate::test_utils::TestEnv;
+use crate::Client;
+
+const SLOT: time::Duration = time::Duration::milliseconds(200);
+
+fn accounts(prefix: &str, count: usize) -> Vec<AccountId> {
+    (1..=count).map(|i| format!("{}{}", prefix, i).parse().unwrap()).collect()
+}
+
+/// One node for every block producer of the schedule, all of them tracking all shards. Keep the
+/// guard alive while the loop runs.
+fn setup(
+    block_producers: Vec<Vec<AccountId>>,
+    num_shards: NumShards,
+    epoch_length: BlockHeightDelta,
+    network: SimulatedNetwork,
+) -> (ClientTestLoop, MockClockGuard) {
+    let vs =
+        ValidatorSchedule::new_with_shards(num_shards).block_producers_per_epoch(block_producers);
+    let accounts: Vec<AccountId> = vs.all_block_producers().cloned().collect();
+    let stores: Vec<_> = accounts.iter().map(|_| create_test_store()).collect();
+    let epoch_managers = stores
+        .iter()
+        .map(|store| MockEpochManager::new_with_validators(store.clone(), vs.clone(), epoch_length))
+        .collect();
+    let mut chain_genesis = ChainGenesis::test();
+    chain_genesis.epoch_length = epoch_length;
+    TestEnv::builder(chain_genesis)
+        .clients(accounts.clone())
+        .validators(accounts)
+        .stores(stores)
+        .mock_epoch_managers(epoch_managers)
+        .track_all_shards()
+        .build_test_loop(network, SLOT)
+}
+
+/// Hashes of the canonical blocks of the client up to the given height, None for skipped heights.
+fn canonical_chain(client: &Client, up_to: BlockHeight) -> Vec<Option<CryptoHash>> {
+    (0..=up_to).map(|height| client.chain.get_block_hash_by_height(height).ok()).collect()
+}
+
+/// Asserts that all the nodes have the same canonical chain, except for the last couple of
+/// blocks, which may still be in flight. Returns the height up to which the chains agree.
+fn assert_chains_agree(test: &ClientTestLoop) -> BlockHeight {
+    let min_head =
+        test.data.iter().map(|node| node.client.chain.head().unwrap().height).min().unwrap();
+    let up_to = min_head.saturating_sub(2);
+    let expected = canonical_chain(&test.data[0].client, up_to);
+    for (idx, node) in test.data.iter().enumerate() {
+        assert_eq!(canonical_chain(&node.client, up_to), expected, "node {} disagrees", idx);
+    }
+    up_to
+}
+
+/// Port of `test_consensus_with_epoch_switches`: three sets of block producers take turns every
+/// epoch. All the nodes agree on the chain, every set produces blocks, and no node finalizes a
+/// block off the agreed chain.
+#[test]
+fn test_epoch_switches_with_rotating_block_producers() {
+    init_test_logger();
+    let block_producers = vec![accounts("test1.", 4), accounts("test2.", 4), accounts("test3.", 4)];
+    let network = SimulatedNetwork::new(
+        0,
+        time::Duration::milliseconds(10),
+        time::Duration::milliseconds(80),
+    );
+    let (mut test, _clock_guard) = setup(block_producers.clone(), 4, 5, network);
+    test.run_for(SLOT * 60);
+
+    let agreed = assert_chains_agree(&test);
+    assert!(agreed > 40, "the chain only agrees up to height {}", agreed);
+    let client = &test.data[0].client;
+    let mut epochs = HashSet::new();
+    let mut producers = HashSet::new();
+    for height in 1..=agreed {
+        let Ok(block) = client.chain.get_block_by_height(height) else { continue };
+        let epoch_id = block.header().epoch_id().clone();
+        producers.insert(client.epoch_manager.get_block_producer(&epoch_id, height).unwrap());
+        epochs.insert(epoch_id);
+    }
+    assert!(epochs.len() >= 6, "only {} epochs passed", epochs.len());
+    for set in &block_producers {
+        assert!(set.iter().any(|account_id| producers.contains(account_id)));
+    }
+    for node in &test.data {
+        let final_head = node.client.chain.final_head().unwrap();
+        assert!(final_head.height > 0);
+        if final_head.height <= agreed {
+            assert_eq!(
+                client.chain.get_block_hash_by_height(final_head.height).unwrap(),
+                final_head.last_block_hash
+            );
+        }
+    }
+}
+
+/// One of four validators is cut off from the others across two epoch boundaries, and both sides
+/// keep producing blocks on their own forks. Once the network heals, the nodes converge on one
+/// chain by requesting the blocks they missed.
+#[test]
+fn test_epoch_switches_with_network_partition() {
+    init_test_logger();
+    let network = SimulatedNetwork::new(
+        1,
+        time::Duration::milliseconds(10),
+        time::Duration::milliseconds(50),
+    );
+    let (mut test, _clock_guard) = setup(vec![accounts("test", 4)], 2, 10, network.clone());
+    test.run_for(SLOT * 15);
+    let epoch_before_partition = test.data[0].client.chain.head().unwrap().epoch_id;
+    network.partition(&[&[0, 1, 2], &[3]]);
+    test.run_for(SLOT * 20);
+    let isolated_head = test.data[3].client.chain.head().unwrap();
+    let majority_head = test.data[0].client.chain.head().unwrap();
+    assert_ne!(isolated_head.last_block_hash, majority_head.last_block_hash);
+    assert_ne!(majority_head.epoch_id, epoch_before_partition);
+
+    network.heal();
+    test.run_for(SLOT * 30);
+    let agreed = assert_chains_agree(&test);
+    assert!(agreed > majority_head.height, "the chain only agrees up to height {}", agreed);
+}
+
+/// Runs with the same seed produce the same chain, block for block, even though the network
+/// partitions in the middle.
+#[test]
+fn test_replay_from_seed() {
+    init_test_logger();
+    let run = |seed| {
+        let network = SimulatedNetwork::new(
+            seed,
+            time::Duration::milliseconds(20),
+            time::Duration::milliseconds(150),
+        );
+        let (mut test, _clock_guard) = setup(vec![accounts("test", 4)], 2, 5, network.clone());
+        test.run_for(SLOT * 10);
+        network.partition(&[&[0, 1], &[2, 3]]);
+        test.run_for(SLOT * 5);
+        network.heal();
+        test.run_for(SLOT * 15);
+        test.data
+            .iter()
+            .map(|node| {
+                let head = node.client.chain.head().unwrap();
+                (1..=head.height)
+                    .filter_map(|height| node.client.chain.get_block_by_height(height).ok())
+                    .map(|block| (block.header().height(), *block.hash()))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(run(7), run(7));
+}
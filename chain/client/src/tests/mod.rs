@@ -4,6 +4,8 @@ mod chunks_management;
 mod consensus;
 mod cross_shard_tx;
 mod doomslug;
+mod epoch_switches;
 mod maintenance_windows;
 mod process_blocks;
 mod query_client;
+mod tracked_shards;
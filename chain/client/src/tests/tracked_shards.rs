@@ -0,0 +1,65 @@
+//! Tests of the changes of the tracked shards requested while the node is running.
+
+use std::sync::Arc;
+
+use near_chain::test_utils::{MockEpochManager, ValidatorSchedule};
+use near_chain::ChainGenesis;
+use near_epoch_manager::shard_tracker::{ShardTracker, TrackedConfig};
+use near_epoch_manager::EpochManagerAdapter;
+use near_o11y::testonly::init_test_logger;
+use near_store::test_utils::create_test_store;
+
+use crate::test_utils::TestEnv;
+use crate::UpdateTrackedShards;
+
+/// A shard untracked through the admin RPC stays untracked after a restart. Its state gets
+/// deleted, so a node which forgot about the change would track a shard it has no state for.
+#[test]
+fn test_untracked_shard_stays_untracked_after_restart() {
+    init_test_logger();
+    let store = create_test_store();
+    let vs = ValidatorSchedule::new_with_shards(4)
+        .block_producers_per_epoch(vec![vec!["test0".parse().unwrap()]]);
+    let epoch_manager = MockEpochManager::new_with_validators(store.clone(), vs, 5);
+    // The tracker the node creates on start.
+    let new_shard_tracker = || {
+        ShardTracker::new(
+            TrackedConfig::new_empty(),
+            epoch_manager.clone() as Arc<dyn EpochManagerAdapter>,
+        )
+        .with_persisted_tracking_changes(store.clone())
+        .unwrap()
+    };
+    let mut chain_genesis = ChainGenesis::test();
+    chain_genesis.epoch_length = 5;
+    let mut env = TestEnv::builder(chain_genesis)
+        .stores(vec![store.clone()])
+        .mock_epoch_managers(vec![epoch_manager.clone()])
+        .shard_trackers(vec![new_shard_tracker()])
+        .build();
+    for height in 1..3 {
+        env.produce_block(0, height);
+    }
+
+    let changes = env.clients[0]
+        .update_tracked_shards(UpdateTrackedShards {
+            track_shards: vec![],
+            untrack_shards: vec![1],
+            track_accounts: vec![],
+            untrack_accounts: vec![],
+        })
+        .unwrap();
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].shard_id, Some(1));
+    assert!(!changes[0].track);
+    let tracking_changes = env.clients[0].shard_tracker.tracking_changes();
+
+    env.clients[0].shard_tracker = new_shard_tracker();
+    env.restart(0);
+    assert_eq!(env.clients[0].shard_tracker.tracking_changes(), tracking_changes);
+    assert_eq!(env.clients[0].get_tracked_shards_changes().unwrap(), changes);
+    for height in 3..6 {
+        env.produce_block(0, height);
+    }
+    assert_eq!(env.clients[0].get_tracked_shards_changes().unwrap(), changes);
+}
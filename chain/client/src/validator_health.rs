@@ -0,0 +1,245 @@
+//! Self-monitoring of the validator run by this node.
+//!
+//! Compares the blocks and chunks produced by the validator in the current epoch with the
+//! kickout thresholds of the epoch, and checks that the key of the node matches the key staked
+//! for the validator in the current and the next epoch.
+use crate::metrics;
+use crate::Client;
+use near_chain::near_chain_primitives;
+use near_client_primitives::debug::{
+    KickoutRisk, ProductionHealthView, ValidatorHealthView, ValidatorRole,
+};
+use near_client_primitives::types::StatusError;
+use near_primitives::epoch_manager::epoch_info::EpochInfo;
+use near_primitives::types::{AccountId, BlockHeightDelta, NumBlocks, ValidatorInfoIdentifier};
+use tracing::warn;
+
+/// Projects the production ratio of a validator to the end of the epoch.
+///
+/// The number of blocks (or chunks) expected from the validator by the end of the epoch is
+/// extrapolated from the part of the epoch that already passed.
+pub(crate) fn production_health(
+    produced: NumBlocks,
+    expected: NumBlocks,
+    threshold_percent: u8,
+    elapsed: BlockHeightDelta,
+    epoch_length: BlockHeightDelta,
+) -> ProductionHealthView {
+    let projected_expected = if elapsed == 0 || elapsed >= epoch_length {
+        expected
+    } else {
+        expected.max(
+            (expected as u128 * epoch_length as u128 / elapsed as u128)
+                .try_into()
+                .unwrap_or(NumBlocks::MAX),
+        )
+    };
+    let threshold = u64::from(threshold_percent);
+    let kickout_risk = if produced * 100 >= threshold * expected {
+        KickoutRisk::None
+    } else {
+        let best_case_produced = produced + (projected_expected - expected);
+        if best_case_produced * 100 < threshold * projected_expected {
+            KickoutRisk::Certain
+        } else {
+            KickoutRisk::BelowThreshold
+        }
+    };
+    ProductionHealthView { produced, expected, threshold_percent, projected_expected, kickout_risk }
+}
+
+fn role_in_epoch(epoch_info: &EpochInfo, account_id: &AccountId) -> ValidatorRole {
+    let Some(validator_id) = epoch_info.get_validator_id(account_id) else {
+        return ValidatorRole::None;
+    };
+    if epoch_info.block_producers_settlement().contains(validator_id) {
+        ValidatorRole::BlockProducer
+    } else if epoch_info.chunk_producers_settlement().iter().any(|cps| cps.contains(validator_id)) {
+        ValidatorRole::ChunkOnlyProducer
+    } else {
+        ValidatorRole::None
+    }
+}
+
+fn describe_risk(kind: &str, health: &ProductionHealthView) -> Option<String> {
+    let what = match health.kickout_risk {
+        KickoutRisk::None => return None,
+        KickoutRisk::BelowThreshold => "is below",
+        KickoutRisk::Certain => "will stay below",
+    };
+    Some(format!(
+        "{kind} production {} / {} {what} the kickout threshold of {}%",
+        health.produced, health.expected, health.threshold_percent
+    ))
+}
+
+/// Computes the health of the validator run by this node at the current head.
+pub(crate) fn get_validator_health(
+    client: &Client,
+) -> Result<ValidatorHealthView, near_chain_primitives::Error> {
+    let head = client.chain.head()?;
+    let epoch_config = client.epoch_manager.get_epoch_config(&head.epoch_id)?;
+    let epoch_start_height = client.epoch_manager.get_epoch_start_height(&head.last_block_hash)?;
+    let mut health = ValidatorHealthView {
+        validator_name: None,
+        head_height: head.height,
+        epoch_start_height,
+        epoch_length: epoch_config.epoch_length,
+        current_role: ValidatorRole::None,
+        next_role: ValidatorRole::None,
+        block_production: None,
+        chunk_production: None,
+        key_mismatches: vec![],
+        issues: vec![],
+    };
+    let Some(signer) = &client.validator_signer else {
+        return Ok(health);
+    };
+    let account_id = signer.validator_id();
+    let public_key = signer.public_key();
+    health.validator_name = Some(account_id.clone());
+    health.current_role =
+        role_in_epoch(&*client.epoch_manager.get_epoch_info(&head.epoch_id)?, account_id);
+    health.next_role =
+        role_in_epoch(&*client.epoch_manager.get_epoch_info(&head.next_epoch_id)?, account_id);
+
+    let validator_info = client
+        .epoch_manager
+        .get_validator_info(ValidatorInfoIdentifier::BlockHash(head.last_block_hash))?;
+    let elapsed = (head.height + 1).saturating_sub(epoch_start_height);
+    if let Some(info) =
+        validator_info.current_validators.iter().find(|info| &info.account_id == account_id)
+    {
+        if info.public_key != public_key {
+            health.key_mismatches.push(format!(
+                "validator key {public_key} doesn't match the key {} staked in the current epoch",
+                info.public_key
+            ));
+        }
+        if info.num_expected_blocks > 0 {
+            health.block_production = Some(production_health(
+                info.num_produced_blocks,
+                info.num_expected_blocks,
+                epoch_config.block_producer_kickout_threshold,
+                elapsed,
+                epoch_config.epoch_length,
+            ));
+        }
+        if info.num_expected_chunks > 0 {
+            health.chunk_production = Some(production_health(
+                info.num_produced_chunks,
+                info.num_expected_chunks,
+                epoch_config.chunk_producer_kickout_threshold,
+                elapsed,
+                epoch_config.epoch_length,
+            ));
+        }
+    }
+    if let Some(info) =
+        validator_info.next_validators.iter().find(|info| &info.account_id == account_id)
+    {
+        if info.public_key != public_key {
+            health.key_mismatches.push(format!(
+                "validator key {public_key} doesn't match the key {} staked for the next epoch",
+                info.public_key
+            ));
+        }
+    }
+    health.issues = health.key_mismatches.clone();
+    health
+        .issues
+        .extend(health.block_production.as_ref().and_then(|block| describe_risk("block", block)));
+    health
+        .issues
+        .extend(health.chunk_production.as_ref().and_then(|chunk| describe_risk("chunk", chunk)));
+    Ok(health)
+}
+
+/// Fails the health check of a node whose validator key doesn't match the staked one. A
+/// projected kickout doesn't fail it, the node can still recover by producing.
+pub(crate) fn check_validator_health(health: &ValidatorHealthView) -> Result<(), StatusError> {
+    if health.key_mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(StatusError::ValidatorUnhealthy { issues: health.key_mismatches.clone() })
+    }
+}
+
+/// Exports the health of the validator as metrics and warns about every detected issue.
+pub(crate) fn report_validator_health(health: &ValidatorHealthView) {
+    for (kind, production) in
+        [("block", &health.block_production), ("chunk", &health.chunk_production)]
+    {
+        let at_risk = production
+            .as_ref()
+            .map_or(false, |production| production.kickout_risk != KickoutRisk::None);
+        metrics::VALIDATOR_PROJECTED_KICKOUT.with_label_values(&[kind]).set(at_risk as i64);
+    }
+    metrics::VALIDATOR_HEALTH_ISSUES.set(health.issues.len() as i64);
+    for issue in &health.issues {
+        warn!(target: "client", validator = ?health.validator_name, "Validator health: {issue}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_validator_health, production_health};
+    use near_client_primitives::debug::{KickoutRisk, ValidatorHealthView, ValidatorRole};
+    use near_client_primitives::types::StatusError;
+
+    #[test]
+    fn test_production_health() {
+        // Above the threshold.
+        let health = production_health(9, 10, 80, 50, 100);
+        assert_eq!(health.projected_expected, 20);
+        assert_eq!(health.kickout_risk, KickoutRisk::None);
+        // Below the threshold, but 10 more blocks are expected during the rest of the epoch.
+        let health = production_health(7, 10, 80, 50, 100);
+        assert_eq!(health.kickout_risk, KickoutRisk::BelowThreshold);
+        // Even producing all the remaining blocks isn't enough.
+        let health = production_health(1, 10, 80, 50, 100);
+        assert_eq!(health.kickout_risk, KickoutRisk::Certain);
+        // At the end of the epoch nothing can change anymore.
+        let health = production_health(7, 10, 80, 100, 100);
+        assert_eq!(health.projected_expected, 10);
+        assert_eq!(health.kickout_risk, KickoutRisk::Certain);
+        // Nothing expected yet.
+        let health = production_health(0, 0, 80, 0, 100);
+        assert_eq!(health.kickout_risk, KickoutRisk::None);
+    }
+
+    #[test]
+    fn test_check_validator_health() {
+        let mut health = ValidatorHealthView {
+            validator_name: Some("test0".parse().unwrap()),
+            head_height: 60,
+            epoch_start_height: 1,
+            epoch_length: 100,
+            current_role: ValidatorRole::BlockProducer,
+            next_role: ValidatorRole::BlockProducer,
+            block_production: Some(production_health(9, 10, 80, 60, 100)),
+            chunk_production: None,
+            key_mismatches: vec![],
+            issues: vec![],
+        };
+        assert!(check_validator_health(&health).is_ok());
+        assert_eq!(health.kickout_risk(), KickoutRisk::None);
+
+        // A projected kickout is reported, but doesn't fail the health check.
+        health.block_production = Some(production_health(1, 10, 80, 60, 100));
+        health
+            .issues
+            .push("block production 1 / 10 will stay below the kickout threshold of 80%".into());
+        assert!(check_validator_health(&health).is_ok());
+        assert_eq!(health.kickout_risk(), KickoutRisk::Certain);
+
+        let mismatch =
+            "validator key ed25519:1 doesn't match the key ed25519:2 staked for the next epoch";
+        health.key_mismatches.push(mismatch.to_string());
+        health.issues.push(mismatch.to_string());
+        match check_validator_health(&health) {
+            Err(StatusError::ValidatorUnhealthy { issues }) => assert_eq!(issues, vec![mismatch]),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+}
@@ -11,18 +11,19 @@ use crate::{
 };
 use actix::{Actor, Addr, Handler, SyncArbiter, SyncContext};
 use near_async::messaging::CanSend;
-use near_chain::types::{RuntimeAdapter, Tip};
+use near_chain::types::{DryRunShard, RuntimeAdapter, Tip};
 use near_chain::{
     get_epoch_block_producers_view, Chain, ChainGenesis, ChainStoreAccess, DoomslugThresholdMode,
 };
 use near_chain_configs::{ClientConfig, ProtocolConfigView};
 use near_chain_primitives::error::EpochErrorResultToChainError;
 use near_client_primitives::types::{
-    Error, GetBlock, GetBlockError, GetBlockProof, GetBlockProofError, GetBlockProofResponse,
-    GetBlockWithMerkleTree, GetChunkError, GetExecutionOutcome, GetExecutionOutcomeError,
-    GetExecutionOutcomesForBlock, GetGasPrice, GetGasPriceError, GetMaintenanceWindows,
-    GetMaintenanceWindowsError, GetNextLightClientBlockError, GetProtocolConfig,
-    GetProtocolConfigError, GetReceipt, GetReceiptError, GetSplitStorageInfo,
+    DryRunTransaction, DryRunTransactionError, Error, GetBlock, GetBlockError, GetBlockProof,
+    GetBlockProofError, GetBlockProofResponse, GetBlockWithMerkleTree, GetChunkError,
+    GetExecutionOutcome, GetExecutionOutcomeError, GetExecutionOutcomesForBlock, GetGasPrice,
+    GetGasPriceError, GetMaintenanceWindows, GetMaintenanceWindowsError,
+    GetNextLightClientBlockError, GetProtocolConfig, GetProtocolConfigError, GetReceipt,
+    GetReceiptError, GetReceiptTree, GetReceiptTreeError, GetSplitStorageInfo,
     GetSplitStorageInfoError, GetStateChangesError, GetStateChangesWithCauseInBlock,
     GetStateChangesWithCauseInBlockForTrackedShards, GetValidatorInfoError, Query, QueryError,
     TxStatus, TxStatusError,
@@ -48,15 +49,15 @@ use near_primitives::syncing::{
     ShardStateSyncResponseV2,
 };
 use near_primitives::types::{
-    AccountId, BlockHeight, BlockId, BlockReference, EpochReference, Finality, MaybeBlockId,
-    ShardId, SyncCheckpoint, TransactionOrReceiptId, ValidatorInfoIdentifier,
+    AccountId, BlockHeight, BlockId, BlockReference, EpochReference, Finality, Gas, MaybeBlockId,
+    NumBlocks, ShardId, SyncCheckpoint, TransactionOrReceiptId, ValidatorInfoIdentifier,
 };
 use near_primitives::views::validator_stake_view::ValidatorStakeView;
 use near_primitives::views::{
-    BlockView, ChunkView, EpochValidatorInfo, ExecutionOutcomeWithIdView,
+    AccountBalanceChangeView, BlockView, ChunkView, EpochValidatorInfo, ExecutionOutcomeWithIdView,
     FinalExecutionOutcomeView, FinalExecutionOutcomeViewEnum, GasPriceView, LightClientBlockView,
-    MaintenanceWindowsView, QueryRequest, QueryResponse, ReceiptView, SplitStorageInfoView,
-    StateChangesKindsView, StateChangesView,
+    MaintenanceWindowsView, QueryRequest, QueryResponse, ReceiptTreeView, ReceiptView,
+    SplitStorageInfoView, StateChangesKindsView, StateChangesView, TransactionDryRunView,
 };
 use near_store::{DBCol, COLD_HEAD_KEY, FINAL_HEAD_KEY, HEAD_KEY};
 use std::cmp::Ordering;
@@ -73,6 +74,13 @@ const REQUEST_WAIT_TIME: u64 = 1000;
 
 const POISONED_LOCK_ERR: &str = "The lock was poisoned.";
 
+/// Max number of blocks in which a dry run follows the receipts of a transaction.
+const MAX_DRY_RUN_BLOCKS: NumBlocks = 20;
+/// Max gas a dry run burns in total, the gas limit of one chunk.  The made up blocks also
+/// execute the delayed receipts of their shards, so they may burn much more than the
+/// transaction does.
+const MAX_DRY_RUN_GAS: Gas = 1_000_000_000_000_000;
+
 /// Request and response manager across all instances of ViewClientActor.
 pub struct ViewClientRequestManager {
     /// Transaction query that needs to be forwarded to other shards
@@ -153,6 +161,15 @@ impl ViewClientActor {
         })
     }
 
+    /// Block requested by a peer, also served by the simulated network of `crate::test_loop`.
+    pub(crate) fn handle_block_request(&self, hash: CryptoHash) -> Option<Box<Block>> {
+        if let Ok(block) = self.chain.get_block(&hash) {
+            Some(Box::new(block))
+        } else {
+            None
+        }
+    }
+
     fn maybe_block_id_to_block_header(
         &self,
         block_id: MaybeBlockId,
@@ -818,6 +835,9 @@ impl Handler<WithSpanContext<GetStateChangesWithCauseInBlockForTrackedShards>> f
         let mut state_changes_with_cause_split_by_shard_id: HashMap<ShardId, StateChangesView> =
             HashMap::new();
         for state_change_with_cause in state_changes_with_cause_in_block {
+            if !msg.filter.matches(&state_change_with_cause.value) {
+                continue;
+            }
             let account_id = state_change_with_cause.value.affected_account_id();
             let shard_id = match self
                 .epoch_manager
@@ -1022,6 +1042,98 @@ impl Handler<WithSpanContext<GetReceipt>> for ViewClientActor {
     }
 }
 
+impl Handler<WithSpanContext<DryRunTransaction>> for ViewClientActor {
+    type Result = Result<TransactionDryRunView, DryRunTransactionError>;
+
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<DryRunTransaction>,
+        _: &mut Self::Context,
+    ) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+            .with_label_values(&["DryRunTransaction"])
+            .start_timer();
+        let head = self.chain.head().map_err(|_| DryRunTransactionError::NoSyncedBlocks)?;
+        let header = self.chain.get_block_header(&head.last_block_hash)?;
+        let epoch_id = header.epoch_id();
+        // The receipts can only be executed on the shards whose state the node has.
+        let mut shards = HashMap::new();
+        for shard_id in 0..self.epoch_manager.num_shards(epoch_id).into_chain_error()? {
+            let shard_uid =
+                self.epoch_manager.shard_id_to_uid(shard_id, epoch_id).into_chain_error()?;
+            match self.chain.get_chunk_extra(header.hash(), &shard_uid) {
+                Ok(chunk_extra) => {
+                    shards.insert(
+                        shard_id,
+                        DryRunShard {
+                            state_root: *chunk_extra.state_root(),
+                            gas_limit: chunk_extra.gas_limit(),
+                        },
+                    );
+                }
+                Err(near_chain::near_chain_primitives::Error::DBNotFoundErr(_)) => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+        let signer_id = &msg.transaction.transaction.signer_id;
+        let signer_shard_id =
+            self.epoch_manager.account_id_to_shard_id(signer_id, epoch_id).into_chain_error()?;
+        if !shards.contains_key(&signer_shard_id) {
+            return Err(DryRunTransactionError::DoesNotTrackShard(signer_id.clone()));
+        }
+
+        let result = self
+            .runtime
+            .dry_run_transaction(
+                &header,
+                &shards,
+                &msg.transaction,
+                msg.verify_signature,
+                msg.max_blocks.min(MAX_DRY_RUN_BLOCKS),
+                MAX_DRY_RUN_GAS,
+            )?
+            .map_err(DryRunTransactionError::InvalidTransaction)?;
+        Ok(TransactionDryRunView {
+            block_hash: *header.hash(),
+            block_height: header.height(),
+            outcomes: result.outcomes.into_iter().map(Into::into).collect(),
+            state_changes: result.state_changes.into_iter().map(Into::into).collect(),
+            balance_changes: result
+                .balance_changes
+                .into_iter()
+                .map(|change| AccountBalanceChangeView {
+                    account_id: change.account_id,
+                    amount_before: change.amount_before,
+                    amount_after: change.amount_after,
+                    locked_before: change.locked_before,
+                    locked_after: change.locked_after,
+                })
+                .collect(),
+            pending_receipts: result.pending_receipts.into_iter().map(Into::into).collect(),
+        })
+    }
+}
+
+impl Handler<WithSpanContext<GetReceiptTree>> for ViewClientActor {
+    type Result = Result<ReceiptTreeView, GetReceiptTreeError>;
+
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<GetReceiptTree>,
+        _: &mut Self::Context,
+    ) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        let _timer =
+            metrics::VIEW_CLIENT_MESSAGE_TIME.with_label_values(&["GetReceiptTree"]).start_timer();
+        self.chain
+            .get_receipt_tree(&msg.transaction_hash)?
+            .ok_or(GetReceiptTreeError::UnknownTransaction(msg.transaction_hash))
+    }
+}
+
 impl Handler<WithSpanContext<GetBlockProof>> for ViewClientActor {
     type Result = Result<GetBlockProofResponse, GetBlockProofError>;
 
@@ -1164,11 +1276,7 @@ impl Handler<WithSpanContext<BlockRequest>> for ViewClientActor {
         let _timer =
             metrics::VIEW_CLIENT_MESSAGE_TIME.with_label_values(&["BlockRequest"]).start_timer();
         let BlockRequest(hash) = msg;
-        if let Ok(block) = self.chain.get_block(&hash) {
-            Some(Box::new(block))
-        } else {
-            None
-        }
+        self.handle_block_request(hash)
     }
 }
 
@@ -33,6 +33,7 @@ use types::BlockHeaderInfo;
 pub use crate::adapter::EpochManagerAdapter;
 pub use crate::reward_calculator::RewardCalculator;
 pub use crate::reward_calculator::NUM_SECONDS_IN_A_YEAR;
+pub use crate::types::EpochSimulationOverrides;
 pub use crate::types::RngSeed;
 
 mod adapter;
@@ -510,21 +511,24 @@ impl EpochManager {
     }
 
     fn collect_blocks_info(
-        &mut self,
+        &self,
         last_block_info: &BlockInfo,
         last_block_hash: &CryptoHash,
+        overrides: &EpochSimulationOverrides,
     ) -> Result<EpochSummary, EpochError> {
         let epoch_info = self.get_epoch_info(last_block_info.epoch_id())?;
         let next_epoch_id = self.get_next_epoch_id(last_block_hash)?;
         let next_epoch_info = self.get_epoch_info(&next_epoch_id)?;
 
+        let mut aggregator = self.get_epoch_info_aggregator_upto_last(last_block_hash)?;
+        overrides.apply(&epoch_info, &mut aggregator);
         let EpochInfoAggregator {
             block_tracker: block_validator_tracker,
             shard_tracker: chunk_validator_tracker,
             all_proposals,
             version_tracker,
             ..
-        } = self.get_epoch_info_aggregator_upto_last(last_block_hash)?;
+        } = aggregator;
 
         let mut proposals = vec![];
         let mut validator_kickout = HashMap::new();
@@ -626,14 +630,57 @@ impl EpochManager {
         last_block_hash: &CryptoHash,
         rng_seed: RngSeed,
     ) -> Result<(), EpochError> {
-        let epoch_summary = self.collect_blocks_info(block_info, last_block_hash)?;
+        let epoch_summary = self.collect_blocks_info(
+            block_info,
+            last_block_hash,
+            &EpochSimulationOverrides::default(),
+        )?;
+        self.save_epoch_validator_info(store_update, block_info.epoch_id(), &epoch_summary)?;
+        let next_next_epoch_info =
+            self.compute_next_next_epoch_info(block_info, epoch_summary, rng_seed)?;
+        let next_next_epoch_id = EpochId(*last_block_hash);
+        debug!(target: "epoch_manager", "next next epoch height: {}, id: {:?}, protocol version: {} shard layout: {:?} config: {:?}",
+               next_next_epoch_info.epoch_height(),
+               &next_next_epoch_id,
+               next_next_epoch_info.protocol_version(),
+               self.config.for_protocol_version(next_next_epoch_info.protocol_version()).shard_layout,
+            self.config.for_protocol_version(next_next_epoch_info.protocol_version()));
+        // This epoch info is computed for the epoch after next (T+2),
+        // where epoch_id of it is the hash of last block in this epoch (T).
+        self.save_epoch_info(store_update, &next_next_epoch_id, Arc::new(next_next_epoch_info))?;
+        Ok(())
+    }
+
+    /// Computes what the `EpochInfo` of the epoch after next (T + 2) would be if the epoch (T)
+    /// ended with the given block, after applying `overrides` to the proposals and validator
+    /// statistics recorded up to that block.  Nothing is written to the store.
+    ///
+    /// Used to answer "what if" questions about validator selection and rewards.
+    pub fn simulate_epoch_end(
+        &self,
+        last_block_hash: &CryptoHash,
+        rng_seed: RngSeed,
+        overrides: &EpochSimulationOverrides,
+    ) -> Result<EpochInfo, EpochError> {
+        let block_info = self.get_block_info(last_block_hash)?;
+        let epoch_summary = self.collect_blocks_info(&block_info, last_block_hash, overrides)?;
+        self.compute_next_next_epoch_info(&block_info, epoch_summary, rng_seed)
+    }
+
+    /// Computes rewards and selects validators of the epoch after next (T + 2) based on the
+    /// summary of the epoch (T) which ends with the given block.
+    fn compute_next_next_epoch_info(
+        &self,
+        block_info: &BlockInfo,
+        epoch_summary: EpochSummary,
+        rng_seed: RngSeed,
+    ) -> Result<EpochInfo, EpochError> {
         let epoch_info = self.get_epoch_info(block_info.epoch_id())?;
         let epoch_protocol_version = epoch_info.protocol_version();
         let validator_stake =
             epoch_info.validators_iter().map(|r| r.account_and_stake()).collect::<HashMap<_, _>>();
         let next_epoch_id = self.get_next_epoch_id_from_info(block_info)?;
         let next_epoch_info = self.get_epoch_info(&next_epoch_id)?;
-        self.save_epoch_validator_info(store_update, block_info.epoch_id(), &epoch_summary)?;
 
         let EpochSummary {
             all_proposals,
@@ -686,17 +733,7 @@ impl EpochManager {
             }
             Err(err) => return Err(err),
         };
-        let next_next_epoch_id = EpochId(*last_block_hash);
-        debug!(target: "epoch_manager", "next next epoch height: {}, id: {:?}, protocol version: {} shard layout: {:?} config: {:?}",
-               next_next_epoch_info.epoch_height(),
-               &next_next_epoch_id,
-               next_next_epoch_info.protocol_version(),
-               self.config.for_protocol_version(next_next_epoch_info.protocol_version()).shard_layout,
-            self.config.for_protocol_version(next_next_epoch_info.protocol_version()));
-        // This epoch info is computed for the epoch after next (T+2),
-        // where epoch_id of it is the hash of last block in this epoch (T).
-        self.save_epoch_info(store_update, &next_next_epoch_id, Arc::new(next_next_epoch_info))?;
-        Ok(())
+        Ok(next_next_epoch_info)
     }
 
     pub fn record_block_info(
@@ -1,14 +1,17 @@
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 use crate::EpochManagerAdapter;
+use borsh::{BorshDeserialize, BorshSerialize};
 use near_cache::SyncLruCache;
 use near_chain_configs::ClientConfig;
 use near_primitives::errors::EpochError;
 use near_primitives::hash::CryptoHash;
-use near_primitives::shard_layout::account_id_to_shard_id;
-use near_primitives::types::{AccountId, EpochId, ShardId};
+use near_primitives::shard_layout::{account_id_to_shard_id, ShardLayout};
+use near_primitives::types::{AccountId, EpochHeight, EpochId, ShardId};
+use near_store::db::TRACKING_CHANGES_KEY;
+use near_store::{DBCol, Store};
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub enum TrackedConfig {
     Accounts(Vec<AccountId>),
     AllShards,
@@ -27,11 +30,39 @@ impl TrackedConfig {
         } else if !config.tracked_shard_schedule.is_empty() {
             TrackedConfig::Schedule(config.tracked_shard_schedule.clone())
         } else {
-            TrackedConfig::Accounts(config.tracked_accounts.clone())
+            TrackedConfig::Accounts(config.tracked_accounts.get())
+        }
+    }
+}
+
+/// Shard to start or stop tracking while the node is running.
+/// An account stands for the shard it belongs to in the given epoch.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum TrackingTarget {
+    Shard(ShardId),
+    Account(AccountId),
+}
+
+impl TrackingTarget {
+    pub fn shard_id(&self, shard_layout: &ShardLayout) -> ShardId {
+        match self {
+            TrackingTarget::Shard(shard_id) => *shard_id,
+            TrackingTarget::Account(account_id) => account_id_to_shard_id(account_id, shard_layout),
         }
     }
 }
 
+/// Change of the tracked shards requested while the node is running.
+/// Changes are applied on top of `TrackedConfig` in the order they were added.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TrackingChange {
+    pub target: TrackingTarget,
+    /// Whether to start or stop tracking the target.
+    pub track: bool,
+    /// The change affects epochs with this or greater height.
+    pub from_epoch_height: EpochHeight,
+}
+
 // bit mask for which shard to track
 type BitMask = Vec<bool>;
 
@@ -40,34 +71,133 @@ type BitMask = Vec<bool>;
 /// TrackedConfig::AllShards: track all shards
 #[derive(Clone)]
 pub struct ShardTracker {
-    tracked_config: TrackedConfig,
+    /// Shared by all clones of the tracker, because tracked accounts can be
+    /// changed while the node is running.
+    tracked_config: Arc<RwLock<TrackedConfig>>,
     /// Stores shard tracking information by epoch, only useful if TrackedState == Accounts
     tracking_shards_cache: Arc<SyncLruCache<EpochId, BitMask>>,
+    /// Changes of the tracked shards requested while the node is running.
+    /// Kept in memory only, unless the tracker was given a store with
+    /// `with_persisted_tracking_changes`.
+    tracking_changes: Arc<RwLock<Vec<TrackingChange>>>,
+    /// Store the tracking changes are persisted to, so that they survive restarts.
+    store: Option<Store>,
     epoch_manager: Arc<dyn EpochManagerAdapter>,
 }
 
 impl ShardTracker {
     pub fn new(tracked_config: TrackedConfig, epoch_manager: Arc<dyn EpochManagerAdapter>) -> Self {
         ShardTracker {
-            tracked_config,
+            tracked_config: Arc::new(RwLock::new(tracked_config)),
             // 1024 epochs on mainnet is about 512 days which is more than enough,
             // and this is a cache anyway. The data size is pretty small as well,
             // only one bit per shard per epoch.
             tracking_shards_cache: Arc::new(SyncLruCache::new(1024)),
+            tracking_changes: Arc::new(RwLock::new(vec![])),
+            store: None,
             epoch_manager,
         }
     }
 
+    /// Loads the tracking changes persisted in the store, and persists the ones added later.
+    ///
+    /// The state of the shards which stopped being tracked gets deleted, so a node that
+    /// forgot about the changes on restart would track shards it has no state for.
+    pub fn with_persisted_tracking_changes(mut self, store: Store) -> std::io::Result<Self> {
+        let changes: Vec<TrackingChange> =
+            store.get_ser(DBCol::Misc, TRACKING_CHANGES_KEY)?.unwrap_or_default();
+        *self.tracking_changes.write().unwrap() = changes;
+        self.store = Some(store);
+        Ok(self)
+    }
+
     pub fn new_empty(epoch_manager: Arc<dyn EpochManagerAdapter>) -> Self {
         Self::new(TrackedConfig::new_empty(), epoch_manager)
     }
 
-    fn tracks_shard_at_epoch(
+    /// Replaces the list of tracked accounts.
+    /// Only supported if the tracker is configured to track accounts.
+    /// Doesn't make the node sync the state of newly tracked shards, so callers
+    /// have to check that the node already has it.
+    pub fn update_tracked_accounts(&self, accounts: Vec<AccountId>) -> Result<(), String> {
+        let mut tracked_config = self.tracked_config.write().unwrap();
+        match &mut *tracked_config {
+            TrackedConfig::Accounts(tracked_accounts) => {
+                *tracked_accounts = accounts;
+                self.tracking_shards_cache.clear();
+                Ok(())
+            }
+            other => Err(format!("the node tracks shards as {:?}, not by accounts", other)),
+        }
+    }
+
+    /// Adds changes of the tracked shards. The caller is responsible for choosing
+    /// `from_epoch_height` so that the node has time to catch up with the state of
+    /// newly tracked shards.
+    /// Not supported if the tracker is configured to track all shards.
+    pub fn add_tracking_changes(&self, changes: Vec<TrackingChange>) -> Result<(), String> {
+        if self.tracks_all_shards() {
+            return Err("the node tracks all shards".to_string());
+        }
+        let mut tracking_changes = self.tracking_changes.write().unwrap();
+        if let Some(store) = &self.store {
+            let all_changes: Vec<_> =
+                tracking_changes.iter().chain(changes.iter()).cloned().collect();
+            let mut store_update = store.store_update();
+            store_update
+                .set_ser(DBCol::Misc, TRACKING_CHANGES_KEY, &all_changes)
+                .and_then(|()| store_update.commit())
+                .map_err(|err| format!("failed to persist the tracking changes: {}", err))?;
+        }
+        tracking_changes.extend(changes);
+        Ok(())
+    }
+
+    /// Changes of the tracked shards added while the node is running, the oldest first.
+    pub fn tracking_changes(&self) -> Vec<TrackingChange> {
+        self.tracking_changes.read().unwrap().clone()
+    }
+
+    pub fn tracks_shard_at_epoch(
+        &self,
+        shard_id: ShardId,
+        epoch_id: &EpochId,
+    ) -> Result<bool, EpochError> {
+        let tracks_shard = self.tracks_shard_at_epoch_by_config(shard_id, epoch_id)?;
+        self.apply_tracking_changes(tracks_shard, shard_id, epoch_id)
+    }
+
+    /// Applies the changes requested while the node is running on top of whether
+    /// the shard is tracked according to `TrackedConfig`.
+    fn apply_tracking_changes(
+        &self,
+        mut tracks_shard: bool,
+        shard_id: ShardId,
+        epoch_id: &EpochId,
+    ) -> Result<bool, EpochError> {
+        let tracking_changes = self.tracking_changes.read().unwrap();
+        if tracking_changes.is_empty() {
+            return Ok(tracks_shard);
+        }
+        let epoch_height = self.epoch_manager.get_epoch_info(epoch_id)?.epoch_height();
+        let shard_layout = self.epoch_manager.get_shard_layout(epoch_id)?;
+        for change in tracking_changes.iter() {
+            if change.from_epoch_height > epoch_height {
+                continue;
+            }
+            if change.target.shard_id(&shard_layout) == shard_id {
+                tracks_shard = change.track;
+            }
+        }
+        Ok(tracks_shard)
+    }
+
+    fn tracks_shard_at_epoch_by_config(
         &self,
         shard_id: ShardId,
         epoch_id: &EpochId,
     ) -> Result<bool, EpochError> {
-        match &self.tracked_config {
+        match &*self.tracked_config.read().unwrap() {
             TrackedConfig::Accounts(tracked_accounts) => {
                 let shard_layout = self.epoch_manager.get_shard_layout(epoch_id)?;
                 let tracking_mask = self.tracking_shards_cache.get_or_put(epoch_id.clone(), |_| {
@@ -92,6 +222,10 @@ impl ShardTracker {
         }
     }
 
+    fn tracks_all_shards(&self) -> bool {
+        matches!(*self.tracked_config.read().unwrap(), TrackedConfig::AllShards)
+    }
+
     fn tracks_shard(&self, shard_id: ShardId, prev_hash: &CryptoHash) -> Result<bool, EpochError> {
         let epoch_id = self.epoch_manager.get_epoch_id_from_prev_block(prev_hash)?;
         self.tracks_shard_at_epoch(shard_id, &epoch_id)
@@ -132,12 +266,11 @@ impl ShardTracker {
                 return true;
             }
         }
-        match self.tracked_config {
-            TrackedConfig::AllShards => {
-                // Avoid looking up EpochId as a performance optimization.
-                true
-            }
-            _ => self.tracks_shard(shard_id, parent_hash).unwrap_or(false),
+        if self.tracks_all_shards() {
+            // Avoid looking up EpochId as a performance optimization.
+            true
+        } else {
+            self.tracks_shard(shard_id, parent_hash).unwrap_or(false)
         }
     }
 
@@ -169,14 +302,11 @@ impl ShardTracker {
                 return true;
             }
         }
-        match self.tracked_config {
-            TrackedConfig::AllShards => {
-                // Avoid looking up EpochId as a performance optimization.
-                true
-            }
-            _ => {
-                self.tracks_shard_next_epoch_from_prev_block(shard_id, parent_hash).unwrap_or(false)
-            }
+        if self.tracks_all_shards() {
+            // Avoid looking up EpochId as a performance optimization.
+            true
+        } else {
+            self.tracks_shard_next_epoch_from_prev_block(shard_id, parent_hash).unwrap_or(false)
         }
     }
 }
@@ -184,7 +314,7 @@ impl ShardTracker {
 #[cfg(test)]
 mod tests {
     use super::{account_id_to_shard_id, ShardTracker};
-    use crate::shard_tracker::TrackedConfig;
+    use crate::shard_tracker::{TrackedConfig, TrackingChange, TrackingTarget};
     use crate::test_utils::hash_range;
     use crate::{EpochManager, EpochManagerAdapter, EpochManagerHandle, RewardCalculator};
     use near_crypto::{KeyType, PublicKey};
@@ -385,6 +515,98 @@ mod tests {
         assert_eq!(get_all_shards_will_care_about(&tracker, num_shards, &h[7]), subset3);
     }
 
+    #[test]
+    fn test_tracking_changes() {
+        let num_shards = 4;
+        let epoch_manager = Arc::new(get_epoch_manager(PROTOCOL_VERSION, num_shards, false));
+        let tracker =
+            ShardTracker::new(TrackedConfig::Schedule(vec![vec![0, 1]]), epoch_manager.clone());
+
+        let h = hash_range(8);
+        {
+            let mut epoch_manager = epoch_manager.write();
+            for i in 0..8 {
+                record_block(
+                    &mut epoch_manager,
+                    if i > 0 { h[i - 1] } else { CryptoHash::default() },
+                    h[i],
+                    i as u64,
+                    vec![],
+                    PROTOCOL_VERSION,
+                );
+            }
+        }
+
+        let epoch_id = epoch_manager.get_epoch_id_from_prev_block(&h[5]).unwrap();
+        let from_epoch_height = epoch_manager.get_epoch_info(&epoch_id).unwrap().epoch_height();
+        tracker
+            .add_tracking_changes(vec![
+                TrackingChange { target: TrackingTarget::Shard(2), track: true, from_epoch_height },
+                TrackingChange {
+                    target: TrackingTarget::Shard(0),
+                    track: false,
+                    from_epoch_height,
+                },
+            ])
+            .unwrap();
+
+        let subset_before = HashSet::from([0, 1]);
+        let subset_after = HashSet::from([1, 2]);
+        assert_eq!(get_all_shards_care_about(&tracker, num_shards, &h[4]), subset_before);
+        assert_eq!(get_all_shards_will_care_about(&tracker, num_shards, &h[4]), subset_after);
+        assert_eq!(get_all_shards_care_about(&tracker, num_shards, &h[5]), subset_after);
+        assert_eq!(get_all_shards_care_about(&tracker, num_shards, &h[7]), subset_after);
+
+        // A later change overrides an earlier one.
+        tracker
+            .add_tracking_changes(vec![TrackingChange {
+                target: TrackingTarget::Shard(2),
+                track: false,
+                from_epoch_height: from_epoch_height + 1,
+            }])
+            .unwrap();
+        assert_eq!(get_all_shards_care_about(&tracker, num_shards, &h[5]), subset_after);
+        assert_eq!(get_all_shards_care_about(&tracker, num_shards, &h[6]), HashSet::from([1]));
+
+        let all_shards_tracker = ShardTracker::new(TrackedConfig::AllShards, epoch_manager);
+        assert!(all_shards_tracker.add_tracking_changes(vec![]).is_err());
+    }
+
+    #[test]
+    fn test_persisted_tracking_changes() {
+        let epoch_manager = Arc::new(get_epoch_manager(PROTOCOL_VERSION, 4, false));
+        let store = create_test_store();
+        let new_tracker = || {
+            ShardTracker::new(TrackedConfig::new_empty(), epoch_manager.clone())
+                .with_persisted_tracking_changes(store.clone())
+                .unwrap()
+        };
+        let changes = vec![
+            TrackingChange { target: TrackingTarget::Shard(2), track: true, from_epoch_height: 3 },
+            TrackingChange {
+                target: TrackingTarget::Account("test1".parse().unwrap()),
+                track: false,
+                from_epoch_height: 2,
+            },
+        ];
+        let tracker = new_tracker();
+        tracker.add_tracking_changes(changes[..1].to_vec()).unwrap();
+        tracker.add_tracking_changes(changes[1..].to_vec()).unwrap();
+        assert_eq!(tracker.tracking_changes(), changes);
+
+        // A tracker created after a restart starts with the changes, and keeps adding to them.
+        let tracker = new_tracker();
+        assert_eq!(tracker.tracking_changes(), changes);
+        let change =
+            TrackingChange { target: TrackingTarget::Shard(2), track: false, from_epoch_height: 4 };
+        tracker.add_tracking_changes(vec![change.clone()]).unwrap();
+        assert_eq!(new_tracker().tracking_changes().last(), Some(&change));
+
+        // Without a store, the changes are kept in memory only.
+        let tracker = ShardTracker::new(TrackedConfig::new_empty(), epoch_manager.clone());
+        assert!(tracker.tracking_changes().is_empty());
+    }
+
     #[test]
     fn test_track_shards_shard_layout_change() {
         let simple_nightshade_version = SimpleNightshade.protocol_version();
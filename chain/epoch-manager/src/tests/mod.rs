@@ -2014,6 +2014,69 @@ fn test_all_kickout_edge_case() {
     assert_eq!(last_epoch_info.unwrap().validator_kickout(), &HashMap::default());
 }
 
+/// Simulating the end of an epoch without overrides gives the same result as finalizing it,
+/// overrides change the outcome without being persisted.
+#[test]
+fn test_simulate_epoch_end() {
+    let amount_staked = 1_000_000;
+    let validators = vec![
+        ("test1".parse().unwrap(), amount_staked),
+        ("test2".parse().unwrap(), amount_staked),
+        ("test3".parse().unwrap(), amount_staked),
+    ];
+    let epoch_length = 10;
+    let mut epoch_manager = setup_default_epoch_manager(validators, epoch_length, 1, 3, 0, 90, 60);
+    let h = hash_range((epoch_length + 5) as usize);
+    record_block(&mut epoch_manager, CryptoHash::default(), h[0], 0, vec![]);
+    for i in 1..h.len() {
+        record_block(&mut epoch_manager, h[i - 1], h[i], i as u64, vec![]);
+    }
+
+    let last_block_hash =
+        *h.iter().skip(1).find(|x| epoch_manager.get_epoch_info(&EpochId(**x)).is_ok()).unwrap();
+    let simulated_epoch_info = epoch_manager
+        .simulate_epoch_end(&last_block_hash, [0; 32], &EpochSimulationOverrides::default())
+        .unwrap();
+    assert_eq!(
+        &simulated_epoch_info,
+        epoch_manager.get_epoch_info(&EpochId(last_block_hash)).unwrap().as_ref()
+    );
+
+    // A validator which produced none of its blocks gets kicked out.
+    let epoch_id = epoch_manager.get_epoch_id(&h[5]).unwrap();
+    let (account_id, stats) = ["test1", "test2", "test3"]
+        .into_iter()
+        .map(|account_id| account_id.parse::<AccountId>().unwrap())
+        .map(|account_id| {
+            let stats =
+                epoch_manager.get_num_validator_blocks(&epoch_id, &h[5], &account_id).unwrap();
+            (account_id, stats)
+        })
+        .find(|(_, stats)| stats.expected > 0)
+        .unwrap();
+    let overrides = EpochSimulationOverrides {
+        uptime_percent: HashMap::from([(account_id.clone(), 0)]),
+        ..Default::default()
+    };
+    let simulated_epoch_info =
+        epoch_manager.simulate_epoch_end(&h[5], [0; 32], &overrides).unwrap();
+    assert_eq!(
+        simulated_epoch_info.validator_kickout().get(&account_id),
+        Some(&NotEnoughBlocks { produced: 0, expected: stats.expected })
+    );
+
+    // An overridden proposal is taken into account.
+    let overrides = EpochSimulationOverrides {
+        proposals: vec![stake("test4".parse().unwrap(), 2 * amount_staked)],
+        ..Default::default()
+    };
+    let simulated_epoch_info =
+        epoch_manager.simulate_epoch_end(&h[5], [0; 32], &overrides).unwrap();
+    let validator_id = simulated_epoch_info.get_validator_id(&"test4".parse().unwrap()).unwrap();
+    assert_eq!(simulated_epoch_info.validator_stake(*validator_id), 2 * amount_staked);
+    assert!(epoch_manager.get_epoch_info(&EpochId(h[5])).is_err());
+}
+
 fn check_validators(epoch_info: &EpochInfo, expected_validators: &[(&str, u128)]) {
     for (v, (account_id, stake)) in
         epoch_info.validators_iter().zip(expected_validators.into_iter())
@@ -54,6 +54,40 @@ impl BlockHeaderInfo {
     }
 }
 
+/// Changes applied on top of the statistics recorded in an epoch by
+/// [`EpochManager::simulate_epoch_end`].
+#[derive(Default, Clone, Debug)]
+pub struct EpochSimulationOverrides {
+    /// Proposals which replace the latest proposals of the same accounts in the epoch.
+    pub proposals: Vec<ValidatorStake>,
+    /// Percentage of the expected blocks and chunks produced by the given validators.
+    pub uptime_percent: HashMap<AccountId, u8>,
+}
+
+impl EpochSimulationOverrides {
+    pub(crate) fn apply(&self, epoch_info: &EpochInfo, aggregator: &mut EpochInfoAggregator) {
+        for proposal in &self.proposals {
+            aggregator.all_proposals.insert(proposal.account_id().clone(), proposal.clone());
+        }
+        let set_uptime = |stats: &mut ValidatorStats, uptime_percent: u8| {
+            stats.produced = stats.expected * u64::from(uptime_percent.min(100)) / 100;
+        };
+        for (account_id, uptime_percent) in &self.uptime_percent {
+            let Some(validator_id) = epoch_info.get_validator_id(account_id) else {
+                continue;
+            };
+            if let Some(stats) = aggregator.block_tracker.get_mut(validator_id) {
+                set_uptime(stats, *uptime_percent);
+            }
+            for tracker in aggregator.shard_tracker.values_mut() {
+                if let Some(stats) = tracker.get_mut(validator_id) {
+                    set_uptime(stats, *uptime_percent);
+                }
+            }
+        }
+    }
+}
+
 /// Aggregator of information needed for validator computation at the end of the epoch.
 #[derive(Clone, BorshSerialize, BorshDeserialize, Debug, Default)]
 pub struct EpochInfoAggregator {
@@ -8,6 +8,20 @@ pub struct StreamerMessage {
     pub shards: Vec<IndexerShard>,
 }
 
+/// Message of the optimistic streamer, which streams blocks before they are final.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum OptimisticStreamerMessage {
+    /// Block built on top of the previously streamed blocks.  It isn't final yet and may be
+    /// discarded by a later `Rollback`.
+    Block(StreamerMessage),
+    /// The streamed blocks above `to_height` are no longer on the canonical chain and have to
+    /// be discarded.  The following blocks are built on top of the block at `to_height`.
+    Rollback { to_height: types::BlockHeight },
+    /// The streamed block at `height` is final and won't be discarded anymore.
+    Finalized { height: types::BlockHeight },
+}
+
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct IndexerChunkView {
     pub author: types::AccountId,
@@ -32,6 +46,10 @@ pub struct IndexerExecutionOutcomeWithOptionalReceipt {
 pub struct IndexerExecutionOutcomeWithReceipt {
     pub execution_outcome: views::ExecutionOutcomeWithIdView,
     pub receipt: views::ReceiptView,
+    /// NEP-297 events parsed from the logs of the outcome.  Only set if the indexer is
+    /// configured to parse events.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub events: Option<views::ContractEventsView>,
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -40,4 +58,55 @@ pub struct IndexerShard {
     pub chunk: Option<IndexerChunkView>,
     pub receipt_execution_outcomes: Vec<IndexerExecutionOutcomeWithReceipt>,
     pub state_changes: views::StateChangesView,
+    /// Balance changes of the accounts of the shard.  Only set if the indexer is configured to
+    /// account balance changes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub balance_changes: Option<Vec<IndexerAccountBalanceChanges>>,
+}
+
+/// What a balance change is attributed to.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum BalanceChangeCause {
+    /// The signer of the transaction is charged for the gas and the deposits.
+    Transaction { tx_hash: CryptoHash },
+    /// Execution of a receipt, e.g. a transfer, a stake or a storage deposit.
+    Receipt { receipt_id: CryptoHash },
+    /// Refund of unused gas or of the deposits of a failed receipt.
+    GasRefund { receipt_id: CryptoHash },
+    /// Share of the gas burnt by a function call rewarded to the called contract.
+    ContractReward { receipt_id: CryptoHash },
+    /// Validator rewards, returned stakes and slashing at the start of an epoch.
+    EpochReward,
+    /// Any other change, e.g. a migration of the state.
+    Other { cause: views::StateChangeCauseView },
+}
+
+/// Change of the balance of an account made by a single state change.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct IndexerBalanceChange {
+    pub cause: BalanceChangeCause,
+    /// Change of the liquid balance.
+    #[serde(with = "near_primitives::serialize::dec_format")]
+    pub delta_amount: i128,
+    /// Change of the locked balance.
+    #[serde(with = "near_primitives::serialize::dec_format")]
+    pub delta_locked: i128,
+}
+
+/// All the changes of the balance of an account in a block.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct IndexerAccountBalanceChanges {
+    pub account_id: types::AccountId,
+    /// Balance at the end of the previous block.
+    #[serde(with = "near_primitives::serialize::dec_format")]
+    pub amount_before: types::Balance,
+    #[serde(with = "near_primitives::serialize::dec_format")]
+    pub locked_before: types::Balance,
+    /// Balance at the end of the block, read from the state rather than from the state changes.
+    #[serde(with = "near_primitives::serialize::dec_format")]
+    pub amount_after: types::Balance,
+    #[serde(with = "near_primitives::serialize::dec_format")]
+    pub locked_after: types::Balance,
+    pub changes: Vec<IndexerBalanceChange>,
 }
@@ -1,21 +1,27 @@
 #![doc = include_str!("../README.md")]
 
+use std::sync::Arc;
+
 use anyhow::Context;
 use tokio::sync::mpsc;
 
 use near_chain_configs::GenesisValidationMode;
 pub use near_primitives;
-use near_primitives::types::Gas;
+use near_primitives::types::{BlockHeight, Gas};
 pub use nearcore::{get_default_home, init_configs, NearConfig};
 
 pub use near_indexer_primitives::{
-    IndexerChunkView, IndexerExecutionOutcomeWithOptionalReceipt,
-    IndexerExecutionOutcomeWithReceipt, IndexerShard, IndexerTransactionWithOutcome,
-    StreamerMessage,
+    BalanceChangeCause, IndexerAccountBalanceChanges, IndexerBalanceChange, IndexerChunkView,
+    IndexerExecutionOutcomeWithOptionalReceipt, IndexerExecutionOutcomeWithReceipt, IndexerShard,
+    IndexerTransactionWithOutcome, OptimisticStreamerMessage, StreamerMessage,
 };
 
 mod streamer;
 
+pub use near_client::{AccountPattern, StateChangeValueKind};
+pub use streamer::cursor::Acknowledger;
+pub use streamer::filter::{AccountRole, ActionKind, IndexerFilter};
+
 pub const INDEXER: &str = "indexer";
 
 /// Config wrapper to simplify signature and usage of `nearcore::init_configs`
@@ -57,6 +63,10 @@ pub enum SyncModeEnum {
     LatestSynced,
     /// Starts syncing from the block NEAR Indexer was interrupted last time
     FromInterruption,
+    /// Starts syncing from the block after the last one acknowledged with
+    /// [`Acknowledger::commit`], or from the latest block if nothing was acknowledged yet.
+    /// Blocks streamed but not acknowledged before the interruption are streamed again.
+    FromAcknowledged,
     /// Specific block height to start syncing from
     BlockHeight(u64),
 }
@@ -81,6 +91,62 @@ pub struct IndexerConfig {
     pub await_for_node_synced: AwaitForNodeSyncedEnum,
     /// Tells whether to validate the genesis file before starting
     pub validate_genesis: bool,
+    /// Selects the transactions, receipts, outcomes and state changes to stream.  Data that
+    /// doesn't match is neither fetched from the node nor included in `StreamerMessage`s.
+    pub filter: IndexerFilter,
+    /// Whether to parse NEP-297 events from the logs of receipt execution outcomes, see
+    /// [`IndexerExecutionOutcomeWithReceipt::events`].
+    pub parse_events: bool,
+    /// Whether to account the balance changes of every account in every block, see
+    /// [`IndexerShard::balance_changes`].  Requires two additional queries of the state per
+    /// changed account.
+    pub balance_changes: bool,
+    /// Number of `StreamerMessage`s buffered for the consumer.  The streamer stops fetching
+    /// blocks while the buffer is full.
+    pub buffer_size: usize,
+}
+
+/// Configuration of [`replay`].
+#[derive(Debug, Clone)]
+pub struct ReplayConfig {
+    /// Path to `home_dir` of the node whose database is replayed
+    pub home_dir: std::path::PathBuf,
+    /// First block height to stream
+    pub start_height: BlockHeight,
+    /// Last block height to stream (inclusive)
+    pub end_height: BlockHeight,
+    /// Number of blocks built at the same time.  `StreamerMessage`s are always sent in the
+    /// order of block heights.
+    pub parallelism: usize,
+    /// Selects the data included in `StreamerMessage`s, same as for the live streamer.
+    pub filter: IndexerFilter,
+    /// Whether to parse NEP-297 events, same as for the live streamer.
+    pub parse_events: bool,
+    /// Whether to account balance changes, same as for the live streamer.
+    pub balance_changes: bool,
+    /// Number of `StreamerMessage`s buffered for the consumer, same as for the live streamer.
+    pub buffer_size: usize,
+    /// Whether to skip the blocks whose data is missing from the database (e.g. garbage
+    /// collected) instead of terminating the stream.  The skipped heights are logged at the end
+    /// of the replay.
+    pub skip_incomplete_blocks: bool,
+}
+
+/// Streams the blocks of the configured height range directly from the database of a stopped
+/// node, typically an archival one, without starting the node.  The database is opened in
+/// read-only mode; if the node is configured with a cold store, both hot and cold databases
+/// are read.  Heights without a block are skipped.  The stream is closed once the last height
+/// is sent, or early if the data of a block is missing and `skip_incomplete_blocks` isn't set.
+///
+/// Has to be called from within an actix system.
+pub fn replay(config: ReplayConfig) -> Result<mpsc::Receiver<StreamerMessage>, anyhow::Error> {
+    let near_config =
+        nearcore::config::load_config(&config.home_dir, GenesisValidationMode::UnsafeFast)
+            .with_context(|| format!("failed to load config from {}", config.home_dir.display()))?;
+    let context = streamer::ReplayContext::open(&config.home_dir, &near_config)?;
+    let (sender, receiver) = mpsc::channel(config.buffer_size.max(1));
+    actix::spawn(streamer::replay(context, config, sender));
+    Ok(receiver)
 }
 
 /// This is the core component, which handles `nearcore` and internal `streamer`.
@@ -89,6 +155,7 @@ pub struct Indexer {
     near_config: nearcore::NearConfig,
     view_client: actix::Addr<near_client::ViewClientActor>,
     client: actix::Addr<near_client::ClientActor>,
+    db: Arc<streamer::cursor::IndexerDb>,
 }
 
 impl Indexer {
@@ -116,24 +183,62 @@ impl Indexer {
             ",
             indexer_config.home_dir.join("config.json").display()
         );
+        let indexer_db_path = near_store::NodeStorage::opener(
+            &indexer_config.home_dir,
+            near_config.config.archive,
+            &near_config.config.store,
+            None,
+        )
+        .path()
+        .join("indexer");
+        let db = Arc::new(streamer::cursor::IndexerDb::open(&indexer_db_path)?);
         let nearcore::NearNode { client, view_client, .. } =
             nearcore::start_with_config(&indexer_config.home_dir, near_config.clone())
                 .with_context(|| "start_with_config")?;
-        Ok(Self { view_client, client, near_config, indexer_config })
+        Ok(Self { view_client, client, near_config, indexer_config, db })
     }
 
     /// Boots up `near_indexer::streamer`, so it monitors the new blocks with chunks, transactions, receipts, and execution outcomes inside. The returned stream handler should be drained and handled on the user side.
-    pub fn streamer(&self) -> mpsc::Receiver<StreamerMessage> {
-        let (sender, receiver) = mpsc::channel(100);
+    /// Fails if the height to resume from can't be read from the indexer database.
+    pub fn streamer(&self) -> Result<mpsc::Receiver<StreamerMessage>, anyhow::Error> {
+        let start_height = streamer::start_block_height(&self.indexer_config.sync_mode, &self.db)?;
+        let (sender, receiver) = mpsc::channel(self.indexer_config.buffer_size.max(1));
         actix::spawn(streamer::start(
             self.view_client.clone(),
             self.client.clone(),
             self.indexer_config.clone(),
-            self.near_config.config.store.clone(),
-            self.near_config.config.archive,
+            self.db.clone(),
+            start_height,
+            sender,
+        ));
+        Ok(receiver)
+    }
+
+    /// Boots up the optimistic streamer, which streams blocks as soon as they are at the head
+    /// of the chain instead of waiting for them to be final.  Blocks discarded by a fork switch
+    /// are announced with [`OptimisticStreamerMessage::Rollback`] and blocks which became final
+    /// with [`OptimisticStreamerMessage::Finalized`].  Use either this or [`Self::streamer`],
+    /// not both.  Fails if the height to resume from can't be read from the indexer database.
+    pub fn optimistic_streamer(
+        &self,
+    ) -> Result<mpsc::Receiver<OptimisticStreamerMessage>, anyhow::Error> {
+        let start_height = streamer::start_block_height(&self.indexer_config.sync_mode, &self.db)?;
+        let (sender, receiver) = mpsc::channel(self.indexer_config.buffer_size.max(1));
+        actix::spawn(streamer::start_optimistic(
+            self.view_client.clone(),
+            self.client.clone(),
+            self.indexer_config.clone(),
+            self.db.clone(),
+            start_height,
             sender,
         ));
-        receiver
+        Ok(receiver)
+    }
+
+    /// Returns a handle to acknowledge the blocks the consumer has durably processed, used to
+    /// resume with [`SyncModeEnum::FromAcknowledged`].
+    pub fn acknowledger(&self) -> Acknowledger {
+        Acknowledger::new(self.db.clone())
     }
 
     /// Expose neard config
@@ -0,0 +1,377 @@
+//! Accounting of the changes of account balances in a block.
+//!
+//! Every update of an account in the state changes of the block is turned into a change of
+//! the liquid and locked balances, attributed to the transaction, receipt or epoch update
+//! which caused it.  The changes are reported along with the balances of the account at the end
+//! of the previous block and at the end of the block.
+//!
+//! The changes are not checked: that no tokens are created or destroyed is checked by the
+//! `runtime::balance_checker` on every chunk application.
+use std::collections::HashMap;
+
+use near_client::{AccountPattern, StateChangeValueKind, StateChangesFilter};
+use near_indexer_primitives::{
+    BalanceChangeCause, IndexerAccountBalanceChanges, IndexerBalanceChange,
+};
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::{AccountId, Balance, EpochId, ShardId};
+use near_primitives::views::{self, StateChangeCauseView, StateChangeValueView};
+
+use super::errors::FailedToFetchData;
+use super::source::StreamerSource;
+
+/// Attributes state changes to the causes exposed to the indexers.  Receipts are fetched to
+/// tell refunds, which are sent by the system account, from other receipts.
+struct CauseResolver<'a, S: StreamerSource> {
+    source: &'a S,
+    refunds: HashMap<CryptoHash, bool>,
+}
+
+impl<'a, S: StreamerSource> CauseResolver<'a, S> {
+    async fn is_refund(&mut self, receipt_id: CryptoHash) -> Result<bool, FailedToFetchData> {
+        if let Some(is_refund) = self.refunds.get(&receipt_id) {
+            return Ok(*is_refund);
+        }
+        let is_refund = self
+            .source
+            .receipt(receipt_id)
+            .await?
+            .map_or(false, |receipt| receipt.predecessor_id.is_system());
+        self.refunds.insert(receipt_id, is_refund);
+        Ok(is_refund)
+    }
+
+    async fn resolve(
+        &mut self,
+        cause: StateChangeCauseView,
+    ) -> Result<BalanceChangeCause, FailedToFetchData> {
+        Ok(match cause {
+            StateChangeCauseView::TransactionProcessing { tx_hash } => {
+                BalanceChangeCause::Transaction { tx_hash }
+            }
+            StateChangeCauseView::ActionReceiptProcessingStarted { receipt_hash }
+            | StateChangeCauseView::ReceiptProcessing { receipt_hash }
+            | StateChangeCauseView::PostponedReceipt { receipt_hash } => {
+                if self.is_refund(receipt_hash).await? {
+                    BalanceChangeCause::GasRefund { receipt_id: receipt_hash }
+                } else {
+                    BalanceChangeCause::Receipt { receipt_id: receipt_hash }
+                }
+            }
+            StateChangeCauseView::ActionReceiptGasReward { receipt_hash } => {
+                BalanceChangeCause::ContractReward { receipt_id: receipt_hash }
+            }
+            StateChangeCauseView::ValidatorAccountsUpdate => BalanceChangeCause::EpochReward,
+            cause => BalanceChangeCause::Other { cause },
+        })
+    }
+}
+
+fn balances(account: Option<&views::AccountView>) -> (Balance, Balance) {
+    account.map_or((0, 0), |account| (account.amount, account.locked))
+}
+
+/// Computes the balance changes of the accounts matching `accounts` in the block, grouped by
+/// shard.
+pub(crate) async fn fetch_balance_changes<S: StreamerSource>(
+    source: &S,
+    block: &views::BlockView,
+    accounts: Option<Vec<AccountPattern>>,
+) -> Result<HashMap<ShardId, Vec<IndexerAccountBalanceChanges>>, FailedToFetchData> {
+    let filter = StateChangesFilter {
+        accounts,
+        kinds: Some(vec![
+            StateChangeValueKind::AccountUpdate,
+            StateChangeValueKind::AccountDeletion,
+        ]),
+    };
+    let state_changes =
+        source.state_changes(block.header.hash, EpochId(block.header.epoch_id), filter).await?;
+    let mut causes = CauseResolver { source, refunds: HashMap::new() };
+    let mut result = HashMap::new();
+    for (shard_id, state_changes) in state_changes {
+        let mut shard_changes: Vec<IndexerAccountBalanceChanges> = vec![];
+        // Index of the account in `shard_changes` and its balances after the last change.
+        let mut last_balances: HashMap<AccountId, (usize, Balance, Balance)> = HashMap::new();
+        for state_change in state_changes {
+            let (account_id, (amount, locked)) = match &state_change.value {
+                StateChangeValueView::AccountUpdate { account_id, account } => {
+                    (account_id, balances(Some(account)))
+                }
+                StateChangeValueView::AccountDeletion { account_id } => (account_id, (0, 0)),
+                _ => continue,
+            };
+            let (index, last_amount, last_locked) = match last_balances.get(account_id) {
+                Some(last) => *last,
+                None => {
+                    let before = source.account(block.header.prev_hash, account_id).await?;
+                    let (amount_before, locked_before) = balances(before.as_ref());
+                    shard_changes.push(IndexerAccountBalanceChanges {
+                        account_id: account_id.clone(),
+                        amount_before,
+                        locked_before,
+                        amount_after: 0,
+                        locked_after: 0,
+                        changes: vec![],
+                    });
+                    (shard_changes.len() - 1, amount_before, locked_before)
+                }
+            };
+            last_balances.insert(account_id.clone(), (index, amount, locked));
+            if amount == last_amount && locked == last_locked {
+                continue;
+            }
+            shard_changes[index].changes.push(IndexerBalanceChange {
+                cause: causes.resolve(state_change.cause).await?,
+                delta_amount: amount as i128 - last_amount as i128,
+                delta_locked: locked as i128 - last_locked as i128,
+            });
+        }
+        // Only accounts whose balance changed are reported.
+        shard_changes.retain(|account| !account.changes.is_empty());
+        for account in &mut shard_changes {
+            let after = source.account(block.header.hash, &account.account_id).await?;
+            (account.amount_after, account.locked_after) = balances(after.as_ref());
+        }
+        result.insert(shard_id, shard_changes);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use near_chain_configs::ProtocolConfigView;
+    use near_indexer_primitives::IndexerExecutionOutcomeWithOptionalReceipt;
+    use near_primitives::block::Block;
+    use near_primitives::version::PROTOCOL_VERSION;
+
+    use super::*;
+
+    /// Source with the accounts of two blocks, the state changes and the receipts of the second
+    /// one.  The state changes can be fetched only once.
+    #[derive(Default)]
+    struct MockSource {
+        accounts: HashMap<(CryptoHash, AccountId), views::AccountView>,
+        state_changes: Cell<views::StateChangesView>,
+        receipts: HashMap<CryptoHash, views::ReceiptView>,
+    }
+
+    #[async_trait::async_trait(?Send)]
+    impl StreamerSource for MockSource {
+        async fn block(&self, _: CryptoHash) -> Result<views::BlockView, FailedToFetchData> {
+            Err(FailedToFetchData::String("no blocks in the mock source".to_string()))
+        }
+
+        async fn block_chunks(
+            &self,
+            _: &views::BlockView,
+        ) -> Result<Vec<views::ChunkView>, FailedToFetchData> {
+            Ok(vec![])
+        }
+
+        async fn protocol_config(
+            &self,
+            _: CryptoHash,
+        ) -> Result<ProtocolConfigView, FailedToFetchData> {
+            Err(FailedToFetchData::String("no protocol config in the mock source".to_string()))
+        }
+
+        async fn outcomes(
+            &self,
+            _: CryptoHash,
+            _: &dyn Fn(&views::ExecutionOutcomeWithIdView) -> bool,
+        ) -> Result<
+            HashMap<ShardId, Vec<IndexerExecutionOutcomeWithOptionalReceipt>>,
+            FailedToFetchData,
+        > {
+            Ok(HashMap::new())
+        }
+
+        async fn state_changes(
+            &self,
+            _: CryptoHash,
+            _: EpochId,
+            _: StateChangesFilter,
+        ) -> Result<HashMap<ShardId, views::StateChangesView>, FailedToFetchData> {
+            Ok(HashMap::from([(0, self.state_changes.take())]))
+        }
+
+        async fn receipt(
+            &self,
+            receipt_id: CryptoHash,
+        ) -> Result<Option<views::ReceiptView>, FailedToFetchData> {
+            Ok(self.receipts.get(&receipt_id).cloned())
+        }
+
+        async fn account(
+            &self,
+            block_hash: CryptoHash,
+            account_id: &AccountId,
+        ) -> Result<Option<views::AccountView>, FailedToFetchData> {
+            Ok(self.accounts.get(&(block_hash, account_id.clone())).cloned())
+        }
+    }
+
+    fn account_view(amount: Balance, locked: Balance) -> views::AccountView {
+        views::AccountView {
+            amount,
+            locked,
+            code_hash: CryptoHash::default(),
+            storage_usage: 100,
+            storage_paid_at: 0,
+        }
+    }
+
+    impl MockSource {
+        fn set_account(&mut self, block_hash: CryptoHash, account_id: &str, amount: Balance) {
+            self.set_staked_account(block_hash, account_id, amount, 0);
+        }
+
+        fn set_staked_account(
+            &mut self,
+            block_hash: CryptoHash,
+            account_id: &str,
+            amount: Balance,
+            locked: Balance,
+        ) {
+            self.accounts
+                .insert((block_hash, account_id.parse().unwrap()), account_view(amount, locked));
+        }
+
+        fn update(&mut self, cause: StateChangeCauseView, account_id: &str, amount: Balance) {
+            self.update_staked(cause, account_id, amount, 0);
+        }
+
+        fn update_staked(
+            &mut self,
+            cause: StateChangeCauseView,
+            account_id: &str,
+            amount: Balance,
+            locked: Balance,
+        ) {
+            self.state_changes.get_mut().push(views::StateChangeWithCauseView {
+                cause,
+                value: StateChangeValueView::AccountUpdate {
+                    account_id: account_id.parse().unwrap(),
+                    account: account_view(amount, locked),
+                },
+            });
+        }
+
+        fn add_receipt(&mut self, receipt_id: CryptoHash, predecessor_id: &str) {
+            self.receipts.insert(
+                receipt_id,
+                views::ReceiptView {
+                    predecessor_id: predecessor_id.parse().unwrap(),
+                    receiver_id: "alice".parse().unwrap(),
+                    receipt_id,
+                    receipt: views::ReceiptEnumView::Data {
+                        data_id: CryptoHash::default(),
+                        data: None,
+                    },
+                },
+            );
+        }
+    }
+
+    #[test]
+    fn test_fetch_balance_changes() {
+        let block = Block::genesis(
+            PROTOCOL_VERSION,
+            vec![],
+            chrono::Utc::now(),
+            1,
+            1000,
+            1000,
+            CryptoHash::default(),
+        );
+        let block = views::BlockView::from_author_block("test0".parse().unwrap(), block);
+        let (prev_hash, hash) = (block.header.prev_hash, block.header.hash);
+        let tx_hash = CryptoHash::hash_bytes(b"tx");
+        let transfer_id = CryptoHash::hash_bytes(b"transfer");
+        let refund_id = CryptoHash::hash_bytes(b"refund");
+        let call_id = CryptoHash::hash_bytes(b"call");
+
+        let mut source = MockSource::default();
+        source.add_receipt(transfer_id, "alice");
+        source.add_receipt(refund_id, "system");
+        // Alice transfers 10 to Bob, gets a refund of unused gas and a reward for a call of her
+        // contract.
+        source.set_account(prev_hash, "alice", 100);
+        source.update(StateChangeCauseView::TransactionProcessing { tx_hash }, "alice", 88);
+        source.update(
+            StateChangeCauseView::ReceiptProcessing { receipt_hash: refund_id },
+            "alice",
+            89,
+        );
+        source.update(
+            StateChangeCauseView::ActionReceiptGasReward { receipt_hash: call_id },
+            "alice",
+            92,
+        );
+        source.set_account(hash, "alice", 92);
+        source.set_account(prev_hash, "bob", 50);
+        source.update(
+            StateChangeCauseView::ReceiptProcessing { receipt_hash: transfer_id },
+            "bob",
+            60,
+        );
+        source.set_account(hash, "bob", 60);
+        // The reward of the validator is added to its stake.
+        source.set_staked_account(prev_hash, "validator", 10, 500);
+        source.update_staked(StateChangeCauseView::ValidatorAccountsUpdate, "validator", 10, 505);
+        source.set_staked_account(hash, "validator", 10, 505);
+        // Carol only changes her storage usage, her balance doesn't change.
+        source.set_account(prev_hash, "carol", 30);
+        source.update(StateChangeCauseView::TransactionProcessing { tx_hash }, "carol", 30);
+        source.set_account(hash, "carol", 30);
+        // The balances after the block are read from the state, not from the state changes.
+        source.set_account(prev_hash, "dave", 40);
+        source.update(
+            StateChangeCauseView::ReceiptProcessing { receipt_hash: transfer_id },
+            "dave",
+            45,
+        );
+        source.set_account(hash, "dave", 47);
+
+        let mut changes =
+            futures::executor::block_on(fetch_balance_changes(&source, &block, None)).unwrap();
+        let changes = changes.remove(&0).unwrap();
+        let summary: Vec<_> = changes
+            .iter()
+            .map(|account| {
+                let deltas: Vec<_> = account
+                    .changes
+                    .iter()
+                    .map(|change| (change.cause.clone(), change.delta_amount, change.delta_locked))
+                    .collect();
+                (account.account_id.as_str(), deltas)
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    "alice",
+                    vec![
+                        (BalanceChangeCause::Transaction { tx_hash }, -12, 0),
+                        (BalanceChangeCause::GasRefund { receipt_id: refund_id }, 1, 0),
+                        (BalanceChangeCause::ContractReward { receipt_id: call_id }, 3, 0),
+                    ]
+                ),
+                ("bob", vec![(BalanceChangeCause::Receipt { receipt_id: transfer_id }, 10, 0)]),
+                ("validator", vec![(BalanceChangeCause::EpochReward, 0, 5)]),
+                ("dave", vec![(BalanceChangeCause::Receipt { receipt_id: transfer_id }, 5, 0)]),
+            ]
+        );
+        let alice = &changes[0];
+        assert_eq!(
+            (alice.amount_before, alice.locked_before, alice.amount_after, alice.locked_after),
+            (100, 0, 92, 0)
+        );
+        let dave = &changes[3];
+        assert_eq!((dave.amount_before, dave.amount_after), (40, 47));
+    }
+}
@@ -0,0 +1,139 @@
+//! Database of the indexer keeping track of the streamed and the acknowledged blocks.
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context;
+use rocksdb::{Options, DB};
+
+use near_primitives::types::BlockHeight;
+
+use super::metrics;
+
+/// Column of the acknowledged cursor, the streamed height lives in the default column.
+const ACKNOWLEDGED_COLUMN: &str = "acknowledged";
+const STREAMED_HEIGHT_KEY: &[u8] = b"last_synced_block_height";
+const ACKNOWLEDGED_HEIGHT_KEY: &[u8] = b"acknowledged_block_height";
+
+fn parse_height(value: Option<Vec<u8>>) -> anyhow::Result<Option<BlockHeight>> {
+    value
+        .map(|value| Ok(String::from_utf8(value)?.parse::<BlockHeight>()?))
+        .transpose()
+        .context("invalid block height in the indexer database")
+}
+
+pub(crate) struct IndexerDb {
+    db: DB,
+}
+
+impl IndexerDb {
+    pub(crate) fn open(path: &Path) -> anyhow::Result<Self> {
+        let mut options = Options::default();
+        options.create_if_missing(true);
+        options.create_missing_column_families(true);
+        let db = DB::open_cf(&options, path, [ACKNOWLEDGED_COLUMN])
+            .with_context(|| format!("failed to open the indexer database {}", path.display()))?;
+        let db = Self { db };
+        if let Some(height) = db.acknowledged_height()? {
+            metrics::ACKNOWLEDGED_BLOCK_HEIGHT.set(height as i64);
+        }
+        Ok(db)
+    }
+
+    /// Height of the last block sent to the consumer.
+    pub(crate) fn streamed_height(&self) -> anyhow::Result<Option<BlockHeight>> {
+        parse_height(self.db.get(STREAMED_HEIGHT_KEY)?)
+    }
+
+    pub(crate) fn set_streamed_height(&self, height: BlockHeight) -> anyhow::Result<()> {
+        Ok(self.db.put(STREAMED_HEIGHT_KEY, height.to_string())?)
+    }
+
+    /// Height of the last block acknowledged by the consumer.
+    pub(crate) fn acknowledged_height(&self) -> anyhow::Result<Option<BlockHeight>> {
+        let column = self.acknowledged_column();
+        parse_height(self.db.get_cf(column, ACKNOWLEDGED_HEIGHT_KEY)?)
+    }
+
+    fn set_acknowledged_height(&self, height: BlockHeight) -> anyhow::Result<()> {
+        let column = self.acknowledged_column();
+        Ok(self.db.put_cf(column, ACKNOWLEDGED_HEIGHT_KEY, height.to_string())?)
+    }
+
+    fn acknowledged_column(&self) -> &rocksdb::ColumnFamily {
+        self.db.cf_handle(ACKNOWLEDGED_COLUMN).expect("the column is created when opening")
+    }
+}
+
+/// Handle with which the consumer acknowledges the blocks it has durably processed.
+///
+/// With [`crate::SyncModeEnum::FromAcknowledged`] the indexer resumes after the last
+/// acknowledged block, so every block is delivered at least once even if the consumer crashes.
+#[derive(Clone)]
+pub struct Acknowledger {
+    db: Arc<IndexerDb>,
+    lock: Arc<Mutex<()>>,
+}
+
+impl Acknowledger {
+    pub(crate) fn new(db: Arc<IndexerDb>) -> Self {
+        Self { db, lock: Default::default() }
+    }
+
+    /// Commits that all the blocks up to `height` (inclusive) are processed.  The cursor only
+    /// moves forward, committing a lower height than the last committed one is a no-op.
+    pub fn commit(&self, height: BlockHeight) -> anyhow::Result<()> {
+        let _guard = self.lock.lock().unwrap();
+        if self.db.acknowledged_height()?.map_or(false, |acknowledged| acknowledged >= height) {
+            return Ok(());
+        }
+        self.db.set_acknowledged_height(height)?;
+        metrics::ACKNOWLEDGED_BLOCK_HEIGHT.set(height as i64);
+        Ok(())
+    }
+
+    /// Returns the height of the last acknowledged block.
+    pub fn acknowledged_height(&self) -> anyhow::Result<Option<BlockHeight>> {
+        self.db.acknowledged_height()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::streamer::start_block_height;
+    use crate::SyncModeEnum;
+
+    #[test]
+    fn test_cursors() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Arc::new(IndexerDb::open(dir.path()).unwrap());
+        assert_eq!(start_block_height(&SyncModeEnum::FromAcknowledged, &db).unwrap(), None);
+        assert_eq!(start_block_height(&SyncModeEnum::FromInterruption, &db).unwrap(), None);
+
+        let acknowledger = Acknowledger::new(db.clone());
+        acknowledger.commit(10).unwrap();
+        // The cursor only moves forward.
+        acknowledger.commit(7).unwrap();
+        assert_eq!(acknowledger.acknowledged_height().unwrap(), Some(10));
+        acknowledger.commit(12).unwrap();
+        assert_eq!(acknowledger.acknowledged_height().unwrap(), Some(12));
+        db.set_streamed_height(15).unwrap();
+        assert_eq!(start_block_height(&SyncModeEnum::FromAcknowledged, &db).unwrap(), Some(13));
+        assert_eq!(start_block_height(&SyncModeEnum::FromInterruption, &db).unwrap(), Some(15));
+
+        // The cursors survive reopening the database.
+        drop(acknowledger);
+        drop(db);
+        let db = Arc::new(IndexerDb::open(dir.path()).unwrap());
+        assert_eq!(start_block_height(&SyncModeEnum::FromAcknowledged, &db).unwrap(), Some(13));
+        assert_eq!(start_block_height(&SyncModeEnum::FromInterruption, &db).unwrap(), Some(15));
+        assert_eq!(start_block_height(&SyncModeEnum::LatestSynced, &db).unwrap(), None);
+        assert_eq!(start_block_height(&SyncModeEnum::BlockHeight(5), &db).unwrap(), Some(5));
+        Acknowledger::new(db.clone()).commit(11).unwrap();
+        assert_eq!(db.acknowledged_height().unwrap(), Some(12));
+
+        // A corrupted cursor is an error rather than a panic.
+        db.db.put(STREAMED_HEIGHT_KEY, "not a height").unwrap();
+        assert!(start_block_height(&SyncModeEnum::FromInterruption, &db).is_err());
+    }
+}
@@ -23,17 +23,16 @@ pub(crate) async fn fetch_status(
         .map_err(|err| FailedToFetchData::String(err.to_string()))
 }
 
-/// Fetches the status to retrieve `latest_block_height` to determine if we need to fetch
+/// Fetches the latest block with the given finality to determine if we need to fetch
 /// entire block or we already fetched this block.
 pub(crate) async fn fetch_latest_block(
     client: &Addr<near_client::ViewClientActor>,
+    finality: near_primitives::types::Finality,
 ) -> Result<views::BlockView, FailedToFetchData> {
     client
         .send(
-            near_client::GetBlock(near_primitives::types::BlockReference::Finality(
-                near_primitives::types::Finality::Final,
-            ))
-            .with_span_context(),
+            near_client::GetBlock(near_primitives::types::BlockReference::Finality(finality))
+                .with_span_context(),
         )
         .await?
         .map_err(|err| FailedToFetchData::String(err.to_string()))
@@ -71,21 +70,28 @@ pub(crate) async fn fetch_state_changes(
     client: &Addr<near_client::ViewClientActor>,
     block_hash: CryptoHash,
     epoch_id: near_primitives::types::EpochId,
+    filter: near_client::StateChangesFilter,
 ) -> Result<HashMap<near_primitives::types::ShardId, views::StateChangesView>, FailedToFetchData> {
     client
         .send(
-            near_client::GetStateChangesWithCauseInBlockForTrackedShards { block_hash, epoch_id }
-                .with_span_context(),
+            near_client::GetStateChangesWithCauseInBlockForTrackedShards {
+                block_hash,
+                epoch_id,
+                filter,
+            }
+            .with_span_context(),
         )
         .await?
         .map_err(|err| FailedToFetchData::String(err.to_string()))
 }
 
-/// Fetch all ExecutionOutcomeWithId for current block
+/// Fetch ExecutionOutcomeWithId for current block, skipping the ones rejected by `keep`
+/// before their receipts are fetched.
 /// Returns a HashMap where the key is shard id IndexerExecutionOutcomeWithOptionalReceipt
 pub(crate) async fn fetch_outcomes(
     client: &Addr<near_client::ViewClientActor>,
     block_hash: CryptoHash,
+    keep: impl Fn(&views::ExecutionOutcomeWithIdView) -> bool,
 ) -> Result<
     HashMap<near_primitives::types::ShardId, Vec<IndexerExecutionOutcomeWithOptionalReceipt>>,
     FailedToFetchData,
@@ -101,7 +107,7 @@ pub(crate) async fn fetch_outcomes(
     > = HashMap::new();
     for (shard_id, shard_outcomes) in outcomes {
         let mut outcomes_with_receipts: Vec<IndexerExecutionOutcomeWithOptionalReceipt> = vec![];
-        for outcome in shard_outcomes {
+        for outcome in shard_outcomes.into_iter().filter(|outcome| keep(outcome)) {
             let receipt = match fetch_receipt_by_id(&client, outcome.id).await {
                 Ok(res) => res,
                 Err(e) => {
@@ -125,7 +131,7 @@ pub(crate) async fn fetch_outcomes(
     Ok(shard_execution_outcomes_with_receipts)
 }
 
-async fn fetch_receipt_by_id(
+pub(crate) async fn fetch_receipt_by_id(
     client: &Addr<near_client::ViewClientActor>,
     receipt_id: CryptoHash,
 ) -> Result<Option<views::ReceiptView>, FailedToFetchData> {
@@ -180,3 +186,32 @@ pub(crate) async fn fetch_protocol_config(
         .await?
         .map_err(|err| FailedToFetchData::String(err.to_string()))?)
 }
+
+/// Fetches the account at the end of the given block, returns `None` if the account doesn't
+/// exist.
+pub(crate) async fn fetch_account(
+    client: &Addr<near_client::ViewClientActor>,
+    block_hash: CryptoHash,
+    account_id: &types::AccountId,
+) -> Result<Option<views::AccountView>, FailedToFetchData> {
+    let response = client
+        .send(
+            near_client::Query::new(
+                types::BlockReference::from(types::BlockId::Hash(block_hash)),
+                views::QueryRequest::ViewAccount { account_id: account_id.clone() },
+            )
+            .with_span_context(),
+        )
+        .await?;
+    match response {
+        Ok(views::QueryResponse {
+            kind: views::QueryResponseKind::ViewAccount(account), ..
+        }) => Ok(Some(account)),
+        Ok(response) => Err(FailedToFetchData::String(format!(
+            "unexpected response to the account query: {:?}",
+            response.kind
+        ))),
+        Err(near_client::QueryError::UnknownAccount { .. }) => Ok(None),
+        Err(err) => Err(FailedToFetchData::String(err.to_string())),
+    }
+}
@@ -0,0 +1,234 @@
+//! Filters applied by the streamer before the data is fetched and the views are built.
+use near_client::{AccountPattern, StateChangeValueKind, StateChangesFilter};
+use near_primitives::types::AccountId;
+use near_primitives::views::{ActionView, ReceiptEnumView, ReceiptView, SignedTransactionView};
+
+/// Role in which an account appears in a transaction or a receipt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountRole {
+    /// Receiver of a transaction or a receipt.
+    Receiver,
+    /// Signer of a transaction or predecessor of a receipt.
+    Predecessor,
+}
+
+/// Kind of an action of a transaction or a receipt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionKind {
+    CreateAccount,
+    DeployContract,
+    FunctionCall,
+    Transfer,
+    Stake,
+    AddKey,
+    DeleteKey,
+    DeleteAccount,
+    Delegate,
+}
+
+impl From<&ActionView> for ActionKind {
+    fn from(action: &ActionView) -> Self {
+        match action {
+            ActionView::CreateAccount => Self::CreateAccount,
+            ActionView::DeployContract { .. } => Self::DeployContract,
+            ActionView::FunctionCall { .. } => Self::FunctionCall,
+            ActionView::Transfer { .. } => Self::Transfer,
+            ActionView::Stake { .. } => Self::Stake,
+            ActionView::AddKey { .. } => Self::AddKey,
+            ActionView::DeleteKey { .. } => Self::DeleteKey,
+            ActionView::DeleteAccount { .. } => Self::DeleteAccount,
+            ActionView::Delegate { .. } => Self::Delegate,
+        }
+    }
+}
+
+/// Selects the data included in `StreamerMessage`s.  Every field set to `None` doesn't
+/// restrict the data, so the default filter streams everything.
+///
+/// Transactions, receipts and their execution outcomes are kept if one of the `accounts`
+/// appears in one of the `roles` and one of their actions matches `action_kinds` and
+/// `method_names`.  `method_names` only restricts function call actions.  Data receipts
+/// are dropped as soon as actions are restricted.  State changes are kept if the affected
+/// account matches `accounts` and the kind of the change matches `state_change_kinds`.
+/// Blocks and chunk headers are always streamed.
+#[derive(Debug, Clone, Default)]
+pub struct IndexerFilter {
+    pub accounts: Option<Vec<AccountPattern>>,
+    pub roles: Option<Vec<AccountRole>>,
+    pub method_names: Option<Vec<String>>,
+    pub action_kinds: Option<Vec<ActionKind>>,
+    pub state_change_kinds: Option<Vec<StateChangeValueKind>>,
+}
+
+impl IndexerFilter {
+    fn matches_account(&self, account_id: &AccountId) -> bool {
+        self.accounts
+            .as_ref()
+            .map_or(true, |accounts| accounts.iter().any(|pattern| pattern.matches(account_id)))
+    }
+
+    fn has_role(&self, role: AccountRole) -> bool {
+        self.roles.as_ref().map_or(true, |roles| roles.contains(&role))
+    }
+
+    fn matches_parties(&self, receiver_id: &AccountId, predecessor_id: &AccountId) -> bool {
+        (self.has_role(AccountRole::Receiver) && self.matches_account(receiver_id))
+            || (self.has_role(AccountRole::Predecessor) && self.matches_account(predecessor_id))
+    }
+
+    fn restricts_actions(&self) -> bool {
+        self.action_kinds.is_some() || self.method_names.is_some()
+    }
+
+    fn matches_action(&self, action: &ActionView) -> bool {
+        if let Some(action_kinds) = &self.action_kinds {
+            if !action_kinds.contains(&ActionKind::from(action)) {
+                return false;
+            }
+        }
+        match (action, &self.method_names) {
+            (ActionView::FunctionCall { method_name, .. }, Some(method_names)) => {
+                method_names.contains(method_name)
+            }
+            _ => true,
+        }
+    }
+
+    fn matches_actions(&self, actions: &[ActionView]) -> bool {
+        !self.restricts_actions() || actions.iter().any(|action| self.matches_action(action))
+    }
+
+    pub(crate) fn matches_transaction(&self, transaction: &SignedTransactionView) -> bool {
+        self.matches_parties(&transaction.receiver_id, &transaction.signer_id)
+            && self.matches_actions(&transaction.actions)
+    }
+
+    pub(crate) fn matches_receipt(&self, receipt: &ReceiptView) -> bool {
+        if !self.matches_parties(&receipt.receiver_id, &receipt.predecessor_id) {
+            return false;
+        }
+        match &receipt.receipt {
+            ReceiptEnumView::Action { actions, .. } => self.matches_actions(actions),
+            ReceiptEnumView::Data { .. } => !self.restricts_actions(),
+        }
+    }
+
+    /// Whether the receipt executed by the account may match the filter.  Used to skip
+    /// fetching receipts which can't match.
+    pub(crate) fn may_match_executor(&self, executor_id: &AccountId) -> bool {
+        // The executor is the receiver of the receipt, the predecessor is only known once the
+        // receipt is fetched.
+        self.has_role(AccountRole::Predecessor)
+            || (self.has_role(AccountRole::Receiver) && self.matches_account(executor_id))
+    }
+
+    /// Returns the filter of the state changes to request from the view client, or `None` if
+    /// no state changes need to be requested at all.
+    pub(crate) fn state_changes_filter(&self) -> Option<StateChangesFilter> {
+        if self.state_change_kinds.as_ref().map_or(false, |kinds| kinds.is_empty())
+            || self.accounts.as_ref().map_or(false, |accounts| accounts.is_empty())
+        {
+            return None;
+        }
+        Some(StateChangesFilter {
+            accounts: self.accounts.clone(),
+            kinds: self.state_change_kinds.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_crypto::{KeyType, PublicKey, Signature};
+    use near_primitives::hash::CryptoHash;
+
+    fn transaction(
+        signer_id: &str,
+        receiver_id: &str,
+        actions: Vec<ActionView>,
+    ) -> SignedTransactionView {
+        SignedTransactionView {
+            signer_id: signer_id.parse().unwrap(),
+            public_key: PublicKey::empty(KeyType::ED25519),
+            nonce: 0,
+            receiver_id: receiver_id.parse().unwrap(),
+            actions,
+            signature: Signature::default(),
+            hash: CryptoHash::default(),
+        }
+    }
+
+    fn function_call(method_name: &str) -> ActionView {
+        ActionView::FunctionCall {
+            method_name: method_name.to_string(),
+            args: vec![].into(),
+            gas: 0,
+            deposit: 0,
+        }
+    }
+
+    #[test]
+    fn test_account_patterns() {
+        let matches = |pattern: &str, account_id: &str| {
+            pattern.parse::<AccountPattern>().unwrap().matches(&account_id.parse().unwrap())
+        };
+        assert!(matches("aurora", "aurora"));
+        assert!(!matches("aurora", "relay.aurora"));
+        assert!(matches("*.aurora", "relay.aurora"));
+        assert!(!matches("*.aurora", "aurora"));
+        assert!(matches("*", "near"));
+        assert!(matches("token.*", "token.sweat"));
+        assert!(matches("a*b*c", "a.b.c"));
+        assert!(!matches("a*b*b", "a.b"));
+    }
+
+    #[test]
+    fn test_default_filter_matches_everything() {
+        let filter = IndexerFilter::default();
+        assert!(filter.matches_transaction(&transaction("alice.near", "bob.near", vec![])));
+        assert!(filter.may_match_executor(&"bob.near".parse().unwrap()));
+        let state_changes_filter = filter.state_changes_filter().unwrap();
+        assert!(state_changes_filter.accounts.is_none() && state_changes_filter.kinds.is_none());
+    }
+
+    #[test]
+    fn test_roles_and_actions() {
+        let filter = IndexerFilter {
+            accounts: Some(vec!["*.sweat".parse().unwrap()]),
+            roles: Some(vec![AccountRole::Receiver]),
+            method_names: Some(vec!["ft_transfer".to_string()]),
+            ..Default::default()
+        };
+        let call = |signer_id, receiver_id, method_name| {
+            filter.matches_transaction(&transaction(
+                signer_id,
+                receiver_id,
+                vec![function_call(method_name)],
+            ))
+        };
+        assert!(call("alice.near", "token.sweat", "ft_transfer"));
+        assert!(!call("alice.near", "token.sweat", "ft_balance_of"));
+        assert!(!call("token.sweat", "alice.near", "ft_transfer"));
+        // Method names don't restrict other actions.
+        assert!(filter.matches_transaction(&transaction(
+            "alice.near",
+            "token.sweat",
+            vec![ActionView::Transfer { deposit: 1 }]
+        )));
+        assert!(filter.may_match_executor(&"token.sweat".parse().unwrap()));
+        assert!(!filter.may_match_executor(&"alice.near".parse().unwrap()));
+
+        let filter = IndexerFilter {
+            action_kinds: Some(vec![ActionKind::FunctionCall]),
+            state_change_kinds: Some(vec![]),
+            ..filter
+        };
+        assert!(!filter.matches_transaction(&transaction(
+            "alice.near",
+            "token.sweat",
+            vec![ActionView::Transfer { deposit: 1 }]
+        )));
+        assert!(filter.state_changes_filter().is_none());
+    }
+}
@@ -43,3 +43,35 @@ pub(crate) static BUILD_STREAMER_MESSAGE_TIME: Lazy<Histogram> = Lazy::new(|| {
     )
     .unwrap()
 });
+
+pub(crate) static ACKNOWLEDGED_BLOCK_HEIGHT: Lazy<IntGauge> = Lazy::new(|| {
+    try_create_int_gauge(
+        "near_indexer_acknowledged_block_height",
+        "Height of the last block acknowledged by the consumer",
+    )
+    .unwrap()
+});
+
+pub(crate) static BUFFERED_STREAMER_MESSAGES: Lazy<IntGauge> = Lazy::new(|| {
+    try_create_int_gauge(
+        "near_indexer_buffered_streamer_messages",
+        "Number of Streamer messages sent but not yet received by the consumer",
+    )
+    .unwrap()
+});
+
+pub(crate) static STREAMER_MESSAGE_SEND_WAIT_TIME: Lazy<Histogram> = Lazy::new(|| {
+    try_create_histogram(
+        "near_indexer_streamer_message_send_wait_time",
+        "Time the streamer waits for a free slot in the buffer to send a message",
+    )
+    .unwrap()
+});
+
+pub(crate) static NUM_OPTIMISTIC_ROLLBACKS: Lazy<IntCounter> = Lazy::new(|| {
+    try_create_int_counter(
+        "near_indexer_num_optimistic_rollbacks",
+        "Number of rollbacks of non-final blocks sent by the optimistic streamer",
+    )
+    .unwrap()
+});
@@ -1,13 +1,13 @@
+use std::sync::Arc;
 use std::time::Duration;
 
 use actix::Addr;
 use async_recursion::async_recursion;
 use near_indexer_primitives::types::ProtocolVersion;
 use node_runtime::config::RuntimeConfig;
-use rocksdb::DB;
 use tokio::sync::mpsc;
 use tokio::time;
-use tracing::{debug, info};
+use tracing::{debug, error, info};
 
 use near_indexer_primitives::{
     IndexerChunkView, IndexerExecutionOutcomeWithOptionalReceipt,
@@ -15,24 +15,34 @@ use near_indexer_primitives::{
     StreamerMessage,
 };
 use near_primitives::hash::CryptoHash;
+use near_primitives::types::{BlockHeight, Finality};
 use near_primitives::views;
 
-use crate::{AwaitForNodeSyncedEnum, IndexerConfig};
+use crate::{AwaitForNodeSyncedEnum, IndexerConfig, IndexerFilter};
+
+use self::balance_changes::fetch_balance_changes;
+use self::cursor::IndexerDb;
 
 use self::errors::FailedToFetchData;
-use self::fetchers::{
-    fetch_block, fetch_block_by_height, fetch_block_chunks, fetch_latest_block, fetch_outcomes,
-    fetch_state_changes, fetch_status,
-};
+use self::fetchers::{fetch_block_by_height, fetch_latest_block, fetch_status};
+use self::source::StreamerSource;
 use self::utils::convert_transactions_sir_into_local_receipts;
-use crate::streamer::fetchers::fetch_protocol_config;
 use crate::INDEXER;
 
+mod balance_changes;
+pub(crate) mod cursor;
 mod errors;
 mod fetchers;
+pub(crate) mod filter;
 mod metrics;
+mod optimistic;
+mod replay;
+mod source;
 mod utils;
 
+pub(crate) use self::optimistic::start_optimistic;
+pub(crate) use self::replay::{replay, ReplayContext};
+
 const INTERVAL: Duration = Duration::from_millis(500);
 
 /// Blocks #47317863 and #47317864 with restored receipts.
@@ -65,18 +75,28 @@ fn test_problematic_blocks_hash() {
     );
 }
 
+/// Selects the data included in `StreamerMessage`s, both by the streamer and by the replay.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MessageOptions {
+    pub(crate) filter: IndexerFilter,
+    pub(crate) parse_events: bool,
+    pub(crate) balance_changes: bool,
+}
+
 /// This function supposed to return the entire `StreamerMessage`.
 /// It fetches the block and all related parts (chunks, outcomes, state changes etc.)
-/// and returns everything together in one struct
-#[async_recursion]
-async fn build_streamer_message(
-    client: &Addr<near_client::ViewClientActor>,
+/// matching the filter and returns everything together in one struct
+#[async_recursion(?Send)]
+async fn build_streamer_message<S: StreamerSource>(
+    source: &S,
     block: views::BlockView,
+    options: &MessageOptions,
 ) -> Result<StreamerMessage, FailedToFetchData> {
     let _timer = metrics::BUILD_STREAMER_MESSAGE_TIME.start_timer();
-    let chunks = fetch_block_chunks(&client, &block).await?;
+    let filter = &options.filter;
+    let chunks = source.block_chunks(&block).await?;
 
-    let protocol_config_view = fetch_protocol_config(&client, block.header.hash).await?;
+    let protocol_config_view = source.protocol_config(block.header.hash).await?;
     let num_shards = protocol_config_view.num_block_producer_seats_per_shard.len()
         as near_primitives::types::NumShards;
 
@@ -84,19 +104,38 @@ async fn build_streamer_message(
         near_primitives::runtime::config_store::RuntimeConfigStore::new(None);
     let runtime_config = runtime_config_store.get_config(protocol_config_view.protocol_version);
 
-    let mut shards_outcomes = fetch_outcomes(&client, block.header.hash).await?;
-    let mut state_changes = fetch_state_changes(
-        &client,
-        block.header.hash,
-        near_primitives::types::EpochId(block.header.epoch_id),
-    )
-    .await?;
+    // Outcomes of transactions are always fetched, since they are matched with the
+    // transactions of the chunks by position.  Receipts of the other outcomes are only
+    // fetched if they can match the filter.
+    let transaction_hashes: std::collections::HashSet<CryptoHash> = chunks
+        .iter()
+        .flat_map(|chunk| chunk.transactions.iter().map(|transaction| transaction.hash))
+        .collect();
+    let mut shards_outcomes = source
+        .outcomes(block.header.hash, &|outcome| {
+            transaction_hashes.contains(&outcome.id)
+                || filter.may_match_executor(&outcome.outcome.executor_id)
+        })
+        .await?;
+    let mut state_changes = match filter.state_changes_filter() {
+        Some(state_changes_filter) => {
+            source
+                .state_changes(
+                    block.header.hash,
+                    near_primitives::types::EpochId(block.header.epoch_id),
+                    state_changes_filter,
+                )
+                .await?
+        }
+        None => Default::default(),
+    };
     let mut indexer_shards = (0..num_shards)
         .map(|shard_id| IndexerShard {
             shard_id,
             chunk: None,
             receipt_execution_outcomes: vec![],
             state_changes: state_changes.remove(&shard_id).unwrap_or_default(),
+            balance_changes: None,
         })
         .collect::<Vec<_>>();
 
@@ -113,7 +152,7 @@ async fn build_streamer_message(
         // Take execution outcomes for receipts from the vec and keep only the ones for transactions
         let mut receipt_outcomes = outcomes.split_off(transactions.len());
 
-        let indexer_transactions = transactions
+        let mut indexer_transactions = transactions
             .into_iter()
             .zip(outcomes.into_iter())
             .map(|(transaction, outcome)| {
@@ -126,7 +165,7 @@ async fn build_streamer_message(
             .collect::<Vec<IndexerTransactionWithOutcome>>();
 
         let chunk_local_receipts = convert_transactions_sir_into_local_receipts(
-            &client,
+            source,
             &runtime_config,
             protocol_config_view.protocol_version,
             indexer_transactions
@@ -165,7 +204,7 @@ async fn build_streamer_message(
                     if prev_block_tried > 1000 {
                         panic!("Failed to find local receipt in 1000 prev blocks");
                     }
-                    let prev_block = match fetch_block(&client, prev_block_hash).await {
+                    let prev_block = match source.block(prev_block_hash).await {
                         Ok(block) => block,
                         Err(err) => panic!("Unable to get previous block: {:?}", err),
                     };
@@ -173,7 +212,7 @@ async fn build_streamer_message(
                     prev_block_hash = prev_block.header.prev_hash;
 
                     if let Some(receipt) = find_local_receipt_by_id_in_block(
-                        &client,
+                        source,
                         &runtime_config,
                         protocol_config_view.protocol_version,
                         prev_block,
@@ -187,8 +226,11 @@ async fn build_streamer_message(
                     prev_block_tried += 1;
                 }
             };
-            receipt_execution_outcomes
-                .push(IndexerExecutionOutcomeWithReceipt { execution_outcome, receipt: receipt });
+            receipt_execution_outcomes.push(IndexerExecutionOutcomeWithReceipt {
+                execution_outcome,
+                receipt,
+                events: None,
+            });
         }
 
         // Blocks #47317863 and #47317864
@@ -216,6 +258,10 @@ async fn build_streamer_message(
 
         chunk_receipts.extend(chunk_non_local_receipts);
 
+        indexer_transactions.retain(|tx| filter.matches_transaction(&tx.transaction));
+        chunk_receipts.retain(|receipt| filter.matches_receipt(receipt));
+        receipt_execution_outcomes.retain(|outcome| filter.matches_receipt(&outcome.receipt));
+
         indexer_shards[shard_id].receipt_execution_outcomes = receipt_execution_outcomes;
         // Put the chunk into corresponding indexer shard
         indexer_shards[shard_id].chunk = Some(IndexerChunkView {
@@ -231,28 +277,68 @@ async fn build_streamer_message(
     // That might happen before the fix https://github.com/near/nearcore/pull/4228
     for (shard_id, outcomes) in shards_outcomes {
         indexer_shards[shard_id as usize].receipt_execution_outcomes.extend(
-            outcomes.into_iter().map(|outcome| IndexerExecutionOutcomeWithReceipt {
-                execution_outcome: outcome.execution_outcome,
-                receipt: outcome.receipt.expect("`receipt` must be present at this moment"),
-            }),
+            outcomes
+                .into_iter()
+                .map(|outcome| IndexerExecutionOutcomeWithReceipt {
+                    execution_outcome: outcome.execution_outcome,
+                    receipt: outcome.receipt.expect("`receipt` must be present at this moment"),
+                    events: None,
+                })
+                .filter(|outcome| filter.matches_receipt(&outcome.receipt)),
         )
     }
 
-    Ok(StreamerMessage { block, shards: indexer_shards })
+    let mut streamer_message = StreamerMessage { block, shards: indexer_shards };
+    if options.parse_events {
+        parse_events(&mut streamer_message);
+    }
+    if options.balance_changes {
+        let mut balance_changes =
+            fetch_balance_changes(source, &streamer_message.block, filter.accounts.clone()).await?;
+        for shard in &mut streamer_message.shards {
+            shard.balance_changes =
+                Some(balance_changes.remove(&shard.shard_id).unwrap_or_default());
+        }
+    }
+    Ok(streamer_message)
+}
+
+/// Attaches the NEP-297 events logged by every receipt execution outcome of the message.
+fn parse_events(streamer_message: &mut StreamerMessage) {
+    for shard in &mut streamer_message.shards {
+        for outcome in &mut shard.receipt_execution_outcomes {
+            outcome.events =
+                Some(views::ContractEventsView::from_logs(&outcome.execution_outcome.outcome.logs));
+        }
+    }
+}
+
+/// Sends the message to the consumer and records how long the streamer waited for a free slot
+/// in the buffer.  Returns `false` if the consumer is gone.
+async fn send_message<T>(blocks_sink: &mpsc::Sender<T>, message: T) -> bool {
+    let timer = metrics::STREAMER_MESSAGE_SEND_WAIT_TIME.start_timer();
+    let sent = blocks_sink.send(message).await.is_ok();
+    timer.observe_duration();
+    metrics::BUFFERED_STREAMER_MESSAGES
+        .set((blocks_sink.max_capacity() - blocks_sink.capacity()) as i64);
+    if sent {
+        metrics::NUM_STREAMER_MESSAGES_SENT.inc();
+    }
+    sent
 }
 
 /// Function that tries to find specific local receipt by it's ID and returns it
 /// otherwise returns None
-async fn find_local_receipt_by_id_in_block(
-    client: &Addr<near_client::ViewClientActor>,
+async fn find_local_receipt_by_id_in_block<S: StreamerSource>(
+    source: &S,
     runtime_config: &RuntimeConfig,
     protocol_version: ProtocolVersion,
     block: views::BlockView,
     receipt_id: near_primitives::hash::CryptoHash,
 ) -> Result<Option<views::ReceiptView>, FailedToFetchData> {
-    let chunks = fetch_block_chunks(&client, &block).await?;
+    let chunks = source.block_chunks(&block).await?;
 
-    let mut shards_outcomes = fetch_outcomes(&client, block.header.hash).await?;
+    let mut shards_outcomes = source.outcomes(block.header.hash, &|_| true).await?;
 
     for chunk in chunks {
         let views::ChunkView { header, transactions, .. } = chunk;
@@ -274,7 +360,7 @@ async fn find_local_receipt_by_id_in_block(
         {
             let indexer_transaction = IndexerTransactionWithOutcome { transaction, outcome };
             let local_receipts = convert_transactions_sir_into_local_receipts(
-                &client,
+                source,
                 &runtime_config,
                 protocol_version,
                 vec![&indexer_transaction],
@@ -288,65 +374,97 @@ async fn find_local_receipt_by_id_in_block(
     Ok(None)
 }
 
+/// Returns whether the streamer may stream, i.e. whether the node is synced or the indexer is
+/// configured to stream while syncing.
+async fn node_is_ready(
+    client: &Addr<near_client::ClientActor>,
+    await_for_node_synced: &AwaitForNodeSyncedEnum,
+) -> bool {
+    match await_for_node_synced {
+        AwaitForNodeSyncedEnum::WaitForFullSync => {
+            let status = fetch_status(client).await;
+            if let Ok(status) = status {
+                if status.sync_info.syncing {
+                    return false;
+                }
+            }
+        }
+        AwaitForNodeSyncedEnum::StreamWhileSyncing => {}
+    };
+    true
+}
+
+/// Height of the first block to stream after the start, according to the sync mode, or `None`
+/// to start from the latest final block once the node is ready.
+pub(crate) fn start_block_height(
+    sync_mode: &crate::SyncModeEnum,
+    db: &IndexerDb,
+) -> anyhow::Result<Option<BlockHeight>> {
+    Ok(match sync_mode {
+        crate::SyncModeEnum::FromInterruption => db.streamed_height()?,
+        crate::SyncModeEnum::FromAcknowledged => {
+            db.acknowledged_height()?.map(|acknowledged_height| acknowledged_height + 1)
+        }
+        crate::SyncModeEnum::LatestSynced => None,
+        crate::SyncModeEnum::BlockHeight(height) => Some(*height),
+    })
+}
+
+/// Records the block as streamed.  Returns `false` if the database can't be written, in which
+/// case the streamer has to stop, since it would stream the block again after a restart.
+fn set_streamed_height(db: &IndexerDb, block_height: BlockHeight) -> bool {
+    if let Err(err) = db.set_streamed_height(block_height) {
+        error!(
+            target: INDEXER,
+            "Unable to record block #{} as streamed, terminating: {:#}",
+            block_height,
+            err
+        );
+        return false;
+    }
+    true
+}
+
 /// Function that starts Streamer's busy loop. Every half a seconds it fetches the status
 /// compares to already fetched block height and in case it differs fetches new block of given height.
 ///
 /// We have to pass `client: Addr<near_client::ClientActor>` and `view_client: Addr<near_client::ViewClientActor>`.
+/// `start_height` is the result of [`start_block_height`].
 pub(crate) async fn start(
     view_client: Addr<near_client::ViewClientActor>,
     client: Addr<near_client::ClientActor>,
     indexer_config: IndexerConfig,
-    store_config: near_store::StoreConfig,
-    archive: bool,
+    db: Arc<IndexerDb>,
+    start_height: Option<BlockHeight>,
     blocks_sink: mpsc::Sender<StreamerMessage>,
 ) {
     info!(target: INDEXER, "Starting Streamer...");
-    let indexer_db_path =
-        near_store::NodeStorage::opener(&indexer_config.home_dir, archive, &store_config, None)
-            .path()
-            .join("indexer");
-
-    // TODO: implement proper error handling
-    let db = DB::open_default(indexer_db_path).unwrap();
-    let mut last_synced_block_height: Option<near_primitives::types::BlockHeight> = None;
+    let mut last_synced_block_height: Option<BlockHeight> = None;
+    let options = MessageOptions {
+        filter: indexer_config.filter.clone(),
+        parse_events: indexer_config.parse_events,
+        balance_changes: indexer_config.balance_changes,
+    };
 
     'main: loop {
         time::sleep(INTERVAL).await;
-        match indexer_config.await_for_node_synced {
-            AwaitForNodeSyncedEnum::WaitForFullSync => {
-                let status = fetch_status(&client).await;
-                if let Ok(status) = status {
-                    if status.sync_info.syncing {
-                        continue;
-                    }
-                }
-            }
-            AwaitForNodeSyncedEnum::StreamWhileSyncing => {}
-        };
+        if !node_is_ready(&client, &indexer_config.await_for_node_synced).await {
+            continue;
+        }
 
-        let block = if let Ok(block) = fetch_latest_block(&view_client).await {
+        let block = if let Ok(block) = fetch_latest_block(&view_client, Finality::Final).await {
             block
         } else {
             continue;
         };
 
         let latest_block_height = block.header.height;
-        let start_syncing_block_height = if let Some(last_synced_block_height) =
-            last_synced_block_height
-        {
-            last_synced_block_height + 1
-        } else {
-            match indexer_config.sync_mode {
-                crate::SyncModeEnum::FromInterruption => {
-                    match db.get(b"last_synced_block_height").unwrap() {
-                        Some(value) => String::from_utf8(value).unwrap().parse::<u64>().unwrap(),
-                        None => latest_block_height,
-                    }
-                }
-                crate::SyncModeEnum::LatestSynced => latest_block_height,
-                crate::SyncModeEnum::BlockHeight(height) => height,
-            }
-        };
+        let start_syncing_block_height =
+            if let Some(last_synced_block_height) = last_synced_block_height {
+                last_synced_block_height + 1
+            } else {
+                start_height.unwrap_or(latest_block_height)
+            };
 
         debug!(
             target: INDEXER,
@@ -359,19 +477,17 @@ pub(crate) async fn start(
         for block_height in start_syncing_block_height..=latest_block_height {
             metrics::CURRENT_BLOCK_HEIGHT.set(block_height as i64);
             if let Ok(block) = fetch_block_by_height(&view_client, block_height).await {
-                let response = build_streamer_message(&view_client, block).await;
+                let response = build_streamer_message(&view_client, block, &options).await;
 
                 match response {
                     Ok(streamer_message) => {
                         debug!(target: INDEXER, "{:#?}", &streamer_message);
-                        if blocks_sink.send(streamer_message).await.is_err() {
+                        if !send_message(&blocks_sink, streamer_message).await {
                             info!(
                                 target: INDEXER,
                                 "Unable to send StreamerMessage to listener, listener doesn't listen. terminating..."
                             );
                             break 'main;
-                        } else {
-                            metrics::NUM_STREAMER_MESSAGES_SENT.inc();
                         }
                     }
                     Err(err) => {
@@ -383,7 +499,9 @@ pub(crate) async fn start(
                     }
                 }
             }
-            db.put(b"last_synced_block_height", &block_height.to_string()).unwrap();
+            if !set_streamed_height(&db, block_height) {
+                break 'main;
+            }
             last_synced_block_height = Some(block_height);
         }
     }
@@ -0,0 +1,547 @@
+//! Streaming of blocks before they are final.
+//!
+//! Every iteration the streamer fetches the final block and the head of the chain.  Blocks
+//! below the final block are streamed by height, as by the final streamer.  The blocks
+//! between the final block and the head are found by walking back from the head until a
+//! block which was already streamed; if the head switched to another fork, the streamed
+//! blocks which are no longer on the canonical chain are rolled back first.
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use actix::Addr;
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+use tokio::time;
+use tracing::{debug, info, warn};
+
+use near_indexer_primitives::{OptimisticStreamerMessage, StreamerMessage};
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::{BlockHeight, Finality};
+use near_primitives::views;
+
+use super::cursor::IndexerDb;
+use super::errors::FailedToFetchData;
+use super::fetchers::{fetch_block, fetch_block_by_height, fetch_latest_block};
+use super::{
+    build_streamer_message, metrics, node_is_ready, send_message, set_streamed_height,
+    MessageOptions, INTERVAL,
+};
+use crate::{IndexerConfig, INDEXER};
+
+/// Streamed blocks of the canonical chain.
+#[derive(Default)]
+struct StreamedChain {
+    /// Last streamed block which is final.
+    last_final: Option<(BlockHeight, CryptoHash)>,
+    /// Streamed blocks which aren't final yet, in the order of heights.
+    pending: VecDeque<(BlockHeight, CryptoHash)>,
+}
+
+impl StreamedChain {
+    fn contains(&self, block_hash: &CryptoHash) -> bool {
+        self.last_final.map_or(false, |(_, hash)| &hash == block_hash)
+            || self.pending.iter().any(|(_, hash)| hash == block_hash)
+    }
+
+    /// Height of the last streamed block.
+    fn tip_height(&self) -> Option<BlockHeight> {
+        self.pending.back().or(self.last_final.as_ref()).map(|(height, _)| *height)
+    }
+
+    /// Discards the pending blocks above `height`, returns whether any block was discarded.
+    fn rollback(&mut self, height: BlockHeight) -> bool {
+        let len = self.pending.len();
+        self.pending.retain(|(pending_height, _)| *pending_height <= height);
+        self.pending.len() != len
+    }
+}
+
+/// Where the optimistic streamer reads the blocks from.
+#[async_trait(?Send)]
+trait OptimisticSource {
+    async fn block(&self, block_hash: CryptoHash) -> Result<views::BlockView, FailedToFetchData>;
+
+    async fn block_by_height(
+        &self,
+        block_height: BlockHeight,
+    ) -> Result<views::BlockView, FailedToFetchData>;
+
+    async fn streamer_message(
+        &self,
+        block: views::BlockView,
+        options: &MessageOptions,
+    ) -> Result<StreamerMessage, FailedToFetchData>;
+}
+
+#[async_trait(?Send)]
+impl OptimisticSource for Addr<near_client::ViewClientActor> {
+    async fn block(&self, block_hash: CryptoHash) -> Result<views::BlockView, FailedToFetchData> {
+        fetch_block(self, block_hash).await
+    }
+
+    async fn block_by_height(
+        &self,
+        block_height: BlockHeight,
+    ) -> Result<views::BlockView, FailedToFetchData> {
+        fetch_block_by_height(self, block_height).await
+    }
+
+    async fn streamer_message(
+        &self,
+        block: views::BlockView,
+        options: &MessageOptions,
+    ) -> Result<StreamerMessage, FailedToFetchData> {
+        build_streamer_message(self, block, options).await
+    }
+}
+
+/// Returns the blocks from the head down to the first one which was already streamed (or is
+/// below `start_height`), and the height of that block.  The blocks are in the order of
+/// heights.
+async fn fetch_new_branch<S: OptimisticSource>(
+    source: &S,
+    chain: &StreamedChain,
+    head: views::BlockView,
+    start_height: BlockHeight,
+) -> Result<(Vec<views::BlockView>, Option<BlockHeight>), FailedToFetchData> {
+    let mut branch = vec![];
+    let mut block = head;
+    let fork_height = loop {
+        if chain.contains(&block.header.hash) {
+            break Some(block.header.height);
+        }
+        if block.header.height < start_height {
+            break if chain.tip_height().is_some() { Some(block.header.height) } else { None };
+        }
+        let prev_hash = block.header.prev_hash;
+        branch.push(block);
+        if prev_hash == CryptoHash::default() {
+            break None;
+        }
+        block = source.block(prev_hash).await?;
+    };
+    branch.reverse();
+    Ok((branch, fork_height))
+}
+
+/// Why the streamer stops.
+enum Stop {
+    /// The consumer dropped the receiver.
+    Disconnected,
+    /// The streamed height can't be recorded in the indexer database.
+    Database,
+}
+
+/// Builds and sends the message of the block.  Returns `Ok(false)` if the message can't be
+/// built, in which case the block isn't streamed and has to be retried later.
+async fn stream_block<S: OptimisticSource>(
+    source: &S,
+    block: views::BlockView,
+    options: &MessageOptions,
+    blocks_sink: &mpsc::Sender<OptimisticStreamerMessage>,
+) -> Result<bool, Stop> {
+    metrics::CURRENT_BLOCK_HEIGHT.set(block.header.height as i64);
+    let block_height = block.header.height;
+    match source.streamer_message(block, options).await {
+        Ok(streamer_message) => {
+            debug!(target: INDEXER, "{:#?}", &streamer_message);
+            send(blocks_sink, OptimisticStreamerMessage::Block(streamer_message)).await?;
+            Ok(true)
+        }
+        Err(err) => {
+            warn!(
+                target: INDEXER,
+                "Missing data of block #{}, retrying it later: {:?}", block_height, err
+            );
+            Ok(false)
+        }
+    }
+}
+
+async fn send(
+    blocks_sink: &mpsc::Sender<OptimisticStreamerMessage>,
+    message: OptimisticStreamerMessage,
+) -> Result<(), Stop> {
+    if send_message(blocks_sink, message).await {
+        Ok(())
+    } else {
+        Err(Stop::Disconnected)
+    }
+}
+
+/// State of the optimistic streamer between its iterations.
+struct OptimisticStreamer<'a> {
+    options: MessageOptions,
+    db: &'a IndexerDb,
+    chain: StreamedChain,
+    /// Height of the first block to stream, the final block of the first iteration if unset.
+    start_height: Option<BlockHeight>,
+}
+
+impl OptimisticStreamer<'_> {
+    /// Streams the blocks up to the head and finalizes the streamed blocks up to the final
+    /// block.
+    async fn step<S: OptimisticSource>(
+        &mut self,
+        source: &S,
+        final_block: views::BlockView,
+        head: views::BlockView,
+        blocks_sink: &mpsc::Sender<OptimisticStreamerMessage>,
+    ) -> Result<(), Stop> {
+        let final_height = final_block.header.height;
+        let start_height = *self.start_height.get_or_insert(final_height);
+        metrics::LATEST_BLOCK_HEIGHT.set(head.header.height as i64);
+
+        // Catch up with the final block by height if no block is pending.
+        if self.chain.pending.is_empty() {
+            let next_height = self.chain.tip_height().map_or(start_height, |height| height + 1);
+            if next_height <= final_height {
+                metrics::START_BLOCK_HEIGHT.set(next_height as i64);
+            }
+            for block_height in next_height..=final_height {
+                let block = match source.block_by_height(block_height).await {
+                    Ok(block) => block,
+                    Err(_) => continue,
+                };
+                let block_hash = block.header.hash;
+                if !stream_block(source, block, &self.options, blocks_sink).await? {
+                    return Ok(());
+                }
+                send(blocks_sink, OptimisticStreamerMessage::Finalized { height: block_height })
+                    .await?;
+                self.set_streamed_height(block_height)?;
+                self.chain.last_final = Some((block_height, block_hash));
+            }
+        }
+
+        let (branch, fork_height) =
+            match fetch_new_branch(source, &self.chain, head, start_height).await {
+                Ok(branch) => branch,
+                Err(err) => {
+                    warn!(target: INDEXER, "Unable to fetch the head of the chain: {:?}", err);
+                    return Ok(());
+                }
+            };
+        if let Some(fork_height) = fork_height {
+            if self.chain.rollback(fork_height) {
+                info!(target: INDEXER, "Head switched forks, rolling back to #{}", fork_height);
+                metrics::NUM_OPTIMISTIC_ROLLBACKS.inc();
+                send(blocks_sink, OptimisticStreamerMessage::Rollback { to_height: fork_height })
+                    .await?;
+            }
+        }
+        for block in branch {
+            let block_id = (block.header.height, block.header.hash);
+            // The blocks above one which can't be streamed are fetched again in the next
+            // iteration.
+            if !stream_block(source, block, &self.options, blocks_sink).await? {
+                break;
+            }
+            self.chain.pending.push_back(block_id);
+        }
+
+        while let Some(&(block_height, block_hash)) = self.chain.pending.front() {
+            if block_height > final_height {
+                break;
+            }
+            send(blocks_sink, OptimisticStreamerMessage::Finalized { height: block_height })
+                .await?;
+            self.set_streamed_height(block_height)?;
+            self.chain.pending.pop_front();
+            self.chain.last_final = Some((block_height, block_hash));
+        }
+        Ok(())
+    }
+
+    fn set_streamed_height(&self, block_height: BlockHeight) -> Result<(), Stop> {
+        if set_streamed_height(self.db, block_height) {
+            Ok(())
+        } else {
+            Err(Stop::Database)
+        }
+    }
+}
+
+/// Function that starts the busy loop of the optimistic streamer.  Blocks are streamed as soon
+/// as they are at the head of the chain, followed by `Rollback` messages when the head switches
+/// to another fork and `Finalized` messages when the streamed blocks become final.
+///
+/// Only final blocks are recorded as streamed in the indexer database, so the streamer resumes
+/// from the last final block after an interruption.  `start_height` is the result of
+/// [`super::start_block_height`].
+pub(crate) async fn start_optimistic(
+    view_client: Addr<near_client::ViewClientActor>,
+    client: Addr<near_client::ClientActor>,
+    indexer_config: IndexerConfig,
+    db: Arc<IndexerDb>,
+    start_height: Option<BlockHeight>,
+    blocks_sink: mpsc::Sender<OptimisticStreamerMessage>,
+) {
+    info!(target: INDEXER, "Starting optimistic Streamer...");
+    let mut streamer = OptimisticStreamer {
+        options: MessageOptions {
+            filter: indexer_config.filter.clone(),
+            parse_events: indexer_config.parse_events,
+            balance_changes: indexer_config.balance_changes,
+        },
+        db: &db,
+        chain: StreamedChain::default(),
+        start_height,
+    };
+
+    loop {
+        time::sleep(INTERVAL).await;
+        if !node_is_ready(&client, &indexer_config.await_for_node_synced).await {
+            continue;
+        }
+        // The final block has to be fetched first, so that the head is built on top of it.
+        let final_block = match fetch_latest_block(&view_client, Finality::Final).await {
+            Ok(block) => block,
+            Err(_) => continue,
+        };
+        let head = match fetch_latest_block(&view_client, Finality::None).await {
+            Ok(block) => block,
+            Err(_) => continue,
+        };
+        match streamer.step(&view_client, final_block, head, &blocks_sink).await {
+            Ok(()) => {}
+            Err(Stop::Disconnected) => {
+                info!(
+                    target: INDEXER,
+                    "Unable to send StreamerMessage to listener, listener doesn't listen. terminating..."
+                );
+                break;
+            }
+            Err(Stop::Database) => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use near_primitives::block::Block;
+    use near_primitives::test_utils::{create_test_signer, TestBlockBuilder};
+    use near_primitives::validator_signer::ValidatorSigner;
+    use near_primitives::version::PROTOCOL_VERSION;
+
+    use super::*;
+
+    /// Blocks of a chain with forks, the canonical chain is set by height.
+    #[derive(Default)]
+    struct MockChain {
+        blocks: HashMap<CryptoHash, Block>,
+        canonical: HashMap<BlockHeight, CryptoHash>,
+        /// Blocks whose message can't be built.
+        missing_data: HashSet<CryptoHash>,
+    }
+
+    impl MockChain {
+        fn new() -> (Self, Block) {
+            let genesis = Block::genesis(
+                PROTOCOL_VERSION,
+                vec![],
+                chrono::Utc::now(),
+                0,
+                1000,
+                1000,
+                CryptoHash::default(),
+            );
+            let mut chain = Self::default();
+            chain.add(&genesis);
+            (chain, genesis)
+        }
+
+        fn add(&mut self, block: &Block) {
+            self.blocks.insert(*block.hash(), block.clone());
+            self.canonical.insert(block.header().height(), *block.hash());
+        }
+
+        fn produce(&mut self, prev: &Block, height: BlockHeight) -> Block {
+            let signer = Arc::new(create_test_signer("test0")) as Arc<dyn ValidatorSigner>;
+            let block = TestBlockBuilder::new(prev, signer).height(height).build();
+            self.add(&block);
+            block
+        }
+
+        fn view(&self, block: &Block) -> views::BlockView {
+            views::BlockView::from_author_block("test0".parse().unwrap(), block.clone())
+        }
+    }
+
+    #[async_trait(?Send)]
+    impl OptimisticSource for MockChain {
+        async fn block(
+            &self,
+            block_hash: CryptoHash,
+        ) -> Result<views::BlockView, FailedToFetchData> {
+            let block = self.blocks.get(&block_hash).ok_or_else(|| {
+                FailedToFetchData::String(format!("unknown block {}", block_hash))
+            })?;
+            Ok(self.view(block))
+        }
+
+        async fn block_by_height(
+            &self,
+            block_height: BlockHeight,
+        ) -> Result<views::BlockView, FailedToFetchData> {
+            let block_hash = self.canonical.get(&block_height).ok_or_else(|| {
+                FailedToFetchData::String(format!("no block at height {}", block_height))
+            })?;
+            self.block(*block_hash).await
+        }
+
+        async fn streamer_message(
+            &self,
+            block: views::BlockView,
+            _: &MessageOptions,
+        ) -> Result<StreamerMessage, FailedToFetchData> {
+            if self.missing_data.contains(&block.header.hash) {
+                return Err(FailedToFetchData::String("missing data".to_string()));
+            }
+            Ok(StreamerMessage { block, shards: vec![] })
+        }
+    }
+
+    #[test]
+    fn test_streamed_chain() {
+        let hash = |height: BlockHeight| CryptoHash::hash_bytes(&height.to_le_bytes());
+        let mut chain = StreamedChain::default();
+        assert_eq!(chain.tip_height(), None);
+        chain.last_final = Some((1, hash(1)));
+        assert_eq!(chain.tip_height(), Some(1));
+        chain.pending.extend([(2, hash(2)), (4, hash(4)), (5, hash(5))]);
+        assert_eq!(chain.tip_height(), Some(5));
+        assert!(chain.contains(&hash(1)));
+        assert!(chain.contains(&hash(4)));
+        assert!(!chain.contains(&hash(3)));
+
+        assert!(!chain.rollback(5));
+        assert!(chain.rollback(3));
+        assert_eq!(chain.pending, [(2, hash(2))]);
+        assert_eq!(chain.tip_height(), Some(2));
+        assert!(!chain.contains(&hash(4)));
+        // The final block is never rolled back.
+        assert!(chain.rollback(0));
+        assert_eq!(chain.tip_height(), Some(1));
+    }
+
+    #[test]
+    fn test_fetch_new_branch() {
+        let (mut mock, genesis) = MockChain::new();
+        let b1 = mock.produce(&genesis, 1);
+        let b2 = mock.produce(&b1, 2);
+        let a3 = mock.produce(&b2, 3);
+        let c5 = mock.produce(&b2, 5);
+        let c6 = mock.produce(&c5, 6);
+        let d5 = mock.produce(&b1, 5);
+        let heights = |branch: &[views::BlockView]| -> Vec<BlockHeight> {
+            branch.iter().map(|block| block.header.height).collect()
+        };
+        let fetch = |chain: &StreamedChain, head: &Block, start_height: BlockHeight| {
+            let (branch, fork_height) = futures::executor::block_on(fetch_new_branch(
+                &mock,
+                chain,
+                mock.view(head),
+                start_height,
+            ))
+            .unwrap();
+            (heights(&branch), fork_height)
+        };
+
+        // Nothing streamed yet: the walk stops at the start height, or at the genesis.
+        let mut chain = StreamedChain::default();
+        assert_eq!(fetch(&chain, &a3, 2), (vec![2, 3], None));
+        assert_eq!(fetch(&chain, &a3, 0), (vec![0, 1, 2, 3], None));
+
+        // The walk stops at the first streamed block.
+        chain.last_final = Some((1, *b1.hash()));
+        chain.pending.extend([(2, *b2.hash()), (3, *a3.hash())]);
+        assert_eq!(fetch(&chain, &a3, 1), (vec![], Some(3)));
+        // The head switched to a fork built on top of the block at height 2.
+        assert_eq!(fetch(&chain, &c6, 1), (vec![5, 6], Some(2)));
+        // The fork goes below the start height.
+        chain.last_final = None;
+        chain.pending.clear();
+        chain.pending.push_back((5, *d5.hash()));
+        assert_eq!(fetch(&chain, &c6, 3), (vec![5, 6], Some(2)));
+    }
+
+    fn describe(message: OptimisticStreamerMessage) -> String {
+        match message {
+            OptimisticStreamerMessage::Block(message) => {
+                format!("block {}", message.block.header.height)
+            }
+            OptimisticStreamerMessage::Rollback { to_height } => format!("rollback {}", to_height),
+            OptimisticStreamerMessage::Finalized { height } => format!("finalized {}", height),
+        }
+    }
+
+    /// Streams a chain switching forks, with a block which can't be built for a while.
+    #[test]
+    fn test_optimistic_streamer() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = IndexerDb::open(dir.path()).unwrap();
+        let (mut mock, genesis) = MockChain::new();
+        let b1 = mock.produce(&genesis, 1);
+        let b2 = mock.produce(&b1, 2);
+        let a3 = mock.produce(&b2, 3);
+        let a4 = mock.produce(&a3, 4);
+        let mut streamer = OptimisticStreamer {
+            options: MessageOptions {
+                filter: Default::default(),
+                parse_events: false,
+                balance_changes: false,
+            },
+            db: &db,
+            chain: StreamedChain::default(),
+            start_height: Some(1),
+        };
+        let (sender, mut receiver) = mpsc::channel(100);
+        let mut step = |mock: &MockChain, final_block: &Block, head: &Block| {
+            futures::executor::block_on(streamer.step(
+                mock,
+                mock.view(final_block),
+                mock.view(head),
+                &sender,
+            ))
+            .unwrap_or_else(|_| panic!("the streamer stopped"));
+            let mut messages = vec![];
+            while let Ok(message) = receiver.try_recv() {
+                messages.push(describe(message));
+            }
+            messages
+        };
+
+        assert_eq!(
+            step(&mock, &b1, &a4),
+            ["block 1", "finalized 1", "block 2", "block 3", "block 4"]
+        );
+        // The head switches to a fork skipping the heights 3 and 4.
+        let c5 = mock.produce(&b2, 5);
+        assert_eq!(step(&mock, &b2, &c5), ["rollback 2", "block 5", "finalized 2"]);
+        // The block at height 6 can't be built, neither it nor the block on top of it are
+        // streamed.
+        let c6 = mock.produce(&c5, 6);
+        let c7 = mock.produce(&c6, 7);
+        mock.missing_data.insert(*c6.hash());
+        assert_eq!(step(&mock, &c5, &c7), ["finalized 5"]);
+        assert_eq!(db.streamed_height().unwrap(), Some(5));
+        // Both are streamed once the block can be built.
+        mock.missing_data.clear();
+        assert_eq!(step(&mock, &c5, &c7), ["block 6", "block 7"]);
+        assert_eq!(step(&mock, &c7, &c7), ["finalized 6", "finalized 7"]);
+        assert_eq!(step(&mock, &c7, &c7), Vec::<String>::new());
+        assert_eq!(db.streamed_height().unwrap(), Some(7));
+
+        // A final block which can't be built isn't finalized before it's streamed.
+        let c8 = mock.produce(&c7, 8);
+        mock.missing_data.insert(*c8.hash());
+        let c9 = mock.produce(&c8, 9);
+        assert_eq!(step(&mock, &c9, &c9), Vec::<String>::new());
+        assert_eq!(db.streamed_height().unwrap(), Some(7));
+        mock.missing_data.clear();
+        assert_eq!(step(&mock, &c9, &c9), ["block 8", "finalized 8", "block 9", "finalized 9"]);
+    }
+}
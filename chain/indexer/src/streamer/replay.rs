@@ -0,0 +1,440 @@
+//! Replay of the blocks stored in the database of a stopped node.
+//!
+//! The data is read straight from the database the same way the view client reads it, so
+//! the replayed `StreamerMessage`s are identical to the ones streamed by a running node.
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use futures::stream::StreamExt;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+
+use near_chain::near_chain_primitives::error::QueryError;
+use near_chain::types::RuntimeAdapter;
+use near_chain::{ChainStore, ChainStoreAccess};
+use near_chain_configs::ProtocolConfigView;
+use near_client::StateChangesFilter;
+use near_epoch_manager::{EpochManager, EpochManagerAdapter, EpochManagerHandle};
+use near_indexer_primitives::{IndexerExecutionOutcomeWithOptionalReceipt, StreamerMessage};
+use near_primitives::hash::CryptoHash;
+use near_primitives::receipt::Receipt;
+use near_primitives::types::{AccountId, BlockHeight, EpochId, ShardId};
+use near_primitives::views;
+use near_store::{Mode, NodeStorage, Store};
+use nearcore::{NearConfig, NightshadeRuntime};
+
+use super::errors::FailedToFetchData;
+use super::source::StreamerSource;
+use super::{build_streamer_message, metrics, send_message, MessageOptions};
+use crate::{ReplayConfig, INDEXER};
+
+fn chain_error(err: impl std::fmt::Display) -> FailedToFetchData {
+    FailedToFetchData::String(err.to_string())
+}
+
+/// Database of the node opened in read-only mode, shared by all the blocks built in
+/// parallel.
+pub(crate) struct ReplayContext {
+    store: Store,
+    genesis_height: BlockHeight,
+    epoch_manager: Arc<EpochManagerHandle>,
+    runtime: Arc<NightshadeRuntime>,
+}
+
+impl ReplayContext {
+    /// Opens the database of the node in read-only mode.  If the node is configured with a
+    /// cold store, blocks are read from both the hot and the cold database.
+    pub(crate) fn open(home_dir: &Path, near_config: &NearConfig) -> anyhow::Result<Self> {
+        let storage = NodeStorage::opener(
+            home_dir,
+            near_config.config.archive,
+            &near_config.config.store,
+            near_config.config.cold_store.as_ref(),
+        )
+        .open_in_mode(Mode::ReadOnly)
+        .with_context(|| format!("failed to open the database in {}", home_dir.display()))?;
+        let store = storage.get_split_store().unwrap_or_else(|| storage.get_hot_store());
+        let epoch_manager =
+            EpochManager::new_arc_handle(store.clone(), &near_config.genesis.config);
+        let runtime = NightshadeRuntime::from_config(
+            home_dir,
+            store.clone(),
+            near_config,
+            epoch_manager.clone(),
+        );
+        Ok(Self {
+            store,
+            genesis_height: near_config.genesis.config.genesis_height,
+            epoch_manager,
+            runtime,
+        })
+    }
+}
+
+/// Reads the data of the blocks from the database.  Every replayed block gets its own source,
+/// since the caches of `ChainStore` can't be shared between threads.
+struct StoreSource<'a> {
+    context: &'a ReplayContext,
+    chain_store: ChainStore,
+}
+
+impl<'a> StoreSource<'a> {
+    fn new(context: &'a ReplayContext) -> Self {
+        let chain_store = ChainStore::new(context.store.clone(), context.genesis_height, false);
+        Self { context, chain_store }
+    }
+
+    fn chunk(&self, chunk_hash: CryptoHash) -> Result<views::ChunkView, FailedToFetchData> {
+        let chunk = self.chain_store.get_chunk(&chunk_hash.into()).map_err(chain_error)?;
+        let chunk_header = chunk.cloned_header();
+        let epoch_id = self
+            .context
+            .epoch_manager
+            .get_epoch_id_from_prev_block(chunk_header.prev_block_hash())
+            .map_err(chain_error)?;
+        let author = self
+            .context
+            .epoch_manager
+            .get_chunk_producer(&epoch_id, chunk_header.height_created(), chunk_header.shard_id())
+            .map_err(chain_error)?;
+        Ok(views::ChunkView::from_author_chunk(author, (*chunk).clone()))
+    }
+
+    /// Builds the message of the block at the given height, or returns `None` if no block
+    /// was produced at the height.
+    async fn build_message(
+        &self,
+        height: BlockHeight,
+        options: &MessageOptions,
+    ) -> Result<Option<StreamerMessage>, FailedToFetchData> {
+        let block_hash = match self.chain_store.get_block_hash_by_height(height) {
+            Ok(block_hash) => block_hash,
+            Err(near_chain::Error::DBNotFoundErr(_)) => return Ok(None),
+            Err(err) => return Err(chain_error(err)),
+        };
+        let block = self.block(block_hash).await?;
+        build_streamer_message(self, block, options).await.map(Some)
+    }
+}
+
+#[async_trait(?Send)]
+impl StreamerSource for StoreSource<'_> {
+    async fn block(&self, block_hash: CryptoHash) -> Result<views::BlockView, FailedToFetchData> {
+        let block = self.chain_store.get_block(&block_hash).map_err(chain_error)?;
+        let author = self
+            .context
+            .epoch_manager
+            .get_block_producer(block.header().epoch_id(), block.header().height())
+            .map_err(chain_error)?;
+        Ok(views::BlockView::from_author_block(author, block))
+    }
+
+    async fn block_chunks(
+        &self,
+        block: &views::BlockView,
+    ) -> Result<Vec<views::ChunkView>, FailedToFetchData> {
+        block
+            .chunks
+            .iter()
+            .filter(|chunk| chunk.height_included == block.header.height)
+            .map(|chunk| self.chunk(chunk.chunk_hash))
+            .collect()
+    }
+
+    async fn protocol_config(
+        &self,
+        block_hash: CryptoHash,
+    ) -> Result<ProtocolConfigView, FailedToFetchData> {
+        let header = self.chain_store.get_block_header(&block_hash).map_err(chain_error)?;
+        let config =
+            self.context.runtime.get_protocol_config(header.epoch_id()).map_err(chain_error)?;
+        Ok(config.into())
+    }
+
+    async fn outcomes(
+        &self,
+        block_hash: CryptoHash,
+        keep: &dyn Fn(&views::ExecutionOutcomeWithIdView) -> bool,
+    ) -> Result<HashMap<ShardId, Vec<IndexerExecutionOutcomeWithOptionalReceipt>>, FailedToFetchData>
+    {
+        let outcomes =
+            self.chain_store.get_block_execution_outcomes(&block_hash).map_err(chain_error)?;
+        let mut shard_execution_outcomes_with_receipts = HashMap::new();
+        for (shard_id, shard_outcomes) in outcomes {
+            let mut outcomes_with_receipts = vec![];
+            for outcome in shard_outcomes.into_iter().map(views::ExecutionOutcomeWithIdView::from) {
+                if !keep(&outcome) {
+                    continue;
+                }
+                let receipt = match self.chain_store.get_receipt(&outcome.id) {
+                    Ok(receipt) => receipt.map(|receipt| Receipt::clone(&receipt).into()),
+                    Err(err) => {
+                        warn!(
+                            target: INDEXER,
+                            "Unable to read Receipt with id {}. Skipping it in ExecutionOutcome \n {:#?}",
+                            outcome.id,
+                            err,
+                        );
+                        None
+                    }
+                };
+                outcomes_with_receipts.push(IndexerExecutionOutcomeWithOptionalReceipt {
+                    execution_outcome: outcome,
+                    receipt,
+                });
+            }
+            shard_execution_outcomes_with_receipts.insert(shard_id, outcomes_with_receipts);
+        }
+        Ok(shard_execution_outcomes_with_receipts)
+    }
+
+    async fn state_changes(
+        &self,
+        block_hash: CryptoHash,
+        epoch_id: EpochId,
+        filter: StateChangesFilter,
+    ) -> Result<HashMap<ShardId, views::StateChangesView>, FailedToFetchData> {
+        let state_changes = self
+            .chain_store
+            .get_state_changes_with_cause_in_block(&block_hash)
+            .map_err(chain_error)?;
+        let mut state_changes_split_by_shard_id: HashMap<ShardId, views::StateChangesView> =
+            HashMap::new();
+        for state_change in state_changes {
+            if !filter.matches(&state_change.value) {
+                continue;
+            }
+            let shard_id = self
+                .context
+                .epoch_manager
+                .account_id_to_shard_id(state_change.value.affected_account_id(), &epoch_id)
+                .map_err(chain_error)?;
+            state_changes_split_by_shard_id.entry(shard_id).or_default().push(state_change.into());
+        }
+        Ok(state_changes_split_by_shard_id)
+    }
+
+    async fn receipt(
+        &self,
+        receipt_id: CryptoHash,
+    ) -> Result<Option<views::ReceiptView>, FailedToFetchData> {
+        let receipt = self.chain_store.get_receipt(&receipt_id).map_err(chain_error)?;
+        Ok(receipt.map(|receipt| Receipt::clone(&receipt).into()))
+    }
+
+    async fn account(
+        &self,
+        block_hash: CryptoHash,
+        account_id: &AccountId,
+    ) -> Result<Option<views::AccountView>, FailedToFetchData> {
+        let header = self.chain_store.get_block_header(&block_hash).map_err(chain_error)?;
+        let epoch_manager = &self.context.epoch_manager;
+        let shard_id = epoch_manager
+            .account_id_to_shard_id(account_id, header.epoch_id())
+            .map_err(chain_error)?;
+        let shard_uid =
+            epoch_manager.shard_id_to_uid(shard_id, header.epoch_id()).map_err(chain_error)?;
+        let chunk_extra =
+            self.chain_store.get_chunk_extra(&block_hash, &shard_uid).map_err(chain_error)?;
+        let response = self.context.runtime.query(
+            shard_uid,
+            chunk_extra.state_root(),
+            header.height(),
+            header.raw_timestamp(),
+            header.prev_hash(),
+            header.hash(),
+            header.epoch_id(),
+            &views::QueryRequest::ViewAccount { account_id: account_id.clone() },
+        );
+        match response {
+            Ok(views::QueryResponse {
+                kind: views::QueryResponseKind::ViewAccount(account),
+                ..
+            }) => Ok(Some(account)),
+            Ok(response) => Err(chain_error(format!(
+                "unexpected response to the account query: {:?}",
+                response.kind
+            ))),
+            Err(QueryError::UnknownAccount { .. }) => Ok(None),
+            Err(err) => Err(chain_error(err)),
+        }
+    }
+}
+
+/// Builds the messages of the configured range of heights on the blocking thread pool, up to
+/// `parallelism` blocks at a time, and sends them to the sink in the order of heights.
+/// Terminates at the first block whose data is missing, unless `skip_incomplete_blocks` is set.
+pub(crate) async fn replay(
+    context: ReplayContext,
+    config: ReplayConfig,
+    blocks_sink: mpsc::Sender<StreamerMessage>,
+) {
+    info!(
+        target: INDEXER,
+        "Replaying blocks #{}..=#{} from the database...", config.start_height, config.end_height
+    );
+    let context = Arc::new(context);
+    let options = Arc::new(MessageOptions {
+        filter: config.filter,
+        parse_events: config.parse_events,
+        balance_changes: config.balance_changes,
+    });
+    let mut messages = futures::stream::iter(config.start_height..=config.end_height)
+        .map(|height| {
+            let context = context.clone();
+            let options = options.clone();
+            let message = tokio::task::spawn_blocking(move || {
+                let source = StoreSource::new(&context);
+                futures::executor::block_on(source.build_message(height, &options))
+            });
+            async move { (height, message.await) }
+        })
+        .buffered(config.parallelism.max(1));
+
+    let mut skipped_heights = vec![];
+    while let Some((height, message)) = messages.next().await {
+        metrics::CURRENT_BLOCK_HEIGHT.set(height as i64);
+        let streamer_message = match message {
+            Ok(Ok(Some(streamer_message))) => streamer_message,
+            Ok(Ok(None)) => {
+                debug!(target: INDEXER, "No block at height #{}, skipping it...", height);
+                continue;
+            }
+            Ok(Err(err)) if config.skip_incomplete_blocks => {
+                warn!(target: INDEXER, "Missing data, skipping block #{}: {:?}", height, err);
+                skipped_heights.push(height);
+                continue;
+            }
+            Ok(Err(err)) => {
+                error!(target: INDEXER, "Missing data of block #{}, terminating: {:?}", height, err);
+                break;
+            }
+            Err(err) => {
+                error!(target: INDEXER, "Failed to build block #{}, terminating: {}", height, err);
+                break;
+            }
+        };
+        if !send_message(&blocks_sink, streamer_message).await {
+            info!(
+                target: INDEXER,
+                "Unable to send StreamerMessage to listener, listener doesn't listen. terminating..."
+            );
+            break;
+        }
+    }
+    if !skipped_heights.is_empty() {
+        warn!(
+            target: INDEXER,
+            "Skipped {} blocks with missing data at heights {:?}",
+            skipped_heights.len(),
+            skipped_heights
+        );
+    }
+    info!(target: INDEXER, "Replay finished");
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use near_actix_test_utils::run_actix;
+    use near_chain_configs::Genesis;
+    use near_client::{GetBlock, ProcessTxRequest};
+    use near_crypto::{InMemorySigner, KeyType};
+    use near_network::tcp;
+    use near_o11y::testonly::init_integration_logger;
+    use near_o11y::WithSpanContextExt;
+    use near_primitives::transaction::SignedTransaction;
+    use nearcore::config::GenesisExt;
+    use nearcore::{load_test_config, start_with_config, NearNode};
+
+    use super::*;
+    use crate::streamer::fetchers::fetch_block_by_height;
+    use crate::IndexerFilter;
+
+    /// The messages replayed from the database are the same as the ones built from the view
+    /// client of the running node.
+    #[test]
+    fn test_store_source_matches_view_client() {
+        init_integration_logger();
+        let genesis = Genesis::test(vec!["test0".parse().unwrap()], 1);
+        let near_config = load_test_config("test0", tcp::ListenerAddr::reserve_for_test(), genesis);
+        let dir = tempfile::Builder::new().prefix("indexer_replay").tempdir().unwrap();
+        run_actix(async move {
+            let NearNode { client, view_client, .. } =
+                start_with_config(dir.path(), near_config.clone()).unwrap();
+            let genesis_block = fetch_block_by_height(&view_client, 0).await.unwrap();
+            let signer =
+                InMemorySigner::from_seed("test0".parse().unwrap(), KeyType::ED25519, "test0");
+            for nonce in 1..=3 {
+                let transaction = SignedTransaction::send_money(
+                    nonce,
+                    "test0".parse().unwrap(),
+                    "test0".parse().unwrap(),
+                    &signer,
+                    nonce as u128,
+                    genesis_block.header.hash,
+                );
+                client
+                    .send(
+                        ProcessTxRequest { transaction, is_forwarded: false, check_only: false }
+                            .with_span_context(),
+                    )
+                    .await
+                    .unwrap();
+            }
+            let end_height = 10;
+            loop {
+                let head = view_client.send(GetBlock::latest().with_span_context()).await;
+                if head.unwrap().unwrap().header.height > end_height {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+
+            let context = ReplayContext::open(dir.path(), &near_config).unwrap();
+            let options = MessageOptions {
+                filter: IndexerFilter::default(),
+                parse_events: true,
+                balance_changes: true,
+            };
+            let mut num_outcomes = 0;
+            for height in 1..=end_height {
+                let replayed = StoreSource::new(&context).build_message(height, &options).await;
+                let streamed = match fetch_block_by_height(&view_client, height).await {
+                    Ok(block) => Some(build_streamer_message(&view_client, block, &options).await),
+                    Err(_) => None,
+                };
+                match (replayed.unwrap(), streamed) {
+                    (Some(replayed), Some(streamed)) => {
+                        let streamed = streamed.unwrap();
+                        num_outcomes += replayed
+                            .shards
+                            .iter()
+                            .map(|shard| shard.receipt_execution_outcomes.len())
+                            .sum::<usize>();
+                        assert_eq!(
+                            serde_json::to_value(&replayed).unwrap(),
+                            serde_json::to_value(&streamed).unwrap(),
+                            "messages differ at height {}",
+                            height
+                        );
+                    }
+                    (None, None) => {}
+                    (replayed, streamed) => panic!(
+                        "block at height {} replayed: {}, streamed: {}",
+                        height,
+                        replayed.is_some(),
+                        streamed.is_some()
+                    ),
+                }
+            }
+            // The transfers got executed within the range.
+            assert!(num_outcomes >= 3, "only {} receipts executed", num_outcomes);
+            actix::System::current().stop();
+        });
+    }
+}
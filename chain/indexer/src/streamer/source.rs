@@ -0,0 +1,118 @@
+//! Abstraction over the place `StreamerMessage`s are built from, so that the same code
+//! streams from a running node and replays the database of a stopped one.
+use std::collections::HashMap;
+
+use actix::Addr;
+use async_trait::async_trait;
+
+use near_chain_configs::ProtocolConfigView;
+use near_client::StateChangesFilter;
+use near_indexer_primitives::IndexerExecutionOutcomeWithOptionalReceipt;
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::{AccountId, EpochId, ShardId};
+use near_primitives::views;
+
+use super::errors::FailedToFetchData;
+use super::fetchers::{
+    fetch_account, fetch_block, fetch_block_chunks, fetch_outcomes, fetch_protocol_config,
+    fetch_receipt_by_id, fetch_state_changes,
+};
+
+/// Source of the data of a block.  Sources aren't required to be `Send`, the database source
+/// keeps caches which can't be shared between threads.
+#[async_trait(?Send)]
+pub(crate) trait StreamerSource {
+    async fn block(&self, block_hash: CryptoHash) -> Result<views::BlockView, FailedToFetchData>;
+
+    /// Returns the chunks included in the block.
+    async fn block_chunks(
+        &self,
+        block: &views::BlockView,
+    ) -> Result<Vec<views::ChunkView>, FailedToFetchData>;
+
+    async fn protocol_config(
+        &self,
+        block_hash: CryptoHash,
+    ) -> Result<ProtocolConfigView, FailedToFetchData>;
+
+    /// Returns the execution outcomes of the block accepted by `keep` together with their
+    /// receipts, grouped by shard.
+    async fn outcomes(
+        &self,
+        block_hash: CryptoHash,
+        keep: &dyn Fn(&views::ExecutionOutcomeWithIdView) -> bool,
+    ) -> Result<HashMap<ShardId, Vec<IndexerExecutionOutcomeWithOptionalReceipt>>, FailedToFetchData>;
+
+    async fn state_changes(
+        &self,
+        block_hash: CryptoHash,
+        epoch_id: EpochId,
+        filter: StateChangesFilter,
+    ) -> Result<HashMap<ShardId, views::StateChangesView>, FailedToFetchData>;
+
+    async fn receipt(
+        &self,
+        receipt_id: CryptoHash,
+    ) -> Result<Option<views::ReceiptView>, FailedToFetchData>;
+
+    /// Returns the account at the end of the block, or `None` if it doesn't exist.
+    async fn account(
+        &self,
+        block_hash: CryptoHash,
+        account_id: &AccountId,
+    ) -> Result<Option<views::AccountView>, FailedToFetchData>;
+}
+
+#[async_trait(?Send)]
+impl StreamerSource for Addr<near_client::ViewClientActor> {
+    async fn block(&self, block_hash: CryptoHash) -> Result<views::BlockView, FailedToFetchData> {
+        fetch_block(self, block_hash).await
+    }
+
+    async fn block_chunks(
+        &self,
+        block: &views::BlockView,
+    ) -> Result<Vec<views::ChunkView>, FailedToFetchData> {
+        fetch_block_chunks(self, block).await
+    }
+
+    async fn protocol_config(
+        &self,
+        block_hash: CryptoHash,
+    ) -> Result<ProtocolConfigView, FailedToFetchData> {
+        fetch_protocol_config(self, block_hash).await
+    }
+
+    async fn outcomes(
+        &self,
+        block_hash: CryptoHash,
+        keep: &dyn Fn(&views::ExecutionOutcomeWithIdView) -> bool,
+    ) -> Result<HashMap<ShardId, Vec<IndexerExecutionOutcomeWithOptionalReceipt>>, FailedToFetchData>
+    {
+        fetch_outcomes(self, block_hash, keep).await
+    }
+
+    async fn state_changes(
+        &self,
+        block_hash: CryptoHash,
+        epoch_id: EpochId,
+        filter: StateChangesFilter,
+    ) -> Result<HashMap<ShardId, views::StateChangesView>, FailedToFetchData> {
+        fetch_state_changes(self, block_hash, epoch_id, filter).await
+    }
+
+    async fn receipt(
+        &self,
+        receipt_id: CryptoHash,
+    ) -> Result<Option<views::ReceiptView>, FailedToFetchData> {
+        fetch_receipt_by_id(self, receipt_id).await
+    }
+
+    async fn account(
+        &self,
+        block_hash: CryptoHash,
+        account_id: &AccountId,
+    ) -> Result<Option<views::AccountView>, FailedToFetchData> {
+        fetch_account(self, block_hash, account_id).await
+    }
+}
@@ -1,15 +1,13 @@
-use actix::Addr;
-
 use near_indexer_primitives::types::ProtocolVersion;
 use near_indexer_primitives::IndexerTransactionWithOutcome;
 use near_primitives::views;
 use node_runtime::config::{tx_cost, RuntimeConfig};
 
 use super::errors::FailedToFetchData;
-use super::fetchers::fetch_block;
+use super::source::StreamerSource;
 
-pub(crate) async fn convert_transactions_sir_into_local_receipts(
-    client: &Addr<near_client::ViewClientActor>,
+pub(crate) async fn convert_transactions_sir_into_local_receipts<S: StreamerSource>(
+    source: &S,
     runtime_config: &RuntimeConfig,
     protocol_version: ProtocolVersion,
     txs: Vec<&IndexerTransactionWithOutcome>,
@@ -18,7 +16,7 @@ pub(crate) async fn convert_transactions_sir_into_local_receipts(
     if txs.is_empty() {
         return Ok(vec![]);
     }
-    let prev_block = fetch_block(&client, block.header.prev_hash).await?;
+    let prev_block = source.block(block.header.prev_hash).await?;
     let prev_block_gas_price = prev_block.header.gas_price;
 
     let local_receipts: Vec<views::ReceiptView> =
@@ -5,6 +5,11 @@ use serde_json::Value;
 pub struct RpcClientConfigResponse {
     #[serde(flatten)]
     pub client_config: near_chain_configs::ClientConfig,
+    /// Latest changes of the config applied while the node is running, the oldest first.
+    pub applied_changes: Vec<near_chain_configs::ConfigValueChange>,
+    /// Reason why the latest change of the config was rejected, if it was.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rejected_change: Option<String>,
 }
 
 #[derive(thiserror::Error, Debug, Serialize)]
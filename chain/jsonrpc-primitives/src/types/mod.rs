@@ -12,5 +12,6 @@ pub mod receipts;
 pub mod sandbox;
 pub mod split_storage;
 pub mod status;
+pub mod tracked_shards;
 pub mod transactions;
 pub mod validator;
@@ -38,3 +38,38 @@ impl From<RpcReceiptError> for crate::errors::RpcError {
         Self::new_internal_or_handler_error(Some(error_data.clone()), error_data)
     }
 }
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct RpcReceiptTreeRequest {
+    pub tx_hash: near_primitives::hash::CryptoHash,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct RpcReceiptTreeResponse {
+    #[serde(flatten)]
+    pub receipt_tree: near_primitives::views::ReceiptTreeView,
+}
+
+#[derive(thiserror::Error, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcReceiptTreeError {
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+    #[error("Transaction {transaction_hash} has never been observed on this node")]
+    UnknownTransaction { transaction_hash: near_primitives::hash::CryptoHash },
+}
+
+impl From<RpcReceiptTreeError> for crate::errors::RpcError {
+    fn from(error: RpcReceiptTreeError) -> Self {
+        let error_data = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcReceiptTreeError: {:?}", err),
+                )
+            }
+        };
+        Self::new_internal_or_handler_error(Some(error_data.clone()), error_data)
+    }
+}
@@ -1,10 +1,11 @@
 #[cfg(feature = "debug_types")]
 use near_client_primitives::debug::{
-    DebugBlockStatusData, EpochInfoView, TrackedShardsView, ValidatorStatus,
+    CatchupInfoView, DebugBlockStatusData, EpochInfoView, TrackedShardsView, ValidatorHealthView,
+    ValidatorStatus,
 };
 #[cfg(feature = "debug_types")]
 use near_primitives::views::{
-    CatchupStatusView, ChainProcessingInfo, NetworkGraphView, PeerStoreView,
+    BlockTimelineView, ChainProcessingInfo, NetworkGraphView, PeerStoreView,
     RecentOutboundConnectionsView, RequestedStatePartsView, SyncStatusView,
 };
 
@@ -18,7 +19,7 @@ pub struct RpcStatusResponse {
 #[derive(serde::Serialize, Debug)]
 pub enum DebugStatusResponse {
     SyncStatus(SyncStatusView),
-    CatchupStatus(Vec<CatchupStatusView>),
+    CatchupStatus(CatchupInfoView),
     TrackedShards(TrackedShardsView),
     // List of epochs - in descending order (next epoch is first).
     EpochInfo(Vec<EpochInfoView>),
@@ -32,6 +33,8 @@ pub enum DebugStatusResponse {
     RequestedStateParts(Vec<RequestedStatePartsView>),
     NetworkGraph(NetworkGraphView),
     RecentOutboundConnections(RecentOutboundConnectionsView),
+    ValidatorHealth(ValidatorHealthView),
+    BlockTimeline(BlockTimelineView),
 }
 
 #[cfg(feature = "debug_types")]
@@ -50,6 +53,8 @@ pub enum RpcStatusError {
     NodeIsSyncing,
     #[error("No blocks for {elapsed:?}")]
     NoNewBlocks { elapsed: std::time::Duration },
+    #[error("Validator is unhealthy: {}", issues.join("; "))]
+    ValidatorUnhealthy { issues: Vec<String> },
     #[error("Epoch Out Of Bounds {epoch_id:?}")]
     EpochOutOfBounds { epoch_id: near_primitives::types::EpochId },
     #[error("The node reached its limits. Try again later. More details: {error_message}")]
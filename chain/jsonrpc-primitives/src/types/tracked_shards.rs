@@ -0,0 +1,56 @@
+use near_primitives::types::{AccountId, ShardId};
+use near_primitives::views::TrackedShardsChangeView;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcUpdateTrackedShardsRequest {
+    #[serde(default)]
+    pub track_shards: Vec<ShardId>,
+    #[serde(default)]
+    pub untrack_shards: Vec<ShardId>,
+    #[serde(default)]
+    pub track_accounts: Vec<AccountId>,
+    #[serde(default)]
+    pub untrack_accounts: Vec<AccountId>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcUpdateTrackedShardsResponse {
+    /// All changes of the tracked shards requested while the node is running, the oldest first.
+    pub changes: Vec<TrackedShardsChangeView>,
+}
+
+#[derive(thiserror::Error, Debug, Serialize, Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcUpdateTrackedShardsError {
+    #[error("Invalid request: {error_message}")]
+    InvalidRequest { error_message: String },
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<RpcUpdateTrackedShardsError> for crate::errors::RpcError {
+    fn from(error: RpcUpdateTrackedShardsError) -> Self {
+        let error_data = match &error {
+            RpcUpdateTrackedShardsError::InvalidRequest { .. } => {
+                Some(Value::String(error.to_string()))
+            }
+            RpcUpdateTrackedShardsError::InternalError { .. } => {
+                Some(Value::String(error.to_string()))
+            }
+        };
+
+        let error_data_value = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcUpdateTrackedShardsError: {:?}", err),
+                )
+            }
+        };
+
+        Self::new_internal_or_handler_error(error_data, error_data_value)
+    }
+}
@@ -43,6 +43,49 @@ pub enum RpcTransactionError {
 pub struct RpcTransactionResponse {
     #[serde(flatten)]
     pub final_execution_outcome: near_primitives::views::FinalExecutionOutcomeViewEnum,
+    /// NEP-297 events logged by the receipts of the transaction.  Only returned by
+    /// `EXPERIMENTAL_tx_status`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub events: Option<Vec<RpcReceiptEvents>>,
+}
+
+/// NEP-297 events logged by the execution of a receipt.
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct RpcReceiptEvents {
+    pub receipt_id: near_primitives::hash::CryptoHash,
+    pub executor_id: near_primitives::types::AccountId,
+    #[serde(flatten)]
+    pub events: near_primitives::views::ContractEventsView,
+}
+
+impl RpcTransactionResponse {
+    /// Decodes the events logged by the receipts of the transaction into `events`.  Receipts
+    /// which logged no events are skipped.
+    pub fn decode_events(&mut self) {
+        let outcome = match &self.final_execution_outcome {
+            near_primitives::views::FinalExecutionOutcomeViewEnum::FinalExecutionOutcome(
+                outcome,
+            ) => outcome,
+            near_primitives::views::FinalExecutionOutcomeViewEnum::FinalExecutionOutcomeWithReceipt(
+                outcome,
+            ) => &outcome.final_outcome,
+        };
+        let events = outcome
+            .receipts_outcome
+            .iter()
+            .filter_map(|receipt_outcome| {
+                let events = near_primitives::views::ContractEventsView::from_logs(
+                    &receipt_outcome.outcome.logs,
+                );
+                (!events.is_empty()).then(|| RpcReceiptEvents {
+                    receipt_id: receipt_outcome.id,
+                    executor_id: receipt_outcome.outcome.executor_id.clone(),
+                    events,
+                })
+            })
+            .collect();
+        self.events = Some(events);
+    }
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
@@ -80,3 +123,49 @@ impl From<RpcTransactionError> for crate::errors::RpcError {
         Self::new_internal_or_handler_error(Some(error_data), error_data_value)
     }
 }
+
+/// Request of `EXPERIMENTAL_dry_run_tx`, applies a transaction on top of the latest state
+/// without committing anything.
+#[derive(Debug, Clone)]
+pub struct RpcDryRunTransactionRequest {
+    /// The transaction to apply.  Its signature isn't verified if `verify_signature` is false,
+    /// which is the case of the transactions passed unsigned.
+    pub signed_transaction: near_primitives::transaction::SignedTransaction,
+    pub verify_signature: bool,
+    /// Number of blocks in which the receipts generated by the transaction are followed.
+    pub max_blocks: near_primitives::types::NumBlocks,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct RpcDryRunTransactionResponse {
+    #[serde(flatten)]
+    pub dry_run: near_primitives::views::TransactionDryRunView,
+}
+
+#[derive(thiserror::Error, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcDryRunTransactionError {
+    #[error("Invalid transaction: {error}")]
+    InvalidTransaction { error: near_primitives::errors::InvalidTxError },
+    #[error("Node doesn't track the shard of the signer {signer_id}")]
+    DoesNotTrackShard { signer_id: near_primitives::types::AccountId },
+    #[error("There are no fully synchronized blocks on the node yet")]
+    NoSyncedBlocks,
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<RpcDryRunTransactionError> for crate::errors::RpcError {
+    fn from(error: RpcDryRunTransactionError) -> Self {
+        let error_data = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcDryRunTransactionError: {:?}", err),
+                )
+            }
+        };
+        Self::new_internal_or_handler_error(Some(error_data.clone()), error_data)
+    }
+}
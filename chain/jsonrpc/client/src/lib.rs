@@ -5,13 +5,19 @@ use near_jsonrpc_primitives::message::{from_slice, Message};
 use near_jsonrpc_primitives::types::changes::{
     RpcStateChangesInBlockByTypeRequest, RpcStateChangesInBlockByTypeResponse,
 };
+use near_jsonrpc_primitives::types::light_client::{
+    RpcLightClientExecutionProofRequest, RpcLightClientExecutionProofResponse,
+    RpcLightClientNextBlockRequest,
+};
 use near_jsonrpc_primitives::types::validator::RpcValidatorsOrderedRequest;
 use near_primitives::hash::CryptoHash;
-use near_primitives::types::{AccountId, BlockId, BlockReference, MaybeBlockId, ShardId};
+use near_primitives::types::{
+    AccountId, BlockId, BlockReference, MaybeBlockId, NumBlocks, ShardId,
+};
 use near_primitives::views::validator_stake_view::ValidatorStakeView;
 use near_primitives::views::{
     BlockView, ChunkView, EpochValidatorInfo, FinalExecutionOutcomeView, GasPriceView,
-    StatusResponse,
+    LightClientBlockView, StatusResponse,
 };
 use std::time::Duration;
 
@@ -236,6 +242,54 @@ impl JsonRpcClient {
         call_method(&self.client, &self.server_addr, "EXPERIMENTAL_validators_ordered", request)
     }
 
+    /// Returns `None` if the node has no light client block past `last_block_hash` yet.
+    pub fn next_light_client_block(
+        &self,
+        request: RpcLightClientNextBlockRequest,
+    ) -> RpcRequest<Option<LightClientBlockView>> {
+        call_method::<_, serde_json::Value>(
+            &self.client,
+            &self.server_addr,
+            "next_light_client_block",
+            request,
+        )
+        .and_then(|value| {
+            // The response is an empty object when there is no new block.
+            future::ready(match value {
+                serde_json::Value::Object(map) if map.is_empty() => Ok(None),
+                value => serde_json::from_value(value)
+                    .map(Some)
+                    .map_err(|err| RpcError::parse_error(format!("Failed to parse: {:?}", err))),
+            })
+        })
+        .boxed_local()
+    }
+
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_light_client_proof(
+        &self,
+        request: RpcLightClientExecutionProofRequest,
+    ) -> RpcRequest<RpcLightClientExecutionProofResponse> {
+        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_light_client_proof", request)
+    }
+
+    /// Dry runs the transaction, `tx` being the borsh serialization of the signed transaction
+    /// in base64.
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_dry_run_tx(
+        &self,
+        tx: String,
+        max_blocks: NumBlocks,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::transactions::RpcDryRunTransactionResponse>
+    {
+        call_method(
+            &self.client,
+            &self.server_addr,
+            "EXPERIMENTAL_dry_run_tx",
+            serde_json::json!({ "signed_tx_base64": tx, "max_blocks": max_blocks }),
+        )
+    }
+
     #[allow(non_snake_case)]
     pub fn EXPERIMENTAL_receipt(
         &self,
@@ -244,6 +298,14 @@ impl JsonRpcClient {
         call_method(&self.client, &self.server_addr, "EXPERIMENTAL_receipt", request)
     }
 
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_receipt_tree(
+        &self,
+        request: near_jsonrpc_primitives::types::receipts::RpcReceiptTreeRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::receipts::RpcReceiptTreeResponse> {
+        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_receipt_tree", request)
+    }
+
     #[allow(non_snake_case)]
     pub fn EXPERIMENTAL_protocol_config(
         &self,
@@ -17,6 +17,7 @@ mod receipts;
 mod sandbox;
 mod split_storage;
 mod status;
+mod tracked_shards;
 mod transactions;
 mod validator;
 
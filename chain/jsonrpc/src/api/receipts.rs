@@ -1,8 +1,9 @@
 use super::{Params, RpcFrom, RpcRequest};
-use near_client_primitives::types::{GetReceipt, GetReceiptError};
+use near_client_primitives::types::{GetReceipt, GetReceiptError, GetReceiptTreeError};
 use near_jsonrpc_primitives::errors::RpcParseError;
 use near_jsonrpc_primitives::types::receipts::{
-    ReceiptReference, RpcReceiptError, RpcReceiptRequest,
+    ReceiptReference, RpcReceiptError, RpcReceiptRequest, RpcReceiptTreeError,
+    RpcReceiptTreeRequest,
 };
 use serde_json::Value;
 
@@ -39,3 +40,36 @@ impl RpcFrom<GetReceiptError> for RpcReceiptError {
         }
     }
 }
+
+impl RpcRequest for RpcReceiptTreeRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcFrom<actix::MailboxError> for RpcReceiptTreeError {
+    fn rpc_from(error: actix::MailboxError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<GetReceiptTreeError> for RpcReceiptTreeError {
+    fn rpc_from(error: GetReceiptTreeError) -> Self {
+        match error {
+            GetReceiptTreeError::IOError(error_message) => Self::InternalError { error_message },
+            GetReceiptTreeError::UnknownTransaction(hash) => {
+                Self::UnknownTransaction { transaction_hash: hash }
+            }
+            GetReceiptTreeError::IncompleteTree(_) => {
+                Self::InternalError { error_message: error.to_string() }
+            }
+            GetReceiptTreeError::Unreachable(ref error_message) => {
+                tracing::warn!(target: "jsonrpc", "Unreachable error occurred: {}", error_message);
+                crate::metrics::RPC_UNREACHABLE_ERROR_COUNT
+                    .with_label_values(&["RpcReceiptTreeError"])
+                    .inc();
+                Self::InternalError { error_message: error.to_string() }
+            }
+        }
+    }
+}
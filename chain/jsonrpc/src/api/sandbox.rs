@@ -1,10 +1,17 @@
 use serde_json::Value;
+use serde_with::base64::Base64;
+use serde_with::serde_as;
 
 use near_jsonrpc_primitives::errors::RpcParseError;
 use near_jsonrpc_primitives::types::sandbox::{
-    RpcSandboxFastForwardError, RpcSandboxFastForwardRequest, RpcSandboxPatchStateError,
-    RpcSandboxPatchStateRequest,
+    RpcSandboxDropSnapshotError, RpcSandboxDropSnapshotRequest, RpcSandboxFastForwardError,
+    RpcSandboxFastForwardRequest, RpcSandboxImpersonatedTransactionRequest,
+    RpcSandboxPatchStateError, RpcSandboxPatchStateRequest, RpcSandboxRestoreError,
+    RpcSandboxRestoreRequest, RpcSandboxSetTimeError, RpcSandboxSetTimeRequest,
+    RpcSandboxSnapshotError,
 };
+use near_primitives::borsh::BorshDeserialize;
+use near_primitives::transaction::Transaction;
 
 use super::{Params, RpcFrom, RpcRequest};
 
@@ -20,6 +27,37 @@ impl RpcRequest for RpcSandboxFastForwardRequest {
     }
 }
 
+impl RpcRequest for RpcSandboxRestoreRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcRequest for RpcSandboxDropSnapshotRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcRequest for RpcSandboxSetTimeRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcRequest for RpcSandboxImpersonatedTransactionRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        #[serde_as]
+        #[derive(serde::Deserialize)]
+        struct Payload(#[serde_as(as = "(Base64,)")] (Vec<u8>,));
+
+        let Payload((bytes,)) = Params::<Payload>::parse(value)?;
+        let transaction = Transaction::try_from_slice(&bytes)
+            .map_err(|err| RpcParseError(format!("Failed to decode transaction: {}", err)))?;
+        Ok(Self { transaction })
+    }
+}
+
 impl RpcFrom<actix::MailboxError> for RpcSandboxPatchStateError {
     fn rpc_from(error: actix::MailboxError) -> Self {
         Self::InternalError { error_message: error.to_string() }
@@ -31,3 +69,27 @@ impl RpcFrom<actix::MailboxError> for RpcSandboxFastForwardError {
         Self::InternalError { error_message: error.to_string() }
     }
 }
+
+impl RpcFrom<actix::MailboxError> for RpcSandboxSnapshotError {
+    fn rpc_from(error: actix::MailboxError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<actix::MailboxError> for RpcSandboxRestoreError {
+    fn rpc_from(error: actix::MailboxError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<actix::MailboxError> for RpcSandboxDropSnapshotError {
+    fn rpc_from(error: actix::MailboxError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<actix::MailboxError> for RpcSandboxSetTimeError {
+    fn rpc_from(error: actix::MailboxError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
@@ -49,6 +49,12 @@ impl RpcFrom<near_client_primitives::debug::DebugStatusResponse>
                     x,
                 )
             }
+            near_client_primitives::debug::DebugStatusResponse::ValidatorHealth(x) => {
+                near_jsonrpc_primitives::types::status::DebugStatusResponse::ValidatorHealth(x)
+            }
+            near_client_primitives::debug::DebugStatusResponse::BlockTimeline(x) => {
+                near_jsonrpc_primitives::types::status::DebugStatusResponse::BlockTimeline(x)
+            }
         }
     }
 }
@@ -83,6 +89,7 @@ impl RpcFrom<StatusError> for RpcStatusError {
             StatusError::InternalError { error_message } => Self::InternalError { error_message },
             StatusError::NodeIsSyncing => Self::NodeIsSyncing,
             StatusError::NoNewBlocks { elapsed } => Self::NoNewBlocks { elapsed },
+            StatusError::ValidatorUnhealthy { issues } => Self::ValidatorUnhealthy { issues },
             StatusError::EpochOutOfBounds { epoch_id } => Self::EpochOutOfBounds { epoch_id },
             StatusError::Unreachable { ref error_message } => {
                 tracing::warn!(target: "jsonrpc", "Unreachable error occurred: {}", error_message);
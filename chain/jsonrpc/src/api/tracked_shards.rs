@@ -0,0 +1,40 @@
+use near_client_primitives::types::UpdateTrackedShardsError;
+use near_jsonrpc_primitives::errors::RpcParseError;
+use near_jsonrpc_primitives::types::tracked_shards::{
+    RpcUpdateTrackedShardsError, RpcUpdateTrackedShardsRequest,
+};
+use serde_json::Value;
+
+use super::{Params, RpcFrom, RpcRequest};
+
+impl RpcRequest for RpcUpdateTrackedShardsRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcFrom<actix::MailboxError> for RpcUpdateTrackedShardsError {
+    fn rpc_from(error: actix::MailboxError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<UpdateTrackedShardsError> for RpcUpdateTrackedShardsError {
+    fn rpc_from(error: UpdateTrackedShardsError) -> Self {
+        match error {
+            UpdateTrackedShardsError::InvalidRequest(error_message) => {
+                Self::InvalidRequest { error_message }
+            }
+            UpdateTrackedShardsError::IOError(error_message) => {
+                Self::InternalError { error_message }
+            }
+            UpdateTrackedShardsError::Unreachable(ref error_message) => {
+                tracing::warn!(target: "jsonrpc", "Unreachable error occurred: {}", error_message);
+                crate::metrics::RPC_UNREACHABLE_ERROR_COUNT
+                    .with_label_values(&["RpcUpdateTrackedShardsError"])
+                    .inc();
+                Self::InternalError { error_message: error.to_string() }
+            }
+        }
+    }
+}
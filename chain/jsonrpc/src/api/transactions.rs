@@ -2,14 +2,17 @@ use serde_json::Value;
 use serde_with::base64::Base64;
 use serde_with::serde_as;
 
-use near_client_primitives::types::TxStatusError;
+use near_client_primitives::types::{DryRunTransactionError, TxStatusError};
+use near_crypto::Signature;
 use near_jsonrpc_primitives::errors::RpcParseError;
 use near_jsonrpc_primitives::types::transactions::{
-    RpcBroadcastTransactionRequest, RpcTransactionError, RpcTransactionResponse,
-    RpcTransactionStatusCommonRequest, TransactionInfo,
+    RpcBroadcastTransactionRequest, RpcDryRunTransactionError, RpcDryRunTransactionRequest,
+    RpcTransactionError, RpcTransactionResponse, RpcTransactionStatusCommonRequest,
+    TransactionInfo,
 };
 use near_primitives::borsh::BorshDeserialize;
-use near_primitives::transaction::SignedTransaction;
+use near_primitives::transaction::{SignedTransaction, Transaction};
+use near_primitives::types::NumBlocks;
 use near_primitives::views::FinalExecutionOutcomeViewEnum;
 
 use super::{Params, RpcFrom, RpcRequest};
@@ -32,6 +35,78 @@ impl RpcRequest for RpcTransactionStatusCommonRequest {
     }
 }
 
+impl RpcRequest for RpcDryRunTransactionRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        #[serde_as]
+        #[derive(serde::Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct Payload {
+            #[serde_as(as = "Option<Base64>")]
+            #[serde(default)]
+            signed_tx_base64: Option<Vec<u8>>,
+            #[serde_as(as = "Option<Base64>")]
+            #[serde(default)]
+            tx_base64: Option<Vec<u8>>,
+            #[serde(default = "default_max_blocks")]
+            max_blocks: NumBlocks,
+        }
+
+        fn default_max_blocks() -> NumBlocks {
+            10
+        }
+
+        let payload = Params::<Payload>::parse(value)?;
+        let (signed_transaction, verify_signature) =
+            match (payload.signed_tx_base64, payload.tx_base64) {
+                (Some(bytes), None) => (
+                    SignedTransaction::try_from_slice(&bytes).map_err(|err| {
+                        RpcParseError(format!("Failed to decode transaction: {}", err))
+                    })?,
+                    true,
+                ),
+                (None, Some(bytes)) => {
+                    let transaction = Transaction::try_from_slice(&bytes).map_err(|err| {
+                        RpcParseError(format!("Failed to decode transaction: {}", err))
+                    })?;
+                    let signature = Signature::empty(transaction.public_key.key_type());
+                    (SignedTransaction::new(signature, transaction), false)
+                }
+                _ => {
+                    return Err(RpcParseError(
+                        "Exactly one of `signed_tx_base64` and `tx_base64` is expected".to_string(),
+                    ))
+                }
+            };
+        Ok(Self { signed_transaction, verify_signature, max_blocks: payload.max_blocks })
+    }
+}
+
+impl RpcFrom<actix::MailboxError> for RpcDryRunTransactionError {
+    fn rpc_from(error: actix::MailboxError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<DryRunTransactionError> for RpcDryRunTransactionError {
+    fn rpc_from(error: DryRunTransactionError) -> Self {
+        match error {
+            DryRunTransactionError::InvalidTransaction(error) => Self::InvalidTransaction { error },
+            DryRunTransactionError::DoesNotTrackShard(signer_id) => {
+                Self::DoesNotTrackShard { signer_id }
+            }
+            DryRunTransactionError::NoSyncedBlocks => Self::NoSyncedBlocks,
+            DryRunTransactionError::IOError(error_message) => Self::InternalError { error_message },
+            DryRunTransactionError::Unreachable(ref error_message) => {
+                tracing::warn!(target: "jsonrpc", "Unreachable error occurred: {}", error_message);
+                crate::metrics::RPC_UNREACHABLE_ERROR_COUNT
+                    .with_label_values(&["RpcDryRunTransactionError"])
+                    .inc();
+                Self::InternalError { error_message: error.to_string() }
+            }
+        }
+    }
+}
+
 impl RpcFrom<actix::MailboxError> for RpcTransactionError {
     fn rpc_from(error: actix::MailboxError) -> Self {
         Self::InternalError { debug_info: error.to_string() }
@@ -55,7 +130,7 @@ impl RpcFrom<TxStatusError> for RpcTransactionError {
 
 impl RpcFrom<FinalExecutionOutcomeViewEnum> for RpcTransactionResponse {
     fn rpc_from(final_execution_outcome: FinalExecutionOutcomeViewEnum) -> Self {
-        Self { final_execution_outcome }
+        Self { final_execution_outcome, events: None }
     }
 }
 
@@ -13,11 +13,11 @@ use near_chain_configs::GenesisConfig;
 use near_client::{
     ClientActor, DebugStatus, GetBlock, GetBlockProof, GetChunk, GetClientConfig,
     GetExecutionOutcome, GetGasPrice, GetMaintenanceWindows, GetNetworkInfo,
-    GetNextLightClientBlock, GetProtocolConfig, GetReceipt, GetStateChanges,
+    GetNextLightClientBlock, GetProtocolConfig, GetReceipt, GetReceiptTree, GetStateChanges,
     GetStateChangesInBlock, GetValidatorInfo, GetValidatorOrdered, ProcessTxRequest,
-    ProcessTxResponse, Query, Status, TxStatus, ViewClientActor,
+    ProcessTxResponse, Query, Status, TxStatus, UpdateTrackedShards, ViewClientActor,
 };
-use near_client_primitives::types::GetSplitStorageInfo;
+use near_client_primitives::types::{DryRunTransaction, GetSplitStorageInfo};
 pub use near_jsonrpc_client as client;
 use near_jsonrpc_primitives::errors::RpcError;
 use near_jsonrpc_primitives::message::{Message, Request};
@@ -84,6 +84,14 @@ pub struct RpcConfig {
     // We disable it by default, as some of those endpoints might be quite CPU heavy.
     #[serde(default = "default_enable_debug_rpc")]
     pub enable_debug_rpc: bool,
+    // If true, enable the admin RPC endpoints which change the state of the node
+    // (like the one to change the tracked shards). Must not be exposed publicly.
+    #[serde(default)]
+    pub enable_admin_rpc: bool,
+    // If true, enable `EXPERIMENTAL_dry_run_tx`, which applies transactions and their receipts
+    // on the view client. Disabled by default, as a single request can be quite CPU heavy.
+    #[serde(default)]
+    pub enable_dry_run_rpc: bool,
     // For node developers only: if specified, the HTML files used to serve the debug pages will
     // be read from this directory, instead of the contents compiled into the binary. This allows
     // for quick iterative development.
@@ -99,6 +107,8 @@ impl Default for RpcConfig {
             polling_config: Default::default(),
             limits_config: Default::default(),
             enable_debug_rpc: false,
+            enable_admin_rpc: false,
+            enable_dry_run_rpc: false,
             experimental_debug_pages_src_path: None,
         }
     }
@@ -218,6 +228,8 @@ struct JsonRpcHandler {
     polling_config: RpcPollingConfig,
     genesis_config: GenesisConfig,
     enable_debug_rpc: bool,
+    enable_admin_rpc: bool,
+    enable_dry_run_rpc: bool,
     debug_pages_src_path: Option<PathBuf>,
 }
 
@@ -270,6 +282,9 @@ impl JsonRpcHandler {
 
         match request.method.as_ref() {
             // Handlers ordered alphabetically
+            "admin_update_tracked_shards" if self.enable_admin_rpc => {
+                process_method_call(request, |params| self.update_tracked_shards(params)).await
+            }
             "block" => process_method_call(request, |params| self.block(params)).await,
             "broadcast_tx_async" => {
                 process_method_call(request, |params| async {
@@ -322,6 +337,9 @@ impl JsonRpcHandler {
             "EXPERIMENTAL_check_tx" => {
                 process_method_call(request, |params| self.check_tx(params)).await
             }
+            "EXPERIMENTAL_dry_run_tx" if self.enable_dry_run_rpc => {
+                process_method_call(request, |params| self.dry_run_tx(params)).await
+            }
             "EXPERIMENTAL_genesis_config" => {
                 process_method_call(request, |_params: ()| async {
                     Result::<_, std::convert::Infallible>::Ok(&self.genesis_config)
@@ -340,6 +358,9 @@ impl JsonRpcHandler {
             "EXPERIMENTAL_receipt" => {
                 process_method_call(request, |params| self.receipt(params)).await
             }
+            "EXPERIMENTAL_receipt_tree" => {
+                process_method_call(request, |params| self.receipt_tree(params)).await
+            }
             "EXPERIMENTAL_tx_status" => {
                 process_method_call(request, |params| self.tx_status_common(params, true)).await
             }
@@ -360,6 +381,27 @@ impl JsonRpcHandler {
             "sandbox_fast_forward" => {
                 process_method_call(request, |params| self.sandbox_fast_forward(params)).await
             }
+            #[cfg(feature = "sandbox")]
+            "sandbox_snapshot" => {
+                process_method_call(request, |_params: ()| self.sandbox_snapshot()).await
+            }
+            #[cfg(feature = "sandbox")]
+            "sandbox_restore" => {
+                process_method_call(request, |params| self.sandbox_restore(params)).await
+            }
+            #[cfg(feature = "sandbox")]
+            "sandbox_drop_snapshot" => {
+                process_method_call(request, |params| self.sandbox_drop_snapshot(params)).await
+            }
+            #[cfg(feature = "sandbox")]
+            "sandbox_set_time" => {
+                process_method_call(request, |params| self.sandbox_set_time(params)).await
+            }
+            #[cfg(feature = "sandbox")]
+            "sandbox_send_impersonated_tx" => {
+                process_method_call(request, |params| self.sandbox_send_impersonated_tx(params))
+                    .await
+            }
             _ => Err(RpcError::method_not_found(request.method)),
         }
     }
@@ -572,6 +614,7 @@ impl JsonRpcHandler {
                         break Ok(
                             near_jsonrpc_primitives::types::transactions::RpcTransactionResponse {
                                 final_execution_outcome: tx_status,
+                                events: None,
                             },
                         )
                     }
@@ -685,6 +728,23 @@ impl JsonRpcHandler {
         }
     }
 
+    async fn dry_run_tx(
+        &self,
+        request_data: near_jsonrpc_primitives::types::transactions::RpcDryRunTransactionRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::transactions::RpcDryRunTransactionResponse,
+        near_jsonrpc_primitives::types::transactions::RpcDryRunTransactionError,
+    > {
+        let dry_run = self
+            .view_client_send(DryRunTransaction {
+                transaction: request_data.signed_transaction,
+                verify_signature: request_data.verify_signature,
+                max_blocks: request_data.max_blocks,
+            })
+            .await?;
+        Ok(near_jsonrpc_primitives::types::transactions::RpcDryRunTransactionResponse { dry_run })
+    }
+
     async fn send_tx_commit(
         &self,
         request_data: near_jsonrpc_primitives::types::transactions::RpcBroadcastTransactionRequest,
@@ -705,6 +765,7 @@ impl JsonRpcHandler {
             Ok(outcome) => {
                 return Ok(near_jsonrpc_primitives::types::transactions::RpcTransactionResponse {
                     final_execution_outcome: outcome,
+                    events: None,
                 });
             }
             Err(err @ near_jsonrpc_primitives::types::transactions::RpcTransactionError::InvalidTransaction {
@@ -797,6 +858,9 @@ impl JsonRpcHandler {
                     "/debug/api/requested_state_parts" => {
                         self.client_send(DebugStatus::RequestedStateParts).await?.rpc_into()
                     }
+                    "/debug/api/validator_health" => {
+                        self.client_send(DebugStatus::ValidatorHealth).await?.rpc_into()
+                    }
                     "/debug/api/peer_store" => self
                         .peer_manager_send(near_network::debug::GetDebugStatus::PeerStore)
                         .await?
@@ -839,6 +903,24 @@ impl JsonRpcHandler {
         }
     }
 
+    pub async fn debug_block_timeline(
+        &self,
+        height: BlockHeight,
+    ) -> Result<
+        Option<near_jsonrpc_primitives::types::status::RpcDebugStatusResponse>,
+        near_jsonrpc_primitives::types::status::RpcStatusError,
+    > {
+        if self.enable_debug_rpc {
+            let debug_status =
+                self.client_send(DebugStatus::BlockTimeline(height)).await?.rpc_into();
+            Ok(Some(near_jsonrpc_primitives::types::status::RpcDebugStatusResponse {
+                status_response: debug_status,
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
     pub async fn protocol_config(
         &self,
         request_data: near_jsonrpc_primitives::types::config::RpcProtocolConfigRequest,
@@ -873,7 +955,12 @@ impl JsonRpcHandler {
         near_jsonrpc_primitives::types::transactions::RpcTransactionError,
     > {
         let tx_status = self.tx_status_fetch(request_data.transaction_info, fetch_receipt).await?;
-        Ok(tx_status.rpc_into())
+        let mut response: near_jsonrpc_primitives::types::transactions::RpcTransactionResponse =
+            tx_status.rpc_into();
+        if fetch_receipt {
+            response.decode_events();
+        }
+        Ok(response)
     }
 
     async fn block(
@@ -921,6 +1008,19 @@ impl JsonRpcHandler {
         }
     }
 
+    async fn receipt_tree(
+        &self,
+        request_data: near_jsonrpc_primitives::types::receipts::RpcReceiptTreeRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::receipts::RpcReceiptTreeResponse,
+        near_jsonrpc_primitives::types::receipts::RpcReceiptTreeError,
+    > {
+        let receipt_tree = self
+            .view_client_send(GetReceiptTree { transaction_hash: request_data.tx_hash })
+            .await?;
+        Ok(near_jsonrpc_primitives::types::receipts::RpcReceiptTreeResponse { receipt_tree })
+    }
+
     async fn changes_in_block(
         &self,
         request: near_jsonrpc_primitives::types::changes::RpcStateChangesInBlockRequest,
@@ -1102,8 +1202,12 @@ impl JsonRpcHandler {
         near_jsonrpc_primitives::types::client_config::RpcClientConfigResponse,
         near_jsonrpc_primitives::types::client_config::RpcClientConfigError,
     > {
-        let client_config = self.client_send(GetClientConfig {}).await?;
-        Ok(near_jsonrpc_primitives::types::client_config::RpcClientConfigResponse { client_config })
+        let response = self.client_send(GetClientConfig {}).await?;
+        Ok(near_jsonrpc_primitives::types::client_config::RpcClientConfigResponse {
+            client_config: response.client_config,
+            applied_changes: response.applied_changes,
+            rejected_change: response.rejected_change,
+        })
     }
 
     pub async fn split_storage_info(
@@ -1116,6 +1220,32 @@ impl JsonRpcHandler {
         let split_storage = self.view_client_send(GetSplitStorageInfo {}).await?;
         Ok(RpcSplitStorageInfoResponse { result: split_storage })
     }
+
+    async fn update_tracked_shards(
+        &self,
+        request: near_jsonrpc_primitives::types::tracked_shards::RpcUpdateTrackedShardsRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::tracked_shards::RpcUpdateTrackedShardsResponse,
+        near_jsonrpc_primitives::types::tracked_shards::RpcUpdateTrackedShardsError,
+    > {
+        let near_jsonrpc_primitives::types::tracked_shards::RpcUpdateTrackedShardsRequest {
+            track_shards,
+            untrack_shards,
+            track_accounts,
+            untrack_accounts,
+        } = request;
+        let changes = self
+            .client_send(UpdateTrackedShards {
+                track_shards,
+                untrack_shards,
+                track_accounts,
+                untrack_accounts,
+            })
+            .await?;
+        Ok(near_jsonrpc_primitives::types::tracked_shards::RpcUpdateTrackedShardsResponse {
+            changes,
+        })
+    }
 }
 
 #[cfg(feature = "sandbox")]
@@ -1217,6 +1347,178 @@ impl JsonRpcHandler {
 
         Ok(near_jsonrpc_primitives::types::sandbox::RpcSandboxFastForwardResponse {})
     }
+
+    async fn sandbox_snapshot(
+        &self,
+    ) -> Result<
+        near_jsonrpc_primitives::types::sandbox::RpcSandboxSnapshotResponse,
+        near_jsonrpc_primitives::types::sandbox::RpcSandboxSnapshotError,
+    > {
+        use near_client_primitives::types::SandboxResponse;
+
+        let response = self
+            .client_addr
+            .send(
+                near_client_primitives::types::SandboxMessage::SandboxSnapshot.with_span_context(),
+            )
+            .await
+            .map_err(RpcFrom::rpc_from)?;
+        match response {
+            SandboxResponse::SandboxSnapshotCreated(snapshot_id) => {
+                Ok(near_jsonrpc_primitives::types::sandbox::RpcSandboxSnapshotResponse {
+                    snapshot_id,
+                })
+            }
+            SandboxResponse::SandboxSnapshotFailed(error_message) => Err(
+                near_jsonrpc_primitives::types::sandbox::RpcSandboxSnapshotError::InternalError {
+                    error_message,
+                },
+            ),
+            response => Err(
+                near_jsonrpc_primitives::types::sandbox::RpcSandboxSnapshotError::InternalError {
+                    error_message: format!("unexpected response: {:?}", response),
+                },
+            ),
+        }
+    }
+
+    async fn sandbox_restore(
+        &self,
+        restore_request: near_jsonrpc_primitives::types::sandbox::RpcSandboxRestoreRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::sandbox::RpcSandboxRestoreResponse,
+        near_jsonrpc_primitives::types::sandbox::RpcSandboxRestoreError,
+    > {
+        use near_client_primitives::types::SandboxResponse;
+        use near_jsonrpc_primitives::types::sandbox::RpcSandboxRestoreError;
+
+        let response = self
+            .client_addr
+            .send(
+                near_client_primitives::types::SandboxMessage::SandboxRestore(
+                    restore_request.snapshot_id,
+                )
+                .with_span_context(),
+            )
+            .await
+            .map_err(RpcFrom::rpc_from)?;
+        if let SandboxResponse::SandboxRestoreFailed(error_message) = response {
+            return Err(RpcSandboxRestoreError::RestoreFailed { error_message });
+        }
+
+        // The restore waits for the blocks being processed, which takes at most a few blocks.
+        timeout(self.polling_config.polling_timeout, async {
+            loop {
+                let restore_finished = self
+                    .client_addr
+                    .send(
+                        near_client_primitives::types::SandboxMessage::SandboxRestoreStatus
+                            .with_span_context(),
+                    )
+                    .await;
+                match restore_finished {
+                    Ok(SandboxResponse::SandboxRestoreFinished(true)) => break,
+                    Ok(SandboxResponse::SandboxRestoreFailed(error_message)) => {
+                        return Err(RpcSandboxRestoreError::RestoreFailed { error_message })
+                    }
+                    _ => (),
+                }
+                let _ = sleep(self.polling_config.polling_interval).await;
+            }
+            Ok(())
+        })
+        .await
+        .map_err(|_| RpcSandboxRestoreError::InternalError {
+            error_message: "sandbox failed to restore the snapshot in time".to_string(),
+        })??;
+
+        Ok(near_jsonrpc_primitives::types::sandbox::RpcSandboxRestoreResponse {})
+    }
+
+    async fn sandbox_drop_snapshot(
+        &self,
+        drop_request: near_jsonrpc_primitives::types::sandbox::RpcSandboxDropSnapshotRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::sandbox::RpcSandboxDropSnapshotResponse,
+        near_jsonrpc_primitives::types::sandbox::RpcSandboxDropSnapshotError,
+    > {
+        use near_client_primitives::types::SandboxResponse;
+        use near_jsonrpc_primitives::types::sandbox::RpcSandboxDropSnapshotError;
+
+        let response = self
+            .client_addr
+            .send(
+                near_client_primitives::types::SandboxMessage::SandboxDropSnapshot(
+                    drop_request.snapshot_id,
+                )
+                .with_span_context(),
+            )
+            .await
+            .map_err(RpcFrom::rpc_from)?;
+        match response {
+            SandboxResponse::SandboxSnapshotDropped => {
+                Ok(near_jsonrpc_primitives::types::sandbox::RpcSandboxDropSnapshotResponse {})
+            }
+            SandboxResponse::SandboxDropSnapshotFailed(error_message) => {
+                Err(RpcSandboxDropSnapshotError::DropFailed { error_message })
+            }
+            response => Err(RpcSandboxDropSnapshotError::InternalError {
+                error_message: format!("unexpected response: {:?}", response),
+            }),
+        }
+    }
+
+    async fn sandbox_set_time(
+        &self,
+        set_time_request: near_jsonrpc_primitives::types::sandbox::RpcSandboxSetTimeRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::sandbox::RpcSandboxSetTimeResponse,
+        near_jsonrpc_primitives::types::sandbox::RpcSandboxSetTimeError,
+    > {
+        use near_client_primitives::types::{SandboxMessage, SandboxResponse};
+        use near_jsonrpc_primitives::types::sandbox::{
+            RpcSandboxSetTimeError, RpcSandboxSetTimeRequest,
+        };
+
+        let message = match set_time_request {
+            RpcSandboxSetTimeRequest::Timestamp(timestamp) => {
+                SandboxMessage::SandboxSetTimestamp(timestamp)
+            }
+            RpcSandboxSetTimeRequest::Offset(offset) => SandboxMessage::SandboxOffsetTime(offset),
+        };
+        let response =
+            self.client_addr.send(message.with_span_context()).await.map_err(RpcFrom::rpc_from)?;
+        match response {
+            SandboxResponse::SandboxNoResponse => {
+                Ok(near_jsonrpc_primitives::types::sandbox::RpcSandboxSetTimeResponse {})
+            }
+            SandboxResponse::SandboxSetTimeFailed(error_message) => {
+                Err(RpcSandboxSetTimeError::SetTimeFailed { error_message })
+            }
+            response => Err(RpcSandboxSetTimeError::InternalError {
+                error_message: format!("unexpected response: {:?}", response),
+            }),
+        }
+    }
+
+    /// Executes the transaction on behalf of its signer without checking its signature, access
+    /// key and nonce, and waits for its outcome like `broadcast_tx_commit`.
+    async fn sandbox_send_impersonated_tx(
+        &self,
+        request: near_jsonrpc_primitives::types::sandbox::RpcSandboxImpersonatedTransactionRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::transactions::RpcTransactionResponse,
+        near_jsonrpc_primitives::types::transactions::RpcTransactionError,
+    > {
+        let signed_transaction =
+            near_primitives::sandbox::impersonation::impersonate(request.transaction);
+        self.send_tx_commit(
+            near_jsonrpc_primitives::types::transactions::RpcBroadcastTransactionRequest {
+                signed_transaction,
+            },
+        )
+        .await
+    }
 }
 
 #[cfg(feature = "test_features")]
@@ -1382,6 +1684,17 @@ async fn debug_block_status_handler(
     }
 }
 
+async fn debug_block_timeline_handler(
+    path: web::Path<u64>,
+    handler: web::Data<JsonRpcHandler>,
+) -> Result<HttpResponse, HttpError> {
+    match handler.debug_block_timeline(*path).await {
+        Ok(Some(value)) => Ok(HttpResponse::Ok().json(&value)),
+        Ok(None) => Ok(HttpResponse::MethodNotAllowed().finish()),
+        Err(_) => Ok(HttpResponse::ServiceUnavailable().finish()),
+    }
+}
+
 fn health_handler(
     handler: web::Data<JsonRpcHandler>,
 ) -> impl Future<Output = Result<HttpResponse, HttpError>> {
@@ -1525,6 +1838,8 @@ pub fn start_http(
         polling_config,
         limits_config,
         enable_debug_rpc,
+        enable_admin_rpc,
+        enable_dry_run_rpc,
         experimental_debug_pages_src_path: debug_pages_src_path,
     } = config;
     let prometheus_addr = prometheus_addr.filter(|it| it != &addr.to_string());
@@ -1541,6 +1856,8 @@ pub fn start_http(
                 polling_config,
                 genesis_config: genesis_config.clone(),
                 enable_debug_rpc,
+                enable_admin_rpc,
+                enable_dry_run_rpc,
                 debug_pages_src_path: debug_pages_src_path.clone().map(Into::into),
             }))
             .app_data(web::JsonConfig::default().limit(limits_config.json_payload_max_size))
@@ -1567,6 +1884,10 @@ pub fn start_http(
                 web::resource("/debug/api/block_status/{starting_height}")
                     .route(web::get().to(debug_block_status_handler)),
             )
+            .service(
+                web::resource("/debug/api/block_timeline/{height}")
+                    .route(web::get().to(debug_block_timeline_handler)),
+            )
             .service(
                 web::resource("/debug/client_config").route(web::get().to(client_config_handler)),
             )
@@ -1,6 +1,7 @@
 use crate::network_protocol::{
-    Encoding, Handshake, HandshakeFailureReason, PartialEdgeInfo, PeerChainInfoV2, PeerIdOrHash,
-    PeerMessage, Ping, Pong, RawRoutedMessage, RoutedMessageBody, RoutingTableUpdate,
+    Edge, Encoding, Handshake, HandshakeFailureReason, PartialEdgeInfo, PeerChainInfoV2,
+    PeerIdOrHash, PeerMessage, Ping, Pong, RawRoutedMessage, RoutedMessageBody, RoutingTableUpdate,
+    SignedAccountData, SyncAccountsData,
 };
 use crate::tcp;
 use crate::types::{
@@ -15,9 +16,11 @@ use near_primitives::hash::CryptoHash;
 use near_primitives::network::{AnnounceAccount, PeerId};
 use near_primitives::types::{BlockHeight, ShardId};
 use near_primitives::version::{ProtocolVersion, PROTOCOL_VERSION};
+use std::collections::VecDeque;
 use std::fmt;
 use std::io;
 use std::net::SocketAddr;
+use std::sync::Arc;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
 /// Represents a connection to a peer, and provides only minimal functionality.
@@ -36,6 +39,9 @@ pub struct Connection {
     // If this field is true, it means we expect to receive a message that won't parse as a proto, and
     // will accept and drop one such message without giving an error.
     borsh_message_expected: bool,
+    // a single PeerMessage may correspond to more than one Message (e.g. a SyncRoutingTable
+    // carrying both edges and accounts). The ones not yet returned by recv() are kept here.
+    pending_messages: VecDeque<(Message, Instant)>,
 }
 
 // The types of messages it's possible to route to a target PeerId via the connected peer as a first hop
@@ -45,6 +51,7 @@ pub struct Connection {
 pub enum RoutedMessage {
     Ping { nonce: u64 },
     Pong { nonce: u64, source: PeerId },
+    StateRequestHeader(ShardId, CryptoHash),
     StateRequestPart(ShardId, CryptoHash, u64),
     VersionedStateResponse(StateResponseInfo),
     PartialEncodedChunkRequest(PartialEncodedChunkRequestMsg),
@@ -62,6 +69,9 @@ impl fmt::Debug for RoutedMessage {
         match self {
             Self::Ping { nonce } => write!(f, "Ping({})", nonce),
             Self::Pong { nonce, source } => write!(f, "Pong({}, {})", nonce, source),
+            Self::StateRequestHeader(shard_id, hash) => {
+                write!(f, "StateRequestHeader({}, {})", shard_id, hash)
+            }
             Self::StateRequestPart(shard_id, hash, part_id) => {
                 write!(f, "StateRequestPart({}, {}, {})", shard_id, hash, part_id)
             }
@@ -89,6 +99,10 @@ impl fmt::Debug for RoutedMessage {
 #[derive(Clone, strum::IntoStaticStr)]
 pub enum DirectMessage {
     AnnounceAccounts(Vec<AnnounceAccount>),
+    /// Routing table edges, as sent in SyncRoutingTable messages.
+    Edges(Vec<Edge>),
+    /// AccountData of the current validators, as sent in SyncAccountsData messages.
+    AccountsData(Vec<Arc<SignedAccountData>>),
     BlockRequest(CryptoHash),
     Block(Block),
     BlockHeadersRequest(Vec<CryptoHash>),
@@ -105,6 +119,12 @@ impl fmt::Debug for DirectMessage {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::AnnounceAccounts(a) => write!(f, "AnnounceAccounts({:?})", a),
+            Self::Edges(e) => write!(f, "Edges({} edges)", e.len()),
+            Self::AccountsData(a) => write!(
+                f,
+                "AccountsData({:?})",
+                a.iter().map(|d| &d.account_key).collect::<Vec<_>>()
+            ),
             Self::BlockRequest(r) => write!(f, "BlockRequest({})", r),
             Self::Block(b) => write!(f, "Block(#{} {})", b.header().height(), b.header().hash()),
             Self::BlockHeadersRequest(r) => write!(f, "BlockHeadersRequest({:?})", r),
@@ -244,6 +264,7 @@ impl Connection {
             my_peer_id,
             route_cache: lru::LruCache::new(1_000_000),
             borsh_message_expected: false,
+            pending_messages: VecDeque::new(),
         };
         peer.do_handshake(
             my_protocol_version.unwrap_or(PROTOCOL_VERSION),
@@ -312,6 +333,7 @@ impl Connection {
             peer_id,
             route_cache: lru::LruCache::new(1_000_000),
             borsh_message_expected,
+            pending_messages: VecDeque::new(),
         })
     }
 
@@ -363,6 +385,16 @@ impl Connection {
             DirectMessage::AnnounceAccounts(accounts) => {
                 PeerMessage::SyncRoutingTable(RoutingTableUpdate { edges: Vec::new(), accounts })
             }
+            DirectMessage::Edges(edges) => {
+                PeerMessage::SyncRoutingTable(RoutingTableUpdate { edges, accounts: Vec::new() })
+            }
+            DirectMessage::AccountsData(accounts_data) => {
+                PeerMessage::SyncAccountsData(SyncAccountsData {
+                    accounts_data,
+                    requesting_full_sync: false,
+                    incremental: true,
+                })
+            }
             DirectMessage::BlockRequest(h) => PeerMessage::BlockRequest(h),
             DirectMessage::Block(b) => PeerMessage::Block(b),
             DirectMessage::BlockHeadersRequest(h) => PeerMessage::BlockHeadersRequest(h),
@@ -372,6 +404,18 @@ impl Connection {
         self.stream.write_message(&peer_msg).await
     }
 
+    /// Asks the peer to send us all the AccountData it knows about. It will be received
+    /// as a DirectMessage::AccountsData from recv().
+    pub async fn request_accounts_data(&mut self) -> io::Result<()> {
+        self.stream
+            .write_message(&PeerMessage::SyncAccountsData(SyncAccountsData {
+                accounts_data: Vec::new(),
+                requesting_full_sync: true,
+                incremental: false,
+            }))
+            .await
+    }
+
     // Try to send a routed PeerMessage corresponding to the given RoutedMessage
     pub async fn send_routed_message(
         &mut self,
@@ -386,6 +430,9 @@ impl Connection {
             RoutedMessage::Pong { nonce, source } => {
                 RoutedMessageBody::Pong(Pong { nonce, source })
             }
+            RoutedMessage::StateRequestHeader(shard_id, block_hash) => {
+                RoutedMessageBody::StateRequestHeader(shard_id, block_hash)
+            }
             RoutedMessage::StateRequestPart(shard_id, block_hash, part_id) => {
                 RoutedMessageBody::StateRequestPart(shard_id, block_hash, part_id)
             }
@@ -434,6 +481,9 @@ impl Connection {
             RoutedMessageBody::VersionedStateResponse(state_response_info) => {
                 Some(RoutedMessage::VersionedStateResponse(state_response_info.clone()))
             }
+            RoutedMessageBody::StateRequestHeader(shard_id, hash) => {
+                Some(RoutedMessage::StateRequestHeader(*shard_id, *hash))
+            }
             RoutedMessageBody::StateRequestPart(shard_id, hash, part_id) => {
                 Some(RoutedMessage::StateRequestPart(*shard_id, *hash, *part_id))
             }
@@ -450,6 +500,9 @@ impl Connection {
     /// Reads from the socket until we receive some message that we care to pass to the caller
     /// (that is, represented in `DirectMessage` or `RoutedMessage`).
     pub async fn recv(&mut self) -> io::Result<(Message, Instant)> {
+        if let Some(m) = self.pending_messages.pop_front() {
+            return Ok(m);
+        }
         loop {
             let (msg, timestamp) = match self.stream.recv_message().await {
                 Ok(m) => m,
@@ -474,10 +527,25 @@ impl Connection {
                     }
                 }
                 PeerMessage::SyncRoutingTable(r) => {
-                    return Ok((
-                        Message::Direct(DirectMessage::AnnounceAccounts(r.accounts)),
-                        timestamp,
-                    ));
+                    if !r.edges.is_empty() {
+                        self.pending_messages
+                            .push_back((Message::Direct(DirectMessage::Edges(r.edges)), timestamp));
+                    }
+                    if !r.accounts.is_empty() || self.pending_messages.is_empty() {
+                        self.pending_messages.push_back((
+                            Message::Direct(DirectMessage::AnnounceAccounts(r.accounts)),
+                            timestamp,
+                        ));
+                    }
+                    return Ok(self.pending_messages.pop_front().unwrap());
+                }
+                PeerMessage::SyncAccountsData(d) => {
+                    if !d.accounts_data.is_empty() {
+                        return Ok((
+                            Message::Direct(DirectMessage::AccountsData(d.accounts_data)),
+                            timestamp,
+                        ));
+                    }
                 }
                 PeerMessage::BlockRequest(hash) => {
                     return Ok((Message::Direct(DirectMessage::BlockRequest(hash)), timestamp));
@@ -71,6 +71,12 @@ impl TransactionPool {
         }
     }
 
+    /// Changes the size limit of the pool. Transactions already in the pool are kept even if
+    /// they don't fit into the new limit.
+    pub fn set_total_transaction_size_limit(&mut self, total_transaction_size_limit: Option<u64>) {
+        self.total_transaction_size_limit = total_transaction_size_limit;
+    }
+
     fn key(&self, account_id: &AccountId, public_key: &PublicKey) -> PoolKey {
         let mut v = public_key.try_to_vec().unwrap();
         v.extend_from_slice(&self.key_seed);
@@ -166,6 +172,11 @@ impl TransactionPool {
         self.transaction_pool_size_metric.set(self.total_transaction_size as i64);
     }
 
+    /// Returns all the transactions in the pool, in no particular order.
+    pub fn transactions(&self) -> impl Iterator<Item = &SignedTransaction> {
+        self.transactions.values().flatten()
+    }
+
     /// Returns the number of unique transactions in the pool.
     pub fn len(&self) -> usize {
         self.unique_transactions.len()
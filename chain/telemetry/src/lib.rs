@@ -3,9 +3,11 @@ mod metrics;
 use actix::{Actor, Addr, Context, Handler};
 use awc::{Client, Connector};
 use futures::FutureExt;
+use near_crypto::{KeyType, SecretKey};
 use near_o11y::{handler_debug_span, OpenTelemetrySpanExt, WithSpanContext, WithSpanContextExt};
 use near_performance_metrics_macros::perf;
 use near_primitives::static_clock::StaticClock;
+use near_primitives::telemetry::sign_telemetry_with_node_key;
 use std::ops::Sub;
 use std::time::{Duration, Instant};
 
@@ -41,16 +43,19 @@ pub struct TelemetryActor {
     config: TelemetryConfig,
     client: Client,
     last_telemetry_update: Instant,
+    /// Signs the reports, so that the receivers can tell that they come from the node in their
+    /// `node_id`.
+    node_key: SecretKey,
 }
 
 impl Default for TelemetryActor {
     fn default() -> Self {
-        Self::new(TelemetryConfig::default())
+        Self::new(TelemetryConfig::default(), SecretKey::from_random(KeyType::ED25519))
     }
 }
 
 impl TelemetryActor {
-    pub fn new(config: TelemetryConfig) -> Self {
+    pub fn new(config: TelemetryConfig, node_key: SecretKey) -> Self {
         for endpoint in config.endpoints.iter() {
             if endpoint.is_empty() {
                 panic!(
@@ -70,6 +75,7 @@ impl TelemetryActor {
             client,
             // Let the node report telemetry info at the startup.
             last_telemetry_update: std::time::Instant::now().sub(reporting_interval),
+            node_key,
         }
     }
 }
@@ -91,6 +97,8 @@ impl Handler<WithSpanContext<TelemetryEvent>> for TelemetryActor {
             // request per `self.config.reporting_interval`.
             return;
         }
+        let mut content = msg.content;
+        sign_telemetry_with_node_key(&mut content, &self.node_key);
         for endpoint in self.config.endpoints.iter() {
             let endpoint = endpoint.clone();
             near_performance_metrics::actix::spawn(
@@ -98,7 +106,7 @@ impl Handler<WithSpanContext<TelemetryEvent>> for TelemetryActor {
                 self.client
                     .post(endpoint.clone())
                     .insert_header(("Content-Type", "application/json"))
-                    .send_json(&msg.content)
+                    .send_json(&content)
                     .map(move |response| {
                         let result = if let Err(error) = response {
                             tracing::warn!(
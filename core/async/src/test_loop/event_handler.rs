@@ -219,3 +219,18 @@ pub fn interval<Data, Event: Clone + PartialEq>(
         move |actual_event| actual_event == &event_cloned,
     )
 }
+
+/// An event handler that handles no events, but allows every event that is
+/// still in the queue at the end of the test to be dropped. Register it last
+/// in simulations that never become idle, e.g. where network messages are
+/// always in flight, so that the test can end at any point in time.
+pub fn ignore_events_on_drop<Data, Event>() -> LoopEventHandler<Data, Event> {
+    LoopEventHandler {
+        inner: Box::new(LoopEventHandlerImplByFunction {
+            initial_event: None,
+            handler: Box::new(|event, _, _| Err(event)),
+            ok_to_drop: Box::new(|_| true),
+            context: None,
+        }),
+    }
+}
@@ -77,7 +77,7 @@ fn default_num_concurrent_requests() -> u32 {
     DEFAULT_STATE_SYNC_NUM_CONCURRENT_REQUESTS_EXTERNAL
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
 pub struct ExternalStorageConfig {
     /// Location of state parts.
     pub location: ExternalStorageLocation,
@@ -87,7 +87,7 @@ pub struct ExternalStorageConfig {
     pub num_concurrent_requests: u32,
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
 pub enum ExternalStorageLocation {
     S3 {
         /// Location of state dumps on S3.
@@ -101,7 +101,7 @@ pub enum ExternalStorageLocation {
 }
 
 /// Configures how to dump state to external storage.
-#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
 pub struct DumpConfig {
     /// Specifies where to write the obtained state parts.
     pub location: ExternalStorageLocation,
@@ -116,7 +116,7 @@ pub struct DumpConfig {
 }
 
 /// Configures how to fetch state parts during state sync.
-#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq)]
 pub enum SyncConfig {
     /// Syncs state from the peers without reading anything from external storage.
     Peers,
@@ -130,7 +130,7 @@ impl Default for SyncConfig {
     }
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default)]
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq)]
 /// Options for dumping state to S3.
 pub struct StateSyncConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -175,15 +175,15 @@ pub struct ClientConfig {
     /// Sync height threshold: below this difference in height don't start syncing.
     pub sync_height_threshold: BlockHeightDelta,
     /// How much time to wait after initial header sync
-    pub header_sync_initial_timeout: Duration,
+    pub header_sync_initial_timeout: MutableConfigValue<Duration>,
     /// How much time to wait after some progress is made in header sync
-    pub header_sync_progress_timeout: Duration,
+    pub header_sync_progress_timeout: MutableConfigValue<Duration>,
     /// How much time to wait before banning a peer in header sync if sync is too slow
-    pub header_sync_stall_ban_timeout: Duration,
+    pub header_sync_stall_ban_timeout: MutableConfigValue<Duration>,
     /// Expected increase of header head weight per second during header sync
     pub header_sync_expected_height_per_second: u64,
     /// How long to wait for a response during state sync
-    pub state_sync_timeout: Duration,
+    pub state_sync_timeout: MutableConfigValue<Duration>,
     /// Minimum number of peers to start syncing.
     pub min_num_peers: usize,
     /// Period between logging summary information.
@@ -211,9 +211,9 @@ pub struct ClientConfig {
     /// Behind this horizon header fetch kicks in.
     pub block_header_fetch_horizon: BlockHeightDelta,
     /// Garbage collection configuration.
-    pub gc: GCConfig,
+    pub gc: MutableConfigValue<GCConfig>,
     /// Accounts that this client tracks
-    pub tracked_accounts: Vec<AccountId>,
+    pub tracked_accounts: MutableConfigValue<Vec<AccountId>>,
     /// Shards that this client tracks
     pub tracked_shards: Vec<ShardId>,
     /// Rotate between these sets of tracked shards.
@@ -237,7 +237,7 @@ pub struct ClientConfig {
     /// Max burnt gas per view method.  If present, overrides value stored in
     /// genesis file.  The value only affects the RPCs without influencing the
     /// protocol thus changing it per-node doesn’t affect the blockchain.
-    pub max_gas_burnt_view: Option<Gas>,
+    pub max_gas_burnt_view: MutableConfigValue<Option<Gas>>,
     /// Re-export storage layer statistics as prometheus metrics.
     pub enable_statistics_export: bool,
     /// Number of threads to execute background migration work in client.
@@ -250,12 +250,12 @@ pub struct ClientConfig {
     /// If disabled, the node will do Block Sync instead of State Sync.
     pub state_sync_enabled: bool,
     /// Options for syncing state.
-    pub state_sync: StateSyncConfig,
+    pub state_sync: MutableConfigValue<StateSyncConfig>,
     /// Testing only. Makes a state snapshot after every epoch, but also every N blocks. The first snapshot is done after processng the first block.
     pub state_snapshot_every_n_blocks: Option<u64>,
     /// Limit of the size of per-shard transaction pool measured in bytes. If not set, the size
     /// will be unbounded.
-    pub transaction_pool_size_limit: Option<u64>,
+    pub transaction_pool_size_limit: MutableConfigValue<Option<u64>>,
     // Allows more detailed logging, for example a list of orphaned blocks.
     pub enable_multiline_logging: bool,
 }
@@ -293,10 +293,22 @@ impl ClientConfig {
             sync_check_period: Duration::from_millis(100),
             sync_step_period: Duration::from_millis(10),
             sync_height_threshold: 1,
-            header_sync_initial_timeout: Duration::from_secs(10),
-            header_sync_progress_timeout: Duration::from_secs(2),
-            header_sync_stall_ban_timeout: Duration::from_secs(30),
-            state_sync_timeout: Duration::from_secs(TEST_STATE_SYNC_TIMEOUT),
+            header_sync_initial_timeout: MutableConfigValue::new(
+                Duration::from_secs(10),
+                "header_sync_initial_timeout",
+            ),
+            header_sync_progress_timeout: MutableConfigValue::new(
+                Duration::from_secs(2),
+                "header_sync_progress_timeout",
+            ),
+            header_sync_stall_ban_timeout: MutableConfigValue::new(
+                Duration::from_secs(30),
+                "header_sync_stall_ban_timeout",
+            ),
+            state_sync_timeout: MutableConfigValue::new(
+                Duration::from_secs(TEST_STATE_SYNC_TIMEOUT),
+                "state_sync_timeout",
+            ),
             header_sync_expected_height_per_second: 1,
             min_num_peers: 1,
             log_summary_period: Duration::from_secs(10),
@@ -313,8 +325,11 @@ impl ClientConfig {
             ),
             doosmslug_step_period: Duration::from_millis(100),
             block_header_fetch_horizon: 50,
-            gc: GCConfig { gc_blocks_limit: 100, ..GCConfig::default() },
-            tracked_accounts: vec![],
+            gc: MutableConfigValue::new(
+                GCConfig { gc_blocks_limit: 100, ..GCConfig::default() },
+                "gc",
+            ),
+            tracked_accounts: MutableConfigValue::new(vec![], "tracked_accounts"),
             tracked_shards: vec![],
             tracked_shard_schedule: vec![],
             archive,
@@ -324,15 +339,18 @@ impl ClientConfig {
             epoch_sync_enabled,
             view_client_throttle_period: Duration::from_secs(1),
             trie_viewer_state_size_limit: None,
-            max_gas_burnt_view: None,
+            max_gas_burnt_view: MutableConfigValue::new(None, "max_gas_burnt_view"),
             enable_statistics_export: true,
             client_background_migration_threads: 1,
             flat_storage_creation_enabled: true,
             flat_storage_creation_period: Duration::from_secs(1),
             state_sync_enabled,
-            state_sync: StateSyncConfig::default(),
+            state_sync: MutableConfigValue::new(StateSyncConfig::default(), "state_sync"),
             state_snapshot_every_n_blocks: None,
-            transaction_pool_size_limit: None,
+            transaction_pool_size_limit: MutableConfigValue::new(
+                None,
+                "transaction_pool_size_limit",
+            ),
             enable_multiline_logging: false,
         }
     }
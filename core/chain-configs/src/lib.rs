@@ -14,4 +14,7 @@ pub use genesis_config::{
     get_initial_supply, stream_records_from_file, Genesis, GenesisChangeConfig, GenesisConfig,
     GenesisContents, GenesisRecords, GenesisValidationMode, ProtocolConfig, ProtocolConfigView,
 };
-pub use updateable_config::{MutableConfigValue, UpdateableClientConfig};
+pub use updateable_config::{
+    ConfigValueChange, MutableConfigValue, TrieCacheSizeLimits, UpdateableClientConfig,
+    UpdateableClientConfigError,
+};
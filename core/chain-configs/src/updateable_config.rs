@@ -1,7 +1,13 @@
-use near_primitives::types::BlockHeight;
+use crate::client_config::{GCConfig, StateSyncConfig};
+use crate::ClientConfig;
+use near_primitives::shard_layout::ShardUId;
+use near_primitives::static_clock::StaticClock;
+use near_primitives::types::{AccountId, BlockHeight, Gas};
 use serde::{Deserialize, Serialize, Serializer};
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 /// A wrapper for a config value that can be updated while the node is running.
 /// When initializing sub-objects (e.g. `ShardsManager`), please make sure to
@@ -32,38 +38,43 @@ impl<T: Serialize> Serialize for MutableConfigValue<T> {
     }
 }
 
-impl<T: Copy + PartialEq + Debug> MutableConfigValue<T> {
+impl<T: Clone + PartialEq + Debug> MutableConfigValue<T> {
     /// Initializes a value.
     /// `field_name` is needed to export the config value as a prometheus metric.
     pub fn new(val: T, field_name: &str) -> Self {
         let res = Self {
-            value: Arc::new(Mutex::new(val)),
+            value: Arc::new(Mutex::new(val.clone())),
             field_name: field_name.to_string(),
             #[cfg(feature = "metrics")]
             last_update: near_primitives::static_clock::StaticClock::utc(),
         };
-        res.set_metric_value(val, 1);
+        res.set_metric_value(&val, 1);
         res
     }
 
     pub fn get(&self) -> T {
-        *self.value.lock().unwrap()
+        self.value.lock().unwrap().clone()
     }
 
-    pub fn update(&self, val: T) {
+    /// Sets a new value and returns a description of the change, or `None`
+    /// if the value remained the same.
+    pub fn update(&self, val: T) -> Option<ConfigValueChange> {
         let mut lock = self.value.lock().unwrap();
         if *lock != val {
             tracing::info!(target: "config", "Updated config field '{}' from {:?} to {:?}", self.field_name, *lock, val);
-            self.set_metric_value(*lock, 0);
+            let change = ConfigValueChange::new(&self.field_name, &*lock, &val);
+            self.set_metric_value(&*lock, 0);
             *lock = val;
-            self.set_metric_value(val, 1);
+            self.set_metric_value(&*lock, 1);
+            Some(change)
         } else {
             tracing::info!(target: "config", "Mutable config field '{}' remains the same: {:?}", self.field_name, val);
+            None
         }
     }
 
     #[cfg(feature = "metrics")]
-    fn set_metric_value(&self, value: T, metric_value: i64) {
+    fn set_metric_value(&self, value: &T, metric_value: i64) {
         // Use field_name as a label to tell different mutable config values apart.
         // Use timestamp as a label to give some idea to the node operator (or
         // people helping them debug their node) when exactly and what values
@@ -80,12 +91,219 @@ impl<T: Copy + PartialEq + Debug> MutableConfigValue<T> {
     }
 
     #[cfg(not(feature = "metrics"))]
-    fn set_metric_value(&self, _value: T, _metric_value: i64) {}
+    fn set_metric_value(&self, _value: &T, _metric_value: i64) {}
 }
 
-#[derive(Default, Clone, Serialize, Deserialize)]
+/// Describes a change of a mutable config value applied while the node was running.
+#[derive(Clone, Debug, Serialize)]
+pub struct ConfigValueChange {
+    pub field_name: String,
+    pub old_value: String,
+    pub new_value: String,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl ConfigValueChange {
+    pub fn new<T: Debug>(field_name: &str, old_value: &T, new_value: &T) -> Self {
+        Self {
+            field_name: field_name.to_string(),
+            old_value: format!("{:?}", old_value),
+            new_value: format!("{:?}", new_value),
+            updated_at: StaticClock::utc(),
+        }
+    }
+}
+
+/// Size limits of the trie caches.
+/// Mirrors the size-related fields of `TrieCacheConfig` of `near-store`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TrieCacheSizeLimits {
+    /// Limit of the memory consumption of the trie cache of a shard.
+    pub default_max_bytes: u64,
+    /// Overwrites `default_max_bytes` for specific shards.
+    pub per_shard_max_bytes: HashMap<ShardUId, u64>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum UpdateableClientConfigError {
+    #[error("'{field_name}' must be greater than 0")]
+    ZeroValue { field_name: &'static str },
+    #[error("Changing '{field_name}' while the node is running is not supported: {reason}")]
+    UnsafeChange { field_name: &'static str, reason: String },
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 /// A subset of Config that can be updated white the node is running.
 pub struct UpdateableClientConfig {
     /// Graceful shutdown at expected block height.
     pub expected_shutdown: Option<BlockHeight>,
+    /// Garbage collection limits. `gc_num_epochs_to_keep` can't be changed.
+    pub gc: GCConfig,
+    /// Limit of the size of per-shard transaction pool measured in bytes.
+    pub transaction_pool_size_limit: Option<u64>,
+    /// Gas limit of view function calls.
+    pub max_gas_burnt_view: Option<Gas>,
+    /// Header sync timeouts.
+    pub header_sync_initial_timeout: Duration,
+    pub header_sync_progress_timeout: Duration,
+    pub header_sync_stall_ban_timeout: Duration,
+    /// State sync timeout before switching to another peer.
+    pub state_sync_timeout: Duration,
+    /// Accounts whose shards the node tracks.
+    /// Only removing accounts or adding accounts of already tracked shards is allowed.
+    pub tracked_accounts: Vec<AccountId>,
+    /// State sync settings. Enabling, disabling or relocating the state dump can't be done
+    /// at runtime.
+    pub state_sync: StateSyncConfig,
+    /// Size limits of the trie caches used by the client.
+    pub trie_cache: TrieCacheSizeLimits,
+    /// Size limits of the trie caches used by view calls.
+    pub view_trie_cache: TrieCacheSizeLimits,
+}
+
+impl UpdateableClientConfig {
+    /// Checks the values that would be rejected regardless of the current config.
+    pub fn validate(&self) -> Result<(), UpdateableClientConfigError> {
+        let non_zero = [
+            ("gc.gc_blocks_limit", self.gc.gc_blocks_limit),
+            ("gc.gc_fork_clean_step", self.gc.gc_fork_clean_step),
+            ("transaction_pool_size_limit", self.transaction_pool_size_limit.unwrap_or(1)),
+            ("max_gas_burnt_view", self.max_gas_burnt_view.unwrap_or(1)),
+            ("header_sync_initial_timeout", self.header_sync_initial_timeout.as_nanos() as u64),
+            ("header_sync_progress_timeout", self.header_sync_progress_timeout.as_nanos() as u64),
+            ("header_sync_stall_ban_timeout", self.header_sync_stall_ban_timeout.as_nanos() as u64),
+            ("state_sync_timeout", self.state_sync_timeout.as_nanos() as u64),
+            ("store.trie_cache.default_max_bytes", self.trie_cache.default_max_bytes),
+            ("store.view_trie_cache.default_max_bytes", self.view_trie_cache.default_max_bytes),
+        ];
+        for (field_name, value) in non_zero {
+            if value == 0 {
+                return Err(UpdateableClientConfigError::ZeroValue { field_name });
+            }
+        }
+        for (field_name, limits) in [
+            ("store.trie_cache.per_shard_max_bytes", &self.trie_cache),
+            ("store.view_trie_cache.per_shard_max_bytes", &self.view_trie_cache),
+        ] {
+            if limits.per_shard_max_bytes.values().any(|max_bytes| *max_bytes == 0) {
+                return Err(UpdateableClientConfigError::ZeroValue { field_name });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ClientConfig {
+    /// Checks that the update only changes what can be safely changed without a restart.
+    /// Checks that depend on the chain state are done by the client.
+    pub fn check_update(
+        &self,
+        update: &UpdateableClientConfig,
+    ) -> Result<(), UpdateableClientConfigError> {
+        update.validate()?;
+        let gc = self.gc.get();
+        if gc.gc_num_epochs_to_keep() != update.gc.gc_num_epochs_to_keep() {
+            return Err(UpdateableClientConfigError::UnsafeChange {
+                field_name: "gc.gc_num_epochs_to_keep",
+                reason: format!(
+                    "the runtime and the state sync rely on the data of the last {} epochs",
+                    gc.gc_num_epochs_to_keep()
+                ),
+            });
+        }
+        let dump_location = self.state_sync.get().dump.map(|dump| dump.location);
+        let new_dump_location = update.state_sync.dump.as_ref().map(|dump| &dump.location);
+        if dump_location.as_ref() != new_dump_location {
+            return Err(UpdateableClientConfigError::UnsafeChange {
+                field_name: "state_sync.dump.location",
+                reason: "state dump threads are started with the node".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Updates the mutable fields of the config and returns the applied changes.
+    /// Call `check_update()` first.
+    pub fn apply_update(&self, update: UpdateableClientConfig) -> Vec<ConfigValueChange> {
+        [
+            self.expected_shutdown.update(update.expected_shutdown),
+            self.gc.update(update.gc),
+            self.transaction_pool_size_limit.update(update.transaction_pool_size_limit),
+            self.max_gas_burnt_view.update(update.max_gas_burnt_view),
+            self.header_sync_initial_timeout.update(update.header_sync_initial_timeout),
+            self.header_sync_progress_timeout.update(update.header_sync_progress_timeout),
+            self.header_sync_stall_ban_timeout.update(update.header_sync_stall_ban_timeout),
+            self.state_sync_timeout.update(update.state_sync_timeout),
+            self.tracked_accounts.update(update.tracked_accounts),
+            self.state_sync.update(update.state_sync),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn updateable_config(config: &ClientConfig) -> UpdateableClientConfig {
+        UpdateableClientConfig {
+            expected_shutdown: config.expected_shutdown.get(),
+            gc: config.gc.get(),
+            transaction_pool_size_limit: config.transaction_pool_size_limit.get(),
+            max_gas_burnt_view: config.max_gas_burnt_view.get(),
+            header_sync_initial_timeout: config.header_sync_initial_timeout.get(),
+            header_sync_progress_timeout: config.header_sync_progress_timeout.get(),
+            header_sync_stall_ban_timeout: config.header_sync_stall_ban_timeout.get(),
+            state_sync_timeout: config.state_sync_timeout.get(),
+            tracked_accounts: config.tracked_accounts.get(),
+            state_sync: config.state_sync.get(),
+            trie_cache: TrieCacheSizeLimits { default_max_bytes: 1, ..Default::default() },
+            view_trie_cache: TrieCacheSizeLimits { default_max_bytes: 1, ..Default::default() },
+        }
+    }
+
+    #[test]
+    fn test_apply_update() {
+        let config = ClientConfig::test(true, 10, 20, 1, false, true, true, true);
+        let mut update = updateable_config(&config);
+        config.check_update(&update).unwrap();
+        assert!(config.apply_update(update.clone()).is_empty());
+
+        update.gc.gc_blocks_limit += 1;
+        update.max_gas_burnt_view = Some(1000);
+        config.check_update(&update).unwrap();
+        let changes = config.apply_update(update);
+        let changed_fields: Vec<_> =
+            changes.iter().map(|change| change.field_name.as_str()).collect();
+        assert_eq!(changed_fields, vec!["gc", "max_gas_burnt_view"]);
+        assert_eq!(config.max_gas_burnt_view.get(), Some(1000));
+    }
+
+    #[test]
+    fn test_reject_update() {
+        let config = ClientConfig::test(true, 10, 20, 1, false, true, true, true);
+
+        let mut update = updateable_config(&config);
+        update.gc.gc_num_epochs_to_keep += 1;
+        assert!(matches!(
+            config.check_update(&update),
+            Err(UpdateableClientConfigError::UnsafeChange {
+                field_name: "gc.gc_num_epochs_to_keep",
+                ..
+            })
+        ));
+
+        let mut update = updateable_config(&config);
+        update.state_sync_timeout = Duration::ZERO;
+        assert!(matches!(
+            config.check_update(&update),
+            Err(UpdateableClientConfigError::ZeroValue { field_name: "state_sync_timeout" })
+        ));
+
+        let mut update = updateable_config(&config);
+        update.trie_cache.per_shard_max_bytes.insert(ShardUId::single_shard(), 0);
+        assert!(config.check_update(&update).is_err());
+    }
 }
@@ -10,6 +10,7 @@ use opentelemetry::sdk::Resource;
 use opentelemetry::KeyValue;
 use opentelemetry_semantic_conventions::resource::SERVICE_NAME;
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use tracing::level_filters::LevelFilter;
 use tracing::subscriber::DefaultGuard;
@@ -29,6 +30,7 @@ pub mod log_config;
 pub mod macros;
 pub mod metrics;
 pub mod testonly;
+pub mod trace_file;
 
 /// Produce a tracing-event for target "io_tracer" that will be consumed by the
 /// IO-tracer, if the feature has been enabled.
@@ -49,6 +51,9 @@ macro_rules! io_trace {
 static LOG_LAYER_RELOAD_HANDLE: OnceCell<reload::Handle<EnvFilter, Registry>> = OnceCell::new();
 static OTLP_LAYER_RELOAD_HANDLE: OnceCell<reload::Handle<LevelFilter, LogLayer<Registry>>> =
     OnceCell::new();
+static TRACE_FILE_LAYER_RELOAD_HANDLE: OnceCell<
+    reload::Handle<EnvFilter, TracingLayer<LogLayer<Registry>>>,
+> = OnceCell::new();
 
 type LogLayer<Inner> = Layered<
     Filtered<
@@ -73,8 +78,15 @@ type TracingLayer<Inner> = Layered<
     Inner,
 >;
 
+type TraceFileLayer<Inner> = Layered<
+    Option<Filtered<trace_file::TraceFileLayer, reload::Layer<EnvFilter, Inner>, Inner>>,
+    Inner,
+>;
+
 // Records the level of opentelemetry tracing verbosity configured via command-line flags at the startup.
 static DEFAULT_OTLP_LEVEL: OnceCell<OpenTelemetryLevel> = OnceCell::new();
+// Records the trace file filter configured via command-line flags at the startup.
+static DEFAULT_TRACE_FILE_FILTER: OnceCell<String> = OnceCell::new();
 
 /// The default value for the `RUST_LOG` environment variable if one isn't specified otherwise.
 pub const DEFAULT_RUST_LOG: &str = "tokio_reactor=info,\
@@ -107,6 +119,8 @@ pub struct DefaultSubscriberGuard<S> {
     writer_guard: Option<tracing_appender::non_blocking::WorkerGuard>,
     #[allow(dead_code)] // This field is never read, but has semantic purpose as a drop guard.
     io_trace_guard: Option<tracing_appender::non_blocking::WorkerGuard>,
+    #[allow(dead_code)] // This field is never read, but has semantic purpose as a drop guard.
+    trace_file_guard: Option<tracing_appender::non_blocking::WorkerGuard>,
 }
 
 // Doesn't define WARN and ERROR, because the highest verbosity of spans is INFO.
@@ -139,6 +153,24 @@ pub struct Options {
     /// Enable JSON output of IO events, written to a file.
     #[clap(long)]
     record_io_trace: Option<PathBuf>,
+
+    /// Enables writing span data to rotating files in this directory, for the hosts which can't
+    /// run an opentelemetry collector. Convert them with `neard convert-trace-file`.
+    #[clap(long)]
+    trace_file_dir: Option<PathBuf>,
+
+    /// Spans written to the trace files, as `RUST_LOG` directives. Overridden by
+    /// `trace_file_filter` in `log_config.json`.
+    #[clap(long, default_value = "info")]
+    trace_file_filter: String,
+
+    /// Size in megabytes after which a new trace file is started.
+    #[clap(long, default_value = "64")]
+    trace_file_max_size_mb: u64,
+
+    /// Number of trace files kept, the oldest ones are deleted.
+    #[clap(long, default_value = "16")]
+    trace_file_max_files: usize,
 }
 
 impl<S: tracing::Subscriber + Send + Sync> DefaultSubscriberGuard<S> {
@@ -228,6 +260,15 @@ where
     (subscriber.with(layer), handle)
 }
 
+// Prefer account name as the node name.
+// Fallback to a node public key if a validator key is unavailable.
+fn service_name(node_public_key: &PublicKey, account_id: Option<&AccountId>) -> String {
+    match account_id {
+        Some(account_id) => format!("neard:{}", account_id),
+        None => format!("neard:{}", node_public_key),
+    }
+}
+
 /// Constructs an OpenTelemetryConfig which sends span data to an external collector.
 //
 // NB: this function is `async` because `install_batch(Tokio)` requires a tokio context to
@@ -249,14 +290,10 @@ where
         KeyValue::new("chain_id", chain_id),
         KeyValue::new("node_id", node_public_key.to_string()),
     ];
-    // Prefer account name as the node name.
-    // Fallback to a node public key if a validator key is unavailable.
-    let service_name = if let Some(account_id) = account_id {
+    let service_name = service_name(&node_public_key, account_id.as_ref());
+    if let Some(account_id) = account_id {
         resource.push(KeyValue::new("account_id", account_id.to_string()));
-        format!("neard:{}", account_id)
-    } else {
-        format!("neard:{}", node_public_key)
-    };
+    }
     resource.push(KeyValue::new(SERVICE_NAME, service_name));
 
     let tracer = opentelemetry_otlp::new_pipeline()
@@ -274,6 +311,44 @@ where
     (subscriber.with(layer), handle)
 }
 
+/// Constructs a layer which writes span data to rotating files in `options.trace_file_dir`, if
+/// it's set.
+fn add_trace_file_layer<S>(
+    options: &Options,
+    resource: BTreeMap<String, String>,
+    subscriber: S,
+) -> (
+    TraceFileLayer<S>,
+    Option<reload::Handle<EnvFilter, S>>,
+    Option<tracing_appender::non_blocking::WorkerGuard>,
+)
+where
+    S: tracing::Subscriber + for<'span> LookupSpan<'span> + Send + Sync,
+{
+    let Some(dir) = &options.trace_file_dir else {
+        return (subscriber.with(None), None, None);
+    };
+    let filter = EnvFilterBuilder::new(options.trace_file_filter.as_str())
+        .finish()
+        .expect("invalid --trace-file-filter");
+    let (filter, handle) = reload::Layer::<EnvFilter, S>::new(filter);
+    // Distinguishes the runs of the node in the trace files.
+    let run_id = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64);
+    let files = trace_file::RotatingTraceFiles::new(
+        dir,
+        options.trace_file_max_size_mb.saturating_mul(1024 * 1024),
+        options.trace_file_max_files,
+        run_id,
+        resource,
+    )
+    .expect("unable to create the trace file directory");
+    let (writer, guard) = tracing_appender::non_blocking(files);
+    let layer = trace_file::TraceFileLayer::new(writer).with_filter(filter);
+    (subscriber.with(Some(layer)), Some(handle), Some(guard))
+}
+
 pub fn get_opentelemetry_filter(opentelemetry_level: OpenTelemetryLevel) -> LevelFilter {
     match opentelemetry_level {
         OpenTelemetryLevel::OFF => LevelFilter::OFF,
@@ -356,6 +431,7 @@ pub fn default_subscriber(
         local_subscriber_guard: None,
         writer_guard: None,
         io_trace_guard,
+        trace_file_guard: None,
     }
 }
 
@@ -363,6 +439,7 @@ pub fn set_default_otlp_level(options: &Options) {
     // Record the initial tracing level specified as a command-line flag. Use this recorded value to
     // reset opentelemetry filter when the LogConfig file gets deleted.
     DEFAULT_OTLP_LEVEL.set(options.opentelemetry).unwrap();
+    DEFAULT_TRACE_FILE_FILTER.set(options.trace_file_filter.clone()).unwrap();
 }
 
 /// Constructs a subscriber set to the option appropriate for the NEAR code.
@@ -398,6 +475,15 @@ pub async fn default_subscriber_with_opentelemetry(
         .set(handle)
         .unwrap_or_else(|_| panic!("Failed to set Log Layer Filter"));
 
+    let mut resource = BTreeMap::from([
+        ("chain_id".to_string(), chain_id.clone()),
+        ("node_id".to_string(), node_public_key.to_string()),
+    ]);
+    if let Some(account_id) = &account_id {
+        resource.insert("account_id".to_string(), account_id.to_string());
+    }
+    resource.insert(SERVICE_NAME.to_string(), service_name(&node_public_key, account_id.as_ref()));
+
     let (subscriber, handle) = add_opentelemetry_layer(
         options.opentelemetry,
         chain_id,
@@ -410,6 +496,14 @@ pub async fn default_subscriber_with_opentelemetry(
         .set(handle)
         .unwrap_or_else(|_| panic!("Failed to set OTLP Layer Filter"));
 
+    let (subscriber, handle, trace_file_guard) =
+        add_trace_file_layer(options, resource, subscriber);
+    if let Some(handle) = handle {
+        TRACE_FILE_LAYER_RELOAD_HANDLE
+            .set(handle)
+            .unwrap_or_else(|_| panic!("Failed to set Trace File Layer Filter"));
+    }
+
     #[allow(unused_mut)]
     let mut io_trace_guard = None;
     #[cfg(feature = "io_trace")]
@@ -427,6 +521,7 @@ pub async fn default_subscriber_with_opentelemetry(
         local_subscriber_guard: None,
         writer_guard: Some(writer_guard),
         io_trace_guard,
+        trace_file_guard,
     }
 }
 
@@ -441,6 +536,8 @@ pub enum ReloadError {
     ReloadLogLayer(#[source] reload::Error),
     #[error("could not set the new opentelemetry filter")]
     ReloadOpentelemetryLayer(#[source] reload::Error),
+    #[error("could not set the new trace file filter")]
+    ReloadTraceFileLayer(#[source] reload::Error),
     #[error("could not create the log filter")]
     Parse(#[source] BuildEnvFilterError),
 }
@@ -451,11 +548,12 @@ pub fn reload_log_config(config: Option<&log_config::LogConfig>) {
             config.rust_log.as_deref(),
             config.verbose_module.as_deref(),
             config.opentelemetry_level,
+            config.trace_file_filter.as_deref(),
         )
     } else {
         // When the LOG_CONFIG_FILENAME is not available, reset to the tracing and logging config
         // when the node was started.
-        reload(None, None, None)
+        reload(None, None, None, None)
     };
     match result {
         Ok(_) => {
@@ -476,10 +574,12 @@ pub fn reload_log_config(config: Option<&log_config::LogConfig>) {
 /// `rust_log` is equivalent to setting `RUST_LOG` environment variable.
 /// `verbose` indicates whether `--verbose` command-line flag is present.
 /// `verbose_module` is equivalent to the value of the `--verbose` command-line flag.
+/// `trace_file_filter` selects the spans written to the trace files, if they are enabled.
 pub fn reload(
     rust_log: Option<&str>,
     verbose_module: Option<&str>,
     opentelemetry_level: Option<OpenTelemetryLevel>,
+    trace_file_filter: Option<&str>,
 ) -> Result<(), Vec<ReloadError>> {
     let log_reload_result = LOG_LAYER_RELOAD_HANDLE.get().map_or(
         Err(ReloadError::NoLogReloadHandle),
@@ -514,6 +614,21 @@ pub fn reload(
         },
     );
 
+    // Trace files are optional, so a missing handle isn't an error.
+    let trace_file_reload_result =
+        TRACE_FILE_LAYER_RELOAD_HANDLE.get().map_or(Ok(()), |reload_handle| {
+            let trace_file_filter = trace_file_filter
+                .or(DEFAULT_TRACE_FILE_FILTER.get().map(String::as_str))
+                .unwrap_or("info");
+            let filter =
+                EnvFilterBuilder::new(trace_file_filter).finish().map_err(ReloadError::Parse)?;
+            reload_handle
+                .modify(|trace_file_filter| {
+                    *trace_file_filter = filter;
+                })
+                .map_err(ReloadError::ReloadTraceFileLayer)
+        });
+
     let mut errors: Vec<ReloadError> = vec![];
     if let Err(err) = log_reload_result {
         errors.push(err);
@@ -521,6 +636,9 @@ pub fn reload(
     if let Err(err) = opentelemetry_reload_result {
         errors.push(err);
     }
+    if let Err(err) = trace_file_reload_result {
+        errors.push(err);
+    }
 
     if errors.is_empty() {
         Ok(())
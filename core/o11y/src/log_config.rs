@@ -12,6 +12,8 @@ pub struct LogConfig {
     pub verbose_module: Option<String>,
     /// Verbosity level of collected traces.
     pub opentelemetry_level: Option<crate::OpenTelemetryLevel>,
+    /// Spans written to the trace files enabled with `--trace-file-dir`, as `RUST_LOG` directives.
+    pub trace_file_filter: Option<String>,
 }
 
 impl LogConfig {
@@ -0,0 +1,545 @@
+//! `tracing` layer writing spans to rotating local files, for the nodes which can't export them to
+//! an OpenTelemetry collector.
+//!
+//! Every file starts with a [`TraceFileRecord::Resource`] line describing the node, followed by a
+//! [`TraceFileRecord::Span`] line for every closed span, together with the events logged inside
+//! it. The files can be converted into Chrome trace or OTLP JSON with [`ConvertTraceFileCommand`].
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::field::{Field, Visit};
+use tracing::{span, Subscriber};
+use tracing_appender::non_blocking::NonBlocking;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+const TRACE_FILE_PREFIX: &str = "spans-";
+const TRACE_FILE_EXTENSION: &str = "jsonl";
+/// Files created within the same millisecond are told apart by a suffix padded to 4 digits, so
+/// that the names sort in creation order.
+const MAX_TRACE_FILE_SUFFIX: u32 = 9999;
+
+/// Line of a trace file.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type")]
+pub enum TraceFileRecord {
+    /// Describes the node which wrote the spans following it.
+    Resource {
+        /// Distinguishes the runs of the node, as span ids are only unique within a run.
+        run_id: u64,
+        attributes: BTreeMap<String, String>,
+    },
+    Span(SpanRecord),
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SpanRecord {
+    /// Span id of the root span.
+    pub trace_id: u64,
+    pub span_id: u64,
+    pub parent_span_id: Option<u64>,
+    pub name: String,
+    pub target: String,
+    pub level: String,
+    pub thread_id: u64,
+    pub start_unix_nanos: u64,
+    pub end_unix_nanos: u64,
+    pub attributes: BTreeMap<String, String>,
+    pub events: Vec<SpanEventRecord>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SpanEventRecord {
+    pub unix_nanos: u64,
+    pub level: String,
+    /// The message of the event is in the `message` attribute.
+    pub attributes: BTreeMap<String, String>,
+}
+
+fn unix_nanos() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos() as u64)
+}
+
+fn current_thread_id() -> u64 {
+    static NEXT_THREAD_ID: AtomicU64 = AtomicU64::new(1);
+    thread_local! {
+        static THREAD_ID: u64 = NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed);
+    }
+    THREAD_ID.with(|id| *id)
+}
+
+#[derive(Default)]
+struct AttributeVisitor(BTreeMap<String, String>);
+
+impl Visit for AttributeVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0.insert(field.name().to_string(), format!("{:?}", value));
+    }
+}
+
+/// Span being recorded, kept in the extensions of the span.
+struct OpenSpan {
+    trace_id: u64,
+    span_id: u64,
+    parent_span_id: Option<u64>,
+    thread_id: u64,
+    start_unix_nanos: u64,
+    attributes: AttributeVisitor,
+    events: Vec<SpanEventRecord>,
+}
+
+/// Tracing layer that writes closed spans to a trace file, one JSON line per span.
+pub struct TraceFileLayer {
+    make_writer: NonBlocking,
+    next_span_id: AtomicU64,
+}
+
+impl TraceFileLayer {
+    pub fn new(make_writer: NonBlocking) -> Self {
+        Self { make_writer, next_span_id: AtomicU64::new(1) }
+    }
+}
+
+impl<S: Subscriber + for<'span> LookupSpan<'span>> Layer<S> for TraceFileLayer {
+    fn on_new_span(
+        &self,
+        attrs: &span::Attributes<'_>,
+        id: &span::Id,
+        ctx: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        let span = ctx.span(id).unwrap();
+        let span_id = self.next_span_id.fetch_add(1, Ordering::Relaxed);
+        let parent = span.parent().and_then(|parent| {
+            parent.extensions().get::<OpenSpan>().map(|open| (open.trace_id, open.span_id))
+        });
+        let mut attributes = AttributeVisitor::default();
+        attrs.record(&mut attributes);
+        span.extensions_mut().insert(OpenSpan {
+            trace_id: parent.map_or(span_id, |(trace_id, _)| trace_id),
+            span_id,
+            parent_span_id: parent.map(|(_, parent_span_id)| parent_span_id),
+            thread_id: current_thread_id(),
+            start_unix_nanos: unix_nanos(),
+            attributes,
+            events: vec![],
+        });
+    }
+
+    fn on_record(
+        &self,
+        id: &span::Id,
+        values: &span::Record<'_>,
+        ctx: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        let span = ctx.span(id).unwrap();
+        let mut extensions = span.extensions_mut();
+        if let Some(open) = extensions.get_mut::<OpenSpan>() {
+            values.record(&mut open.attributes);
+        }
+    }
+
+    fn on_event(&self, event: &tracing::Event<'_>, ctx: tracing_subscriber::layer::Context<'_, S>) {
+        // Only the events inside spans are kept, as the events of spans.
+        let Some(span) = ctx.event_span(event) else { return };
+        let mut attributes = AttributeVisitor::default();
+        event.record(&mut attributes);
+        let mut extensions = span.extensions_mut();
+        if let Some(open) = extensions.get_mut::<OpenSpan>() {
+            open.events.push(SpanEventRecord {
+                unix_nanos: unix_nanos(),
+                level: event.metadata().level().to_string(),
+                attributes: attributes.0,
+            });
+        }
+    }
+
+    fn on_close(&self, id: span::Id, ctx: tracing_subscriber::layer::Context<'_, S>) {
+        let span = ctx.span(&id).unwrap();
+        let Some(open) = span.extensions_mut().remove::<OpenSpan>() else { return };
+        let metadata = span.metadata();
+        let record = TraceFileRecord::Span(SpanRecord {
+            trace_id: open.trace_id,
+            span_id: open.span_id,
+            parent_span_id: open.parent_span_id,
+            name: metadata.name().to_string(),
+            target: metadata.target().to_string(),
+            level: metadata.level().to_string(),
+            thread_id: open.thread_id,
+            start_unix_nanos: open.start_unix_nanos,
+            end_unix_nanos: unix_nanos(),
+            attributes: open.attributes.0,
+            events: open.events,
+        });
+        let mut line = serde_json::to_vec(&record).unwrap();
+        line.push(b'\n');
+        // Written as a single message, so that rotations happen between lines.
+        let _ = self.make_writer.make_writer().write_all(&line);
+    }
+}
+
+/// Writes the trace files into a directory, starting a new file once the current one exceeds
+/// `max_file_size` bytes and keeping at most `max_files` files.
+pub struct RotatingTraceFiles {
+    dir: PathBuf,
+    max_file_size: u64,
+    max_files: usize,
+    /// Written at the start of every file.
+    header: Vec<u8>,
+    file: Option<BufWriter<File>>,
+    file_size: u64,
+}
+
+impl RotatingTraceFiles {
+    pub fn new(
+        dir: &Path,
+        max_file_size: u64,
+        max_files: usize,
+        run_id: u64,
+        attributes: BTreeMap<String, String>,
+    ) -> std::io::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let mut header = serde_json::to_vec(&TraceFileRecord::Resource { run_id, attributes })?;
+        header.push(b'\n');
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            max_file_size,
+            max_files: max_files.max(1),
+            header,
+            file: None,
+            file_size: 0,
+        })
+    }
+
+    /// Returns the trace files in the directory, oldest first.
+    pub fn list_files(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+        let mut files = vec![];
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            let is_trace_file =
+                path.file_name().and_then(|name| name.to_str()).map_or(false, |name| {
+                    name.starts_with(TRACE_FILE_PREFIX) && name.ends_with(TRACE_FILE_EXTENSION)
+                });
+            if is_trace_file {
+                files.push(path);
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    /// Returns the timestamp and the suffix in the name of a trace file.
+    fn parse_file_name(path: &Path) -> Option<(u128, u32)> {
+        let name = path.file_name()?.to_str()?;
+        let stem = name.strip_prefix(TRACE_FILE_PREFIX)?.strip_suffix(TRACE_FILE_EXTENSION)?;
+        let (millis, suffix) = stem.strip_suffix('.')?.split_once('-')?;
+        Some((millis.parse().ok()?, suffix.parse().ok()?))
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        if let Some(mut file) = self.file.take() {
+            file.flush()?;
+        }
+        let mut files = Self::list_files(&self.dir)?;
+        // The new file must sort after every existing one, even if the clock went backwards or
+        // the oldest files with the same timestamp were just pruned.
+        let newest = files.last().and_then(|path| Self::parse_file_name(path));
+        while files.len() >= self.max_files {
+            std::fs::remove_file(files.remove(0))?;
+        }
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis());
+        let (millis, suffix) = match newest {
+            Some((millis, suffix)) if millis >= now => {
+                if suffix < MAX_TRACE_FILE_SUFFIX {
+                    (millis, suffix + 1)
+                } else {
+                    (millis + 1, 0)
+                }
+            }
+            _ => (now, 0),
+        };
+        let path = self
+            .dir
+            .join(format!("{TRACE_FILE_PREFIX}{millis:013}-{suffix:04}.{TRACE_FILE_EXTENSION}"));
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(&self.header)?;
+        self.file_size = self.header.len() as u64;
+        self.file = Some(file);
+        Ok(())
+    }
+}
+
+impl Write for RotatingTraceFiles {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.file.is_none() || self.file_size >= self.max_file_size {
+            self.rotate()?;
+        }
+        let written = self.file.as_mut().unwrap().write(buf)?;
+        self.file_size = self.file_size.saturating_add(written as u64);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match &mut self.file {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Reads the records of trace files, skipping the lines which can't be parsed, e.g. the last line
+/// of a file being written when the node was killed.
+pub fn read_trace_files(paths: &[PathBuf]) -> std::io::Result<Vec<TraceFileRecord>> {
+    let mut records = vec![];
+    for path in paths {
+        for line in BufReader::new(File::open(path)?).lines() {
+            match serde_json::from_str(&line?) {
+                Ok(record) => records.push(record),
+                Err(err) => tracing::warn!(?path, %err, "Skipping malformed trace file line"),
+            }
+        }
+    }
+    Ok(records)
+}
+
+/// Converts trace file records into the Chrome trace event format, which can be opened in
+/// `chrome://tracing` or Perfetto.
+pub fn to_chrome_trace(records: &[TraceFileRecord]) -> serde_json::Value {
+    let mut trace_events = vec![];
+    // Every run of the node is a separate process.
+    let mut pid = 0;
+    let mut run_ids = BTreeMap::new();
+    for record in records {
+        match record {
+            TraceFileRecord::Resource { run_id, attributes } => {
+                let next_pid = (run_ids.len() as u64).saturating_add(1);
+                pid = *run_ids.entry(*run_id).or_insert(next_pid);
+                if pid == next_pid {
+                    let name = attributes.get("service.name").cloned().unwrap_or_default();
+                    trace_events.push(serde_json::json!({
+                        "name": "process_name",
+                        "ph": "M",
+                        "pid": pid,
+                        "args": { "name": name },
+                    }));
+                }
+            }
+            TraceFileRecord::Span(span) => {
+                trace_events.push(serde_json::json!({
+                    "name": span.name,
+                    "cat": span.target,
+                    "ph": "X",
+                    "ts": span.start_unix_nanos as f64 / 1000.,
+                    "dur": span.end_unix_nanos.saturating_sub(span.start_unix_nanos) as f64 / 1000.,
+                    "pid": pid,
+                    "tid": span.thread_id,
+                    "args": span.attributes,
+                }));
+                for event in &span.events {
+                    trace_events.push(serde_json::json!({
+                        "name": event.attributes.get("message").cloned().unwrap_or_default(),
+                        "cat": span.target,
+                        "ph": "i",
+                        "s": "t",
+                        "ts": event.unix_nanos as f64 / 1000.,
+                        "pid": pid,
+                        "tid": span.thread_id,
+                        "args": event.attributes,
+                    }));
+                }
+            }
+        }
+    }
+    serde_json::json!({ "traceEvents": trace_events })
+}
+
+fn otlp_attributes(attributes: &BTreeMap<String, String>) -> Vec<serde_json::Value> {
+    attributes
+        .iter()
+        .map(|(key, value)| serde_json::json!({ "key": key, "value": { "stringValue": value } }))
+        .collect()
+}
+
+/// Converts trace file records into the OTLP JSON encoding of an `ExportTraceServiceRequest`,
+/// which can be sent to a collector or loaded by tools such as Jaeger.
+pub fn to_otlp_json(records: &[TraceFileRecord]) -> serde_json::Value {
+    let mut resource_spans: Vec<(u64, &BTreeMap<String, String>, Vec<serde_json::Value>)> = vec![];
+    let empty_attributes = BTreeMap::new();
+    for record in records {
+        match record {
+            TraceFileRecord::Resource { run_id, attributes } => {
+                if resource_spans.last().map_or(true, |(last_run_id, _, _)| last_run_id != run_id) {
+                    resource_spans.push((*run_id, attributes, vec![]));
+                }
+            }
+            TraceFileRecord::Span(span) => {
+                if resource_spans.is_empty() {
+                    resource_spans.push((0, &empty_attributes, vec![]));
+                }
+                let (run_id, _, spans) = resource_spans.last_mut().unwrap();
+                let events: Vec<_> = span
+                    .events
+                    .iter()
+                    .map(|event| {
+                        serde_json::json!({
+                            "timeUnixNano": event.unix_nanos.to_string(),
+                            "name": event.attributes.get("message").cloned().unwrap_or_default(),
+                            "attributes": otlp_attributes(&event.attributes),
+                        })
+                    })
+                    .collect();
+                let mut attributes = span.attributes.clone();
+                attributes.insert("target".to_string(), span.target.clone());
+                attributes.insert("level".to_string(), span.level.clone());
+                spans.push(serde_json::json!({
+                    "traceId": format!("{:016x}{:016x}", run_id, span.trace_id),
+                    "spanId": format!("{:016x}", span.span_id),
+                    "parentSpanId": span
+                        .parent_span_id
+                        .map(|id| format!("{:016x}", id))
+                        .unwrap_or_default(),
+                    "name": span.name,
+                    // SPAN_KIND_INTERNAL
+                    "kind": 1,
+                    "startTimeUnixNano": span.start_unix_nanos.to_string(),
+                    "endTimeUnixNano": span.end_unix_nanos.to_string(),
+                    "attributes": otlp_attributes(&attributes),
+                    "events": events,
+                }));
+            }
+        }
+    }
+    let resource_spans: Vec<_> = resource_spans
+        .into_iter()
+        .map(|(_, attributes, spans)| {
+            serde_json::json!({
+                "resource": { "attributes": otlp_attributes(attributes) },
+                "scopeSpans": [{ "scope": { "name": "neard" }, "spans": spans }],
+            })
+        })
+        .collect();
+    serde_json::json!({ "resourceSpans": resource_spans })
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum TraceFileFormat {
+    /// Chrome trace event format, for `chrome://tracing` and Perfetto.
+    Chrome,
+    /// OTLP JSON encoding of an `ExportTraceServiceRequest`.
+    Otlp,
+}
+
+/// Converts trace files written with `--trace-file-dir` for offline analysis.
+#[derive(clap::Parser)]
+pub struct ConvertTraceFileCommand {
+    /// Trace files, or directories containing them.
+    #[clap(required = true)]
+    input: Vec<PathBuf>,
+    #[clap(long, value_enum, default_value = "chrome")]
+    format: TraceFileFormat,
+    /// Where to write the result, the standard output by default.
+    #[clap(long)]
+    output: Option<PathBuf>,
+}
+
+impl ConvertTraceFileCommand {
+    pub fn run(&self) -> std::io::Result<()> {
+        let mut paths = vec![];
+        for input in &self.input {
+            if input.is_dir() {
+                paths.extend(RotatingTraceFiles::list_files(input)?);
+            } else {
+                paths.push(input.clone());
+            }
+        }
+        let records = read_trace_files(&paths)?;
+        let converted = match self.format {
+            TraceFileFormat::Chrome => to_chrome_trace(&records),
+            TraceFileFormat::Otlp => to_otlp_json(&records),
+        };
+        match &self.output {
+            Some(output) => {
+                serde_json::to_writer(BufWriter::new(File::create(output)?), &converted)?
+            }
+            None => serde_json::to_writer(std::io::stdout().lock(), &converted)?,
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_trace_file_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let writer = RotatingTraceFiles::new(
+            dir.path(),
+            // Small enough for every span to start a new file.
+            1,
+            2,
+            7,
+            BTreeMap::from([("service.name".to_string(), "neard:test".to_string())]),
+        )
+        .unwrap();
+        let (writer, guard) = tracing_appender::non_blocking(writer);
+        let subscriber = tracing_subscriber::registry().with(TraceFileLayer::new(writer));
+        tracing::subscriber::with_default(subscriber, || {
+            let _outer = tracing::info_span!(target: "chain", "outer", height = 5).entered();
+            for _ in 0..3 {
+                let _inner = tracing::debug_span!(target: "chain", "inner").entered();
+                tracing::info!(target: "chain", shard_id = 1, "applied");
+            }
+        });
+        drop(guard);
+
+        // Only the last two files are kept.
+        let files = RotatingTraceFiles::list_files(dir.path()).unwrap();
+        assert_eq!(files.len(), 2);
+        let records = read_trace_files(&files).unwrap();
+        let spans: Vec<_> = records
+            .iter()
+            .filter_map(|record| match record {
+                TraceFileRecord::Span(span) => Some(span),
+                TraceFileRecord::Resource { run_id, .. } => {
+                    assert_eq!(*run_id, 7);
+                    None
+                }
+            })
+            .collect();
+        assert_eq!(spans.len(), 2);
+        let (inner, outer) = (spans[0], spans[1]);
+        assert_eq!(inner.name, "inner");
+        assert_eq!(inner.parent_span_id, Some(outer.span_id));
+        assert_eq!(inner.trace_id, outer.span_id);
+        assert_eq!(inner.events[0].attributes["message"], "applied");
+        assert_eq!(inner.events[0].attributes["shard_id"], "1");
+        assert_eq!(outer.attributes["height"], "5");
+        assert!(outer.start_unix_nanos <= inner.start_unix_nanos);
+        assert!(inner.end_unix_nanos <= outer.end_unix_nanos);
+
+        let chrome = to_chrome_trace(&records);
+        let events = chrome["traceEvents"].as_array().unwrap();
+        assert_eq!(events[0]["args"]["name"], "neard:test");
+        assert_eq!(events.iter().filter(|event| event["ph"] == "X").count(), 2);
+        assert_eq!(events.iter().filter(|event| event["ph"] == "i").count(), 1);
+
+        let otlp = to_otlp_json(&records);
+        let resource_spans = otlp["resourceSpans"].as_array().unwrap();
+        assert_eq!(resource_spans.len(), 1);
+        let spans = resource_spans[0]["scopeSpans"][0]["spans"].as_array().unwrap();
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[0]["parentSpanId"], spans[1]["spanId"]);
+        assert_eq!(spans[0]["traceId"].as_str().unwrap().len(), 32);
+    }
+}
@@ -71,6 +71,18 @@ pub mod dec_format {
         }
     }
 
+    impl DecType for i128 {
+        fn serialize(&self) -> Option<String> {
+            Some(self.to_string())
+        }
+        fn try_from_str(value: &str) -> Result<Self, std::num::ParseIntError> {
+            Self::from_str_radix(value, 10)
+        }
+        fn from_u64(value: u64) -> Self {
+            value.into()
+        }
+    }
+
     impl<T: DecType> DecType for Option<T> {
         fn serialize(&self) -> Option<String> {
             self.as_ref().and_then(DecType::serialize)
@@ -160,6 +172,23 @@ fn test_u128_dec_format() {
     assert_de_error::<Test>("{\"field\":42.0}");
 }
 
+#[test]
+fn test_i128_dec_format() {
+    #[derive(PartialEq, Debug, serde::Deserialize, serde::Serialize)]
+    struct Test {
+        #[serde(with = "dec_format")]
+        field: i128,
+    }
+
+    assert_round_trip("{\"field\":\"42\"}", Test { field: 42 });
+    assert_round_trip(
+        "{\"field\":\"-18446744073709551616\"}",
+        Test { field: -18446744073709551616 },
+    );
+    assert_deserialise("{\"field\":42}", Test { field: 42 });
+    assert_de_error::<Test>("{\"field\":42.0}");
+}
+
 #[test]
 fn test_option_u128_dec_format() {
     #[derive(PartialEq, Debug, serde::Deserialize, serde::Serialize)]
@@ -17,6 +17,8 @@ pub mod merkle;
 pub mod network;
 pub mod rand;
 pub mod receipt;
+#[cfg(unix)]
+pub mod remote_validator_signer;
 pub mod runtime;
 pub mod sandbox;
 pub mod shard_layout;
@@ -0,0 +1,273 @@
+//! Validator signer which keeps the validator key in a separate signer process (see
+//! tools/remote-signer) and sends it everything to be signed over a Unix socket.
+//!
+//! The signer process keeps track of the highest block and approval it signed, and refuses
+//! to sign conflicting ones, so that a node which was failed over can't equivocate.
+use std::io::{self, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_crypto::vrf;
+use near_crypto::{PublicKey, Signature};
+
+use crate::block::{ApprovalInner, BlockHeader};
+use crate::challenge::ChallengeBody;
+use crate::hash::CryptoHash;
+use crate::network::PeerId;
+use crate::sharding::ChunkHash;
+use crate::telemetry::TelemetryInfo;
+use crate::types::{AccountId, BlockHeight, EpochId};
+use crate::validator_signer::ValidatorSigner;
+
+/// Requests sent to the signer process, each answered with a single `SignerResponse`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, PartialEq, Eq, strum::IntoStaticStr)]
+pub enum SignerRequest {
+    /// Asks for the account id and public key of the validator key held by the signer.
+    ValidatorInfo,
+    /// Telemetry info serialized to JSON, see `ValidatorSigner::sign_telemetry`.
+    Telemetry {
+        content: String,
+    },
+    BlockHeader {
+        prev_hash: CryptoHash,
+        inner_lite: Vec<u8>,
+        inner_rest: Vec<u8>,
+    },
+    ChunkHash(ChunkHash),
+    Approval {
+        inner: ApprovalInner,
+        target_height: BlockHeight,
+    },
+    Challenge(ChallengeBody),
+    AccountAnnounce {
+        account_id: AccountId,
+        peer_id: PeerId,
+        epoch_id: EpochId,
+    },
+    AccountKeyPayload(Vec<u8>),
+    Vrf(Vec<u8>),
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, PartialEq, Eq, strum::IntoStaticStr)]
+pub enum SignerResponse {
+    ValidatorInfo {
+        account_id: AccountId,
+        public_key: PublicKey,
+    },
+    Signature(Signature),
+    Vrf {
+        value: vrf::Value,
+        proof: vrf::Proof,
+    },
+    /// The signer didn't sign, e.g. because the request conflicts with a block or an
+    /// approval signed before.
+    Refused(String),
+}
+
+/// Requests are at most as large as a block header or a challenge.
+const MAX_MESSAGE_SIZE: usize = 64 * 1024 * 1024;
+
+/// Writes a message prefixed with its length as a little endian u32.
+pub fn write_message(stream: &mut impl Write, message: &impl BorshSerialize) -> io::Result<()> {
+    let bytes = message.try_to_vec()?;
+    let len = u32::try_from(bytes.len()).map_err(|_| {
+        io::Error::new(io::ErrorKind::InvalidInput, "message too large for a signer request")
+    })?;
+    stream.write_all(&len.to_le_bytes())?;
+    stream.write_all(&bytes)?;
+    stream.flush()
+}
+
+/// Reads a message written by `write_message`.
+pub fn read_message<T: BorshDeserialize>(stream: &mut impl Read) -> io::Result<T> {
+    let mut len = [0; 4];
+    stream.read_exact(&mut len)?;
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_MESSAGE_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("message of {} bytes is too large", len),
+        ));
+    }
+    let mut bytes = vec![0; len];
+    stream.read_exact(&mut bytes)?;
+    T::try_from_slice(&bytes)
+}
+
+/// How long to wait for the signer to answer a request before giving up on the connection.
+const SIGNER_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Signer that forwards everything to a signer process listening on a Unix socket.
+///
+/// `ValidatorSigner` methods can't fail, so when the signer can't be reached or refuses
+/// to sign, the error is logged and an empty signature (or a zero VRF) is returned. The
+/// client checks the blocks, chunks and approvals it produces and doesn't send the ones
+/// with such signatures.
+pub struct RemoteValidatorSigner {
+    socket_path: PathBuf,
+    account_id: AccountId,
+    public_key: PublicKey,
+    stream: Mutex<Option<UnixStream>>,
+}
+
+impl RemoteValidatorSigner {
+    /// Connects to the signer listening on `socket_path` and asks it for the validator key it holds.
+    pub fn connect(socket_path: &Path) -> io::Result<Self> {
+        let mut stream = Self::open(socket_path)?;
+        write_message(&mut stream, &SignerRequest::ValidatorInfo)?;
+        match read_message(&mut stream)? {
+            SignerResponse::ValidatorInfo { account_id, public_key } => Ok(Self {
+                socket_path: socket_path.to_path_buf(),
+                account_id,
+                public_key,
+                stream: Mutex::new(Some(stream)),
+            }),
+            response => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unexpected {} response", <&'static str>::from(&response)),
+            )),
+        }
+    }
+
+    fn open(socket_path: &Path) -> io::Result<UnixStream> {
+        let stream = UnixStream::connect(socket_path)?;
+        stream.set_read_timeout(Some(SIGNER_TIMEOUT))?;
+        stream.set_write_timeout(Some(SIGNER_TIMEOUT))?;
+        Ok(stream)
+    }
+
+    /// Sends the request, reconnecting once if the connection turns out to be broken.
+    /// Sending a request again is safe: the signer signs the same block or approval again.
+    fn request(&self, request: &SignerRequest) -> io::Result<SignerResponse> {
+        let mut stream = self.stream.lock().unwrap();
+        let mut result = self.request_once(&mut stream, request);
+        if result.is_err() {
+            result = self.request_once(&mut stream, request);
+        }
+        result
+    }
+
+    fn request_once(
+        &self,
+        stream: &mut Option<UnixStream>,
+        request: &SignerRequest,
+    ) -> io::Result<SignerResponse> {
+        if stream.is_none() {
+            *stream = Some(Self::open(&self.socket_path)?);
+        }
+        let s = stream.as_mut().unwrap();
+        let result = write_message(s, request).and_then(|()| read_message(s));
+        if result.is_err() {
+            // Whatever is left in the socket would be mistaken for the next response.
+            *stream = None;
+        }
+        result
+    }
+
+    fn sign(&self, request: SignerRequest) -> Signature {
+        let kind = <&'static str>::from(&request);
+        match self.request(&request) {
+            Ok(SignerResponse::Signature(signature)) => return signature,
+            Ok(SignerResponse::Refused(reason)) => {
+                tracing::error!(target: "remote_signer", kind, %reason, "signer refused to sign");
+            }
+            Ok(response) => {
+                let response = <&'static str>::from(&response);
+                tracing::error!(target: "remote_signer", kind, response, "unexpected response");
+            }
+            Err(err) => {
+                tracing::error!(target: "remote_signer", kind, %err, "signer request failed");
+            }
+        }
+        Signature::empty(self.public_key.key_type())
+    }
+}
+
+impl ValidatorSigner for RemoteValidatorSigner {
+    fn validator_id(&self) -> &AccountId {
+        &self.account_id
+    }
+
+    fn public_key(&self) -> PublicKey {
+        self.public_key.clone()
+    }
+
+    fn sign_telemetry(&self, info: &TelemetryInfo) -> serde_json::Value {
+        let mut value = serde_json::to_value(info).expect("Telemetry must serialize to JSON");
+        let content = serde_json::to_string(&value).expect("Telemetry must serialize to JSON");
+        value["signature"] = self.sign(SignerRequest::Telemetry { content }).to_string().into();
+        value
+    }
+
+    fn sign_block_header_parts(
+        &self,
+        prev_hash: CryptoHash,
+        inner_lite: &[u8],
+        inner_rest: &[u8],
+    ) -> (CryptoHash, Signature) {
+        let hash = BlockHeader::compute_hash(prev_hash, inner_lite, inner_rest);
+        let signature = self.sign(SignerRequest::BlockHeader {
+            prev_hash,
+            inner_lite: inner_lite.to_vec(),
+            inner_rest: inner_rest.to_vec(),
+        });
+        (hash, signature)
+    }
+
+    fn sign_chunk_hash(&self, chunk_hash: &ChunkHash) -> Signature {
+        self.sign(SignerRequest::ChunkHash(chunk_hash.clone()))
+    }
+
+    fn sign_approval(&self, inner: &ApprovalInner, target_height: BlockHeight) -> Signature {
+        self.sign(SignerRequest::Approval { inner: inner.clone(), target_height })
+    }
+
+    fn sign_challenge(&self, challenge_body: &ChallengeBody) -> (CryptoHash, Signature) {
+        let hash = CryptoHash::hash_borsh(challenge_body);
+        (hash, self.sign(SignerRequest::Challenge(challenge_body.clone())))
+    }
+
+    fn sign_account_announce(
+        &self,
+        account_id: &AccountId,
+        peer_id: &PeerId,
+        epoch_id: &EpochId,
+    ) -> Signature {
+        self.sign(SignerRequest::AccountAnnounce {
+            account_id: account_id.clone(),
+            peer_id: peer_id.clone(),
+            epoch_id: epoch_id.clone(),
+        })
+    }
+
+    fn sign_account_key_payload(&self, proto_bytes: &[u8]) -> Signature {
+        self.sign(SignerRequest::AccountKeyPayload(proto_bytes.to_vec()))
+    }
+
+    fn compute_vrf_with_proof(&self, data: &[u8]) -> (vrf::Value, vrf::Proof) {
+        match self.request(&SignerRequest::Vrf(data.to_vec())) {
+            Ok(SignerResponse::Vrf { value, proof }) => return (value, proof),
+            Ok(SignerResponse::Refused(reason)) => {
+                tracing::error!(target: "remote_signer", %reason, "signer refused to compute a VRF");
+            }
+            Ok(response) => {
+                let response = <&'static str>::from(&response);
+                tracing::error!(target: "remote_signer", response, "unexpected response to a VRF request");
+            }
+            Err(err) => {
+                tracing::error!(target: "remote_signer", %err, "VRF request failed");
+            }
+        }
+        (vrf::Value([0; 32]), vrf::Proof([0; 64]))
+    }
+
+    fn write_to_file(&self, _path: &Path) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the validator key of a RemoteValidatorSigner is only known to the signer process",
+        ))
+    }
+}
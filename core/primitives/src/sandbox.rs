@@ -78,3 +78,32 @@ pub mod state_patch {
         }
     }
 }
+
+/// Transactions submitted on behalf of an account without its keys.
+///
+/// An impersonated transaction is signed with the empty ED25519 key and the empty signature.
+/// The runtime of sandbox builds doesn't verify the signature, the access key and the nonce
+/// of such transactions, the signer is only charged for them.  On non-sandbox builds no
+/// transaction is impersonated.
+pub mod impersonation {
+    use crate::transaction::{SignedTransaction, Transaction};
+    use near_crypto::{KeyType, PublicKey, Signature};
+
+    /// Turns the transaction into an impersonated one.
+    pub fn impersonate(mut transaction: Transaction) -> SignedTransaction {
+        transaction.public_key = PublicKey::empty(KeyType::ED25519);
+        SignedTransaction::new(Signature::empty(KeyType::ED25519), transaction)
+    }
+
+    #[cfg(feature = "sandbox")]
+    pub fn is_impersonated(signed_transaction: &SignedTransaction) -> bool {
+        signed_transaction.transaction.public_key == PublicKey::empty(KeyType::ED25519)
+            && signed_transaction.signature == Signature::empty(KeyType::ED25519)
+    }
+
+    #[cfg(not(feature = "sandbox"))]
+    #[inline(always)]
+    pub fn is_impersonated(_signed_transaction: &SignedTransaction) -> bool {
+        false
+    }
+}
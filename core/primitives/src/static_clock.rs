@@ -45,6 +45,10 @@ struct MockClockPerState {
     utc_call_count: u64,
     /// Number of times `Clock::instant()` method was called since we started mocking.
     instant_call_count: u64,
+    /// Clocks to read the time from once the lists above are empty, set by
+    /// `MockClockGuard::follow`.
+    instant_source: Option<Box<dyn Fn() -> Instant>>,
+    utc_source: Option<Box<dyn Fn() -> DateTime<Utc>>>,
 }
 
 /// Stores the mocking state.
@@ -92,6 +96,24 @@ impl MockClockGuard {
         });
     }
 
+    /// Makes `StaticClock` read the time from the given functions instead of panicking once the
+    /// queued timestamps run out, e.g. to follow the virtual clock of a test.
+    pub fn follow(
+        &self,
+        instant: impl Fn() -> Instant + 'static,
+        utc: impl Fn() -> DateTime<Utc> + 'static,
+    ) {
+        MockClockPerThread::with(|clock| match &mut clock.mock {
+            Some(clock) => {
+                clock.instant_source = Some(Box::new(instant));
+                clock.utc_source = Some(Box::new(utc));
+            }
+            None => {
+                panic!("Use MockClockGuard in your test");
+            }
+        });
+    }
+
     /// Returns number of calls  to `Self::utc` since `Self::mock()` was called.
     pub fn utc_call_count(&self) -> u64 {
         MockClockPerThread::with(|clock| match &mut clock.mock {
@@ -150,9 +172,10 @@ impl StaticClock {
             Some(clock) => {
                 clock.instant_call_count += 1;
                 let x = clock.instant_list.pop_front();
-                match x {
-                    Some(t) => t,
-                    None => {
+                match (x, &clock.instant_source) {
+                    (Some(t), _) => t,
+                    (None, Some(source)) => source(),
+                    (None, None) => {
                         panic!("Mock clock run out of samples");
                     }
                 }
@@ -168,9 +191,10 @@ impl StaticClock {
             Some(clock) => {
                 clock.utc_call_count += 1;
                 let x = clock.utc_list.pop_front();
-                match x {
-                    Some(t) => t,
-                    None => {
+                match (x, &clock.utc_source) {
+                    (Some(t), _) => t,
+                    (None, Some(source)) => source(),
+                    (None, None) => {
                         panic!("Mock clock run out of samples");
                     }
                 }
@@ -2,16 +2,22 @@
 //! node count and their status across the network.
 use crate::types::AccountId;
 use crate::types::BlockHeight;
+use near_crypto::{PublicKey, SecretKey, Signature};
 use near_primitives_core::hash::CryptoHash;
 
-#[derive(serde::Serialize, Debug)]
+/// Field of a telemetry report with the signature of the validator key, if the node has one.
+pub const TELEMETRY_SIGNATURE_FIELD: &str = "signature";
+/// Field of a telemetry report with the signature of the node key.
+pub const TELEMETRY_NODE_SIGNATURE_FIELD: &str = "node_signature";
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
 pub struct TelemetryAgentInfo {
     pub name: String,
     pub version: String,
     pub build: String,
 }
 
-#[derive(serde::Serialize, Debug)]
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
 pub struct TelemetrySystemInfo {
     pub bandwidth_download: u64,
     pub bandwidth_upload: u64,
@@ -20,10 +26,13 @@ pub struct TelemetrySystemInfo {
     pub boot_time_seconds: i64,
 }
 
-#[derive(serde::Serialize, Debug)]
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
 pub struct TelemetryChainInfo {
     pub node_id: String,
     pub account_id: Option<AccountId>,
+    /// Key of the validator signing the report, set together with `account_id`.
+    #[serde(default)]
+    pub validator_public_key: Option<PublicKey>,
     pub is_validator: bool,
     pub status: String,
     pub latest_block_hash: CryptoHash,
@@ -35,7 +44,7 @@ pub struct TelemetryChainInfo {
     pub max_block_wait_delay: f64,
 }
 
-#[derive(serde::Serialize, Debug)]
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
 pub struct TelemetryInfo {
     pub agent: TelemetryAgentInfo,
     pub system: TelemetrySystemInfo,
@@ -43,3 +52,159 @@ pub struct TelemetryInfo {
     // Extra telemetry information that will be ignored by the explorer frontend.
     pub extra_info: String,
 }
+
+/// Returns the part of a telemetry report covered by its signatures: the report serialized to
+/// JSON without the signature fields.
+pub fn telemetry_signed_content(report: &serde_json::Value) -> String {
+    let content = match report.as_object() {
+        Some(fields) => serde_json::Value::Object(
+            fields
+                .iter()
+                .filter(|(key, _)| {
+                    *key != TELEMETRY_SIGNATURE_FIELD && *key != TELEMETRY_NODE_SIGNATURE_FIELD
+                })
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+        ),
+        None => report.clone(),
+    };
+    serde_json::to_string(&content).expect("Telemetry must serialize to JSON")
+}
+
+/// Adds the signature of the node key to a telemetry report, which may already be signed by the
+/// validator key.
+pub fn sign_telemetry_with_node_key(report: &mut serde_json::Value, node_key: &SecretKey) {
+    let content = telemetry_signed_content(report);
+    report[TELEMETRY_NODE_SIGNATURE_FIELD] = node_key.sign(content.as_bytes()).to_string().into();
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum TelemetryVerificationError {
+    #[error("malformed telemetry report: {0}")]
+    Malformed(String),
+    #[error("telemetry report is missing the {0} field")]
+    MissingSignature(&'static str),
+    #[error("invalid {0} of the telemetry report")]
+    InvalidSignature(&'static str),
+    #[error("telemetry report of {0} isn't signed by its validator key")]
+    UnsignedAccount(AccountId),
+}
+
+/// Checks the signatures of a telemetry report: the node signature against the `node_id` of the
+/// report, and the validator signature against its `validator_public_key`. Reports claiming an
+/// `account_id` must be signed by a validator key.
+///
+/// Note that this doesn't check that the validator key belongs to the account, which requires
+/// looking the account up on chain.
+pub fn verify_telemetry(
+    report: &serde_json::Value,
+) -> Result<TelemetryInfo, TelemetryVerificationError> {
+    let info: TelemetryInfo = serde_json::from_value(report.clone())
+        .map_err(|err| TelemetryVerificationError::Malformed(err.to_string()))?;
+    let node_key: PublicKey = info.chain.node_id.parse().map_err(|err| {
+        TelemetryVerificationError::Malformed(format!("invalid node_id: {}", err))
+    })?;
+    let content = telemetry_signed_content(report);
+    let verify = |field: &'static str, key: &PublicKey| {
+        let signature: Signature = report
+            .get(field)
+            .and_then(|signature| signature.as_str())
+            .ok_or(TelemetryVerificationError::MissingSignature(field))?
+            .parse()
+            .map_err(|_| TelemetryVerificationError::InvalidSignature(field))?;
+        if signature.verify(content.as_bytes(), key) {
+            Ok(())
+        } else {
+            Err(TelemetryVerificationError::InvalidSignature(field))
+        }
+    };
+    verify(TELEMETRY_NODE_SIGNATURE_FIELD, &node_key)?;
+    match (&info.chain.account_id, &info.chain.validator_public_key) {
+        (_, Some(validator_key)) => verify(TELEMETRY_SIGNATURE_FIELD, validator_key)?,
+        (Some(account_id), None) => {
+            return Err(TelemetryVerificationError::UnsignedAccount(account_id.clone()))
+        }
+        (None, None) => {}
+    }
+    Ok(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validator_signer::{InMemoryValidatorSigner, ValidatorSigner};
+    use near_crypto::KeyType;
+
+    fn telemetry_info(
+        node_key: &SecretKey,
+        validator: Option<&dyn ValidatorSigner>,
+    ) -> TelemetryInfo {
+        TelemetryInfo {
+            agent: TelemetryAgentInfo {
+                name: "near-rs".to_string(),
+                version: "trunk".to_string(),
+                build: "abc".to_string(),
+            },
+            system: TelemetrySystemInfo {
+                bandwidth_download: 1,
+                bandwidth_upload: 2,
+                cpu_usage: 0.3,
+                memory_usage: 4,
+                boot_time_seconds: 5,
+            },
+            chain: TelemetryChainInfo {
+                node_id: node_key.public_key().to_string(),
+                account_id: validator.map(|vs| vs.validator_id().clone()),
+                validator_public_key: validator.map(|vs| vs.public_key()),
+                is_validator: validator.is_some(),
+                status: "NoSync".to_string(),
+                latest_block_hash: CryptoHash::default(),
+                latest_block_height: 10,
+                num_peers: 3,
+                block_production_tracking_delay: 0.1,
+                min_block_production_delay: 0.6,
+                max_block_production_delay: 2.0,
+                max_block_wait_delay: 6.0,
+            },
+            extra_info: "{}".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_verify_telemetry() {
+        let node_key = SecretKey::from_seed(KeyType::ED25519, "node");
+        let validator =
+            InMemoryValidatorSigner::from_seed("test".parse().unwrap(), KeyType::ED25519, "test");
+
+        let mut report = validator.sign_telemetry(&telemetry_info(&node_key, Some(&validator)));
+        sign_telemetry_with_node_key(&mut report, &node_key);
+        // The aggregators receive the report over the wire.
+        let report: serde_json::Value =
+            serde_json::from_str(&serde_json::to_string(&report).unwrap()).unwrap();
+        let info = verify_telemetry(&report).unwrap();
+        assert_eq!(info.chain.account_id, Some("test".parse().unwrap()));
+
+        let mut tampered = report.clone();
+        tampered["chain"]["latest_block_height"] = 11.into();
+        assert_matches::assert_matches!(
+            verify_telemetry(&tampered),
+            Err(TelemetryVerificationError::InvalidSignature(TELEMETRY_NODE_SIGNATURE_FIELD))
+        );
+        // Claiming to be a validator requires the validator key.
+        let mut report = serde_json::to_value(telemetry_info(&node_key, None)).unwrap();
+        report["chain"]["account_id"] = "test".into();
+        sign_telemetry_with_node_key(&mut report, &node_key);
+        assert_matches::assert_matches!(
+            verify_telemetry(&report),
+            Err(TelemetryVerificationError::UnsignedAccount(_))
+        );
+
+        let mut report = serde_json::to_value(telemetry_info(&node_key, None)).unwrap();
+        assert_matches::assert_matches!(
+            verify_telemetry(&report),
+            Err(TelemetryVerificationError::MissingSignature(TELEMETRY_NODE_SIGNATURE_FIELD))
+        );
+        sign_telemetry_with_node_key(&mut report, &node_key);
+        verify_telemetry(&report).unwrap();
+    }
+}
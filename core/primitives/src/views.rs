@@ -25,9 +25,9 @@ use crate::sharding::{
 };
 use crate::transaction::{
     Action, AddKeyAction, CreateAccountAction, DeleteAccountAction, DeleteKeyAction,
-    DeployContractAction, ExecutionMetadata, ExecutionOutcome, ExecutionOutcomeWithIdAndProof,
-    ExecutionStatus, FunctionCallAction, PartialExecutionOutcome, PartialExecutionStatus,
-    SignedTransaction, StakeAction, TransferAction,
+    DeployContractAction, ExecutionMetadata, ExecutionOutcome, ExecutionOutcomeWithId,
+    ExecutionOutcomeWithIdAndProof, ExecutionStatus, FunctionCallAction, PartialExecutionOutcome,
+    PartialExecutionStatus, SignedTransaction, StakeAction, TransferAction,
 };
 use crate::types::{
     AccountId, AccountWithPublicKey, Balance, BlockHeight, EpochHeight, EpochId, FunctionArgs, Gas,
@@ -480,6 +480,19 @@ pub struct CatchupStatusView {
     pub blocks_to_catchup: Vec<BlockStatusView>,
 }
 
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct TrackedShardsChangeView {
+    // Shard to start or stop tracking, if the change was requested for a shard
+    pub shard_id: Option<ShardId>,
+    // Account whose shard to start or stop tracking, if the change was requested for an account
+    pub account_id: Option<AccountId>,
+    pub track: bool,
+    // The change affects epochs with this or greater height
+    pub from_epoch_height: EpochHeight,
+    // Human readable progress of the change
+    pub status: String,
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq)]
 pub struct RequestedStatePartsView {
     // This is the first block of the epoch that was requested
@@ -634,6 +647,45 @@ pub enum ChunkProcessingStatus {
     Completed,
 }
 
+/// Stage of the processing of a block, or of one of its chunks.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockTimelineStage {
+    /// The block was received by the client.
+    HeaderReceived,
+    /// Missing parts and receipts of the chunk were requested.
+    ChunkRequested,
+    ChunkPartReceived,
+    /// The chunk was decoded from its parts.
+    ChunkReconstructed,
+    Preprocessing,
+    ApplyChunk,
+    Postprocessing,
+    /// Approvals for this height, only received by the block producer of the height.
+    FirstApproval,
+    LastApproval,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BlockTimelineEventView {
+    pub stage: BlockTimelineStage,
+    /// Known for the stages of the block processing, and not for the chunk stages which may
+    /// happen before the block is received.
+    pub block_hash: Option<CryptoHash>,
+    pub shard_id: Option<ShardId>,
+    pub part_ord: Option<u64>,
+    pub start: DateTime<chrono::Utc>,
+    /// `None` for events which don't have a duration.
+    pub end: Option<DateTime<chrono::Utc>>,
+}
+
+/// Everything that happened on this node for the blocks and chunks of a height.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BlockTimelineView {
+    pub height: BlockHeight,
+    /// Sorted by start time.
+    pub events: Vec<BlockTimelineEventView>,
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
 pub struct DetailedDebugStatus {
     pub network_info: NetworkInfoView,
@@ -644,6 +696,21 @@ pub struct DetailedDebugStatus {
     pub block_production_delay_millis: u64,
 }
 
+/// Whether the production of a validator in the current epoch is high enough to avoid being
+/// kicked out at the end of the epoch.
+#[derive(
+    serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord,
+)]
+pub enum KickoutRisk {
+    /// Production ratio is above the threshold.
+    None,
+    /// Production ratio is below the threshold, but can still recover if the validator produces
+    /// everything it is expected to produce during the rest of the epoch.
+    BelowThreshold,
+    /// Production ratio can't recover before the end of the epoch.
+    Certain,
+}
+
 // TODO: add more information to status.
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
 pub struct StatusResponse {
@@ -672,6 +739,10 @@ pub struct StatusResponse {
     pub node_key: Option<PublicKey>,
     /// Uptime of the node.
     pub uptime_sec: i64,
+    /// Kickout risk of the validator of this node, as last computed by its self-monitoring.
+    /// None if the node doesn't run a validator or the risk wasn't computed yet.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub validator_kickout_risk: Option<KickoutRisk>,
     /// Information about last blocks, network, epoch and chain & chunk info.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub detailed_debug_status: Option<DetailedDebugStatus>,
@@ -1587,6 +1658,61 @@ impl ExecutionOutcomeWithIdView {
     }
 }
 
+/// Prefix of the logs which contain events in the format of
+/// [NEP-297](https://nomicon.io/Standards/EventsFormat).
+pub const EVENT_LOG_PREFIX: &str = "EVENT_JSON:";
+
+/// Event emitted by a contract according to NEP-297, e.g. a NEP-141 `ft_transfer`.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ContractEventView {
+    pub standard: String,
+    pub version: String,
+    pub event: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+}
+
+/// Log starting with [`EVENT_LOG_PREFIX`] which isn't a valid NEP-297 event.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MalformedContractEventView {
+    /// Position of the log in the logs of the execution outcome.
+    pub log_index: usize,
+    pub log: String,
+    pub error: String,
+}
+
+/// NEP-297 events parsed from the logs of an execution outcome.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContractEventsView {
+    pub events: Vec<ContractEventView>,
+    pub malformed_events: Vec<MalformedContractEventView>,
+}
+
+impl ContractEventsView {
+    /// Parses the logs starting with [`EVENT_LOG_PREFIX`], other logs are ignored.
+    pub fn from_logs(logs: &[String]) -> Self {
+        let mut result = Self::default();
+        for (log_index, log) in logs.iter().enumerate() {
+            let Some(json) = log.strip_prefix(EVENT_LOG_PREFIX) else {
+                continue;
+            };
+            match serde_json::from_str::<ContractEventView>(json) {
+                Ok(event) => result.events.push(event),
+                Err(err) => result.malformed_events.push(MalformedContractEventView {
+                    log_index,
+                    log: log.clone(),
+                    error: err.to_string(),
+                }),
+            }
+        }
+        result
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty() && self.malformed_events.is_empty()
+    }
+}
+
 #[derive(BorshSerialize, BorshDeserialize, serde::Serialize, serde::Deserialize, Debug)]
 #[serde(untagged)]
 pub enum FinalExecutionOutcomeViewEnum {
@@ -2066,7 +2192,7 @@ impl From<StateChangeKind> for StateChangeKindView {
 pub type StateChangesKindsView = Vec<StateChangeKindView>;
 
 /// See crate::types::StateChangeCause for details.
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "snake_case", tag = "type")]
 pub enum StateChangeCauseView {
     NotWritableToDisk,
@@ -2201,6 +2327,97 @@ impl From<StateChangeWithCause> for StateChangeWithCauseView {
 
 pub type StateChangesView = Vec<StateChangeWithCauseView>;
 
+/// Balance of an account before and after a dry run of a transaction.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct AccountBalanceChangeView {
+    pub account_id: AccountId,
+    #[serde(with = "dec_format")]
+    pub amount_before: Balance,
+    #[serde(with = "dec_format")]
+    pub amount_after: Balance,
+    #[serde(with = "dec_format")]
+    pub locked_before: Balance,
+    #[serde(with = "dec_format")]
+    pub locked_after: Balance,
+}
+
+/// Outcome of a transaction or receipt executed by a dry run.  It isn't part of any block so it
+/// has no proof.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct DryRunOutcomeView {
+    pub id: CryptoHash,
+    pub outcome: ExecutionOutcomeView,
+}
+
+impl From<ExecutionOutcomeWithId> for DryRunOutcomeView {
+    fn from(outcome_with_id: ExecutionOutcomeWithId) -> Self {
+        Self { id: outcome_with_id.id, outcome: outcome_with_id.outcome.into() }
+    }
+}
+
+/// Result of a dry run of a transaction on top of the state of a block.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct TransactionDryRunView {
+    /// Block whose post state the transaction was applied on.
+    pub block_hash: CryptoHash,
+    pub block_height: BlockHeight,
+    /// Outcomes of the transaction and of the receipts it generated, in execution order.  The
+    /// metadata of the outcomes holds their gas profiles.
+    pub outcomes: Vec<DryRunOutcomeView>,
+    pub state_changes: StateChangesView,
+    pub balance_changes: Vec<AccountBalanceChangeView>,
+    /// Receipts which weren't executed within the blocks of the dry run or whose shard isn't
+    /// tracked by the node.
+    pub pending_receipts: Vec<ReceiptView>,
+}
+
+/// Where and how a receipt was executed.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ReceiptExecutionView {
+    pub block_hash: CryptoHash,
+    pub block_height: BlockHeight,
+    pub shard_id: ShardId,
+    /// Outcome of the receipt, its metadata holds the gas profile.
+    pub outcome: ExecutionOutcomeView,
+}
+
+/// Receipt of the receipt tree of a transaction.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ReceiptTreeNodeView {
+    pub receipt_id: CryptoHash,
+    /// Transaction or receipt which created this receipt.
+    pub parent_id: CryptoHash,
+    pub predecessor_id: AccountId,
+    pub receiver_id: AccountId,
+    /// Refund receipts are created by the system for the unspent gas and deposits.
+    pub is_refund: bool,
+    /// Actions of the receipt.  `None` if the receipt isn't stored, which is the case of the
+    /// first receipt of a transaction sent to the signer itself.
+    pub receipt: Option<ReceiptEnumView>,
+    /// `None` if the receipt wasn't executed yet.
+    pub execution: Option<ReceiptExecutionView>,
+}
+
+/// Receipt `to` waits for the data `data_id` returned by the receipt `from`, e.g. the callback
+/// of a `promise_then` or `promise_and`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ReceiptDataEdgeView {
+    pub from: CryptoHash,
+    pub to: CryptoHash,
+    pub data_id: CryptoHash,
+}
+
+/// All the receipts of a transaction.  The children of a receipt are the `receipt_ids` of its
+/// outcome, receipts which were neither stored nor executed by the node are left out.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ReceiptTreeView {
+    pub transaction_hash: CryptoHash,
+    pub transaction_outcome: ExecutionOutcomeWithIdView,
+    /// Receipts in depth-first order, every receipt comes after its parent.
+    pub receipts: Vec<ReceiptTreeNodeView>,
+    pub data_edges: Vec<ReceiptDataEdgeView>,
+}
+
 /// Maintenance windows view are a vector of maintenance window.
 pub type MaintenanceWindowsView = Vec<Range<BlockHeight>>;
 
@@ -2752,6 +2969,7 @@ impl From<ExtCostsConfigView> for near_primitives_core::config::ExtCostsConfig {
 mod tests {
     #[cfg(not(feature = "nightly"))]
     use super::ExecutionMetadataView;
+    use super::{ContractEventView, ContractEventsView};
     #[cfg(not(feature = "nightly"))]
     use crate::transaction::ExecutionMetadata;
     #[cfg(not(feature = "nightly"))]
@@ -2798,4 +3016,42 @@ mod tests {
         let view = ExecutionMetadataView::from(metadata);
         insta::assert_json_snapshot!(view);
     }
+
+    #[test]
+    fn test_contract_events_from_logs() {
+        let logs = vec![
+            "Transfer 10 from alice.near to bob.near".to_string(),
+            r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_transfer","data":[{"old_owner_id":"alice.near","new_owner_id":"bob.near","amount":"10"}]}"#.to_string(),
+            r#"EVENT_JSON:{"standard":"nep171","event":"nft_mint"}"#.to_string(),
+            "EVENT_JSON:not json".to_string(),
+            r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_burn"}"#.to_string(),
+        ];
+        let events = ContractEventsView::from_logs(&logs);
+        assert_eq!(
+            events.events,
+            vec![
+                ContractEventView {
+                    standard: "nep141".to_string(),
+                    version: "1.0.0".to_string(),
+                    event: "ft_transfer".to_string(),
+                    data: Some(serde_json::json!([{
+                        "old_owner_id": "alice.near",
+                        "new_owner_id": "bob.near",
+                        "amount": "10",
+                    }])),
+                },
+                ContractEventView {
+                    standard: "nep171".to_string(),
+                    version: "1.0.0".to_string(),
+                    event: "nft_burn".to_string(),
+                    data: None,
+                },
+            ]
+        );
+        // The event without a version and the invalid JSON are reported with their positions.
+        let malformed: Vec<_> =
+            events.malformed_events.iter().map(|event| event.log_index).collect();
+        assert_eq!(malformed, vec![2, 3]);
+        assert!(ContractEventsView::from_logs(&logs[..1]).is_empty());
+    }
 }
@@ -37,6 +37,7 @@ pub const STATE_SYNC_DUMP_KEY: &[u8; 15] = b"STATE_SYNC_DUMP";
 // `DBCol::Misc` keys
 pub const FLAT_STATE_VALUES_INLINING_MIGRATION_STATUS_KEY: &[u8] =
     b"FLAT_STATE_VALUES_INLINING_MIGRATION_STATUS";
+pub const TRACKING_CHANGES_KEY: &[u8] = b"TRACKING_CHANGES";
 
 #[derive(Default, Debug)]
 pub struct DBTransaction {
@@ -11,7 +11,9 @@ pub use crate::trie::shard_tries::{
     KeyForStateChanges, ShardTries, StateSnapshot, StateSnapshotConfig, WrappedTrieChanges,
 };
 pub use crate::trie::trie_storage::{TrieCache, TrieCachingStorage, TrieDBStorage, TrieStorage};
-use crate::trie::trie_storage::{TrieMemoryPartialStorage, TrieRecordingStorage};
+use crate::trie::trie_storage::{
+    TrieMemoryPartialStorage, TrieOverlayStorage, TrieRecordingStorage,
+};
 use crate::StorageError;
 use borsh::{BorshDeserialize, BorshSerialize};
 use near_primitives::challenge::PartialState;
@@ -450,6 +452,22 @@ impl Trie {
         Some(PartialStorage { nodes: PartialState::TrieValues(nodes) })
     }
 
+    /// Returns the trie at the new root of `changes` without committing them.  The nodes and
+    /// values inserted by the changes are kept in memory, the others are read from the storage
+    /// of this trie.  Flat storage isn't used since it doesn't contain the changes.
+    pub fn with_uncommitted_changes(&self, changes: &TrieChanges) -> Self {
+        assert_eq!(self.root, changes.old_root, "trie changes apply to another root");
+        let inserted = changes
+            .insertions
+            .iter()
+            .map(|change| {
+                (change.trie_node_or_value_hash, change.trie_node_or_value.clone().into())
+            })
+            .collect();
+        let storage = TrieOverlayStorage { storage: Rc::clone(&self.storage), inserted };
+        Trie { storage: Rc::new(storage), root: changes.new_root, flat_storage_chunk_view: None }
+    }
+
     pub fn from_recorded_storage(partial_storage: PartialStorage, root: StateRoot) -> Self {
         let PartialState::TrieValues(nodes) = partial_storage.nodes;
         let recorded_storage = nodes.into_iter().map(|value| (hash(&value), value)).collect();
@@ -989,6 +1007,41 @@ mod tests {
         assert_eq!(trie.iter().unwrap().fold(0, |acc, _| acc + 1), 0);
     }
 
+    #[test]
+    fn test_with_uncommitted_changes() {
+        let tries = create_tries();
+        let shard_uid = ShardUId::single_shard();
+        let root = test_populate_trie(
+            &tries,
+            &Trie::EMPTY_ROOT,
+            shard_uid,
+            vec![(b"dog".to_vec(), Some(b"puppy".to_vec()))],
+        );
+        let trie = tries.get_trie_for_shard(shard_uid, root);
+        let changes = trie
+            .update(vec![
+                (b"dog".to_vec(), None),
+                (b"doge".to_vec(), Some(b"coin".to_vec())),
+                (b"horse".to_vec(), Some(b"stallion".to_vec())),
+            ])
+            .unwrap();
+        let overlay = trie.with_uncommitted_changes(&changes);
+        assert_eq!(overlay.get_root(), &changes.new_root);
+        assert_eq!(overlay.get(b"dog"), Ok(None));
+        assert_eq!(overlay.get(b"doge"), Ok(Some(b"coin".to_vec())));
+        // Changes can be stacked on top of each other.
+        let changes = overlay.update(vec![(b"doge".to_vec(), None)]).unwrap();
+        let overlay = overlay.with_uncommitted_changes(&changes);
+        assert_eq!(overlay.get(b"doge"), Ok(None));
+        assert_eq!(overlay.get(b"horse"), Ok(Some(b"stallion".to_vec())));
+        // Nothing was committed.
+        assert_eq!(
+            tries.get_trie_for_shard(shard_uid, root).get(b"dog"),
+            Ok(Some(b"puppy".to_vec()))
+        );
+        assert!(tries.get_trie_for_shard(shard_uid, *overlay.get_root()).get(b"horse").is_err());
+    }
+
     #[test]
     fn test_trie_iter() {
         let tries = create_tries_complex(SHARD_VERSION, 2);
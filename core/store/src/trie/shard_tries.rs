@@ -6,6 +6,7 @@ use crate::trie::{TrieRefcountChange, POISONED_LOCK_ERR};
 use crate::{checkpoint_hot_storage_and_cleanup_columns, metrics, DBCol, NodeStorage, PrefetchApi};
 use crate::{Store, StoreConfig, StoreUpdate, Trie, TrieChanges, TrieUpdate};
 use borsh::BorshSerialize;
+use near_chain_configs::{ConfigValueChange, TrieCacheSizeLimits};
 use near_primitives::borsh::maybestd::collections::HashMap;
 use near_primitives::errors::EpochError;
 use near_primitives::errors::StorageError;
@@ -23,7 +24,8 @@ use std::sync::{Arc, RwLock, TryLockError};
 
 struct ShardTriesInner {
     store: Store,
-    trie_config: TrieConfig,
+    /// Cache size limits can be changed while the node is running.
+    trie_config: RwLock<TrieConfig>,
     /// Cache reserved for client actor to use
     caches: RwLock<HashMap<ShardUId, TrieCache>>,
     /// Cache for readers.
@@ -109,7 +111,7 @@ impl ShardTries {
         metrics::HAS_STATE_SNAPSHOT.set(0);
         ShardTries(Arc::new(ShardTriesInner {
             store,
-            trie_config,
+            trie_config: RwLock::new(trie_config),
             caches: RwLock::new(caches),
             view_caches: RwLock::new(view_caches),
             flat_storage_manager,
@@ -189,7 +191,13 @@ impl ShardTries {
             let mut caches = caches_to_use.write().expect(POISONED_LOCK_ERR);
             caches
                 .entry(shard_uid)
-                .or_insert_with(|| TrieCache::new(&self.0.trie_config, shard_uid, is_view))
+                .or_insert_with(|| {
+                    TrieCache::new(
+                        &self.0.trie_config.read().expect(POISONED_LOCK_ERR),
+                        shard_uid,
+                        is_view,
+                    )
+                })
                 .clone()
         };
         // Do not enable prefetching on view caches.
@@ -197,10 +205,11 @@ impl ShardTries {
         // 2) A lot of the prefetcher code assumes there is only one "main-thread" per shard active.
         //    If you want to enable it for view calls, at least make sure they don't share
         //    the `PrefetchApi` instances with the normal calls.
+        let trie_config = self.0.trie_config.read().expect(POISONED_LOCK_ERR);
         let prefetch_enabled = !is_view
-            && (self.0.trie_config.enable_receipt_prefetching
-                || (!self.0.trie_config.sweat_prefetch_receivers.is_empty()
-                    && !self.0.trie_config.sweat_prefetch_senders.is_empty()));
+            && (trie_config.enable_receipt_prefetching
+                || (!trie_config.sweat_prefetch_receivers.is_empty()
+                    && !trie_config.sweat_prefetch_senders.is_empty()));
         let prefetch_api = prefetch_enabled.then(|| {
             self.0
                 .prefetchers
@@ -208,16 +217,12 @@ impl ShardTries {
                 .expect(POISONED_LOCK_ERR)
                 .entry(shard_uid)
                 .or_insert_with(|| {
-                    PrefetchApi::new(
-                        self.0.store.clone(),
-                        cache.clone(),
-                        shard_uid,
-                        &self.0.trie_config,
-                    )
+                    PrefetchApi::new(self.0.store.clone(), cache.clone(), shard_uid, &trie_config)
                 })
                 .0
                 .clone()
         });
+        drop(trie_config);
 
         let storage = Rc::new(TrieCachingStorage::new(
             self.0.store.clone(),
@@ -277,7 +282,13 @@ impl ShardTries {
             let mut caches = self.0.view_caches.write().expect(POISONED_LOCK_ERR);
             caches
                 .entry(shard_uid)
-                .or_insert_with(|| TrieCache::new(&self.0.trie_config, shard_uid, true))
+                .or_insert_with(|| {
+                    TrieCache::new(
+                        &self.0.trie_config.read().expect(POISONED_LOCK_ERR),
+                        shard_uid,
+                        true,
+                    )
+                })
                 .clone()
         };
         let storage = Rc::new(TrieCachingStorage::new(store, cache, shard_uid, true, None));
@@ -316,11 +327,66 @@ impl ShardTries {
         let mut caches = self.0.caches.write().expect(POISONED_LOCK_ERR);
         let cache = caches
             .entry(shard_uid)
-            .or_insert_with(|| TrieCache::new(&self.0.trie_config, shard_uid, false))
+            .or_insert_with(|| {
+                TrieCache::new(
+                    &self.0.trie_config.read().expect(POISONED_LOCK_ERR),
+                    shard_uid,
+                    false,
+                )
+            })
             .clone();
         cache.update_cache(ops);
     }
 
+    /// Updates the size limits of the trie caches of all shards.
+    /// Returns the applied changes.
+    pub fn update_cache_size_limits(
+        &self,
+        limits: &TrieCacheSizeLimits,
+        is_view: bool,
+    ) -> Vec<ConfigValueChange> {
+        let field_name = if is_view { "store.view_trie_cache" } else { "store.trie_cache" };
+        let mut changes = vec![];
+        {
+            let mut trie_config = self.0.trie_config.write().expect(POISONED_LOCK_ERR);
+            let cache_config = if is_view {
+                &mut trie_config.view_shard_cache_config
+            } else {
+                &mut trie_config.shard_cache_config
+            };
+            if cache_config.default_max_bytes != limits.default_max_bytes {
+                changes.push(ConfigValueChange::new(
+                    &format!("{field_name}.default_max_bytes"),
+                    &cache_config.default_max_bytes,
+                    &limits.default_max_bytes,
+                ));
+                cache_config.default_max_bytes = limits.default_max_bytes;
+            }
+            if cache_config.per_shard_max_bytes != limits.per_shard_max_bytes {
+                changes.push(ConfigValueChange::new(
+                    &format!("{field_name}.per_shard_max_bytes"),
+                    &cache_config.per_shard_max_bytes,
+                    &limits.per_shard_max_bytes,
+                ));
+                cache_config.per_shard_max_bytes = limits.per_shard_max_bytes.clone();
+            }
+        }
+        if !changes.is_empty() {
+            tracing::info!(target: "config", ?changes, "Updated trie cache size limits");
+            let caches = if is_view { &self.0.view_caches } else { &self.0.caches };
+            for (shard_uid, cache) in caches.read().expect(POISONED_LOCK_ERR).iter() {
+                cache.set_total_size_limit(
+                    limits
+                        .per_shard_max_bytes
+                        .get(shard_uid)
+                        .copied()
+                        .unwrap_or(limits.default_max_bytes),
+                );
+            }
+        }
+        changes
+    }
+
     fn apply_deletions_inner(
         &self,
         deletions: &[TrieRefcountChange],
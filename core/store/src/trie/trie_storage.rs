@@ -201,6 +201,18 @@ impl TrieCacheInner {
         }
     }
 
+    /// Changes the memory limit of the cache, evicting LRU entries if the
+    /// cache doesn't fit into the new limit.
+    pub(crate) fn set_total_size_limit(&mut self, total_size_limit: u64) {
+        assert!(total_size_limit > 0);
+        self.total_size_limit = total_size_limit;
+        while self.total_size > self.total_size_limit {
+            self.metrics.shard_cache_pop_lru.inc();
+            let (_, value) = self.cache.pop_lru().expect("Cannot fail because total size is > 0");
+            self.remove_value_of_size(value.len());
+        }
+    }
+
     /// Number of currently cached entries.
     pub fn len(&self) -> usize {
         self.cache.len()
@@ -256,6 +268,10 @@ impl TrieCache {
         self.lock().clear()
     }
 
+    pub fn set_total_size_limit(&self, total_size_limit: u64) {
+        self.lock().set_total_size_limit(total_size_limit)
+    }
+
     pub fn update_cache(&self, ops: Vec<(&CryptoHash, Option<&[u8]>)>) {
         let mut guard = self.lock();
         for (hash, opt_value) in ops {
@@ -380,6 +396,27 @@ impl TrieMemoryPartialStorage {
     }
 }
 
+/// Storage with nodes and values which aren't committed to the underlying storage yet, used to
+/// read the state resulting from trie changes without committing them.
+/// Reads of the nodes kept in memory aren't counted in `get_trie_nodes_count`.
+pub struct TrieOverlayStorage {
+    pub(crate) storage: Rc<dyn TrieStorage>,
+    pub(crate) inserted: HashMap<CryptoHash, Arc<[u8]>>,
+}
+
+impl TrieStorage for TrieOverlayStorage {
+    fn retrieve_raw_bytes(&self, hash: &CryptoHash) -> Result<Arc<[u8]>, StorageError> {
+        match self.inserted.get(hash) {
+            Some(value) => Ok(Arc::clone(value)),
+            None => self.storage.retrieve_raw_bytes(hash),
+        }
+    }
+
+    fn get_trie_nodes_count(&self) -> TrieNodesCount {
+        self.storage.get_trie_nodes_count()
+    }
+}
+
 /// Storage for reading State nodes and values from DB which caches reads.
 pub struct TrieCachingStorage {
     pub(crate) store: Store,
@@ -774,6 +811,22 @@ mod trie_cache_tests {
         assert!(cache.cache.contains(&hash(&[3])));
     }
 
+    /// test that lowering the memory limit evicts the least recently used values
+    #[test]
+    fn test_set_total_size_limit() {
+        let total_size_limit = TrieCacheInner::PER_ENTRY_OVERHEAD * 10;
+        let mut cache = TrieCacheInner::new(100, total_size_limit, 0, false);
+        put_value(&mut cache, &[1]);
+        put_value(&mut cache, &[2]);
+        put_value(&mut cache, &[3]);
+
+        cache.set_total_size_limit(TrieCacheInner::PER_ENTRY_OVERHEAD * 2 + 2);
+        assert!(!cache.cache.contains(&hash(&[1])));
+        assert!(cache.cache.contains(&hash(&[2])));
+        assert!(cache.cache.contains(&hash(&[3])));
+        assert_eq!(cache.current_total_size(), TrieCacheInner::PER_ENTRY_OVERHEAD * 2 + 2);
+    }
+
     #[test]
     fn test_small_memory_limit() {
         let total_size_limit = 1;
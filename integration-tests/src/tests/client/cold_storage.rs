@@ -503,7 +503,7 @@ fn test_cold_loop_on_gc_boundary() {
         .nightshade_runtimes(&genesis)
         .build();
 
-    let height_delta = env.clients[0].config.gc.gc_num_epochs_to_keep * epoch_length * 2;
+    let height_delta = env.clients[0].config.gc.get().gc_num_epochs_to_keep * epoch_length * 2;
 
     let mut last_hash = *env.clients[0].chain.genesis().hash();
 
@@ -5,12 +5,18 @@ use near_client::test_utils::TestEnv;
 use near_client::ProcessTxResponse;
 use near_crypto::{InMemorySigner, KeyType};
 use near_primitives::account::Account;
+use near_primitives::hash::CryptoHash;
+use near_primitives::sandbox::impersonation::impersonate;
 use near_primitives::sandbox::state_patch::SandboxStatePatch;
+use near_primitives::shard_layout::ShardUId;
 use near_primitives::state_record::StateRecord;
 use near_primitives::transaction::{
-    Action, DeployContractAction, FunctionCallAction, SignedTransaction,
+    Action, DeployContractAction, FunctionCallAction, SignedTransaction, Transaction,
+    TransferAction,
 };
 use near_primitives::types::{AccountId, BlockHeight, Nonce};
+use near_primitives::utils::to_timestamp;
+use near_store::flat::{FlatStorageReadyStatus, FlatStorageStatus};
 use nearcore::config::GenesisExt;
 
 fn test_setup() -> (TestEnv, InMemorySigner) {
@@ -108,3 +114,144 @@ fn test_patch_account() {
     let test1_after = env.query_account("test1".parse().unwrap());
     assert_eq!(test1_after.amount, 10);
 }
+
+#[test]
+fn test_snapshot_restore() {
+    let (mut env, signer) = test_setup();
+    let amount_before = env.query_account("test1".parse().unwrap()).amount;
+    // The transaction is in the pool when the snapshot is taken.
+    assert_eq!(
+        send_tx(
+            &mut env,
+            3,
+            "test0".parse().unwrap(),
+            "test1".parse().unwrap(),
+            &signer,
+            vec![Action::Transfer(TransferAction { deposit: 10 })],
+        ),
+        ProcessTxResponse::ValidTx
+    );
+    let head = env.clients[0].chain.head().unwrap();
+    let snapshot_id = env.clients[0].sandbox_snapshot().unwrap();
+
+    do_blocks(&mut env, 9, 14);
+    assert_eq!(env.query_account("test1".parse().unwrap()).amount, amount_before + 10);
+
+    env.clients[0].sandbox_restore(snapshot_id).unwrap();
+    assert_eq!(env.clients[0].chain.head().unwrap().last_block_hash, head.last_block_hash);
+    assert_eq!(env.query_account("test1".parse().unwrap()).amount, amount_before);
+
+    // New blocks are built on top of the snapshot and include the restored transaction.
+    do_blocks(&mut env, 14, 19);
+    let new_head = env.clients[0].chain.head().unwrap();
+    assert_eq!(new_head.height, 18);
+    assert_eq!(env.query_account("test1".parse().unwrap()).amount, amount_before + 10);
+
+    // A snapshot can be restored more than once.
+    env.clients[0].sandbox_restore(snapshot_id).unwrap();
+    assert_eq!(env.query_account("test1".parse().unwrap()).amount, amount_before);
+}
+
+fn flat_head(env: &TestEnv) -> CryptoHash {
+    let flat_storage_manager = env.clients[0].runtime_adapter.get_flat_storage_manager().unwrap();
+    match flat_storage_manager.get_flat_storage_status(ShardUId::single_shard()) {
+        FlatStorageStatus::Ready(FlatStorageReadyStatus { flat_head }) => flat_head.hash,
+        status => panic!("unexpected flat storage status {:?}", status),
+    }
+}
+
+#[test]
+fn test_drop_snapshot() {
+    let (mut env, _signer) = test_setup();
+    let first = env.clients[0].sandbox_snapshot().unwrap();
+    let second = env.clients[0].sandbox_snapshot().unwrap();
+    let locked_flat_head = flat_head(&env);
+    do_blocks(&mut env, 9, 14);
+    assert_eq!(flat_head(&env), locked_flat_head);
+
+    env.clients[0].sandbox_drop_snapshot(first).unwrap();
+    assert!(env.clients[0].sandbox_restore(first).is_err());
+    assert!(env.clients[0].sandbox_drop_snapshot(first).is_err());
+    // The flat storage head stays locked until the last snapshot is dropped.
+    do_blocks(&mut env, 14, 16);
+    assert_eq!(flat_head(&env), locked_flat_head);
+    env.clients[0].sandbox_drop_snapshot(second).unwrap();
+    do_blocks(&mut env, 16, 20);
+    assert_ne!(flat_head(&env), locked_flat_head);
+
+    // Ids of the dropped snapshots aren't reused.
+    assert_eq!(env.clients[0].sandbox_snapshot().unwrap(), 2);
+}
+
+#[test]
+fn test_restore_unknown_snapshot() {
+    let (mut env, _signer) = test_setup();
+    let head = env.clients[0].chain.head().unwrap();
+    assert!(env.clients[0].sandbox_restore(0).is_err());
+    assert_eq!(env.clients[0].chain.head().unwrap(), head);
+}
+
+fn head_timestamp(env: &TestEnv) -> u64 {
+    let head = env.clients[0].chain.head().unwrap();
+    env.clients[0].chain.get_block_header(&head.last_block_hash).unwrap().raw_timestamp()
+}
+
+#[test]
+fn test_set_time() {
+    let (mut env, _signer) = test_setup();
+    let one_day = chrono::Duration::days(1).num_nanoseconds().unwrap();
+    let timestamp = to_timestamp(chrono::Utc::now()) + 365 * one_day as u64;
+    env.clients[0].sandbox_set_timestamp(timestamp).unwrap();
+    do_blocks(&mut env, 9, 10);
+    let block_timestamp = head_timestamp(&env);
+    assert!(block_timestamp >= timestamp);
+    assert!(block_timestamp < timestamp + one_day as u64);
+
+    env.clients[0].sandbox_offset_time(one_day).unwrap();
+    do_blocks(&mut env, 10, 11);
+    assert!(head_timestamp(&env) >= block_timestamp + one_day as u64);
+
+    // Blocks are never timestamped before their previous block.
+    env.clients[0].sandbox_offset_time(-2 * 365 * one_day).unwrap();
+    let block_timestamp = head_timestamp(&env);
+    do_blocks(&mut env, 11, 12);
+    assert!(head_timestamp(&env) > block_timestamp);
+}
+
+#[test]
+fn test_set_time_out_of_range() {
+    let (mut env, _signer) = test_setup();
+    let time = env.clients[0].sandbox_time().unwrap();
+    assert!(env.clients[0].sandbox_set_timestamp(u64::MAX).is_err());
+    assert!(env.clients[0].sandbox_set_timestamp(i64::MAX as u64 + 1).is_err());
+    assert!(env.clients[0].sandbox_offset_time(i64::MAX).is_err());
+    assert!(env.clients[0].sandbox_offset_time(i64::MIN).is_err());
+    // Failed requests leave the clock alone.
+    let delta = env.clients[0].sandbox_time().unwrap() - time;
+    assert!(delta < chrono::Duration::days(1));
+    do_blocks(&mut env, 3, 4);
+}
+
+#[test]
+fn test_impersonated_transaction() {
+    let (mut env, _signer) = test_setup();
+    let amount_before = env.query_account("test1".parse().unwrap()).amount;
+    let block_hash = env.clients[0].chain.head().unwrap().last_block_hash;
+    // Both transactions use the same nonce, which is only accepted because it isn't checked.
+    for deposit in [10, 20] {
+        let transaction = Transaction {
+            signer_id: "test0".parse().unwrap(),
+            public_key: near_crypto::PublicKey::empty(KeyType::ED25519),
+            nonce: 1,
+            receiver_id: "test1".parse().unwrap(),
+            block_hash,
+            actions: vec![Action::Transfer(TransferAction { deposit })],
+        };
+        assert_eq!(
+            env.clients[0].process_tx(impersonate(transaction), false, false),
+            ProcessTxResponse::ValidTx
+        );
+    }
+    do_blocks(&mut env, 9, 14);
+    assert_eq!(env.query_account("test1".parse().unwrap()).amount, amount_before + 30);
+}
@@ -806,3 +806,51 @@ fn test_shard_layout_upgrade_missing_chunks_mid_missing_prob() {
 fn test_shard_layout_upgrade_missing_chunks_high_missing_prob() {
     test_shard_layout_upgrade_missing_chunks(0.9);
 }
+
+/// Port of `test_shard_layout_upgrade_simple` to the deterministic simulation of
+/// `near_client::test_loop`. The validators vote for the protocol version of the binary in the
+/// blocks they produce, and all the nodes, including one that isn't a validator, split their
+/// states in the background and switch to the new shard layout together.
+///
+/// On nightly, the vote skips the simple nightshade layout straight to a layout that can't be
+/// split from the genesis one.
+#[cfg(not(feature = "nightly"))]
+#[test]
+fn test_shard_layout_upgrade_on_test_loop() {
+    use near_async::time;
+    use near_client::test_loop::SimulatedNetwork;
+
+    init_test_logger();
+
+    let epoch_length = 5;
+    let num_validators = 2;
+    let validators: Vec<AccountId> =
+        (0..num_validators).map(|i| format!("test{}", i).parse().unwrap()).collect();
+    let genesis = setup_genesis(epoch_length, num_validators as u64, validators, None);
+    let network = SimulatedNetwork::new(
+        0,
+        time::Duration::milliseconds(10),
+        time::Duration::milliseconds(50),
+    );
+    let mut test = TestEnv::builder(ChainGenesis::new(&genesis))
+        .clients_count(num_validators + 1)
+        .validator_seats(num_validators)
+        .real_epoch_managers(&genesis.config)
+        .nightshade_runtimes(&genesis)
+        .build_test_loop(network, time::Duration::milliseconds(200));
+    test.run_for(time::Duration::milliseconds(200) * (4 * epoch_length) as i32);
+
+    let expected: Vec<_> = (1..=3 * epoch_length)
+        .map(|height| test.data[0].client.chain.get_block_hash_by_height(height).ok())
+        .collect();
+    for node in &test.data {
+        let client = &node.client;
+        let chain: Vec<_> = (1..=3 * epoch_length)
+            .map(|height| client.chain.get_block_hash_by_height(height).ok())
+            .collect();
+        assert_eq!(chain, expected, "{} disagrees on the chain", node.account_id);
+        let head = client.chain.head().unwrap();
+        let shard_layout = client.epoch_manager.get_shard_layout(&head.epoch_id).unwrap();
+        assert!(shard_layout.num_shards() > 1, "{} didn't reshard", node.account_id);
+    }
+}
@@ -4,7 +4,7 @@ use near_chain::near_chain_primitives::error::QueryError;
 use near_chain::types::RuntimeAdapter;
 use near_chain::{ChainGenesis, Provenance};
 use near_chain_configs::ExternalStorageLocation::Filesystem;
-use near_chain_configs::{DumpConfig, Genesis};
+use near_chain_configs::{DumpConfig, Genesis, MutableConfigValue, StateSyncConfig};
 use near_client::sync::external::external_storage_location;
 use near_client::test_utils::TestEnv;
 use near_client::ProcessTxResponse;
@@ -75,11 +75,17 @@ fn test_state_dump() {
         let shard_tracker = chain.shard_tracker.clone();
         let mut config = env.clients[0].config.clone();
         let root_dir = tempfile::Builder::new().prefix("state_dump").tempdir().unwrap();
-        config.state_sync.dump = Some(DumpConfig {
-            location: Filesystem { root_dir: root_dir.path().to_path_buf() },
-            restart_dump_for_shards: None,
-            iteration_delay: Some(Duration::ZERO),
-        });
+        config.state_sync = MutableConfigValue::new(
+            StateSyncConfig {
+                dump: Some(DumpConfig {
+                    location: Filesystem { root_dir: root_dir.path().to_path_buf() },
+                    restart_dump_for_shards: None,
+                    iteration_delay: Some(Duration::ZERO),
+                }),
+                ..StateSyncConfig::default()
+            },
+            "state_sync",
+        );
 
         let _state_sync_dump_handle = spawn_state_sync_dump(
             &config,
@@ -197,11 +203,17 @@ fn run_state_sync_with_dumped_parts(
         let shard_tracker = chain.shard_tracker.clone();
         let mut config = env.clients[0].config.clone();
         let root_dir = tempfile::Builder::new().prefix("state_dump").tempdir().unwrap();
-        config.state_sync.dump = Some(DumpConfig {
-            location: Filesystem { root_dir: root_dir.path().to_path_buf() },
-            restart_dump_for_shards: None,
-            iteration_delay: Some(Duration::ZERO),
-        });
+        config.state_sync = MutableConfigValue::new(
+            StateSyncConfig {
+                dump: Some(DumpConfig {
+                    location: Filesystem { root_dir: root_dir.path().to_path_buf() },
+                    restart_dump_for_shards: None,
+                    iteration_delay: Some(Duration::ZERO),
+                }),
+                ..StateSyncConfig::default()
+            },
+            "state_sync",
+        );
         let _state_sync_dump_handle = spawn_state_sync_dump(
             &config,
             chain_genesis,
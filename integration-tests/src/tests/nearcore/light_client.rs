@@ -0,0 +1,84 @@
+use crate::genesis_helpers::genesis_block;
+use crate::tests::nearcore::node_cluster::NodeCluster;
+use actix::clock::sleep;
+use actix::System;
+use borsh::BorshSerialize;
+use near_actix_test_utils::spawn_interruptible;
+use near_crypto::{InMemorySigner, KeyType};
+use near_jsonrpc::client::new_client;
+use near_light_client::rpc;
+use near_o11y::testonly::init_integration_logger;
+use near_primitives::serialize::to_base64;
+use near_primitives::transaction::SignedTransaction;
+use near_primitives::types::{BlockReference, TransactionOrReceiptId};
+use std::time::Duration;
+
+/// Follows a local node with the light client across several epochs and verifies the
+/// execution proof of a transaction against the followed head.
+#[test]
+#[cfg_attr(not(feature = "expensive_tests"), ignore)]
+fn test_light_client_follows_node() {
+    init_integration_logger();
+
+    let cluster = NodeCluster::default()
+        .set_num_shards(1)
+        .set_num_validator_seats(2)
+        .set_num_lightclients(0)
+        .set_epoch_length(10)
+        .set_genesis_height(0);
+
+    cluster.exec_until_stop(|genesis, rpc_addrs, _| async move {
+        let client = new_client(&format!("http://{}", rpc_addrs[0]));
+        let genesis_hash = *genesis_block(&genesis).hash();
+
+        spawn_interruptible(async move {
+            let trusted_block = loop {
+                if let Ok(block) = client.block(BlockReference::latest()).await {
+                    if block.header.height > 1 {
+                        break block;
+                    }
+                }
+                sleep(Duration::from_millis(500)).await;
+            };
+            let mut state = rpc::bootstrap(&client, trusted_block.header.hash).await.unwrap();
+            assert_eq!(state.head_hash(), trusted_block.header.hash);
+
+            let signer =
+                InMemorySigner::from_seed("near.0".parse().unwrap(), KeyType::ED25519, "near.0");
+            let transaction = SignedTransaction::send_money(
+                1,
+                "near.0".parse().unwrap(),
+                "near.1".parse().unwrap(),
+                &signer,
+                10000,
+                genesis_hash,
+            );
+            let outcome = client
+                .broadcast_tx_commit(to_base64(&transaction.try_to_vec().unwrap()))
+                .await
+                .unwrap();
+
+            // Cross at least two epoch boundaries so that the block producers of the next
+            // epoch are both fetched for the checkpoint and taken from light client blocks.
+            let target_height = trusted_block.header.height + 25;
+            while state.head_height() < target_height {
+                if !rpc::advance_head(&client, &mut state).await.unwrap() {
+                    sleep(Duration::from_millis(200)).await;
+                }
+            }
+
+            let proof = rpc::get_execution_proof(
+                &client,
+                &state,
+                TransactionOrReceiptId::Transaction {
+                    transaction_hash: outcome.transaction_outcome.id,
+                    sender_id: "near.0".parse().unwrap(),
+                },
+            )
+            .await
+            .unwrap();
+            assert_eq!(proof.outcome_proof.id, outcome.transaction_outcome.id);
+            System::current().stop();
+        });
+    });
+}
@@ -53,6 +53,7 @@ fn start_nodes(
             near_config.client_config.tracked_shards = vec![0];
         }
         near_config.client_config.epoch_sync_enabled = false;
+        near_config.rpc_config.as_mut().unwrap().enable_dry_run_rpc = true;
         near_configs.push(near_config);
     }
 
@@ -463,6 +463,126 @@ fn test_send_tx_sync_returns_transaction_hash() {
     });
 }
 
+#[test]
+#[cfg_attr(not(feature = "expensive_tests"), ignore)]
+fn test_dry_run_tx() {
+    init_integration_logger();
+
+    let cluster = NodeCluster::default()
+        .set_num_shards(1)
+        .set_num_nodes(2)
+        .set_num_validator_seats(1)
+        .set_num_lightclients(0)
+        .set_epoch_length(10)
+        .set_genesis_height(0);
+
+    cluster.exec_until_stop(|genesis, rpc_addrs, clients| async move {
+        let view_client = clients[0].1.clone();
+
+        let genesis_hash = *genesis_block(&genesis).hash();
+        let signer =
+            InMemorySigner::from_seed("near.0".parse().unwrap(), KeyType::ED25519, "near.0");
+        let transaction = SignedTransaction::send_money(
+            1,
+            "near.0".parse().unwrap(),
+            "near.1".parse().unwrap(),
+            &signer,
+            10000,
+            genesis_hash,
+        );
+
+        let client = new_client(&format!("http://{}", rpc_addrs[0]));
+        let tx_hash = transaction.get_hash();
+        let bytes = transaction.try_to_vec().unwrap();
+
+        spawn_interruptible(async move {
+            loop {
+                let res = view_client.send(GetBlock::latest().with_span_context()).await;
+                if let Ok(Ok(block)) = res {
+                    if block.header.height > 5 {
+                        // Nothing is committed, so the same transaction can be dry run twice.
+                        for _ in 0..2 {
+                            let response = client
+                                .EXPERIMENTAL_dry_run_tx(to_base64(&bytes), 5)
+                                .await
+                                .unwrap()
+                                .dry_run;
+                            assert_eq!(response.outcomes[0].id, tx_hash);
+                            assert!(response.outcomes.iter().all(|outcome| !matches!(
+                                outcome.outcome.status,
+                                ExecutionStatusView::Failure(_)
+                            )));
+                            assert!(response.pending_receipts.is_empty());
+                            let receiver = response
+                                .balance_changes
+                                .iter()
+                                .find(|change| change.account_id.as_str() == "near.1")
+                                .unwrap();
+                            assert_eq!(receiver.amount_after - receiver.amount_before, 10000);
+                        }
+                        System::current().stop();
+                        break;
+                    }
+                }
+                sleep(std::time::Duration::from_millis(500)).await;
+            }
+        });
+    });
+}
+
+#[test]
+#[cfg_attr(not(feature = "expensive_tests"), ignore)]
+fn test_receipt_tree() {
+    init_integration_logger();
+
+    let cluster = NodeCluster::default()
+        .set_num_shards(1)
+        .set_num_nodes(1)
+        .set_num_validator_seats(1)
+        .set_num_lightclients(0)
+        .set_epoch_length(10)
+        .set_genesis_height(0);
+
+    cluster.exec_until_stop(|genesis, rpc_addrs, _| async move {
+        let genesis_hash = *genesis_block(&genesis).hash();
+        let signer =
+            InMemorySigner::from_seed("near.0".parse().unwrap(), KeyType::ED25519, "near.0");
+        let transaction = SignedTransaction::send_money(
+            1,
+            "near.0".parse().unwrap(),
+            "near.1".parse().unwrap(),
+            &signer,
+            10000,
+            genesis_hash,
+        );
+
+        let client = new_client(&format!("http://{}", rpc_addrs[0]));
+        let tx_hash = transaction.get_hash();
+        let bytes = transaction.try_to_vec().unwrap();
+
+        spawn_interruptible(async move {
+            let outcome = client.broadcast_tx_commit(to_base64(&bytes)).await.unwrap();
+            let transfer_receipt_id = outcome.transaction_outcome.outcome.receipt_ids[0];
+            let tree = client
+                .EXPERIMENTAL_receipt_tree(
+                    near_jsonrpc_primitives::types::receipts::RpcReceiptTreeRequest { tx_hash },
+                )
+                .await
+                .unwrap()
+                .receipt_tree;
+            assert_eq!(tree.transaction_hash, tx_hash);
+            let transfer =
+                tree.receipts.iter().find(|node| node.receipt_id == transfer_receipt_id).unwrap();
+            assert_eq!(transfer.parent_id, tx_hash);
+            assert_eq!(transfer.receiver_id.as_str(), "near.1");
+            assert!(!transfer.is_refund);
+            assert!(transfer.execution.is_some());
+            assert!(tree.data_edges.is_empty());
+            System::current().stop();
+        });
+    });
+}
+
 #[test]
 #[cfg_attr(not(feature = "expensive_tests"), ignore)]
 fn test_send_tx_sync_to_lightclient_must_be_routed() {
@@ -5,7 +5,9 @@ use near_actix_test_utils::run_actix;
 use near_chain::types::RuntimeAdapter;
 use near_chain::{ChainGenesis, Provenance};
 use near_chain_configs::ExternalStorageLocation::Filesystem;
-use near_chain_configs::{DumpConfig, ExternalStorageConfig, Genesis, SyncConfig};
+use near_chain_configs::{
+    DumpConfig, ExternalStorageConfig, Genesis, MutableConfigValue, StateSyncConfig, SyncConfig,
+};
 use near_client::test_utils::TestEnv;
 use near_client::{GetBlock, ProcessTxResponse};
 use near_crypto::{InMemorySigner, KeyType};
@@ -440,11 +442,17 @@ fn sync_state_dump() {
             near1.client_config.max_block_production_delay = Duration::from_millis(600);
             near1.client_config.epoch_sync_enabled = false;
             let dump_dir = tempfile::Builder::new().prefix("state_dump_1").tempdir().unwrap();
-            near1.client_config.state_sync.dump = Some(DumpConfig {
-                location: Filesystem { root_dir: dump_dir.path().to_path_buf() },
-                restart_dump_for_shards: None,
-                iteration_delay: Some(Duration::from_millis(100)),
-            });
+            near1.client_config.state_sync = MutableConfigValue::new(
+                StateSyncConfig {
+                    dump: Some(DumpConfig {
+                        location: Filesystem { root_dir: dump_dir.path().to_path_buf() },
+                        restart_dump_for_shards: None,
+                        iteration_delay: Some(Duration::from_millis(100)),
+                    }),
+                    ..StateSyncConfig::default()
+                },
+                "state_sync",
+            );
 
             let dir1 = tempfile::Builder::new().prefix("sync_nodes_1").tempdir().unwrap();
             let nearcore::NearNode {
@@ -485,14 +493,22 @@ fn sync_state_dump() {
                                 near2.client_config.tracked_shards = vec![0, 1, 2, 3];
                                 near2.client_config.epoch_sync_enabled = false;
                                 near2.client_config.state_sync_enabled = true;
-                                near2.client_config.state_sync_timeout = Duration::from_secs(1);
-                                near2.client_config.state_sync.sync =
-                                    SyncConfig::ExternalStorage(ExternalStorageConfig {
-                                        location: Filesystem {
-                                            root_dir: dump_dir.path().to_path_buf(),
-                                        },
-                                        num_concurrent_requests: 10,
-                                    });
+                                near2.client_config.state_sync_timeout = MutableConfigValue::new(
+                                    Duration::from_secs(1),
+                                    "state_sync_timeout",
+                                );
+                                near2.client_config.state_sync = MutableConfigValue::new(
+                                    StateSyncConfig {
+                                        sync: SyncConfig::ExternalStorage(ExternalStorageConfig {
+                                            location: Filesystem {
+                                                root_dir: dump_dir.path().to_path_buf(),
+                                            },
+                                            num_concurrent_requests: 10,
+                                        }),
+                                        ..StateSyncConfig::default()
+                                    },
+                                    "state_sync",
+                                );
 
                                 let nearcore::NearNode {
                                     view_client: view_client2, arbiters, ..
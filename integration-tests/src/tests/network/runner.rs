@@ -3,6 +3,7 @@ use anyhow::{anyhow, bail, Context};
 use near_async::actix::AddrWithAutoSpanContextExt;
 use near_async::messaging::{IntoSender, LateBoundSender};
 use near_async::time;
+use near_chain::block_timeline::BlockTimeline;
 use near_chain::test_utils::{KeyValueRuntime, MockEpochManager, ValidatorSchedule};
 use near_chain::types::RuntimeAdapter;
 use near_chain::{Chain, ChainGenesis};
@@ -26,7 +27,7 @@ use near_primitives::network::PeerId;
 use near_primitives::test_utils::create_test_signer;
 use near_primitives::types::{AccountId, ValidatorId};
 use near_primitives::validator_signer::ValidatorSigner;
-use near_telemetry::{TelemetryActor, TelemetryConfig};
+use near_telemetry::TelemetryActor;
 use std::collections::HashSet;
 use std::future::Future;
 use std::iter::Iterator;
@@ -56,7 +57,7 @@ fn setup_network_node(
     let shard_tracker = ShardTracker::new_empty(epoch_manager.clone());
     let runtime = KeyValueRuntime::new(store.get_hot_store(), epoch_manager.as_ref());
     let signer = Arc::new(create_test_signer(account_id.as_str()));
-    let telemetry_actor = TelemetryActor::new(TelemetryConfig::default()).start();
+    let telemetry_actor = TelemetryActor::default().start();
 
     let db = store.into_inner(near_store::Temperature::Hot);
     let mut client_config =
@@ -73,6 +74,7 @@ fn setup_network_node(
     let network_adapter = Arc::new(LateBoundSender::default());
     let shards_manager_adapter = Arc::new(LateBoundSender::default());
     let adv = near_client::adversarial::Controls::default();
+    let block_timeline = BlockTimeline::default();
     let client_actor = start_client(
         client_config.clone(),
         chain_genesis.clone(),
@@ -88,6 +90,7 @@ fn setup_network_node(
         None,
         adv.clone(),
         None,
+        block_timeline.clone(),
     )
     .0;
     let view_client_actor = start_view_client(
@@ -108,6 +111,7 @@ fn setup_network_node(
         Some(signer.validator_id().clone()),
         runtime.store().clone(),
         client_config.chunk_request_retry_period,
+        block_timeline,
     );
     shards_manager_adapter.bind(shards_manager_actor);
     let peer_manager = PeerManagerActor::spawn(
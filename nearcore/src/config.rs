@@ -14,6 +14,8 @@ use near_network::tcp;
 use near_o11y::log_config::LogConfig;
 use near_primitives::account::{AccessKey, Account};
 use near_primitives::hash::CryptoHash;
+#[cfg(unix)]
+use near_primitives::remote_validator_signer::RemoteValidatorSigner;
 #[cfg(test)]
 use near_primitives::shard_layout::account_id_to_shard_id;
 use near_primitives::shard_layout::ShardLayout;
@@ -34,7 +36,7 @@ use num_rational::Rational32;
 use std::fs;
 use std::fs::File;
 use std::io::{Read, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
@@ -352,6 +354,17 @@ pub struct Config {
     pub transaction_pool_size_limit: Option<u64>,
     /// If a node needs to upload state parts to S3
     pub s3_credentials_file: Option<String>,
+    /// Directory of a cache of compiled contracts which can be shared with other nodes and
+    /// tools, e.g. filled by `neard contract precompile`.  Relative paths are relative to the
+    /// home directory.  If not set, or if the directory can't be created, compiled contracts are
+    /// cached in the database.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compiled_contract_cache_dir: Option<PathBuf>,
+    /// Unix socket of a `remote-signer` holding the validator key.  If set, everything is
+    /// signed by the remote signer and `validator_key_file` is not used.  Relative paths are
+    /// relative to the home directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote_validator_signer_socket: Option<PathBuf>,
 }
 
 fn is_false(value: &bool) -> bool {
@@ -391,6 +404,8 @@ impl Default for Config {
             state_sync_enabled: None,
             transaction_pool_size_limit: default_transaction_pool_size_limit(),
             s3_credentials_file: None,
+            compiled_contract_cache_dir: None,
+            remote_validator_signer_socket: None,
             enable_multiline_logging: None,
         }
     }
@@ -650,13 +665,25 @@ impl NearConfig {
                 sync_check_period: config.consensus.sync_check_period,
                 sync_step_period: config.consensus.sync_step_period,
                 sync_height_threshold: config.consensus.sync_height_threshold,
-                header_sync_initial_timeout: config.consensus.header_sync_initial_timeout,
-                header_sync_progress_timeout: config.consensus.header_sync_progress_timeout,
-                header_sync_stall_ban_timeout: config.consensus.header_sync_stall_ban_timeout,
+                header_sync_initial_timeout: MutableConfigValue::new(
+                    config.consensus.header_sync_initial_timeout,
+                    "header_sync_initial_timeout",
+                ),
+                header_sync_progress_timeout: MutableConfigValue::new(
+                    config.consensus.header_sync_progress_timeout,
+                    "header_sync_progress_timeout",
+                ),
+                header_sync_stall_ban_timeout: MutableConfigValue::new(
+                    config.consensus.header_sync_stall_ban_timeout,
+                    "header_sync_stall_ban_timeout",
+                ),
                 header_sync_expected_height_per_second: config
                     .consensus
                     .header_sync_expected_height_per_second,
-                state_sync_timeout: config.consensus.state_sync_timeout,
+                state_sync_timeout: MutableConfigValue::new(
+                    config.consensus.state_sync_timeout,
+                    "state_sync_timeout",
+                ),
                 min_num_peers: config.consensus.min_num_peers,
                 log_summary_period: Duration::from_secs(10),
                 produce_empty_blocks: config.consensus.produce_empty_blocks,
@@ -670,26 +697,38 @@ impl NearConfig {
                 catchup_step_period: config.consensus.catchup_step_period,
                 chunk_request_retry_period: config.consensus.chunk_request_retry_period,
                 doosmslug_step_period: config.consensus.doomslug_step_period,
-                tracked_accounts: config.tracked_accounts,
+                tracked_accounts: MutableConfigValue::new(
+                    config.tracked_accounts,
+                    "tracked_accounts",
+                ),
                 tracked_shards: config.tracked_shards,
                 tracked_shard_schedule: config.tracked_shard_schedule.unwrap_or(vec![]),
                 archive: config.archive,
                 save_trie_changes: config.save_trie_changes.unwrap_or(!config.archive),
                 log_summary_style: config.log_summary_style,
-                gc: config.gc,
+                gc: MutableConfigValue::new(config.gc, "gc"),
                 view_client_threads: config.view_client_threads,
                 epoch_sync_enabled: config.epoch_sync_enabled,
                 view_client_throttle_period: config.view_client_throttle_period,
                 trie_viewer_state_size_limit: config.trie_viewer_state_size_limit,
-                max_gas_burnt_view: config.max_gas_burnt_view,
+                max_gas_burnt_view: MutableConfigValue::new(
+                    config.max_gas_burnt_view,
+                    "max_gas_burnt_view",
+                ),
                 enable_statistics_export: config.store.enable_statistics_export,
                 client_background_migration_threads: config.store.background_migration_threads,
                 flat_storage_creation_enabled: config.store.flat_storage_creation_enabled,
                 flat_storage_creation_period: config.store.flat_storage_creation_period,
                 state_sync_enabled: config.state_sync_enabled.unwrap_or(false),
-                state_sync: config.state_sync.unwrap_or_default(),
+                state_sync: MutableConfigValue::new(
+                    config.state_sync.unwrap_or_default(),
+                    "state_sync",
+                ),
                 state_snapshot_every_n_blocks: None,
-                transaction_pool_size_limit: config.transaction_pool_size_limit,
+                transaction_pool_size_limit: MutableConfigValue::new(
+                    config.transaction_pool_size_limit,
+                    "transaction_pool_size_limit",
+                ),
                 enable_multiline_logging: config.enable_multiline_logging.unwrap_or(true),
             },
             network_config: NetworkConfig::new(
@@ -725,7 +764,10 @@ impl NearConfig {
 
         self.config.write_to_file(&dir.join(CONFIG_FILENAME)).expect("Error writing config");
 
-        if let Some(validator_signer) = &self.validator_signer {
+        // The key of a remote validator signer stays with the signer.
+        if let (Some(validator_signer), None) =
+            (&self.validator_signer, &self.config.remote_validator_signer_socket)
+        {
             validator_signer
                 .write_to_file(&dir.join(&self.config.validator_key_file))
                 .expect("Error writing validator key file");
@@ -1385,7 +1427,27 @@ pub fn load_config(
     };
 
     let validator_file = dir.join(&config.validator_key_file);
-    let validator_signer = if validator_file.exists() {
+    let validator_signer = if let Some(socket) = &config.remote_validator_signer_socket {
+        let socket = dir.join(socket);
+        #[cfg(unix)]
+        let result = RemoteValidatorSigner::connect(&socket)
+            .map(|signer| Arc::new(signer) as Arc<dyn ValidatorSigner>);
+        #[cfg(not(unix))]
+        let result: std::io::Result<Arc<dyn ValidatorSigner>> =
+            Err(std::io::ErrorKind::Unsupported.into());
+        match result {
+            Ok(signer) => Some(signer),
+            Err(err) => {
+                let error_message = format!(
+                    "Failed connecting to the remote validator signer at {}: {}",
+                    socket.display(),
+                    err
+                );
+                validation_errors.push_validator_key_file_error(error_message);
+                None
+            }
+        }
+    } else if validator_file.exists() {
         match InMemoryValidatorSigner::from_file(&validator_file) {
             Ok(signer) => Some(Arc::new(signer) as Arc<dyn ValidatorSigner>),
             Err(_) => {
@@ -1,15 +1,19 @@
 use crate::config::Config;
-use near_chain_configs::UpdateableClientConfig;
+use near_chain_configs::{TrieCacheSizeLimits, UpdateableClientConfig};
 use near_dyn_configs::{UpdateableConfigLoaderError, UpdateableConfigs};
 use near_o11y::log_config::LogConfig;
+use near_primitives::types::Gas;
 use serde::Deserialize;
 use std::path::{Path, PathBuf};
 
 pub const LOG_CONFIG_FILENAME: &str = "log_config.json";
 
 /// This function gets called at the startup and each time a config needs to be reloaded.
+/// `max_gas_burnt_view_override` is the value given on the command line, which takes
+/// precedence over the one in `config.json`.
 pub fn read_updateable_configs(
     home_dir: &Path,
+    max_gas_burnt_view_override: Option<Gas>,
 ) -> Result<UpdateableConfigs, UpdateableConfigLoaderError> {
     let mut errs = vec![];
     let log_config = match read_log_config(home_dir) {
@@ -23,7 +27,12 @@ pub fn read_updateable_configs(
         match Config::from_file(&home_dir.join(crate::config::CONFIG_FILENAME))
             .map(get_updateable_client_config)
         {
-            Ok(config) => Some(config),
+            Ok(mut config) => {
+                if max_gas_burnt_view_override.is_some() {
+                    config.max_gas_burnt_view = max_gas_burnt_view_override;
+                }
+                Some(config)
+            }
             Err(err) => {
                 errs.push(UpdateableConfigLoaderError::ConfigFileError {
                     file: PathBuf::from(crate::config::CONFIG_FILENAME),
@@ -45,7 +54,26 @@ pub fn read_updateable_configs(
 pub fn get_updateable_client_config(config: Config) -> UpdateableClientConfig {
     // All fields that can be updated while the node is running should be explicitly set here.
     // Keep this list in-sync with `core/dyn-configs/README.md`.
-    UpdateableClientConfig { expected_shutdown: config.expected_shutdown }
+    UpdateableClientConfig {
+        expected_shutdown: config.expected_shutdown,
+        gc: config.gc,
+        transaction_pool_size_limit: config.transaction_pool_size_limit,
+        max_gas_burnt_view: config.max_gas_burnt_view,
+        header_sync_initial_timeout: config.consensus.header_sync_initial_timeout,
+        header_sync_progress_timeout: config.consensus.header_sync_progress_timeout,
+        header_sync_stall_ban_timeout: config.consensus.header_sync_stall_ban_timeout,
+        state_sync_timeout: config.consensus.state_sync_timeout,
+        tracked_accounts: config.tracked_accounts,
+        state_sync: config.state_sync.unwrap_or_default(),
+        trie_cache: TrieCacheSizeLimits {
+            default_max_bytes: config.store.trie_cache.default_max_bytes,
+            per_shard_max_bytes: config.store.trie_cache.per_shard_max_bytes,
+        },
+        view_trie_cache: TrieCacheSizeLimits {
+            default_max_bytes: config.store.view_trie_cache.default_max_bytes,
+            per_shard_max_bytes: config.store.view_trie_cache.per_shard_max_bytes,
+        },
+    }
 }
 
 fn read_log_config(home_dir: &Path) -> Result<Option<LogConfig>, UpdateableConfigLoaderError> {
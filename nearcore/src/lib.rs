@@ -11,6 +11,7 @@ use cold_storage::ColdStoreLoopHandle;
 use near_async::actix::AddrWithAutoSpanContextExt;
 use near_async::messaging::{IntoSender, LateBoundSender};
 use near_async::time;
+use near_chain::block_timeline::BlockTimeline;
 use near_chain::state_snapshot_actor::{get_make_snapshot_callback, StateSnapshotActor};
 use near_chain::types::RuntimeAdapter;
 use near_chain::{Chain, ChainGenesis};
@@ -235,7 +236,8 @@ pub fn start_with_config_and_synchronization(
     let epoch_manager =
         EpochManager::new_arc_handle(storage.get_hot_store(), &config.genesis.config);
     let shard_tracker =
-        ShardTracker::new(TrackedConfig::from_config(&config.client_config), epoch_manager.clone());
+        ShardTracker::new(TrackedConfig::from_config(&config.client_config), epoch_manager.clone())
+            .with_persisted_tracking_changes(storage.get_hot_store())?;
     let runtime = NightshadeRuntime::from_config(
         home_dir,
         storage.get_hot_store(),
@@ -250,10 +252,8 @@ pub fn start_with_config_and_synchronization(
         if let Some(split_store) = &split_store {
             let view_epoch_manager =
                 EpochManager::new_arc_handle(split_store.clone(), &config.genesis.config);
-            let view_shard_tracker = ShardTracker::new(
-                TrackedConfig::from_config(&config.client_config),
-                epoch_manager.clone(),
-            );
+            // Shares the tracking changes requested while the node is running.
+            let view_shard_tracker = shard_tracker.clone();
             let view_runtime = NightshadeRuntime::from_config(
                 home_dir,
                 split_store.clone(),
@@ -267,7 +267,11 @@ pub fn start_with_config_and_synchronization(
 
     let cold_store_loop_handle = spawn_cold_store_loop(&config, &storage, epoch_manager.clone())?;
 
-    let telemetry = TelemetryActor::new(config.telemetry_config.clone()).start();
+    let telemetry = TelemetryActor::new(
+        config.telemetry_config.clone(),
+        config.network_config.node_key.clone(),
+    )
+    .start();
     let chain_genesis = ChainGenesis::new(&config.genesis);
     let genesis_block =
         Chain::make_genesis_block(epoch_manager.as_ref(), runtime.as_ref(), &chain_genesis)?;
@@ -312,6 +316,8 @@ pub fn start_with_config_and_synchronization(
         } else {
             None
         };
+    // The chunk events recorded by the shards manager end up in the timeline of the chain.
+    let block_timeline = BlockTimeline::default();
     let (client_actor, client_arbiter_handle) = start_client(
         config.client_config.clone(),
         chain_genesis.clone(),
@@ -327,6 +333,7 @@ pub fn start_with_config_and_synchronization(
         shutdown_signal,
         adv,
         config_updater,
+        block_timeline.clone(),
     );
     client_adapter_for_shards_manager.bind(client_actor.clone().with_auto_span_context());
     let (shards_manager_actor, shards_manager_arbiter_handle) = start_shards_manager(
@@ -337,6 +344,7 @@ pub fn start_with_config_and_synchronization(
         config.validator_signer.as_ref().map(|signer| signer.validator_id().clone()),
         split_store.unwrap_or(storage.get_hot_store()),
         config.client_config.chunk_request_retry_period,
+        block_timeline,
     );
     shards_manager_adapter.bind(shards_manager_actor);
 
@@ -4,10 +4,13 @@ use crate::NearConfig;
 use borsh::ser::BorshSerialize;
 use borsh::BorshDeserialize;
 use errors::FromStateViewerErrors;
-use near_chain::types::{ApplySplitStateResult, ApplyTransactionResult, RuntimeAdapter, Tip};
+use near_chain::types::{
+    AccountBalanceChange, ApplySplitStateResult, ApplyTransactionResult, BlockHeader, DryRunResult,
+    DryRunShard, RuntimeAdapter, Tip,
+};
 use near_chain::Error;
 use near_chain_configs::{
-    Genesis, GenesisConfig, ProtocolConfig, DEFAULT_GC_NUM_EPOCHS_TO_KEEP,
+    Genesis, GenesisConfig, MutableConfigValue, ProtocolConfig, DEFAULT_GC_NUM_EPOCHS_TO_KEEP,
     MIN_GC_NUM_EPOCHS_TO_KEEP,
 };
 use near_client_primitives::types::StateSplitApplyingStatus;
@@ -21,7 +24,7 @@ use near_primitives::config::ExtCosts;
 use near_primitives::contract::ContractCode;
 use near_primitives::errors::{InvalidTxError, RuntimeError, StorageError};
 use near_primitives::hash::{hash, CryptoHash};
-use near_primitives::receipt::Receipt;
+use near_primitives::receipt::{Receipt, ReceiptEnum};
 use near_primitives::runtime::config_store::RuntimeConfigStore;
 use near_primitives::runtime::migration_data::{MigrationData, MigrationFlags};
 use near_primitives::sandbox::state_patch::SandboxStatePatch;
@@ -34,7 +37,8 @@ use near_primitives::transaction::SignedTransaction;
 use near_primitives::types::validator_stake::ValidatorStakeIter;
 use near_primitives::types::{
     AccountId, Balance, BlockHeight, EpochHeight, EpochId, EpochInfoProvider, Gas, MerkleHash,
-    NumShards, ShardId, StateChangeCause, StateChangesForSplitStates, StateRoot, StateRootNode,
+    NumBlocks, NumShards, ShardId, StateChangeCause, StateChangeValue, StateChanges,
+    StateChangesExt, StateChangesForSplitStates, StateRoot, StateRootNode,
 };
 use near_primitives::version::ProtocolVersion;
 use near_primitives::views::{
@@ -46,12 +50,13 @@ use near_store::genesis::initialize_genesis_state;
 use near_store::metadata::DbKind;
 use near_store::split_state::get_delayed_receipts;
 use near_store::{
-    get_genesis_hash, get_genesis_state_roots, set_genesis_hash, set_genesis_state_roots,
-    ApplyStatePartResult, DBCol, PartialStorage, ShardTries, StateSnapshotConfig, Store,
-    StoreCompiledContractCache, Trie, TrieConfig, WrappedTrieChanges, COLD_HEAD_KEY,
+    get_account, get_genesis_hash, get_genesis_state_roots, set_genesis_hash,
+    set_genesis_state_roots, ApplyStatePartResult, DBCol, PartialStorage, ShardTries,
+    StateSnapshotConfig, Store, StoreCompiledContractCache, Trie, TrieConfig, WrappedTrieChanges,
+    COLD_HEAD_KEY,
 };
 use near_vm_runner::logic::CompiledContractCache;
-use near_vm_runner::precompile_contract;
+use near_vm_runner::{precompile_contract, FilesystemCompiledContractCache};
 use node_runtime::adapter::ViewRuntimeAdapter;
 use node_runtime::config::RuntimeConfig;
 use node_runtime::state_viewer::TrieViewer;
@@ -59,8 +64,9 @@ use node_runtime::{
     validate_transaction, verify_and_charge_transaction, ApplyState, Runtime,
     ValidatorAccountsUpdate,
 };
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::rc::Rc;
 use std::sync::Arc;
 use std::time::Instant;
 use tracing::{debug, error};
@@ -76,12 +82,16 @@ pub struct NightshadeRuntime {
     store: Store,
     tries: ShardTries,
     trie_viewer: TrieViewer,
+    /// Overrides the gas limit of `trie_viewer`, can be changed while the node is running.
+    max_gas_burnt_view: MutableConfigValue<Option<Gas>>,
     flat_storage_manager: FlatStorageManager,
     pub runtime: Runtime,
     epoch_manager: Arc<EpochManagerHandle>,
     genesis_state_roots: Vec<StateRoot>,
     migration_data: Arc<MigrationData>,
     gc_num_epochs_to_keep: u64,
+    /// Shared cache of compiled contracts used instead of the database if configured.
+    filesystem_contract_cache: Option<FilesystemCompiledContractCache>,
 }
 
 impl NightshadeRuntime {
@@ -107,11 +117,12 @@ impl NightshadeRuntime {
             &config.genesis,
             epoch_manager,
             config.client_config.trie_viewer_state_size_limit,
-            config.client_config.max_gas_burnt_view,
+            config.client_config.max_gas_burnt_view.clone(),
             None,
             config.config.gc.gc_num_epochs_to_keep(),
             TrieConfig::from_store_config(&config.config.store),
             state_snapshot_config,
+            config.config.compiled_contract_cache_dir.as_ref().map(|dir| home_dir.join(dir)),
         )
     }
 
@@ -121,11 +132,12 @@ impl NightshadeRuntime {
         genesis: &Genesis,
         epoch_manager: Arc<EpochManagerHandle>,
         trie_viewer_state_size_limit: Option<u64>,
-        max_gas_burnt_view: Option<Gas>,
+        max_gas_burnt_view: MutableConfigValue<Option<Gas>>,
         runtime_config_store: Option<RuntimeConfigStore>,
         gc_num_epochs_to_keep: u64,
         trie_config: TrieConfig,
         state_snapshot_config: StateSnapshotConfig,
+        compiled_contract_cache_dir: Option<PathBuf>,
     ) -> Arc<Self> {
         let runtime_config_store = match runtime_config_store {
             Some(store) => store,
@@ -133,7 +145,7 @@ impl NightshadeRuntime {
         };
 
         let runtime = Runtime::new();
-        let trie_viewer = TrieViewer::new(trie_viewer_state_size_limit, max_gas_burnt_view);
+        let trie_viewer = TrieViewer::new(trie_viewer_state_size_limit, None);
         let genesis_config = genesis.config.clone();
         assert_eq!(
             genesis_config.shard_layout.num_shards(),
@@ -144,6 +156,20 @@ impl NightshadeRuntime {
         );
         let state_roots =
             Self::initialize_genesis_state_if_needed(store.clone(), home_dir, genesis);
+        // The node can run without the shared cache, so it falls back to the database rather
+        // than failing to start.
+        let filesystem_contract_cache = compiled_contract_cache_dir.and_then(|dir| {
+            FilesystemCompiledContractCache::new(&dir)
+                .map_err(|err| {
+                    tracing::warn!(
+                        target: "runtime",
+                        dir = %dir.display(),
+                        %err,
+                        "Failed to open the compiled contract cache, caching compiled contracts in the database instead",
+                    )
+                })
+                .ok()
+        });
         let flat_storage_manager = FlatStorageManager::new(store.clone());
         let tries = ShardTries::new_with_state_snapshot(
             store.clone(),
@@ -168,11 +194,13 @@ impl NightshadeRuntime {
             tries,
             runtime,
             trie_viewer,
+            max_gas_burnt_view,
             epoch_manager,
             flat_storage_manager,
             genesis_state_roots: state_roots,
             migration_data: Arc::new(load_migration_data(&genesis.config.chain_id)),
             gc_num_epochs_to_keep: gc_num_epochs_to_keep.max(MIN_GC_NUM_EPOCHS_TO_KEEP),
+            filesystem_contract_cache,
         })
     }
 
@@ -189,7 +217,7 @@ impl NightshadeRuntime {
             genesis,
             epoch_manager,
             None,
-            None,
+            MutableConfigValue::new(None, "max_gas_burnt_view"),
             Some(runtime_config_store),
             DEFAULT_GC_NUM_EPOCHS_TO_KEEP,
             Default::default(),
@@ -199,6 +227,7 @@ impl NightshadeRuntime {
                 state_snapshot_subdir: PathBuf::from("state_snapshot"),
                 compaction_enabled: false,
             },
+            None,
         )
     }
 
@@ -217,6 +246,15 @@ impl NightshadeRuntime {
         )
     }
 
+    /// Returns the cache of compiled contracts: the shared directory if one is configured,
+    /// the database otherwise.
+    fn compiled_contract_cache(&self) -> Box<dyn CompiledContractCache> {
+        match &self.filesystem_contract_cache {
+            Some(cache) => Box::new(cache.clone()),
+            None => Box::new(StoreCompiledContractCache::new(&self.store)),
+        }
+    }
+
     /// Create store of runtime configs for the given chain id.
     ///
     /// For mainnet and other chains except testnet we don't need to override runtime config for
@@ -417,7 +455,7 @@ impl NightshadeRuntime {
             random_seed,
             current_protocol_version,
             config: self.runtime_config_store.get_config(current_protocol_version).clone(),
-            cache: Some(Box::new(StoreCompiledContractCache::new(&self.store))),
+            cache: Some(self.compiled_contract_cache()),
             is_new_chunk,
             migration_data: Arc::clone(&self.migration_data),
             migration_flags: MigrationFlags {
@@ -514,7 +552,7 @@ impl NightshadeRuntime {
         let protocol_version = self.epoch_manager.get_epoch_protocol_version(epoch_id)?;
         let runtime_config = self.runtime_config_store.get_config(protocol_version);
         let compiled_contract_cache: Option<Box<dyn CompiledContractCache>> =
-            Some(Box::new(StoreCompiledContractCache::new(&self.store)));
+            Some(self.compiled_contract_cache());
         // Execute precompile_contract in parallel but prevent it from using more than half of all
         // threads so that node will still function normally.
         rayon::scope(|scope| {
@@ -1122,6 +1160,212 @@ impl RuntimeAdapter for NightshadeRuntime {
         res
     }
 
+    fn dry_run_transaction(
+        &self,
+        prev_header: &BlockHeader,
+        shards: &HashMap<ShardId, DryRunShard>,
+        transaction: &SignedTransaction,
+        verify_signature: bool,
+        max_blocks: NumBlocks,
+        max_gas: Gas,
+    ) -> Result<Result<DryRunResult, InvalidTxError>, Error> {
+        let prev_block_hash = prev_header.hash();
+        // The state roots are post state roots of `prev_header`, so the shards are the ones of
+        // its epoch.
+        let prev_epoch_id = prev_header.epoch_id();
+        let epoch_id = self.epoch_manager.get_epoch_id_from_prev_block(prev_block_hash)?;
+        let epoch_height = self.epoch_manager.get_epoch_height_from_prev_block(prev_block_hash)?;
+        let current_protocol_version = self.epoch_manager.get_epoch_protocol_version(&epoch_id)?;
+        let config = self.runtime_config_store.get_config(current_protocol_version);
+        let runtime = if verify_signature {
+            Runtime::new()
+        } else {
+            Runtime::without_signature_verification()
+        };
+        let shard_of = |account_id: &AccountId| {
+            self.epoch_manager.account_id_to_shard_id(account_id, prev_epoch_id)
+        };
+        let base_trie = |shard_id: ShardId| -> Result<Trie, Error> {
+            let shard = shards
+                .get(&shard_id)
+                .ok_or_else(|| Error::Other(format!("shard {shard_id} is not tracked")))?;
+            let shard_uid = self.epoch_manager.shard_id_to_uid(shard_id, prev_epoch_id)?;
+            Ok(self.tries.get_view_trie_for_shard(shard_uid, shard.state_root))
+        };
+
+        let tx_shard_id = shard_of(&transaction.transaction.signer_id)?;
+        // Ids of the transaction and of the receipts it generated, directly or not.  The made up
+        // blocks also execute the receipts already on chain, their outcomes are left out.
+        let mut known_ids = HashSet::from([transaction.get_hash()]);
+        // Ids of the data the known receipts will send.
+        let mut known_data_ids = HashSet::new();
+        let mut incoming: HashMap<ShardId, Vec<Receipt>> = HashMap::new();
+        // Action receipts which were delivered but didn't execute yet, because they were delayed
+        // or are waiting for data.
+        let mut waiting: HashMap<CryptoHash, (ShardId, Receipt)> = HashMap::new();
+        let mut tries: HashMap<ShardId, Trie> = HashMap::new();
+        let mut outcomes = vec![];
+        let mut raw_state_changes = vec![];
+        let mut gas_burnt: Gas = 0;
+        for block_index in 0..max_blocks {
+            if gas_burnt >= max_gas {
+                break;
+            }
+            let mut shard_ids: BTreeSet<ShardId> =
+                incoming.keys().copied().filter(|shard_id| shards.contains_key(shard_id)).collect();
+            shard_ids.extend(waiting.values().map(|(shard_id, _)| *shard_id));
+            if block_index == 0 {
+                shard_ids.insert(tx_shard_id);
+            }
+            if shard_ids.is_empty() {
+                break;
+            }
+            let block_height = prev_header.height() + 1 + block_index;
+            let block_hash = CryptoHash::hash_borsh((prev_block_hash, block_height));
+            let mut outgoing_receipts = vec![];
+            for shard_id in shard_ids {
+                let receipts = incoming.remove(&shard_id).unwrap_or_default();
+                for receipt in &receipts {
+                    if let ReceiptEnum::Action(_) = receipt.receipt {
+                        waiting.insert(receipt.receipt_id, (shard_id, receipt.clone()));
+                    }
+                }
+                let transactions = if block_index == 0 && shard_id == tx_shard_id {
+                    std::slice::from_ref(transaction)
+                } else {
+                    &[]
+                };
+                let trie = match tries.remove(&shard_id) {
+                    Some(trie) => trie,
+                    None => base_trie(shard_id)?,
+                };
+                let apply_state = ApplyState {
+                    block_height,
+                    prev_block_hash: *prev_block_hash,
+                    block_hash,
+                    epoch_id: epoch_id.clone(),
+                    epoch_height,
+                    gas_price: prev_header.gas_price(),
+                    // The made up blocks are one second apart.
+                    block_timestamp: prev_header.raw_timestamp()
+                        + (block_index + 1) * 1_000_000_000,
+                    gas_limit: Some(shards[&shard_id].gas_limit),
+                    random_seed: *prev_header.random_value(),
+                    current_protocol_version,
+                    config: config.clone(),
+                    cache: Some(self.compiled_contract_cache()),
+                    is_new_chunk: true,
+                    migration_data: Arc::clone(&self.migration_data),
+                    migration_flags: MigrationFlags::default(),
+                };
+                let apply_result = match runtime.apply(
+                    Trie::new(Rc::clone(&trie.storage), *trie.get_root(), None),
+                    &None,
+                    &apply_state,
+                    &receipts,
+                    transactions,
+                    self.epoch_manager.as_ref(),
+                    Default::default(),
+                ) {
+                    Ok(apply_result) => apply_result,
+                    Err(RuntimeError::InvalidTxError(err)) => return Ok(Err(err)),
+                    Err(RuntimeError::StorageError(err)) => return Err(Error::StorageError(err)),
+                    Err(err) => return Err(Error::Other(err.to_string())),
+                };
+                tries.insert(shard_id, trie.with_uncommitted_changes(&apply_result.trie_changes));
+                gas_burnt = apply_result
+                    .outcomes
+                    .iter()
+                    .fold(gas_burnt, |gas, outcome| gas.saturating_add(outcome.outcome.gas_burnt));
+                for outcome in apply_result.outcomes {
+                    if known_ids.contains(&outcome.id) {
+                        waiting.remove(&outcome.id);
+                        known_ids.extend(outcome.outcome.receipt_ids.iter().copied());
+                        outcomes.push(outcome);
+                    }
+                }
+                outgoing_receipts.extend(apply_result.outgoing_receipts);
+                raw_state_changes.extend(apply_result.state_changes);
+            }
+            // Receipts are executed in the block after the one which generated them.
+            for receipt in outgoing_receipts {
+                let is_known = match &receipt.receipt {
+                    ReceiptEnum::Action(_) => known_ids.contains(&receipt.receipt_id),
+                    ReceiptEnum::Data(data_receipt) => {
+                        known_data_ids.contains(&data_receipt.data_id)
+                    }
+                };
+                if !is_known {
+                    continue;
+                }
+                known_ids.insert(receipt.receipt_id);
+                if let ReceiptEnum::Action(action_receipt) = &receipt.receipt {
+                    known_data_ids.extend(
+                        action_receipt
+                            .output_data_receivers
+                            .iter()
+                            .map(|receiver| receiver.data_id),
+                    );
+                }
+                incoming.entry(shard_of(&receipt.receiver_id)?).or_default().push(receipt);
+            }
+        }
+
+        let state_changes =
+            StateChanges::from_changes(raw_state_changes.into_iter().filter_map(|mut change| {
+                change.changes.retain(|change| match change.cause {
+                    StateChangeCause::TransactionProcessing { tx_hash } => {
+                        known_ids.contains(&tx_hash)
+                    }
+                    StateChangeCause::ActionReceiptProcessingStarted { receipt_hash }
+                    | StateChangeCause::ActionReceiptGasReward { receipt_hash }
+                    | StateChangeCause::ReceiptProcessing { receipt_hash }
+                    | StateChangeCause::PostponedReceipt { receipt_hash } => {
+                        known_ids.contains(&receipt_hash)
+                    }
+                    _ => false,
+                });
+                (!change.changes.is_empty()).then_some(Ok(change))
+            }))?;
+
+        let mut accounts_after = BTreeMap::new();
+        for change in &state_changes {
+            match &change.value {
+                StateChangeValue::AccountUpdate { account_id, account } => {
+                    accounts_after.insert(account_id.clone(), Some(account.clone()));
+                }
+                StateChangeValue::AccountDeletion { account_id } => {
+                    accounts_after.insert(account_id.clone(), None);
+                }
+                _ => {}
+            }
+        }
+        let balance = |account: &Option<Account>| {
+            account.as_ref().map_or((0, 0), |account| (account.amount(), account.locked()))
+        };
+        let mut balance_changes = vec![];
+        for (account_id, account_after) in accounts_after {
+            let account_before = get_account(&base_trie(shard_of(&account_id)?)?, &account_id)?;
+            let (amount_before, locked_before) = balance(&account_before);
+            let (amount_after, locked_after) = balance(&account_after);
+            if (amount_before, locked_before) != (amount_after, locked_after) {
+                balance_changes.push(AccountBalanceChange {
+                    account_id,
+                    amount_before,
+                    amount_after,
+                    locked_before,
+                    locked_after,
+                });
+            }
+        }
+
+        let mut pending_receipts: Vec<Receipt> =
+            waiting.into_values().map(|(_, receipt)| receipt).collect();
+        pending_receipts.extend(incoming.into_values().flatten());
+        pending_receipts.sort_by_key(|receipt| receipt.receipt_id);
+        Ok(Ok(DryRunResult { outcomes, state_changes, balance_changes, pending_receipts }))
+    }
+
     fn validate_state_part(&self, state_root: &StateRoot, part_id: PartId, data: &[u8]) -> bool {
         match BorshDeserialize::try_from_slice(data) {
             Ok(trie_nodes) => {
@@ -1359,9 +1603,9 @@ impl node_runtime::adapter::ViewRuntimeAdapter for NightshadeRuntime {
             epoch_height,
             block_timestamp,
             current_protocol_version,
-            cache: Some(Box::new(StoreCompiledContractCache::new(&self.tries.get_store()))),
+            cache: Some(self.compiled_contract_cache()),
         };
-        self.trie_viewer.call_function(
+        self.trie_viewer.call_function_with_max_gas_burnt(
             state_update,
             view_state,
             contract_id,
@@ -1369,6 +1613,7 @@ impl node_runtime::adapter::ViewRuntimeAdapter for NightshadeRuntime {
             args,
             logs,
             epoch_info_provider,
+            self.max_gas_burnt_view.get(),
         )
     }
 
@@ -1585,7 +1830,7 @@ mod test {
                 &genesis,
                 epoch_manager.clone(),
                 None,
-                None,
+                MutableConfigValue::new(None, "max_gas_burnt_view"),
                 Some(RuntimeConfigStore::free()),
                 DEFAULT_GC_NUM_EPOCHS_TO_KEEP,
                 Default::default(),
@@ -1595,6 +1840,7 @@ mod test {
                     state_snapshot_subdir: PathBuf::from("state_snapshot"),
                     compaction_enabled: false,
                 },
+                None,
             );
             let (store, state_roots) = runtime.genesis_state();
             let genesis_hash = hash(&[0]);
@@ -2876,6 +3122,31 @@ mod test {
         assert_eq!(state_value, view_state_value);
     }
 
+    /// A compiled contract cache directory which can't be created falls back to the database.
+    #[test]
+    fn test_unusable_compiled_contract_cache_dir() {
+        let (dir, opener) = NodeStorage::test_opener();
+        let store = opener.open().unwrap().get_hot_store();
+        let genesis = Genesis::test(vec!["test0".parse().unwrap()], 1);
+        let epoch_manager = EpochManager::new_arc_handle(store.clone(), &genesis.config);
+        let file = dir.path().join("file");
+        std::fs::write(&file, b"").unwrap();
+        let runtime = NightshadeRuntime::new(
+            dir.path(),
+            store,
+            &genesis,
+            epoch_manager,
+            None,
+            MutableConfigValue::new(None, "max_gas_burnt_view"),
+            Some(RuntimeConfigStore::test()),
+            DEFAULT_GC_NUM_EPOCHS_TO_KEEP,
+            Default::default(),
+            StateSnapshotConfig::Disabled,
+            Some(file.join("cache")),
+        );
+        assert!(runtime.filesystem_contract_cache.is_none());
+    }
+
     /// Check that mainnet genesis hash still matches, to make sure that we're still backwards compatible.
     #[test]
     fn test_genesis_hash() {
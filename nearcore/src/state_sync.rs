@@ -2,7 +2,9 @@ use crate::metrics;
 use borsh::BorshSerialize;
 use near_chain::types::RuntimeAdapter;
 use near_chain::{Chain, ChainGenesis, ChainStoreAccess, DoomslugThresholdMode, Error};
-use near_chain_configs::{ClientConfig, ExternalStorageLocation};
+use near_chain_configs::{
+    ClientConfig, ExternalStorageLocation, MutableConfigValue, StateSyncConfig,
+};
 use near_client::sync::external::{create_bucket_readwrite, external_storage_location};
 use near_client::sync::external::{
     external_storage_location_directory, get_part_id_from_filename, is_part_filename,
@@ -34,7 +36,7 @@ pub fn spawn_state_sync_dump(
     account_id: Option<AccountId>,
     credentials_file: Option<PathBuf>,
 ) -> anyhow::Result<Option<StateSyncDumpHandle>> {
-    let dump_config = if let Some(dump_config) = client_config.state_sync.dump.clone() {
+    let dump_config = if let Some(dump_config) = client_config.state_sync.get().dump {
         dump_config
     } else {
         // Dump is not configured, and therefore not enabled.
@@ -93,7 +95,7 @@ pub fn spawn_state_sync_dump(
                 chain_id.clone(),
                 dump_config.restart_dump_for_shards.clone().unwrap_or_default(),
                 external.clone(),
-                dump_config.iteration_delay.unwrap_or(Duration::from_secs(10)),
+                client_config.state_sync.clone(),
                 account_id.clone(),
                 keep_running.clone(),
             )));
@@ -205,6 +207,9 @@ fn get_current_state(
 
 const FAILURES_ALLOWED_PER_ITERATION: u32 = 10;
 
+/// How often to check if a new epoch has started, unless configured otherwise.
+const DEFAULT_ITERATION_DELAY: Duration = Duration::from_secs(10);
+
 async fn state_sync_dump(
     shard_id: ShardId,
     chain: Chain,
@@ -214,7 +219,7 @@ async fn state_sync_dump(
     chain_id: String,
     restart_dump_for_shards: Vec<ShardId>,
     external: ExternalConnection,
-    iteration_delay: Duration,
+    state_sync_config: MutableConfigValue<StateSyncConfig>,
     account_id: Option<AccountId>,
     keep_running: Arc<AtomicBool>,
 ) {
@@ -387,6 +392,12 @@ async fn state_sync_dump(
 
         if !has_progress {
             // Avoid a busy-loop when there is nothing to do.
+            // The delay is read on every iteration, because it can be changed while the node is running.
+            let iteration_delay = state_sync_config
+                .get()
+                .dump
+                .and_then(|dump_config| dump_config.iteration_delay)
+                .unwrap_or(DEFAULT_ITERATION_DELAY);
             actix_rt::time::sleep(tokio::time::Duration::from(iteration_delay)).await;
         }
     }
@@ -1,9 +1,10 @@
 #[cfg(unix)]
 use anyhow::Context;
 use near_amend_genesis::AmendGenesisCommand;
-use near_chain_configs::GenesisValidationMode;
+use near_chain_configs::{GenesisValidationMode, MutableConfigValue};
 use near_client::ConfigUpdater;
 use near_cold_store_tool::ColdStoreCommand;
+use near_contract_tool::cli::ContractCommand;
 use near_database_tool::commands::DatabaseCommand;
 use near_dyn_configs::{UpdateableConfigLoader, UpdateableConfigLoaderError, UpdateableConfigs};
 use near_flat_storage::commands::FlatStorageCommand;
@@ -16,7 +17,7 @@ use near_o11y::{
     default_subscriber, default_subscriber_with_opentelemetry, BuildEnvFilterError,
     EnvFilterBuilder,
 };
-use near_ping::PingCommand;
+use near_ping::{LatencyMatrixCommand, PingCommand};
 use near_primitives::hash::CryptoHash;
 use near_primitives::merkle::compute_root_from_path;
 use near_primitives::types::{Gas, NumSeats, NumShards};
@@ -108,6 +109,9 @@ impl NeardCmd {
             NeardSubCommand::Ping(cmd) => {
                 cmd.run()?;
             }
+            NeardSubCommand::PingLatencyMatrix(cmd) => {
+                cmd.run()?;
+            }
             NeardSubCommand::Mirror(cmd) => {
                 cmd.run()?;
             }
@@ -135,6 +139,12 @@ impl NeardCmd {
             NeardSubCommand::ForkNetwork(cmd) => {
                 cmd.run(&home_dir, genesis_validation)?;
             }
+            NeardSubCommand::Contract(cmd) => {
+                cmd.run(&home_dir, genesis_validation)?;
+            }
+            NeardSubCommand::ConvertTraceFile(cmd) => {
+                cmd.run()?;
+            }
         };
         Ok(())
     }
@@ -233,6 +243,9 @@ pub(super) enum NeardSubCommand {
     /// us after the handshake is completed, printing stats to stdout.
     Ping(PingCommand),
 
+    /// Builds a latency matrix out of the summaries written by `ping --upload-dir`.
+    PingLatencyMatrix(LatencyMatrixCommand),
+
     /// Mirror transactions from a source chain to a test chain with state forked
     /// from it, reproducing traffic and state as closely as possible.
     Mirror(MirrorCommand),
@@ -260,6 +273,12 @@ pub(super) enum NeardSubCommand {
 
     /// Resets the network into a forked network at the given block height and state.
     ForkNetwork(ForkNetworkCommand),
+
+    /// Tools for the contracts deployed on chain, e.g. compiling them into a shared cache.
+    Contract(ContractCommand),
+
+    /// Converts the span files written with `--trace-file-dir` into Chrome trace or OTLP JSON.
+    ConvertTraceFile(near_o11y::trace_file::ConvertTraceFileCommand),
 }
 
 #[derive(clap::Parser)]
@@ -485,7 +504,8 @@ impl RunCmd {
             near_config.client_config.archive = true;
         }
         if self.max_gas_burnt_view.is_some() {
-            near_config.client_config.max_gas_burnt_view = self.max_gas_burnt_view;
+            near_config.client_config.max_gas_burnt_view =
+                MutableConfigValue::new(self.max_gas_burnt_view, "max_gas_burnt_view");
         }
 
         #[cfg(feature = "sandbox")]
@@ -501,6 +521,7 @@ impl RunCmd {
             }
         }
 
+        let max_gas_burnt_view = self.max_gas_burnt_view;
         let (tx_crash, mut rx_crash) = broadcast::channel::<()>(16);
         let (tx_config_update, rx_config_update) =
             broadcast::channel::<Result<UpdateableConfigs, Arc<UpdateableConfigLoaderError>>>(16);
@@ -522,8 +543,9 @@ impl RunCmd {
             .await
             .global();
 
-            let updateable_configs = nearcore::dyn_config::read_updateable_configs(home_dir)
-                .unwrap_or_else(|e| panic!("Error reading dynamic configs: {:#}", e));
+            let updateable_configs =
+                nearcore::dyn_config::read_updateable_configs(home_dir, max_gas_burnt_view)
+                    .unwrap_or_else(|e| panic!("Error reading dynamic configs: {:#}", e));
             let mut updateable_config_loader =
                 UpdateableConfigLoader::new(updateable_configs.clone(), tx_config_update);
             let config_updater = ConfigUpdater::new(rx_config_update);
@@ -546,7 +568,7 @@ impl RunCmd {
                 let sig = wait_for_interrupt_signal(home_dir, &mut rx_crash).await;
                 if sig == "SIGHUP" {
                     let maybe_updateable_configs =
-                        nearcore::dyn_config::read_updateable_configs(home_dir);
+                        nearcore::dyn_config::read_updateable_configs(home_dir, max_gas_burnt_view);
                     updateable_config_loader.reload(maybe_updateable_configs);
                 } else {
                     break sig;
@@ -569,7 +591,7 @@ impl RunCmd {
             .await;
             actix::System::current().stop();
             // Disable the subscriber to properly shutdown the tracer.
-            near_o11y::reload(Some("error"), None, Some(near_o11y::OpenTelemetryLevel::OFF))
+            near_o11y::reload(Some("error"), None, Some(near_o11y::OpenTelemetryLevel::OFF), None)
                 .unwrap();
         });
         sys.run().unwrap();
@@ -2,11 +2,14 @@ use crate::errors::ContractPrecompilatonResult;
 use crate::logic::errors::{CacheError, CompilationError};
 use crate::logic::{CompiledContract, CompiledContractCache, ProtocolVersion, VMConfig};
 use crate::vm_kind::VMKind;
-use borsh::BorshSerialize;
+use borsh::{BorshDeserialize, BorshSerialize};
 use near_primitives_core::contract::ContractCode;
 use near_primitives_core::hash::CryptoHash;
 use std::collections::HashMap;
 use std::fmt;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 
 #[derive(Debug, Clone, BorshSerialize)]
@@ -88,6 +91,129 @@ impl fmt::Debug for MockCompiledContractCache {
     }
 }
 
+/// Cache of compiled contracts in a directory, which can be shared by several nodes and tools
+/// running on the same machine.
+///
+/// Every entry is stored in a file named after its key, the key already takes into account
+/// the VM, its version and its configuration.  The file starts with a header holding the
+/// format version, the key and the hash of the compiled contract, so that a truncated,
+/// corrupted or misplaced file is discarded and the contract is compiled again instead of
+/// being loaded.  Entries are written to a temporary file which is then renamed, so readers
+/// never see a partially written entry.
+#[derive(Clone)]
+pub struct FilesystemCompiledContractCache {
+    dir: PathBuf,
+    /// Suffix of the temporary files, unique within the process.
+    next_tmp_id: Arc<AtomicU64>,
+}
+
+impl FilesystemCompiledContractCache {
+    const MAGIC: &'static [u8; 8] = b"nearvmcc";
+    const FORMAT_VERSION: u32 = 1;
+    const HEADER_LEN: usize = 8 + 4 + 32 + 32;
+
+    /// Opens the cache in `dir`, creating the directory if it doesn't exist.
+    pub fn new(dir: &Path) -> io::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        Ok(Self { dir: dir.to_path_buf(), next_tmp_id: Default::default() })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn entry_path(&self, key: &CryptoHash) -> PathBuf {
+        self.dir.join(key.to_string())
+    }
+
+    fn encode(key: &CryptoHash, value: &CompiledContract) -> io::Result<Vec<u8>> {
+        let payload = value.try_to_vec()?;
+        let mut bytes = Vec::with_capacity(Self::HEADER_LEN + payload.len());
+        bytes.extend_from_slice(Self::MAGIC);
+        bytes.extend_from_slice(&Self::FORMAT_VERSION.to_le_bytes());
+        bytes.extend_from_slice(key.as_ref());
+        bytes.extend_from_slice(CryptoHash::hash_bytes(&payload).as_ref());
+        bytes.extend_from_slice(&payload);
+        Ok(bytes)
+    }
+
+    /// Decodes the entry of the key, returns the reason if it fails the integrity checks.
+    fn decode(key: &CryptoHash, bytes: &[u8]) -> Result<CompiledContract, &'static str> {
+        if bytes.len() < Self::HEADER_LEN {
+            return Err("truncated header");
+        }
+        let (magic, rest) = bytes.split_at(8);
+        let (version, rest) = rest.split_at(4);
+        let (entry_key, rest) = rest.split_at(32);
+        let (checksum, payload) = rest.split_at(32);
+        if magic != Self::MAGIC {
+            return Err("not a compiled contract");
+        }
+        if version != Self::FORMAT_VERSION.to_le_bytes() {
+            return Err("unsupported format version");
+        }
+        if entry_key != key.as_ref() {
+            return Err("key mismatch");
+        }
+        if checksum != CryptoHash::hash_bytes(payload).as_ref() {
+            return Err("checksum mismatch");
+        }
+        CompiledContract::try_from_slice(payload).map_err(|_| "invalid compiled contract")
+    }
+}
+
+impl CompiledContractCache for FilesystemCompiledContractCache {
+    fn put(&self, key: &CryptoHash, value: CompiledContract) -> io::Result<()> {
+        let bytes = Self::encode(key, &value)?;
+        let tmp_path = self.dir.join(format!(
+            ".{}.{}.{}.tmp",
+            key,
+            std::process::id(),
+            self.next_tmp_id.fetch_add(1, Ordering::Relaxed)
+        ));
+        let result = std::fs::File::create(&tmp_path)
+            .and_then(|mut file| {
+                file.write_all(&bytes)?;
+                file.sync_all()
+            })
+            .and_then(|()| std::fs::rename(&tmp_path, self.entry_path(key)));
+        if result.is_err() {
+            let _ = std::fs::remove_file(&tmp_path);
+        }
+        result
+    }
+
+    fn get(&self, key: &CryptoHash) -> io::Result<Option<CompiledContract>> {
+        let path = self.entry_path(key);
+        let bytes = match std::fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        match Self::decode(key, &bytes) {
+            Ok(value) => Ok(Some(value)),
+            Err(reason) => {
+                tracing::warn!(
+                    target: "vm",
+                    path = %path.display(),
+                    reason,
+                    "discarding invalid compiled contract cache entry"
+                );
+                // Another process might have replaced the entry in the meantime, which is fine:
+                // the contract is compiled again at worst.
+                let _ = std::fs::remove_file(&path);
+                Ok(None)
+            }
+        }
+    }
+}
+
+impl fmt::Debug for FilesystemCompiledContractCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FilesystemCompiledContractCache").field("dir", &self.dir).finish()
+    }
+}
+
 /// Precompiles contract for the current default VM, and stores result to the cache.
 /// Returns `Ok(true)` if compiled code was added to the cache, and `Ok(false)` if element
 /// is already in the cache, or if cache is `None`.
@@ -0,0 +1,326 @@
+//! Checks of a contract against the rules the runtime enforces when preparing it.
+//!
+//! The preparation of a contract stops at the first problem it finds.  The checks here go
+//! through the whole module instead and report every violation, then run the actual
+//! preparation and compilation so that the outcome matches what the runtime would do.
+use crate::cache::MockCompiledContractCache;
+use crate::imports::{host_functions, HostFunction};
+use crate::logic::errors::CompilationError;
+use crate::logic::{ContractPrepareVersion, ProtocolVersion, VMConfig};
+use crate::vm_kind::VMKind;
+use finite_wasm::wasmparser as wp;
+use near_primitives_core::contract::ContractCode;
+use std::collections::HashMap;
+use std::fmt;
+
+/// A rule of the runtime the contract breaks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContractViolation {
+    /// The code is larger than a deploy action allows.
+    ContractTooLarge { size: u64, limit: u64 },
+    /// The module can't be decoded, nothing after `offset` was checked.
+    Malformed { offset: usize, message: String },
+    /// The module isn't valid or uses a Wasm feature which is disabled at this protocol version.
+    Invalid { offset: usize, message: String },
+    /// The module defines and imports more functions than allowed.
+    TooManyFunctions { count: u64, limit: u64 },
+    /// The functions of the module declare more locals than allowed.
+    TooManyLocals { count: u64, limit: u64 },
+    /// Contracts can only import from the `env` module.
+    UnknownImportModule { module: String, name: String },
+    /// Contracts use the memory provided by the runtime and can't import one.
+    MemoryImport { module: String, name: String },
+    /// Contracts can't import tables, globals or tags.
+    UnsupportedImport { module: String, name: String },
+    /// The host function doesn't exist at this protocol version.
+    UnknownHostFunction { name: String },
+    /// The host function is imported with a signature which doesn't match the host's one.
+    HostFunctionSignatureMismatch { name: String, expected: String, found: String },
+}
+
+impl fmt::Display for ContractViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ContractTooLarge { size, limit } => {
+                write!(f, "{size} bytes of code, at most {limit} are allowed")
+            }
+            Self::Malformed { offset, message } => {
+                write!(f, "malformed module at offset {offset}: {message}")
+            }
+            Self::Invalid { offset, message } => {
+                write!(f, "invalid module at offset {offset}: {message}")
+            }
+            Self::TooManyFunctions { count, limit } => {
+                write!(f, "{count} functions, at most {limit} are allowed")
+            }
+            Self::TooManyLocals { count, limit } => {
+                write!(f, "{count} locals, at most {limit} are allowed")
+            }
+            Self::UnknownImportModule { module, name } => {
+                write!(f, "import {module}.{name}: only imports from `env` are allowed")
+            }
+            Self::MemoryImport { module, name } => {
+                write!(f, "import {module}.{name}: memories can't be imported")
+            }
+            Self::UnsupportedImport { module, name } => {
+                write!(f, "import {module}.{name}: only functions can be imported")
+            }
+            Self::UnknownHostFunction { name } => {
+                write!(f, "import env.{name}: no such host function")
+            }
+            Self::HostFunctionSignatureMismatch { name, expected, found } => {
+                write!(f, "import env.{name}: signature is {found}, expected {expected}")
+            }
+        }
+    }
+}
+
+/// Result of [`check_contract`].
+#[derive(Debug)]
+pub struct ContractCheckReport {
+    pub vm_kind: VMKind,
+    pub prepare_version: ContractPrepareVersion,
+    /// Number of functions defined or imported by the module.
+    pub functions: u64,
+    /// Number of locals declared by all the functions of the module.
+    pub locals: u64,
+    pub violations: Vec<ContractViolation>,
+    /// Outcome of preparing and compiling the contract the way the runtime does, or `None` if
+    /// the VM isn't enabled in this build.
+    pub compilation: Option<Result<(), CompilationError>>,
+}
+
+impl ContractCheckReport {
+    pub fn is_ok(&self) -> bool {
+        self.violations.is_empty() && !matches!(self.compilation, Some(Err(_)))
+    }
+}
+
+fn wasm_type(rust_type: &str) -> wp::ValType {
+    match rust_type {
+        "u32" | "i32" => wp::ValType::I32,
+        "u64" | "i64" => wp::ValType::I64,
+        "f32" => wp::ValType::F32,
+        "f64" => wp::ValType::F64,
+        _ => unreachable!("host functions only use integer and float types"),
+    }
+}
+
+fn format_signature(params: &[wp::ValType], results: &[wp::ValType]) -> String {
+    let join = |types: &[wp::ValType]| {
+        types.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+    };
+    format!("({}) -> ({})", join(params), join(results))
+}
+
+/// Checks the contract against the rules of the runtime at the protocol version, `config`
+/// being the VM configuration of that protocol version.
+pub fn check_contract(
+    code: &[u8],
+    config: &VMConfig,
+    protocol_version: ProtocolVersion,
+) -> ContractCheckReport {
+    let vm_kind = VMKind::for_protocol_version(protocol_version);
+    let prepare_version = config.limit_config.contract_prepare_version;
+    let host_functions: HashMap<&str, HostFunction> = host_functions(protocol_version)
+        .into_iter()
+        .filter(|function| function.module == "env")
+        .map(|function| (function.name, function))
+        .collect();
+
+    let limits = &config.limit_config;
+    let mut violations = vec![];
+    let size = code.len() as u64;
+    if size > limits.max_contract_size {
+        violations
+            .push(ContractViolation::ContractTooLarge { size, limit: limits.max_contract_size });
+    }
+    let mut functions = 0u64;
+    let mut locals = 0u64;
+    let mut types = vec![];
+    // Validation of the module stops at its first error, the other checks go on.
+    let features = crate::features::WasmFeatures::from(prepare_version);
+    let mut validator = Some(wp::Validator::new_with_features(features.into()));
+    for payload in wp::Parser::new(0).parse_all(code) {
+        let payload = match payload {
+            Ok(payload) => payload,
+            Err(err) => {
+                violations.push(ContractViolation::Malformed {
+                    offset: err.offset(),
+                    message: err.message().to_string(),
+                });
+                break;
+            }
+        };
+        if let Some(module_validator) = &mut validator {
+            match module_validator.payload(&payload) {
+                // Functions are validated independently of each other.
+                Ok(wp::ValidPayload::Func(func, body)) => {
+                    if let Err(err) = func.into_validator(Default::default()).validate(&body) {
+                        violations.push(ContractViolation::Invalid {
+                            offset: err.offset(),
+                            message: err.message().to_string(),
+                        });
+                    }
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    violations.push(ContractViolation::Invalid {
+                        offset: err.offset(),
+                        message: err.message().to_string(),
+                    });
+                    validator = None;
+                }
+            }
+        }
+        match payload {
+            wp::Payload::TypeSection(reader) => {
+                for ty in reader.into_iter().flatten() {
+                    let wp::Type::Func(ty) = ty;
+                    types.push(ty);
+                }
+            }
+            wp::Payload::ImportSection(reader) => {
+                for import in reader.into_iter().flatten() {
+                    let (module, name) = (import.module.to_string(), import.name.to_string());
+                    let type_index = match import.ty {
+                        wp::TypeRef::Func(type_index) => {
+                            functions += 1;
+                            type_index
+                        }
+                        wp::TypeRef::Memory(_) => {
+                            violations.push(ContractViolation::MemoryImport { module, name });
+                            continue;
+                        }
+                        wp::TypeRef::Table(_) | wp::TypeRef::Global(_) | wp::TypeRef::Tag(_) => {
+                            violations.push(ContractViolation::UnsupportedImport { module, name });
+                            continue;
+                        }
+                    };
+                    if module != "env" {
+                        violations.push(ContractViolation::UnknownImportModule { module, name });
+                        continue;
+                    }
+                    let Some(host_function) = host_functions.get(name.as_str()) else {
+                        violations.push(ContractViolation::UnknownHostFunction { name });
+                        continue;
+                    };
+                    // A missing type is reported by the validator.
+                    let Some(ty) = types.get(type_index as usize) else { continue };
+                    let params: Vec<_> =
+                        host_function.params.iter().map(|ty| wasm_type(ty)).collect();
+                    let results: Vec<_> =
+                        host_function.results.iter().map(|ty| wasm_type(ty)).collect();
+                    if ty.params() != params || ty.results() != results {
+                        violations.push(ContractViolation::HostFunctionSignatureMismatch {
+                            name,
+                            expected: format_signature(&params, &results),
+                            found: format_signature(ty.params(), ty.results()),
+                        });
+                    }
+                }
+            }
+            wp::Payload::CodeSectionStart { count, .. } => {
+                functions = functions.saturating_add(u64::from(count));
+            }
+            wp::Payload::CodeSectionEntry(body) => {
+                if let Ok(reader) = body.get_locals_reader() {
+                    for (count, _ty) in reader.into_iter().flatten() {
+                        locals = locals.saturating_add(u64::from(count));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(limit) = limits.max_functions_number_per_contract {
+        if functions > limit {
+            violations.push(ContractViolation::TooManyFunctions { count: functions, limit });
+        }
+    }
+    if let Some(limit) = limits.max_locals_per_contract {
+        if locals > limit {
+            violations.push(ContractViolation::TooManyLocals { count: locals, limit });
+        }
+    }
+
+    let compilation = vm_kind.runtime(config.clone()).map(|runtime| {
+        let code = ContractCode::new(code.to_vec(), None);
+        runtime
+            .precompile(&code, &MockCompiledContractCache::default())
+            .expect("the in-memory cache doesn't fail")
+            .map(|_| ())
+    });
+    ContractCheckReport { vm_kind, prepare_version, functions, locals, violations, compilation }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_contract, ContractViolation};
+    use crate::logic::VMConfig;
+    use near_primitives_core::version::PROTOCOL_VERSION;
+
+    #[test]
+    fn test_valid_contract() {
+        let config = VMConfig::test();
+        let report = check_contract(near_test_contracts::rs_contract(), &config, PROTOCOL_VERSION);
+        assert_eq!(report.violations, vec![]);
+        assert!(report.is_ok());
+    }
+
+    #[test]
+    fn test_reports_every_violation() {
+        let mut config = VMConfig::test();
+        config.limit_config.max_functions_number_per_contract = Some(2);
+        config.limit_config.max_contract_size = 64;
+        let wasm = wat::parse_str(
+            r#"(module
+                (import "env" "memory" (memory 1))
+                (import "env" "no_such_function" (func))
+                (import "env" "read_register" (func (param i32 i64)))
+                (import "other" "log" (func))
+                (func (export "main")))"#,
+        )
+        .unwrap();
+        let report = check_contract(&wasm, &config, PROTOCOL_VERSION);
+        assert_eq!(
+            report.violations,
+            vec![
+                ContractViolation::ContractTooLarge { size: wasm.len() as u64, limit: 64 },
+                ContractViolation::MemoryImport { module: "env".into(), name: "memory".into() },
+                ContractViolation::UnknownHostFunction { name: "no_such_function".into() },
+                ContractViolation::HostFunctionSignatureMismatch {
+                    name: "read_register".into(),
+                    expected: "(i64, i64) -> ()".into(),
+                    found: "(i32, i64) -> ()".into(),
+                },
+                ContractViolation::UnknownImportModule {
+                    module: "other".into(),
+                    name: "log".into()
+                },
+                ContractViolation::TooManyFunctions { count: 4, limit: 2 },
+            ]
+        );
+        assert!(!report.is_ok());
+    }
+
+    #[test]
+    fn test_disallowed_features() {
+        let config = VMConfig::test();
+        // Bulk memory operations are disabled, every function using them is reported.
+        let wasm = wat::parse_str(
+            r#"(module
+                (memory 1)
+                (func (memory.fill (i32.const 0) (i32.const 0) (i32.const 0)))
+                (func (memory.copy (i32.const 0) (i32.const 0) (i32.const 0))))"#,
+        )
+        .unwrap();
+        let report = check_contract(&wasm, &config, PROTOCOL_VERSION);
+        assert_eq!(report.violations.len(), 2, "{:?}", report.violations);
+        assert!(report
+            .violations
+            .iter()
+            .all(|violation| matches!(violation, ContractViolation::Invalid { .. })));
+        assert!(matches!(report.compilation, Some(Err(_))));
+    }
+}
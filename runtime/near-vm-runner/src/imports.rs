@@ -260,6 +260,35 @@ imports! {
     ##["sandbox"] sandbox_debug_log<[len: u64, ptr: u64] -> []>,
 }
 
+/// Host function available to contracts, with the Rust types of its parameters and results.
+pub(crate) struct HostFunction {
+    pub(crate) module: &'static str,
+    pub(crate) name: &'static str,
+    pub(crate) params: &'static [&'static str],
+    pub(crate) results: &'static [&'static str],
+}
+
+/// Returns the host functions available to contracts at the given protocol version.
+pub(crate) fn host_functions(
+    protocol_version: near_primitives_core::types::ProtocolVersion,
+) -> Vec<HostFunction> {
+    let mut functions = vec![];
+    macro_rules! add_import {
+        (
+          $mod:ident / $name:ident : $func:ident < [ $( $arg_name:ident : $arg_type:ident ),* ] -> [ $( $returns:ident ),* ] >
+        ) => {
+            functions.push(HostFunction {
+                module: stringify!($mod),
+                name: stringify!($name),
+                params: &[$( stringify!($arg_type) ),*],
+                results: &[$( stringify!($returns) ),*],
+            });
+        };
+    }
+    for_each_available_import!(protocol_version, add_import);
+    functions
+}
+
 #[cfg(all(feature = "wasmer0_vm", target_arch = "x86_64"))]
 pub(crate) mod wasmer {
     use super::str_eq;
@@ -1,6 +1,7 @@
 #![doc = include_str!("../README.md")]
 
 mod cache;
+mod check;
 mod errors;
 mod features;
 mod imports;
@@ -25,7 +26,12 @@ mod wasmtime_runner;
 
 pub use crate::logic::with_ext_cost_counter;
 
-pub use cache::{get_contract_cache_key, precompile_contract, MockCompiledContractCache};
+pub use cache::{
+    get_contract_cache_key, precompile_contract, FilesystemCompiledContractCache,
+    MockCompiledContractCache,
+};
+pub use check::{check_contract, ContractCheckReport, ContractViolation};
+pub use errors::ContractPrecompilatonResult;
 pub use runner::{run, VM};
 
 /// This is public for internal experimentation use only, and should otherwise be considered an
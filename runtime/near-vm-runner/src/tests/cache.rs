@@ -9,7 +9,7 @@ use crate::logic::VMConfig;
 use crate::logic::{CompiledContract, CompiledContractCache};
 use crate::runner::VMResult;
 use crate::wasmer2_runner::Wasmer2VM;
-use crate::{prepare, MockCompiledContractCache};
+use crate::{prepare, FilesystemCompiledContractCache, MockCompiledContractCache};
 use assert_matches::assert_matches;
 use near_primitives_core::contract::ContractCode;
 use near_primitives_core::hash::CryptoHash;
@@ -74,6 +74,49 @@ fn test_does_not_cache_io_error() {
     })
 }
 
+#[test]
+fn test_filesystem_cache_discards_corrupt_entries() {
+    let config = VMConfig::test();
+    with_vm_variants(&config, |vm_kind: VMKind| {
+        match vm_kind {
+            VMKind::Wasmer0 | VMKind::Wasmer2 | VMKind::NearVm => {}
+            VMKind::Wasmtime => return,
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let cache = FilesystemCompiledContractCache::new(dir.path()).unwrap();
+        let code = near_test_contracts::trivial_contract();
+        let prepaid_gas = 10u64.pow(12);
+        make_cached_contract_call_vm(&config, &cache, code, "main", prepaid_gas, vm_kind)
+            .expect("bad failure");
+        let key = crate::get_contract_cache_key(
+            &ContractCode::new(code.to_vec(), None),
+            vm_kind,
+            &config,
+        );
+        let path = dir.path().join(key.to_string());
+        let entry = cache.get(&key).unwrap().expect("the contract should be cached");
+
+        // Another instance on the same directory sees the entry.
+        let shared = FilesystemCompiledContractCache::new(dir.path()).unwrap();
+        assert_eq!(shared.get(&key).unwrap(), Some(entry.clone()));
+
+        // A corrupted entry is discarded and compiled again.
+        let mut bytes = std::fs::read(&path).unwrap();
+        *bytes.last_mut().unwrap() ^= 1;
+        std::fs::write(&path, &bytes).unwrap();
+        assert_eq!(cache.get(&key).unwrap(), None);
+        assert!(!path.exists());
+        make_cached_contract_call_vm(&config, &cache, code, "main", prepaid_gas, vm_kind)
+            .expect("bad failure");
+        assert_eq!(cache.get(&key).unwrap(), Some(entry.clone()));
+
+        // So is an entry stored under the key of another contract.
+        let other_key = CryptoHash::hash_bytes(b"other");
+        std::fs::copy(&path, dir.path().join(other_key.to_string())).unwrap();
+        assert_eq!(cache.get(&other_key).unwrap(), None);
+    })
+}
+
 fn make_cached_contract_call_vm(
     config: &VMConfig,
     cache: &dyn CompiledContractCache,
@@ -180,11 +180,19 @@ impl Default for ActionResult {
     }
 }
 
-pub struct Runtime {}
+pub struct Runtime {
+    verify_signatures: bool,
+}
 
 impl Runtime {
     pub fn new() -> Self {
-        Self {}
+        Self { verify_signatures: true }
+    }
+
+    /// Runtime which doesn't verify the signatures of transactions.  Only meant for dry runs
+    /// of transactions which aren't signed yet, never for applying chunks.
+    pub fn without_signature_verification() -> Self {
+        Self { verify_signatures: false }
     }
 
     fn print_log(log: &[LogEntry]) {
@@ -225,7 +233,7 @@ impl Runtime {
             state_update,
             apply_state.gas_price,
             signed_transaction,
-            true,
+            self.verify_signatures,
             Some(apply_state.block_height),
             apply_state.current_protocol_version,
         ) {
@@ -2669,7 +2677,7 @@ pub mod estimator {
         stats: &mut ApplyStats,
         epoch_info_provider: &dyn EpochInfoProvider,
     ) -> Result<ExecutionOutcomeWithId, RuntimeError> {
-        Runtime {}.apply_action_receipt(
+        Runtime::new().apply_action_receipt(
             state_update,
             apply_state,
             receipt,
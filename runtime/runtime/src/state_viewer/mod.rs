@@ -155,6 +155,30 @@ impl TrieViewer {
     }
 
     pub fn call_function(
+        &self,
+        state_update: TrieUpdate,
+        view_state: ViewApplyState,
+        contract_id: &AccountId,
+        method_name: &str,
+        args: &[u8],
+        logs: &mut Vec<String>,
+        epoch_info_provider: &dyn EpochInfoProvider,
+    ) -> Result<Vec<u8>, errors::CallFunctionError> {
+        self.call_function_with_max_gas_burnt(
+            state_update,
+            view_state,
+            contract_id,
+            method_name,
+            args,
+            logs,
+            epoch_info_provider,
+            None,
+        )
+    }
+
+    /// Like `call_function`, but the call may burn up to `max_gas_burnt_view` instead of the
+    /// limit the viewer was created with, if given.
+    pub fn call_function_with_max_gas_burnt(
         &self,
         mut state_update: TrieUpdate,
         view_state: ViewApplyState,
@@ -163,7 +187,9 @@ impl TrieViewer {
         args: &[u8],
         logs: &mut Vec<String>,
         epoch_info_provider: &dyn EpochInfoProvider,
+        max_gas_burnt_view: Option<Gas>,
     ) -> Result<Vec<u8>, errors::CallFunctionError> {
+        let max_gas_burnt_view = max_gas_burnt_view.unwrap_or(self.max_gas_burnt_view);
         let now = Instant::now();
         let root = *state_update.get_root();
         let mut account = get_account(&state_update, contract_id)?.ok_or_else(|| {
@@ -216,7 +242,7 @@ impl TrieViewer {
         let function_call = FunctionCallAction {
             method_name: method_name.to_string(),
             args: args.to_vec(),
-            gas: self.max_gas_burnt_view,
+            gas: max_gas_burnt_view,
             deposit: 0,
         };
         let outcome = execute_function_call(
@@ -230,7 +256,7 @@ impl TrieViewer {
             &empty_hash,
             config,
             true,
-            Some(ViewConfig { max_gas_burnt: self.max_gas_burnt_view }),
+            Some(ViewConfig { max_gas_burnt: max_gas_burnt_view }),
         )
         .map_err(|e| errors::CallFunctionError::InternalError { error_message: e.to_string() })?;
         let elapsed = now.elapsed();
@@ -2,11 +2,12 @@ use near_crypto::key_conversion::is_valid_staking_key;
 use near_primitives::checked_feature;
 use near_primitives::delegate_action::SignedDelegateAction;
 use near_primitives::runtime::config::RuntimeConfig;
+use near_primitives::sandbox::impersonation::is_impersonated;
 use near_primitives::transaction::DeleteAccountAction;
 use near_primitives::types::{BlockHeight, StorageUsage};
 use near_primitives::version::ProtocolFeature;
 use near_primitives::{
-    account::AccessKeyPermission,
+    account::{AccessKey, AccessKeyPermission},
     config::VMLimitConfig,
     errors::{
         ActionsValidationError, InvalidAccessKeyError, InvalidTxError, ReceiptValidationError,
@@ -97,6 +98,7 @@ pub fn validate_transaction(
     let signer_id = &transaction.signer_id;
 
     if verify_signature
+        && !is_impersonated(signed_transaction)
         && !signed_transaction
             .signature
             .verify(signed_transaction.get_hash().as_ref(), &transaction.public_key)
@@ -148,6 +150,9 @@ pub fn verify_and_charge_transaction(
         )?;
     let transaction = &signed_transaction.transaction;
     let signer_id = &transaction.signer_id;
+    // Impersonated transactions of sandbox builds are checked and charged as if they were
+    // signed by a full access key of the signer, without a nonce.
+    let impersonated = is_impersonated(signed_transaction);
 
     let mut signer = match get_account(state_update, signer_id)? {
         Some(signer) => signer,
@@ -155,7 +160,12 @@ pub fn verify_and_charge_transaction(
             return Err(InvalidTxError::SignerDoesNotExist { signer_id: signer_id.clone() }.into());
         }
     };
-    let mut access_key = match get_access_key(state_update, signer_id, &transaction.public_key)? {
+    let access_key = if impersonated {
+        Some(AccessKey::full_access())
+    } else {
+        get_access_key(state_update, signer_id, &transaction.public_key)?
+    };
+    let mut access_key = match access_key {
         Some(access_key) => access_key,
         None => {
             return Err(InvalidTxError::InvalidAccessKeyError(
@@ -168,17 +178,16 @@ pub fn verify_and_charge_transaction(
         }
     };
 
-    if transaction.nonce <= access_key.nonce {
+    if !impersonated && transaction.nonce <= access_key.nonce {
         return Err(InvalidTxError::InvalidNonce {
             tx_nonce: transaction.nonce,
             ak_nonce: access_key.nonce,
         }
         .into());
     }
-    if checked_feature!("stable", AccessKeyNonceRange, current_protocol_version) {
+    if !impersonated && checked_feature!("stable", AccessKeyNonceRange, current_protocol_version) {
         if let Some(height) = block_height {
-            let upper_bound =
-                height * near_primitives::account::AccessKey::ACCESS_KEY_NONCE_RANGE_MULTIPLIER;
+            let upper_bound = height * AccessKey::ACCESS_KEY_NONCE_RANGE_MULTIPLIER;
             if transaction.nonce >= upper_bound {
                 return Err(InvalidTxError::NonceTooLarge {
                     tx_nonce: transaction.nonce,
@@ -272,7 +281,14 @@ pub fn verify_and_charge_transaction(
         }
     };
 
-    set_access_key(state_update, signer_id.clone(), transaction.public_key.clone(), &access_key);
+    if !impersonated {
+        set_access_key(
+            state_update,
+            signer_id.clone(),
+            transaction.public_key.clone(),
+            &access_key,
+        );
+    }
     set_account(state_update, signer_id.clone(), &signer);
 
     Ok(VerificationResult { gas_burnt, gas_remaining, receipt_gas_price, burnt_amount })
@@ -0,0 +1,155 @@
+use near_chain_configs::GenesisValidationMode;
+use near_primitives::config::{ActionCosts, ExtCosts};
+use near_primitives::runtime::config_store::RuntimeConfigStore;
+use near_primitives::types::{BlockHeight, Gas, ProtocolVersion};
+use near_primitives::version::PROTOCOL_VERSION;
+use std::path::{Path, PathBuf};
+
+/// Tools for the contracts deployed on chain.
+#[derive(clap::Parser)]
+pub struct ContractCommand {
+    #[clap(subcommand)]
+    subcmd: ContractSubCommand,
+}
+
+#[derive(clap::Parser)]
+#[clap(subcommand_required = true, arg_required_else_help = true)]
+enum ContractSubCommand {
+    /// Compiles every contract deployed in the state of the node into a cache of compiled
+    /// contracts which can be shared with other nodes and tools.
+    Precompile(PrecompileCmd),
+    /// Checks a contract against the rules the runtime applies when it is deployed and called
+    /// at a protocol version, reporting every violation, and estimates its costs.
+    Check(CheckCmd),
+}
+
+#[derive(clap::Parser)]
+pub struct PrecompileCmd {
+    /// Directory of the cache.  Defaults to `compiled_contract_cache_dir` of the config.
+    #[clap(long)]
+    cache_dir: Option<PathBuf>,
+    /// Height of the block whose state is compiled.  Defaults to the head of the chain.
+    #[clap(long)]
+    height: Option<BlockHeight>,
+    /// Number of contracts compiled in parallel.  Defaults to the number of CPUs.
+    #[clap(long)]
+    threads: Option<usize>,
+}
+
+impl ContractCommand {
+    pub fn run(
+        self,
+        home_dir: &Path,
+        genesis_validation: GenesisValidationMode,
+    ) -> anyhow::Result<()> {
+        match self.subcmd {
+            ContractSubCommand::Precompile(cmd) => cmd.run(home_dir, genesis_validation),
+            ContractSubCommand::Check(cmd) => cmd.run(),
+        }
+    }
+}
+
+impl PrecompileCmd {
+    fn run(self, home_dir: &Path, genesis_validation: GenesisValidationMode) -> anyhow::Result<()> {
+        let near_config = nearcore::config::load_config(home_dir, genesis_validation)?;
+        // Paths in the config are relative to the home directory, the one passed on the
+        // command line is relative to the working directory.
+        let cache_dir = match (self.cache_dir, &near_config.config.compiled_contract_cache_dir) {
+            (Some(dir), _) => dir,
+            (None, Some(dir)) => home_dir.join(dir),
+            (None, None) => anyhow::bail!(
+                "no cache directory: pass --cache-dir or set compiled_contract_cache_dir in the config"
+            ),
+        };
+        let threads = self.threads.unwrap_or_else(rayon::current_num_threads).max(1);
+        let stats = crate::precompile::precompile_state(
+            home_dir,
+            &near_config,
+            &cache_dir,
+            self.height,
+            threads,
+        )?;
+        println!(
+            "{} contracts in {}: {} compiled, {} already in the cache, {} failed to compile",
+            stats.total(),
+            cache_dir.display(),
+            stats.compiled,
+            stats.cached,
+            stats.failed
+        );
+        Ok(())
+    }
+}
+
+#[derive(clap::Parser)]
+pub struct CheckCmd {
+    /// Path to the Wasm code of the contract.
+    wasm_file: PathBuf,
+    /// Protocol version whose rules and costs are used.
+    #[clap(long, default_value_t = PROTOCOL_VERSION)]
+    protocol_version: ProtocolVersion,
+}
+
+fn format_gas(gas: Gas) -> String {
+    format!("{gas} gas ({:.3} Tgas)", gas as f64 / 1e12)
+}
+
+impl CheckCmd {
+    fn run(self) -> anyhow::Result<()> {
+        let code = std::fs::read(&self.wasm_file)?;
+        let config_store = RuntimeConfigStore::new(None);
+        let runtime_config = config_store.get_config(self.protocol_version);
+        let wasm_config = &runtime_config.wasm_config;
+        let limits = &wasm_config.limit_config;
+        let report = near_vm_runner::check_contract(&code, wasm_config, self.protocol_version);
+
+        println!("protocol version: {}", self.protocol_version);
+        println!("vm: {:?}, prepare: {:?}", report.vm_kind, report.prepare_version);
+        println!("size: {} bytes, limit {}", code.len(), limits.max_contract_size);
+        let format_limit = |limit: Option<u64>| limit.map_or("none".to_string(), |l| l.to_string());
+        println!(
+            "functions: {}, limit {}",
+            report.functions,
+            format_limit(limits.max_functions_number_per_contract)
+        );
+        println!(
+            "locals: {}, limit {}",
+            report.locals,
+            format_limit(limits.max_locals_per_contract)
+        );
+
+        let bytes = code.len() as u64;
+        let fees = &runtime_config.fees;
+        let deploy_base = fees.fee(ActionCosts::deploy_contract_base);
+        let deploy_byte = fees.fee(ActionCosts::deploy_contract_byte);
+        // Deploying to the signer's own account, the most common case.
+        let deploy_send = deploy_base.send_fee(true) + deploy_byte.send_fee(true) * bytes;
+        let deploy_exec = deploy_base.exec_fee() + deploy_byte.exec_fee() * bytes;
+        let ext_costs = &wasm_config.ext_costs;
+        let loading = ext_costs.gas_cost(ExtCosts::contract_loading_base)
+            + ext_costs.gas_cost(ExtCosts::contract_loading_bytes) * bytes;
+        println!("deploy cost: {}", format_gas(deploy_send + deploy_exec));
+        println!("loading cost of every call: {}", format_gas(loading));
+        println!(
+            "storage staked: {} yoctoNEAR",
+            runtime_config.storage_amount_per_byte() * u128::from(bytes)
+        );
+
+        for violation in &report.violations {
+            println!("violation: {violation}");
+        }
+        match &report.compilation {
+            Some(Ok(())) => println!("compilation: ok"),
+            Some(Err(err)) => println!("compilation: failed: {err}"),
+            None => {
+                println!("compilation: skipped, {:?} isn't enabled in this build", report.vm_kind)
+            }
+        }
+        anyhow::ensure!(
+            report.is_ok(),
+            "the contract can't be deployed at protocol version {}",
+            self.protocol_version
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+//! Compilation of the contracts deployed in the state of a block into a shared cache.
+use anyhow::Context;
+use near_chain::types::RuntimeAdapter;
+use near_chain::{ChainStore, ChainStoreAccess};
+use near_epoch_manager::{EpochManager, EpochManagerAdapter};
+use near_primitives::contract::ContractCode;
+use near_primitives::hash::CryptoHash;
+use near_primitives::trie_key::col;
+use near_primitives::types::BlockHeight;
+use near_store::{Mode, NodeStorage};
+use near_vm_runner::{
+    precompile_contract, ContractPrecompilatonResult, FilesystemCompiledContractCache,
+};
+use nearcore::{NearConfig, NightshadeRuntime};
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+#[derive(Default)]
+pub(crate) struct PrecompileStats {
+    pub(crate) compiled: usize,
+    pub(crate) cached: usize,
+    pub(crate) failed: usize,
+}
+
+impl PrecompileStats {
+    pub(crate) fn total(&self) -> usize {
+        self.compiled + self.cached + self.failed
+    }
+}
+
+/// Compiles every distinct contract in the state of all the shards at the given height (the
+/// head by default) for the VM and the configuration of the protocol version of its epoch.
+/// Contracts which are already in the cache and pass its integrity checks are skipped.
+pub(crate) fn precompile_state(
+    home_dir: &Path,
+    near_config: &NearConfig,
+    cache_dir: &Path,
+    height: Option<BlockHeight>,
+    threads: usize,
+) -> anyhow::Result<PrecompileStats> {
+    let storage = NodeStorage::opener(
+        home_dir,
+        near_config.config.archive,
+        &near_config.config.store,
+        near_config.config.cold_store.as_ref(),
+    )
+    .open_in_mode(Mode::ReadOnly)
+    .with_context(|| format!("failed to open the database in {}", home_dir.display()))?;
+    let store = storage.get_split_store().unwrap_or_else(|| storage.get_hot_store());
+    let epoch_manager = EpochManager::new_arc_handle(store.clone(), &near_config.genesis.config);
+    let runtime =
+        NightshadeRuntime::from_config(home_dir, store.clone(), near_config, epoch_manager.clone());
+    let chain_store = ChainStore::new(store, near_config.genesis.config.genesis_height, false);
+
+    let block_hash = match height {
+        Some(height) => chain_store.get_block_hash_by_height(height)?,
+        None => chain_store.head()?.last_block_hash,
+    };
+    let header = chain_store.get_block_header(&block_hash)?;
+    let epoch_id = header.epoch_id();
+    let protocol_version = epoch_manager.get_epoch_protocol_version(epoch_id)?;
+    let vm_config = runtime.get_protocol_config(epoch_id)?.runtime_config.wasm_config;
+    let cache = FilesystemCompiledContractCache::new(cache_dir)
+        .with_context(|| format!("failed to open the cache in {}", cache_dir.display()))?;
+    tracing::info!(
+        target: "contract",
+        height = header.height(),
+        %block_hash,
+        protocol_version,
+        "Compiling the contracts of the block"
+    );
+
+    let compiled = AtomicUsize::new(0);
+    let cached = AtomicUsize::new(0);
+    let failed = AtomicUsize::new(0);
+    let cache_error = Mutex::new(None);
+    let tries = runtime.get_tries();
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build()?;
+    let mut seen: HashSet<CryptoHash> = HashSet::new();
+    pool.in_place_scope(|scope| -> anyhow::Result<()> {
+        // Bounds the number of contracts held in memory while waiting to be compiled.
+        let (slot_sender, slot_receiver) = std::sync::mpsc::channel();
+        for _ in 0..threads * 2 {
+            slot_sender.send(()).expect("both sender and receiver are owned here");
+        }
+        let shard_layout = epoch_manager.get_shard_layout(epoch_id)?;
+        for shard_uid in shard_layout.get_shard_uids() {
+            let chunk_extra = chain_store.get_chunk_extra(&block_hash, &shard_uid)?;
+            let trie = tries.get_view_trie_for_shard(shard_uid, *chunk_extra.state_root());
+            let mut iter = trie.iter()?;
+            iter.seek_prefix([col::CONTRACT_CODE])?;
+            for item in iter {
+                let (key, code) = item?;
+                if key.first() != Some(&col::CONTRACT_CODE) {
+                    break;
+                }
+                let code = ContractCode::new(code, None);
+                if !seen.insert(*code.hash()) {
+                    continue;
+                }
+                if cache_error.lock().unwrap().is_some() {
+                    return Ok(());
+                }
+                slot_receiver.recv().expect("could not receive a slot to compile contract");
+                let slot_sender = slot_sender.clone();
+                let (cache, vm_config) = (&cache, &vm_config);
+                let (compiled, cached, failed, cache_error) =
+                    (&compiled, &cached, &failed, &cache_error);
+                scope.spawn(move |_| {
+                    match precompile_contract(&code, vm_config, protocol_version, Some(cache)) {
+                        Ok(Ok(ContractPrecompilatonResult::ContractCompiled)) => {
+                            compiled.fetch_add(1, Ordering::Relaxed);
+                        }
+                        Ok(Ok(_)) => {
+                            cached.fetch_add(1, Ordering::Relaxed);
+                        }
+                        Ok(Err(err)) => {
+                            tracing::debug!(
+                                target: "contract",
+                                code_hash = %code.hash(),
+                                %err,
+                                "Contract doesn't compile"
+                            );
+                            failed.fetch_add(1, Ordering::Relaxed);
+                        }
+                        Err(err) => {
+                            cache_error.lock().unwrap().get_or_insert(err);
+                        }
+                    }
+                    let _ = slot_sender.send(());
+                });
+            }
+        }
+        Ok(())
+    })?;
+    if let Some(err) = cache_error.into_inner().unwrap() {
+        anyhow::bail!("failed to write to the cache in {}: {:?}", cache_dir.display(), err);
+    }
+    Ok(PrecompileStats {
+        compiled: compiled.into_inner(),
+        cached: cached.into_inner(),
+        failed: failed.into_inner(),
+    })
+}
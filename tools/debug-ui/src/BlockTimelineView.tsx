@@ -0,0 +1,112 @@
+import './BlockTimelineView.scss';
+import { useState } from 'react';
+import { useQuery } from 'react-query';
+import { BlockTimelineEvent, fetchBasicStatus, fetchBlockTimeline } from './api';
+
+type BlockTimelineViewProps = {
+    addr: string;
+};
+
+// Events of the same stage and shard are drawn on the same lane, e.g. all the parts received
+// for a chunk.
+function laneName(event: BlockTimelineEvent): string {
+    if (event.shard_id === null) {
+        return event.stage;
+    }
+    return `${event.stage} (shard ${event.shard_id})`;
+}
+
+export const BlockTimelineView = ({ addr }: BlockTimelineViewProps) => {
+    const [height, setHeight] = useState<number | null>(null);
+    const { data: status } = useQuery(['basicStatus', addr], () => fetchBasicStatus(addr), {
+        enabled: height === null,
+    });
+    const shownHeight = height ?? status?.sync_info.latest_block_height ?? null;
+    const {
+        data: timeline,
+        error: timelineError,
+        isLoading: timelineLoading,
+    } = useQuery(
+        ['blockTimeline', addr, shownHeight],
+        () => fetchBlockTimeline(addr, shownHeight!),
+        { enabled: shownHeight !== null }
+    );
+
+    const heightSelector = shownHeight !== null && (
+        <div className="height-selector">
+            <button onClick={() => setHeight(shownHeight - 1)}>Previous</button>
+            <span>Height {shownHeight}</span>
+            <button onClick={() => setHeight(shownHeight + 1)}>Next</button>
+        </div>
+    );
+    if (shownHeight === null || timelineLoading) {
+        return <div>Loading...</div>;
+    } else if (timelineError) {
+        return (
+            <div className="block-timeline-view">
+                {heightSelector}
+                <div className="error">{(timelineError as Error).stack}</div>
+            </div>
+        );
+    }
+    const events = timeline?.status_response.BlockTimeline.events ?? [];
+    if (events.length === 0) {
+        return (
+            <div className="block-timeline-view">
+                {heightSelector}
+                <div className="error">No events recorded for this height</div>
+            </div>
+        );
+    }
+
+    const start = Math.min(...events.map((event) => Date.parse(event.start)));
+    const end = Math.max(...events.map((event) => Date.parse(event.end ?? event.start)));
+    const totalMs = Math.max(end - start, 1);
+    const lanes = new Map<string, BlockTimelineEvent[]>();
+    for (const event of events) {
+        const name = laneName(event);
+        lanes.set(name, [...(lanes.get(name) ?? []), event]);
+    }
+
+    return (
+        <div className="block-timeline-view">
+            {heightSelector}
+            <div className="total">Total: {totalMs}ms</div>
+            <table className="block-timeline-table">
+                <tbody>
+                    {[...lanes.entries()].map(([name, laneEvents]) => (
+                        <tr key={name}>
+                            <td className="lane-name">{name}</td>
+                            <td className="lane">
+                                {laneEvents.map((event, i) => {
+                                    const eventStart = Date.parse(event.start) - start;
+                                    const eventEnd = Date.parse(event.end ?? event.start) - start;
+                                    const duration = eventEnd - eventStart;
+                                    const title =
+                                        event.end === null
+                                            ? `+${eventStart}ms`
+                                            : `+${eventStart}ms, took ${duration}ms`;
+                                    return (
+                                        <div
+                                            key={i}
+                                            className={event.end === null ? 'mark' : 'span'}
+                                            title={
+                                                event.part_ord === null
+                                                    ? title
+                                                    : `part ${event.part_ord}: ${title}`
+                                            }
+                                            style={{
+                                                left: `${(eventStart / totalMs) * 100}%`,
+                                                width: `${(duration / totalMs) * 100}%`,
+                                            }}
+                                        />
+                                    );
+                                })}
+                            </td>
+                        </tr>
+                    ))}
+                </tbody>
+            </table>
+        </div>
+    );
+};
@@ -1,6 +1,7 @@
 import './ChainAndChunkInfoView.scss';
 import { NavLink, Navigate, Route, Routes } from 'react-router-dom';
 import { BlocksView } from './BlocksView';
+import { BlockTimelineView } from './BlockTimelineView';
 import { ChainInfoSummaryView } from './ChainInfoSummaryView';
 import { FloatingChunksView } from './FloatingChunksView';
 
@@ -21,12 +22,16 @@ export const ChainAndChunkInfoView = ({ addr }: ChainAndChunkInfoViewProps) => {
                 <NavLink to="blocks" className={navLinkClassName}>
                     Blocks
                 </NavLink>
+                <NavLink to="block_timeline" className={navLinkClassName}>
+                    Block Timeline
+                </NavLink>
             </div>
             <Routes>
                 <Route path="" element={<Navigate to="chain_info_summary" />} />
                 <Route path="chain_info_summary" element={<ChainInfoSummaryView addr={addr} />} />
                 <Route path="floating_chunks" element={<FloatingChunksView addr={addr} />} />
                 <Route path="blocks" element={<BlocksView addr={addr} />} />
+                <Route path="block_timeline" element={<BlockTimelineView addr={addr} />} />
             </Routes>
         </div>
     );
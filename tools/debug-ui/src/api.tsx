@@ -351,6 +351,35 @@ export interface ChainProcessingStatusResponse {
     };
 }
 
+export type BlockTimelineStage =
+    | 'HeaderReceived'
+    | 'ChunkRequested'
+    | 'ChunkPartReceived'
+    | 'ChunkReconstructed'
+    | 'Preprocessing'
+    | 'ApplyChunk'
+    | 'Postprocessing'
+    | 'FirstApproval'
+    | 'LastApproval';
+
+export interface BlockTimelineEvent {
+    stage: BlockTimelineStage;
+    block_hash: string | null;
+    shard_id: number | null;
+    part_ord: number | null;
+    start: string;
+    end: string | null;
+}
+
+export interface BlockTimelineResponse {
+    status_response: {
+        BlockTimeline: {
+            height: number;
+            events: BlockTimelineEvent[];
+        };
+    };
+}
+
 export async function fetchBasicStatus(addr: string): Promise<StatusResponse> {
     const response = await fetch(`http://${addr}/status`);
     return await response.json();
@@ -403,3 +432,11 @@ export async function fetchChainProcessingStatus(
     const response = await fetch(`http://${addr}/debug/api/chain_processing_status`);
     return await response.json();
 }
+
+export async function fetchBlockTimeline(
+    addr: string,
+    height: number
+): Promise<BlockTimelineResponse> {
+    const response = await fetch(`http://${addr}/debug/api/block_timeline/${height}`);
+    return await response.json();
+}
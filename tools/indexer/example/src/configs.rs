@@ -19,6 +19,24 @@ pub(crate) enum SubCommand {
     Run,
     /// Initialize necessary configs
     Init(InitConfigArgs),
+    /// Replay blocks from the database of a stopped node without starting it
+    Replay(ReplayArgs),
+}
+
+#[derive(clap::Parser, Debug)]
+pub(crate) struct ReplayArgs {
+    /// First block height to replay
+    #[clap(long)]
+    pub start_height: u64,
+    /// Last block height to replay (inclusive)
+    #[clap(long)]
+    pub end_height: u64,
+    /// Number of blocks read from the database at the same time
+    #[clap(long, default_value = "4")]
+    pub parallelism: usize,
+    /// Skip the blocks whose data is missing from the database instead of stopping
+    #[clap(long)]
+    pub skip_incomplete_blocks: bool,
 }
 
 #[derive(clap::Parser, Debug)]
@@ -276,16 +276,38 @@ fn main() -> Result<()> {
                 sync_mode: near_indexer::SyncModeEnum::FromInterruption,
                 await_for_node_synced: near_indexer::AwaitForNodeSyncedEnum::WaitForFullSync,
                 validate_genesis: true,
+                filter: near_indexer::IndexerFilter::default(),
+                parse_events: false,
+                balance_changes: false,
+                buffer_size: 100,
             };
             let system = actix::System::new();
             system.block_on(async move {
                 let indexer = near_indexer::Indexer::new(indexer_config).expect("Indexer::new()");
-                let stream = indexer.streamer();
+                let stream = indexer.streamer().expect("Indexer::streamer()");
                 actix::spawn(listen_blocks(stream));
             });
             system.run()?;
         }
         SubCommand::Init(config) => near_indexer::indexer_init_configs(&home_dir, config.into())?,
+        SubCommand::Replay(args) => {
+            let replay_config = near_indexer::ReplayConfig {
+                home_dir,
+                start_height: args.start_height,
+                end_height: args.end_height,
+                parallelism: args.parallelism,
+                filter: near_indexer::IndexerFilter::default(),
+                parse_events: false,
+                balance_changes: false,
+                buffer_size: 100,
+                skip_incomplete_blocks: args.skip_incomplete_blocks,
+            };
+            actix::System::new().block_on(async move {
+                let stream = near_indexer::replay(replay_config)?;
+                listen_blocks(stream).await;
+                Ok::<_, anyhow::Error>(())
+            })?;
+        }
     }
     Ok(())
 }
@@ -0,0 +1,531 @@
+//! Light client for NEAR that does not depend on a node's storage.
+//!
+//! The client starts from a trusted checkpoint (a block hash together with the block producers
+//! of its epoch) and advances its head using `LightClientBlockView`s served by any node through
+//! the `next_light_client_block` RPC.  Each new head is only accepted if it is endorsed by more
+//! than 2/3 of the stake of the block producers known for its epoch.  Once the head is trusted,
+//! execution outcomes returned by `EXPERIMENTAL_light_client_proof` can be verified against it.
+//!
+//! See <https://nomicon.io/ChainSpec/LightClient> for the specification.
+
+use near_jsonrpc_primitives::types::light_client::RpcLightClientExecutionProofResponse;
+use near_primitives::block_header::{Approval, ApprovalInner};
+use near_primitives::hash::CryptoHash;
+use near_primitives::merkle::{combine_hash, compute_root_from_path};
+use near_primitives::types::{AccountId, Balance, BlockHeight};
+use near_primitives::views::validator_stake_view::ValidatorStakeView;
+use near_primitives::views::{LightClientBlockLiteView, LightClientBlockView};
+
+pub mod rpc;
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum Error {
+    #[error("block at height {height} is not newer than the head at height {head_height}")]
+    NotNewerThanHead { height: BlockHeight, head_height: BlockHeight },
+    #[error("block epoch {epoch_id} is neither the current nor the next epoch of the head")]
+    UnexpectedEpoch { epoch_id: CryptoHash },
+    #[error("block starts a new epoch but does not carry the next block producers")]
+    MissingNextBlockProducers,
+    #[error("block producers of epoch {epoch_id} are not known to the light client")]
+    UnknownBlockProducers { epoch_id: CryptoHash },
+    #[error("block has {num_approvals} approvals but there are only {num_block_producers} block producers")]
+    TooManyApprovals { num_approvals: usize, num_block_producers: usize },
+    #[error("invalid approval signature of {account_id}")]
+    InvalidSignature { account_id: AccountId },
+    #[error(
+        "approved stake {approved_stake} is not more than 2/3 of the total stake {total_stake}"
+    )]
+    NotEnoughApprovedStake { approved_stake: Balance, total_stake: Balance },
+    #[error("hash of the block producers doesn't match next_bp_hash {expected}")]
+    InvalidBlockProducersHash { expected: CryptoHash },
+    #[error("computed outcome root {computed} doesn't match the block one {expected}")]
+    InvalidOutcomeRootProof { computed: CryptoHash, expected: CryptoHash },
+    #[error("hash of the block header {computed} doesn't match the outcome block hash {expected}")]
+    InvalidBlockHash { computed: CryptoHash, expected: CryptoHash },
+    #[error(
+        "computed block merkle root {computed} doesn't match the light client head one {expected}"
+    )]
+    InvalidBlockProof { computed: CryptoHash, expected: CryptoHash },
+}
+
+/// Trusted state of the light client.
+///
+/// It is serialized as JSON to persist the head between runs of the CLI.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct LightClientState {
+    /// The last verified block.
+    pub head: LightClientBlockLiteView,
+    /// Block producers of the epoch of `head`.
+    pub block_producers: Vec<ValidatorStakeView>,
+    /// Block producers of the epoch after the epoch of `head`, if already known.
+    pub next_block_producers: Option<Vec<ValidatorStakeView>>,
+}
+
+impl LightClientState {
+    /// Creates the state from a trusted checkpoint.  Neither the header nor the block
+    /// producers can be verified at this point so they must come from a trusted source.
+    pub fn new(head: LightClientBlockLiteView, block_producers: Vec<ValidatorStakeView>) -> Self {
+        Self { head, block_producers, next_block_producers: None }
+    }
+
+    pub fn head_hash(&self) -> CryptoHash {
+        self.head.hash()
+    }
+
+    pub fn head_height(&self) -> BlockHeight {
+        self.head.inner_lite.height
+    }
+
+    /// Sets the block producers of the next epoch after checking them against the
+    /// `next_bp_hash` of the head.
+    pub fn set_next_block_producers(
+        &mut self,
+        next_block_producers: Vec<ValidatorStakeView>,
+    ) -> Result<(), Error> {
+        let expected = self.head.inner_lite.next_bp_hash;
+        if !block_producers_match_hash(&next_block_producers, &expected) {
+            return Err(Error::InvalidBlockProducersHash { expected });
+        }
+        self.next_block_producers = Some(next_block_producers);
+        Ok(())
+    }
+
+    /// Validates the block against the current head and makes it the new head if it is valid.
+    pub fn validate_and_update_head(&mut self, block: &LightClientBlockView) -> Result<(), Error> {
+        let head_inner = &self.head.inner_lite;
+        let height = block.inner_lite.height;
+        if height <= head_inner.height {
+            return Err(Error::NotNewerThanHead { height, head_height: head_inner.height });
+        }
+
+        let epoch_id = block.inner_lite.epoch_id;
+        let is_next_epoch = if epoch_id == head_inner.epoch_id {
+            false
+        } else if epoch_id == head_inner.next_epoch_id {
+            true
+        } else {
+            return Err(Error::UnexpectedEpoch { epoch_id });
+        };
+        if is_next_epoch && block.next_bps.is_none() {
+            return Err(Error::MissingNextBlockProducers);
+        }
+
+        let block_producers = if is_next_epoch {
+            self.next_block_producers.as_ref().ok_or(Error::UnknownBlockProducers { epoch_id })?
+        } else {
+            &self.block_producers
+        };
+        verify_approvals(block, block_producers)?;
+
+        if let Some(next_bps) = &block.next_bps {
+            let expected = block.inner_lite.next_bp_hash;
+            if !block_producers_match_hash(next_bps, &expected) {
+                return Err(Error::InvalidBlockProducersHash { expected });
+            }
+        }
+
+        self.head = LightClientBlockLiteView {
+            prev_block_hash: block.prev_block_hash,
+            inner_rest_hash: block.inner_rest_hash,
+            inner_lite: block.inner_lite.clone(),
+        };
+        if is_next_epoch {
+            self.block_producers = self.next_block_producers.take().unwrap_or_default();
+        }
+        if let Some(next_bps) = &block.next_bps {
+            self.next_block_producers = Some(next_bps.clone());
+        }
+        Ok(())
+    }
+
+    /// Verifies an execution proof obtained with the head of this light client as
+    /// `light_client_head`.
+    pub fn verify_execution_proof(
+        &self,
+        proof: &RpcLightClientExecutionProofResponse,
+    ) -> Result<(), Error> {
+        verify_execution_proof(proof, &self.head.inner_lite.block_merkle_root)
+    }
+}
+
+/// Returns the hash of the block after `block`, which is what `approvals_after_next` endorse.
+fn next_block_hash(block: &LightClientBlockView) -> CryptoHash {
+    let current_block_hash = LightClientBlockLiteView {
+        prev_block_hash: block.prev_block_hash,
+        inner_rest_hash: block.inner_rest_hash,
+        inner_lite: block.inner_lite.clone(),
+    }
+    .hash();
+    combine_hash(&block.next_block_inner_hash, &current_block_hash)
+}
+
+/// Checks that `approvals_after_next` of the block carry valid signatures of more than 2/3 of
+/// the stake of `block_producers`.
+pub fn verify_approvals(
+    block: &LightClientBlockView,
+    block_producers: &[ValidatorStakeView],
+) -> Result<(), Error> {
+    if block.approvals_after_next.len() > block_producers.len() {
+        return Err(Error::TooManyApprovals {
+            num_approvals: block.approvals_after_next.len(),
+            num_block_producers: block_producers.len(),
+        });
+    }
+    let message = Approval::get_data_for_sig(
+        &ApprovalInner::Endorsement(next_block_hash(block)),
+        block.inner_lite.height + 2,
+    );
+
+    let mut total_stake: Balance = 0;
+    let mut approved_stake: Balance = 0;
+    for (maybe_signature, block_producer) in
+        block.approvals_after_next.iter().zip(block_producers.iter())
+    {
+        let ValidatorStakeView::V1(block_producer) = block_producer;
+        total_stake += block_producer.stake;
+        let Some(signature) = maybe_signature else {
+            continue;
+        };
+        if !signature.verify(&message, &block_producer.public_key) {
+            return Err(Error::InvalidSignature { account_id: block_producer.account_id.clone() });
+        }
+        approved_stake += block_producer.stake;
+    }
+    // Block producers at the end of the list which didn't send an approval are not
+    // included in `approvals_after_next`, but their stake still counts.
+    for block_producer in block_producers.iter().skip(block.approvals_after_next.len()) {
+        let ValidatorStakeView::V1(block_producer) = block_producer;
+        total_stake += block_producer.stake;
+    }
+
+    if approved_stake * 3 <= total_stake * 2 {
+        return Err(Error::NotEnoughApprovedStake { approved_stake, total_stake });
+    }
+    Ok(())
+}
+
+/// Checks that `next_bp_hash` of a block commits to the given block producers.  Blocks produced
+/// before `BlockHeaderV3` hash the legacy `ValidatorStakeV1` representation.
+pub fn block_producers_match_hash(
+    block_producers: &[ValidatorStakeView],
+    next_bp_hash: &CryptoHash,
+) -> bool {
+    let validator_stakes = block_producers.iter().map(|bp| bp.clone().into_validator_stake());
+    if &CryptoHash::hash_borsh_iter(validator_stakes.clone()) == next_bp_hash {
+        return true;
+    }
+    &CryptoHash::hash_borsh_iter(validator_stakes.map(|bp| bp.into_v1())) == next_bp_hash
+}
+
+/// Verifies that the outcome is included in the block from the proof and that the block is
+/// included in the chain whose block merkle tree has the root `block_merkle_root`.
+pub fn verify_execution_proof(
+    proof: &RpcLightClientExecutionProofResponse,
+    block_merkle_root: &CryptoHash,
+) -> Result<(), Error> {
+    let outcome_hash = CryptoHash::hash_borsh(proof.outcome_proof.to_hashes());
+    let shard_outcome_root = compute_root_from_path(&proof.outcome_proof.proof, outcome_hash);
+    let block_outcome_root = compute_root_from_path(
+        &proof.outcome_root_proof,
+        CryptoHash::hash_borsh(shard_outcome_root),
+    );
+    let expected = proof.block_header_lite.inner_lite.outcome_root;
+    if block_outcome_root != expected {
+        return Err(Error::InvalidOutcomeRootProof { computed: block_outcome_root, expected });
+    }
+
+    let block_hash = proof.block_header_lite.hash();
+    if block_hash != proof.outcome_proof.block_hash {
+        return Err(Error::InvalidBlockHash {
+            computed: block_hash,
+            expected: proof.outcome_proof.block_hash,
+        });
+    }
+
+    let computed_merkle_root = compute_root_from_path(&proof.block_proof, block_hash);
+    if &computed_merkle_root != block_merkle_root {
+        return Err(Error::InvalidBlockProof {
+            computed: computed_merkle_root,
+            expected: *block_merkle_root,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_crypto::KeyType;
+    use near_primitives::hash::hash;
+    use near_primitives::types::validator_stake::ValidatorStake;
+    use near_primitives::validator_signer::{InMemoryValidatorSigner, ValidatorSigner};
+    use near_primitives::views::{
+        BlockHeaderInnerLiteView, ExecutionOutcomeView, ExecutionOutcomeWithIdView,
+        ExecutionStatusView,
+    };
+
+    struct TestEpoch {
+        epoch_id: CryptoHash,
+        signers: Vec<InMemoryValidatorSigner>,
+        stakes: Vec<Balance>,
+    }
+
+    impl TestEpoch {
+        fn new(name: &str, stakes: Vec<Balance>) -> Self {
+            let signers = (0..stakes.len())
+                .map(|i| {
+                    let account_id: AccountId = format!("{name}-{i}.near").parse().unwrap();
+                    InMemoryValidatorSigner::from_seed(
+                        account_id.clone(),
+                        KeyType::ED25519,
+                        account_id.as_ref(),
+                    )
+                })
+                .collect();
+            Self { epoch_id: hash(name.as_bytes()), signers, stakes }
+        }
+
+        fn block_producers(&self) -> Vec<ValidatorStakeView> {
+            self.signers
+                .iter()
+                .zip(self.stakes.iter())
+                .map(|(signer, stake)| {
+                    ValidatorStake::new(signer.validator_id().clone(), signer.public_key(), *stake)
+                        .into()
+                })
+                .collect()
+        }
+
+        fn bp_hash(&self) -> CryptoHash {
+            CryptoHash::hash_borsh_iter(
+                self.block_producers().into_iter().map(|bp| bp.into_validator_stake()),
+            )
+        }
+    }
+
+    fn inner_lite(
+        height: BlockHeight,
+        epoch: &TestEpoch,
+        next_epoch: &TestEpoch,
+    ) -> BlockHeaderInnerLiteView {
+        BlockHeaderInnerLiteView {
+            height,
+            epoch_id: epoch.epoch_id,
+            next_epoch_id: next_epoch.epoch_id,
+            prev_state_root: CryptoHash::default(),
+            outcome_root: CryptoHash::default(),
+            timestamp: height,
+            timestamp_nanosec: height,
+            next_bp_hash: next_epoch.bp_hash(),
+            block_merkle_root: hash(&height.to_le_bytes()),
+        }
+    }
+
+    /// Creates a light client block signed by the given subset of the signers of `epoch`.
+    fn signed_block(
+        height: BlockHeight,
+        epoch: &TestEpoch,
+        next_epoch: &TestEpoch,
+        approvers: &[bool],
+    ) -> LightClientBlockView {
+        let mut block = LightClientBlockView {
+            prev_block_hash: hash(&(height - 1).to_le_bytes()),
+            next_block_inner_hash: hash(b"next"),
+            inner_lite: inner_lite(height, epoch, next_epoch),
+            inner_rest_hash: hash(b"rest"),
+            next_bps: Some(next_epoch.block_producers()),
+            approvals_after_next: vec![],
+        };
+        let approval_inner = ApprovalInner::Endorsement(next_block_hash(&block));
+        block.approvals_after_next = epoch
+            .signers
+            .iter()
+            .zip(approvers.iter())
+            .map(|(signer, approves)| {
+                approves.then(|| signer.sign_approval(&approval_inner, height + 2))
+            })
+            .collect();
+        block
+    }
+
+    fn state(epoch: &TestEpoch, next_epoch: &TestEpoch) -> LightClientState {
+        let head = LightClientBlockLiteView {
+            prev_block_hash: CryptoHash::default(),
+            inner_rest_hash: CryptoHash::default(),
+            inner_lite: inner_lite(1, epoch, next_epoch),
+        };
+        LightClientState::new(head, epoch.block_producers())
+    }
+
+    #[test]
+    fn test_update_head_in_same_epoch() {
+        let epoch = TestEpoch::new("a", vec![10, 10, 10]);
+        let next_epoch = TestEpoch::new("b", vec![10, 10, 10]);
+        let mut state = state(&epoch, &next_epoch);
+
+        let block = signed_block(5, &epoch, &next_epoch, &[true, true, true]);
+        state.validate_and_update_head(&block).unwrap();
+        assert_eq!(state.head_height(), 5);
+        assert_eq!(state.head.inner_lite.epoch_id, epoch.epoch_id);
+        assert_eq!(state.next_block_producers, Some(next_epoch.block_producers()));
+
+        let old_block = signed_block(4, &epoch, &next_epoch, &[true, true, true]);
+        assert_eq!(
+            state.validate_and_update_head(&old_block),
+            Err(Error::NotNewerThanHead { height: 4, head_height: 5 })
+        );
+    }
+
+    #[test]
+    fn test_not_enough_approvals() {
+        let epoch = TestEpoch::new("a", vec![10, 10, 10]);
+        let next_epoch = TestEpoch::new("b", vec![10, 10, 10]);
+        let mut state = state(&epoch, &next_epoch);
+
+        // Exactly 2/3 of the stake is not enough.
+        let block = signed_block(5, &epoch, &next_epoch, &[true, true, false]);
+        assert_eq!(
+            state.validate_and_update_head(&block),
+            Err(Error::NotEnoughApprovedStake { approved_stake: 20, total_stake: 30 })
+        );
+        // Missing trailing approvals still count towards the total stake.
+        let block = signed_block(5, &epoch, &next_epoch, &[true, true]);
+        assert_eq!(
+            state.validate_and_update_head(&block),
+            Err(Error::NotEnoughApprovedStake { approved_stake: 20, total_stake: 30 })
+        );
+        assert_eq!(state.head_height(), 1);
+    }
+
+    #[test]
+    fn test_invalid_signature() {
+        let epoch = TestEpoch::new("a", vec![10, 10, 10]);
+        let next_epoch = TestEpoch::new("b", vec![10, 10, 10]);
+        let mut state = state(&epoch, &next_epoch);
+
+        let mut block = signed_block(5, &epoch, &next_epoch, &[true, true, true]);
+        block.approvals_after_next.swap(0, 1);
+        assert_eq!(
+            state.validate_and_update_head(&block),
+            Err(Error::InvalidSignature { account_id: "a-0.near".parse().unwrap() })
+        );
+
+        // Approvals signed by the block producers of another epoch are rejected.
+        let block = signed_block(5, &next_epoch, &next_epoch, &[true, true, true]);
+        assert_eq!(
+            state.validate_and_update_head(&block),
+            Err(Error::UnknownBlockProducers { epoch_id: next_epoch.epoch_id })
+        );
+    }
+
+    #[test]
+    fn test_epoch_transition() {
+        let epoch = TestEpoch::new("a", vec![10, 10, 10]);
+        let next_epoch = TestEpoch::new("b", vec![5, 20]);
+        let next_next_epoch = TestEpoch::new("c", vec![1]);
+        let mut state = state(&epoch, &next_epoch);
+
+        let block = signed_block(15, &next_epoch, &next_next_epoch, &[false, true]);
+        assert_eq!(
+            state.validate_and_update_head(&block),
+            Err(Error::UnknownBlockProducers { epoch_id: next_epoch.epoch_id })
+        );
+
+        // Block producers which don't match next_bp_hash of the head are rejected.
+        assert_eq!(
+            state.set_next_block_producers(next_next_epoch.block_producers()),
+            Err(Error::InvalidBlockProducersHash { expected: next_epoch.bp_hash() })
+        );
+        state.set_next_block_producers(next_epoch.block_producers()).unwrap();
+
+        let mut block_without_bps = block.clone();
+        block_without_bps.next_bps = None;
+        assert_eq!(
+            state.validate_and_update_head(&block_without_bps),
+            Err(Error::MissingNextBlockProducers)
+        );
+
+        state.validate_and_update_head(&block).unwrap();
+        assert_eq!(state.head_height(), 15);
+        assert_eq!(state.block_producers, next_epoch.block_producers());
+        assert_eq!(state.next_block_producers, Some(next_next_epoch.block_producers()));
+
+        // Epochs can't be skipped.
+        let far_epoch = TestEpoch::new("d", vec![1]);
+        let block = signed_block(25, &far_epoch, &far_epoch, &[true]);
+        assert_eq!(
+            state.validate_and_update_head(&block),
+            Err(Error::UnexpectedEpoch { epoch_id: far_epoch.epoch_id })
+        );
+    }
+
+    #[test]
+    fn test_invalid_next_block_producers() {
+        let epoch = TestEpoch::new("a", vec![10, 10, 10]);
+        let next_epoch = TestEpoch::new("b", vec![10, 10, 10]);
+        let mut state = state(&epoch, &next_epoch);
+
+        let mut block = signed_block(5, &epoch, &next_epoch, &[true, true, true]);
+        block.next_bps = Some(epoch.block_producers());
+        assert_eq!(
+            state.validate_and_update_head(&block),
+            Err(Error::InvalidBlockProducersHash { expected: next_epoch.bp_hash() })
+        );
+    }
+
+    #[test]
+    fn test_legacy_block_producers_hash() {
+        let epoch = TestEpoch::new("a", vec![10, 10, 10]);
+        let legacy_hash = CryptoHash::hash_borsh_iter(
+            epoch.block_producers().into_iter().map(|bp| bp.into_validator_stake().into_v1()),
+        );
+        assert!(block_producers_match_hash(&epoch.block_producers(), &legacy_hash));
+        assert!(block_producers_match_hash(&epoch.block_producers(), &epoch.bp_hash()));
+        assert!(!block_producers_match_hash(&epoch.block_producers(), &CryptoHash::default()));
+    }
+
+    #[test]
+    fn test_verify_execution_proof() {
+        let outcome_proof = ExecutionOutcomeWithIdView {
+            proof: vec![],
+            block_hash: CryptoHash::default(),
+            id: hash(b"tx"),
+            outcome: ExecutionOutcomeView {
+                logs: vec!["log".to_string()],
+                receipt_ids: vec![hash(b"receipt")],
+                gas_burnt: 100,
+                tokens_burnt: 1000,
+                executor_id: "alice.near".parse().unwrap(),
+                status: ExecutionStatusView::SuccessValue(vec![]),
+                metadata: Default::default(),
+            },
+        };
+        let outcome_hash = CryptoHash::hash_borsh(outcome_proof.to_hashes());
+        let epoch = TestEpoch::new("a", vec![10]);
+        let mut block_header_lite = LightClientBlockLiteView {
+            prev_block_hash: CryptoHash::default(),
+            inner_rest_hash: CryptoHash::default(),
+            inner_lite: inner_lite(10, &epoch, &epoch),
+        };
+        block_header_lite.inner_lite.outcome_root = CryptoHash::hash_borsh(outcome_hash);
+        let block_hash = block_header_lite.hash();
+        let mut proof = RpcLightClientExecutionProofResponse {
+            outcome_proof: ExecutionOutcomeWithIdView { block_hash, ..outcome_proof },
+            outcome_root_proof: vec![],
+            block_header_lite,
+            block_proof: vec![],
+        };
+        // With empty merkle paths the root of the block merkle tree is the block hash itself.
+        verify_execution_proof(&proof, &block_hash).unwrap();
+        assert_eq!(
+            verify_execution_proof(&proof, &CryptoHash::default()),
+            Err(Error::InvalidBlockProof { computed: block_hash, expected: CryptoHash::default() })
+        );
+
+        proof.outcome_proof.outcome.logs.clear();
+        assert!(matches!(
+            verify_execution_proof(&proof, &block_hash),
+            Err(Error::InvalidOutcomeRootProof { .. })
+        ));
+    }
+}
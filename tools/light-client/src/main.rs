@@ -0,0 +1,145 @@
+use anyhow::Context;
+use near_light_client::{rpc, LightClientState};
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::{AccountId, TransactionOrReceiptId};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::info;
+
+#[derive(clap::Parser)]
+#[clap(about = "Light client which follows the chain through the RPC of an untrusted node")]
+struct Cli {
+    /// URL of the RPC of the node to follow.
+    #[clap(long, default_value = "http://localhost:3030")]
+    rpc_url: String,
+    /// File in which the trusted state of the light client is persisted.
+    #[clap(long, default_value = "light_client_state.json")]
+    state_file: PathBuf,
+    #[clap(subcommand)]
+    command: SubCommand,
+}
+
+#[derive(clap::Subcommand)]
+enum SubCommand {
+    /// Initializes the state file from a trusted block hash.
+    Init(InitCmd),
+    /// Follows the chain and persists every verified head.
+    Follow(FollowCmd),
+    /// Verifies that a transaction or a receipt was executed in the chain of the trusted head.
+    VerifyProof(VerifyProofCmd),
+}
+
+#[derive(clap::Parser)]
+struct InitCmd {
+    /// Hash of the block to use as the trusted checkpoint.
+    #[clap(long)]
+    trusted_block_hash: CryptoHash,
+    /// Overwrite the state file if it already exists.
+    #[clap(long)]
+    force: bool,
+}
+
+#[derive(clap::Parser)]
+struct FollowCmd {
+    /// How long to wait before asking for a new block once the node has no newer one.
+    #[clap(long, default_value = "1000")]
+    poll_interval_ms: u64,
+    /// Stop once the head reaches this height.
+    #[clap(long)]
+    stop_height: Option<u64>,
+}
+
+#[derive(clap::Parser)]
+struct VerifyProofCmd {
+    #[clap(long, requires = "sender_id", conflicts_with = "receipt_id")]
+    transaction_hash: Option<CryptoHash>,
+    #[clap(long)]
+    sender_id: Option<AccountId>,
+    #[clap(long, requires = "receiver_id")]
+    receipt_id: Option<CryptoHash>,
+    #[clap(long)]
+    receiver_id: Option<AccountId>,
+}
+
+impl VerifyProofCmd {
+    fn id(self) -> anyhow::Result<TransactionOrReceiptId> {
+        match (self.transaction_hash, self.sender_id, self.receipt_id, self.receiver_id) {
+            (Some(transaction_hash), Some(sender_id), None, _) => {
+                Ok(TransactionOrReceiptId::Transaction { transaction_hash, sender_id })
+            }
+            (None, _, Some(receipt_id), Some(receiver_id)) => {
+                Ok(TransactionOrReceiptId::Receipt { receipt_id, receiver_id })
+            }
+            _ => anyhow::bail!(
+                "either --transaction-hash with --sender-id or --receipt-id with --receiver-id is required"
+            ),
+        }
+    }
+}
+
+fn read_state(path: &Path) -> anyhow::Result<LightClientState> {
+    let data = std::fs::read(path)
+        .with_context(|| format!("failed to read {}, run `init` first", path.display()))?;
+    serde_json::from_slice(&data).with_context(|| format!("failed to parse {}", path.display()))
+}
+
+/// Writes the state to a temporary file first so that an interrupted write can't corrupt the
+/// trusted head.
+fn write_state(path: &Path, state: &LightClientState) -> anyhow::Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    std::fs::write(&tmp_path, serde_json::to_vec_pretty(state)?)
+        .with_context(|| format!("failed to write {}", tmp_path.display()))?;
+    std::fs::rename(&tmp_path, path)
+        .with_context(|| format!("failed to rename {} to {}", tmp_path.display(), path.display()))
+}
+
+async fn run(cli: Cli) -> anyhow::Result<()> {
+    let client = near_jsonrpc_client::new_client(&cli.rpc_url);
+    match cli.command {
+        SubCommand::Init(cmd) => {
+            anyhow::ensure!(
+                cmd.force || !cli.state_file.exists(),
+                "{} already exists, use --force to overwrite it",
+                cli.state_file.display()
+            );
+            let state = rpc::bootstrap(&client, cmd.trusted_block_hash).await?;
+            write_state(&cli.state_file, &state)?;
+            info!(target: "light-client", height = state.head_height(), hash = %state.head_hash(), "Initialized light client");
+        }
+        SubCommand::Follow(cmd) => {
+            let mut state = read_state(&cli.state_file)?;
+            info!(target: "light-client", height = state.head_height(), hash = %state.head_hash(), "Following the chain");
+            loop {
+                if cmd.stop_height.map_or(false, |stop_height| state.head_height() >= stop_height) {
+                    break;
+                }
+                if rpc::advance_head(&client, &mut state).await? {
+                    write_state(&cli.state_file, &state)?;
+                    info!(target: "light-client", height = state.head_height(), hash = %state.head_hash(), "New head");
+                } else {
+                    actix::clock::sleep(Duration::from_millis(cmd.poll_interval_ms)).await;
+                }
+            }
+        }
+        SubCommand::VerifyProof(cmd) => {
+            let state = read_state(&cli.state_file)?;
+            let proof = rpc::get_execution_proof(&client, &state, cmd.id()?).await?;
+            println!(
+                "Outcome {} is included in block {} at height {} which is in the chain of the trusted head {} at height {}",
+                proof.outcome_proof.id,
+                proof.outcome_proof.block_hash,
+                proof.block_header_lite.inner_lite.height,
+                state.head_hash(),
+                state.head_height(),
+            );
+        }
+    }
+    Ok(())
+}
+
+fn main() -> anyhow::Result<()> {
+    let env_filter = near_o11y::EnvFilterBuilder::from_env().verbose(None).finish()?;
+    let _subscriber = near_o11y::default_subscriber(env_filter, &Default::default()).global();
+    let cli = <Cli as clap::Parser>::parse();
+    actix::System::new().block_on(run(cli))
+}
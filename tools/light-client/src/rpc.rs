@@ -0,0 +1,102 @@
+//! Helpers which drive a [`LightClientState`] using the JSON-RPC of a node.
+//!
+//! The node is not trusted: everything it returns is verified against the state before use.
+
+use crate::LightClientState;
+use anyhow::Context;
+use near_jsonrpc_client::JsonRpcClient;
+use near_jsonrpc_primitives::types::light_client::{
+    RpcLightClientExecutionProofRequest, RpcLightClientExecutionProofResponse,
+    RpcLightClientNextBlockRequest,
+};
+use near_jsonrpc_primitives::types::validator::RpcValidatorsOrderedRequest;
+use near_primitives::block_header::BlockHeader;
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::{BlockId, TransactionOrReceiptId};
+use near_primitives::views::LightClientBlockLiteView;
+
+/// Creates a light client state from the block with the trusted hash.  The block producers of
+/// its epoch are requested from the node and, like the hash, have to be trusted.
+pub async fn bootstrap(
+    client: &JsonRpcClient,
+    trusted_block_hash: CryptoHash,
+) -> anyhow::Result<LightClientState> {
+    let block = client
+        .block_by_id(BlockId::Hash(trusted_block_hash))
+        .await
+        .map_err(|err| anyhow::anyhow!("failed to fetch block {trusted_block_hash}: {err}"))?;
+    let head = LightClientBlockLiteView::from(BlockHeader::from(block.header));
+    anyhow::ensure!(
+        head.hash() == trusted_block_hash,
+        "node returned block {} instead of {}",
+        head.hash(),
+        trusted_block_hash
+    );
+    let block_producers = client
+        .EXPERIMENTAL_validators_ordered(RpcValidatorsOrderedRequest {
+            block_id: Some(BlockId::Hash(trusted_block_hash)),
+        })
+        .await
+        .map_err(|err| anyhow::anyhow!("failed to fetch block producers: {err}"))?;
+    Ok(LightClientState::new(head, block_producers))
+}
+
+/// Requests the next light client block from the node and, if it is valid, makes it the new
+/// head.  Returns `false` if the node has no newer block.
+pub async fn advance_head(
+    client: &JsonRpcClient,
+    state: &mut LightClientState,
+) -> anyhow::Result<bool> {
+    let Some(block) = client
+        .next_light_client_block(RpcLightClientNextBlockRequest {
+            last_block_hash: state.head_hash(),
+        })
+        .await
+        .map_err(|err| anyhow::anyhow!("failed to fetch next light client block: {err}"))?
+    else {
+        return Ok(false);
+    };
+
+    // A head created from a checkpoint doesn't know the block producers of the next epoch.
+    // They are committed to by `next_bp_hash` of the head, so they can be fetched from the
+    // node and verified.
+    if block.inner_lite.epoch_id == state.head.inner_lite.next_epoch_id
+        && state.next_block_producers.is_none()
+    {
+        let block_hash = LightClientBlockLiteView {
+            prev_block_hash: block.prev_block_hash,
+            inner_rest_hash: block.inner_rest_hash,
+            inner_lite: block.inner_lite.clone(),
+        }
+        .hash();
+        let next_block_producers = client
+            .EXPERIMENTAL_validators_ordered(RpcValidatorsOrderedRequest {
+                block_id: Some(BlockId::Hash(block_hash)),
+            })
+            .await
+            .map_err(|err| anyhow::anyhow!("failed to fetch next block producers: {err}"))?;
+        state.set_next_block_producers(next_block_producers)?;
+    }
+
+    state.validate_and_update_head(&block).with_context(|| {
+        format!("invalid light client block at height {}", block.inner_lite.height)
+    })?;
+    Ok(true)
+}
+
+/// Requests the proof of the execution outcome from the node and verifies it against the head.
+pub async fn get_execution_proof(
+    client: &JsonRpcClient,
+    state: &LightClientState,
+    id: TransactionOrReceiptId,
+) -> anyhow::Result<RpcLightClientExecutionProofResponse> {
+    let proof = client
+        .EXPERIMENTAL_light_client_proof(RpcLightClientExecutionProofRequest {
+            id,
+            light_client_head: state.head_hash(),
+        })
+        .await
+        .map_err(|err| anyhow::anyhow!("failed to fetch execution proof: {err}"))?;
+    state.verify_execution_proof(&proof)?;
+    Ok(proof)
+}
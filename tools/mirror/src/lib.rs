@@ -870,10 +870,15 @@ impl<T: ChainAccess> TxMirror<T> {
             sync_mode: near_indexer::SyncModeEnum::FromInterruption,
             await_for_node_synced: near_indexer::AwaitForNodeSyncedEnum::StreamWhileSyncing,
             validate_genesis: false,
+            filter: near_indexer::IndexerFilter::default(),
+            parse_events: false,
+            balance_changes: false,
+            buffer_size: 100,
         })
         .context("failed to start target chain indexer")?;
         let (target_view_client, target_client) = target_indexer.client_actors();
-        let target_stream = target_indexer.streamer();
+        let target_stream =
+            target_indexer.streamer().context("failed to start target chain streamer")?;
 
         Ok(Self {
             source_chain_access,
@@ -5,12 +5,23 @@ use anyhow::{anyhow, Context as AnyhowContext};
 use near_async::time;
 use near_chain::{Block, Chain, ChainStoreAccess, Error};
 use near_client::sync::header::MAX_BLOCK_HEADERS;
-use near_crypto::SecretKey;
+use near_crypto::{KeyType, SecretKey};
 use near_network::raw::{DirectMessage, Listener, Message, RoutedMessage};
 use near_network::tcp;
-use near_network::types::{PartialEncodedChunkRequestMsg, PartialEncodedChunkResponseMsg};
+use near_network::types::{
+    PartialEncodedChunkRequestMsg, PartialEncodedChunkResponseMsg, StateResponseInfo,
+    StateResponseInfoV1, StateResponseInfoV2,
+};
+use near_primitives::hash::CryptoHash;
 use near_primitives::sharding::ChunkHash;
+use near_primitives::syncing::{
+    ShardStateSyncResponse, ShardStateSyncResponseHeader, ShardStateSyncResponseV1,
+    ShardStateSyncResponseV2,
+};
 use near_primitives::types::{BlockHeight, ShardId};
+use near_primitives::validator_signer::InMemoryValidatorSigner;
+use rand::rngs::StdRng;
+use scenario::{Decision, MessageKind, PeerScenario};
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::future::Future;
 use std::path::Path;
@@ -18,6 +29,7 @@ use std::pin::Pin;
 use std::task::Poll;
 use std::time::Duration;
 
+pub mod scenario;
 pub mod setup;
 
 // For now this is a simple struct with one field just to leave the door
@@ -215,11 +227,11 @@ impl IncomingRequests {
 
 struct InFlightMessage {
     message: Message,
-    sent_at: tokio::time::Instant,
+    deliver_at: tokio::time::Instant,
 }
 
-// type that simulates network latency by waiting for `response_delay`
-// before delivering queued up messages
+// type that simulates network latency by waiting for `response_delay`, plus the extra delay
+// of the message set by the scenario, before delivering queued up messages
 #[pin_project::pin_project]
 struct InFlightMessages {
     #[pin]
@@ -237,18 +249,21 @@ impl InFlightMessages {
         }
     }
 
-    fn queue_message(self: Pin<&mut Self>, message: Message) {
+    fn queue_message(self: Pin<&mut Self>, message: Message, extra_delay: Duration) {
         let me = self.project();
-        let now = tokio::time::Instant::now();
-        if me.messages.is_empty() {
-            me.next_delivery.reset(now + *me.response_delay);
-        }
+        let delay = *me.response_delay + extra_delay;
+        let deliver_at = tokio::time::Instant::now() + delay;
         tracing::debug!(
             "mock peer queueing up message {} to be delivered in {:?}",
             &message,
-            me.response_delay
+            delay
         );
-        me.messages.push_back(InFlightMessage { message, sent_at: now });
+        // Keep the messages ordered by delivery time.
+        let index = me.messages.partition_point(|m| m.deliver_at <= deliver_at);
+        me.messages.insert(index, InFlightMessage { message, deliver_at });
+        if index == 0 {
+            me.next_delivery.reset(deliver_at);
+        }
     }
 }
 
@@ -266,7 +281,7 @@ impl Future for InFlightMessages {
                     if let Some(m) = me.messages.front() {
                         // if there's another message after the one we're returning here, reset
                         // the time til the next message gets delivered accordingly.
-                        me.next_delivery.as_mut().reset(m.sent_at + *me.response_delay);
+                        me.next_delivery.as_mut().reset(m.deliver_at);
                     }
                     Poll::Ready(msg.message)
                 }
@@ -283,6 +298,9 @@ struct MockPeer {
     network_config: MockNetworkConfig,
     block_production: tokio::time::Interval,
     incoming_requests: IncomingRequests,
+    scenario: PeerScenario,
+    started_at: std::time::Instant,
+    rng: StdRng,
 }
 
 impl MockPeer {
@@ -296,13 +314,15 @@ impl MockPeer {
         num_shards: ShardId,
         network_start_height: BlockHeight,
         network_config: MockNetworkConfig,
+        scenario: PeerScenario,
+        rng: StdRng,
     ) -> anyhow::Result<Self> {
         let listener = Listener::bind(
             listen_addr,
             secret_key,
             &chain_id,
             *chain.genesis().hash(),
-            network_start_height,
+            scenario.claimed_height.unwrap_or(network_start_height),
             (0..num_shards).collect(),
             archival,
             30 * time::Duration::SECOND,
@@ -336,11 +356,97 @@ impl MockPeer {
             network_config,
             block_production: tokio::time::interval(block_production_delay),
             incoming_requests,
+            scenario,
+            started_at: std::time::Instant::now(),
+            rng,
         })
     }
 
+    fn decide(&mut self, message: MessageKind) -> Decision {
+        self.scenario.decide(message, self.started_at.elapsed(), self.current_height, &mut self.rng)
+    }
+
+    // applies the scenario of the peer to a message it sends, and queues it up unless it's dropped
+    fn send_message(&mut self, message: Message, outbound: Pin<&mut InFlightMessages>) {
+        let decision = match message {
+            Message::Direct(DirectMessage::Block(_)) => self.decide(MessageKind::Block),
+            Message::Direct(DirectMessage::BlockHeaders(_)) => {
+                self.decide(MessageKind::BlockHeaders)
+            }
+            Message::Routed(RoutedMessage::VersionedStateResponse(ref response)) => {
+                let has_part = match response {
+                    StateResponseInfo::V1(response) => response.state_response.part.is_some(),
+                    StateResponseInfo::V2(response) => response.state_response.part().is_some(),
+                };
+                if has_part {
+                    self.decide(MessageKind::StatePart)
+                } else {
+                    self.decide(MessageKind::StateHeader)
+                }
+            }
+            _ => Decision::Send { extra_delay: Duration::ZERO },
+        };
+        match (decision, message) {
+            (Decision::Send { extra_delay }, message) => {
+                outbound.queue_message(message, extra_delay)
+            }
+            (Decision::Drop, message) => {
+                tracing::debug!("mock peer dropping message {}", &message);
+            }
+            (Decision::Lie, Message::Direct(DirectMessage::BlockHeaders(mut headers))) => {
+                // Same contents, signed by someone else.
+                let signer = InMemoryValidatorSigner::from_random(
+                    "mock-node-liar".parse().unwrap(),
+                    KeyType::ED25519,
+                );
+                for header in &mut headers {
+                    header.resign(&signer);
+                }
+                outbound.queue_message(
+                    Message::Direct(DirectMessage::BlockHeaders(headers)),
+                    Duration::ZERO,
+                );
+            }
+            (Decision::Lie, message) => outbound.queue_message(message, Duration::ZERO),
+        }
+    }
+
+    // applies the scenario to every part of a chunk response separately
+    fn send_chunk_response(
+        &mut self,
+        mut response: PartialEncodedChunkResponseMsg,
+        outbound: Pin<&mut InFlightMessages>,
+    ) {
+        let had_parts = !response.parts.is_empty();
+        let mut extra_delay = Duration::ZERO;
+        let mut parts = vec![];
+        for part in std::mem::take(&mut response.parts) {
+            match self.decide(MessageKind::ChunkPart) {
+                Decision::Send { extra_delay: part_delay } => {
+                    extra_delay = extra_delay.max(part_delay);
+                    parts.push(part);
+                }
+                Decision::Drop | Decision::Lie => {
+                    tracing::debug!(
+                        "mock peer dropping part {} of chunk {:?}",
+                        part.part_ord,
+                        response.chunk_hash
+                    );
+                }
+            }
+        }
+        if had_parts && parts.is_empty() {
+            return;
+        }
+        response.parts = parts;
+        outbound.queue_message(
+            Message::Routed(RoutedMessage::PartialEncodedChunkResponse(response)),
+            extra_delay,
+        );
+    }
+
     fn handle_message(
-        &self,
+        &mut self,
         message: Message,
         outbound: Pin<&mut InFlightMessages>,
     ) -> anyhow::Result<()> {
@@ -358,38 +464,49 @@ impl MockPeer {
                                     self.current_height
                                 )
                             })?;
-                        outbound
-                            .queue_message(Message::Direct(DirectMessage::BlockHeaders(headers)));
+                        self.send_message(
+                            Message::Direct(DirectMessage::BlockHeaders(headers)),
+                            outbound,
+                        );
                     }
                     DirectMessage::BlockRequest(hash) => {
                         let block = self
                             .chain
                             .get_block(&hash)
                             .with_context(|| format!("failed getting block {}", &hash))?;
-                        outbound.queue_message(Message::Direct(DirectMessage::Block(block)));
+                        self.send_message(Message::Direct(DirectMessage::Block(block)), outbound);
                     }
                     _ => {}
                 };
             }
-            Message::Routed(r) => {
-                match r {
-                    RoutedMessage::PartialEncodedChunkRequest(request) => {
-                        let response = retrieve_partial_encoded_chunk(&self.chain, &request)
-                            .with_context(|| {
-                                format!(
-                                    "failed getting partial encoded chunk response for {:?}",
-                                    &request
-                                )
-                            })?;
-                        outbound.queue_message(Message::Routed(
-                            RoutedMessage::PartialEncodedChunkResponse(response),
-                        ));
-                    }
-                    // TODO: add state sync requests to possible request types so we can either
-                    // respond or just exit, saying we don't know how to do that
-                    _ => {}
+            Message::Routed(r) => match r {
+                RoutedMessage::PartialEncodedChunkRequest(request) => {
+                    let response = retrieve_partial_encoded_chunk(&self.chain, &request)
+                        .with_context(|| {
+                            format!(
+                                "failed getting partial encoded chunk response for {:?}",
+                                &request
+                            )
+                        })?;
+                    self.send_chunk_response(response, outbound);
                 }
-            }
+                RoutedMessage::StateRequestHeader(shard_id, sync_hash) => {
+                    let response = retrieve_state_header(&self.chain, shard_id, sync_hash);
+                    self.send_message(
+                        Message::Routed(RoutedMessage::VersionedStateResponse(response)),
+                        outbound,
+                    );
+                }
+                RoutedMessage::StateRequestPart(shard_id, sync_hash, part_id) => {
+                    let response =
+                        retrieve_state_part(&mut self.chain, shard_id, sync_hash, part_id);
+                    self.send_message(
+                        Message::Routed(RoutedMessage::VersionedStateResponse(response)),
+                        outbound,
+                    );
+                }
+                _ => {}
+            },
         };
         Ok(())
     }
@@ -399,6 +516,11 @@ impl MockPeer {
     fn produce_block(&mut self) -> anyhow::Result<Option<Block>> {
         let height = self.current_height;
         self.current_height += 1;
+        if self.scenario.fork_from_height.map_or(false, |fork_height| height >= fork_height) {
+            if let Some(block) = self.fork_block(height)? {
+                return Ok(Some(block));
+            }
+        }
         match self.chain.get_block_by_height(height) {
             Ok(b) => Ok(Some(b)),
             Err(near_chain::Error::DBNotFoundErr(_)) => Ok(None),
@@ -406,6 +528,33 @@ impl MockPeer {
         }
     }
 
+    // a block at the given height which isn't on the canonical chain, if the history has one
+    fn fork_block(&self, height: BlockHeight) -> anyhow::Result<Option<Block>> {
+        let store = self.chain.store();
+        let canonical_hash = store.get_block_hash_by_height(height).ok();
+        let hashes = match store.get_all_block_hashes_by_height(height) {
+            Ok(hashes) => hashes,
+            Err(near_chain::Error::DBNotFoundErr(_)) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let mut fork_hashes: Vec<CryptoHash> = hashes
+            .values()
+            .flatten()
+            .filter(|hash| Some(**hash) != canonical_hash)
+            .cloned()
+            .collect();
+        fork_hashes.sort();
+        for hash in fork_hashes {
+            match self.chain.get_block(&hash) {
+                Ok(block) => return Ok(Some(block)),
+                // Only the header of the block may have been recorded.
+                Err(near_chain::Error::DBNotFoundErr(_)) => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(None)
+    }
+
     // returns a message produced by this mock peer. Right now this includes a new block
     // at a rate given by block_production_delay in the config, and extra chunk part requests
     // and blocks as specified by the mock.json config
@@ -447,7 +596,7 @@ impl MockPeer {
                 }
                 msg = self.incoming_message(target_height) => {
                     let msg = msg?;
-                    messages.as_mut().queue_message(msg);
+                    self.send_message(msg, messages.as_mut());
                 }
             }
         }
@@ -488,3 +637,66 @@ fn retrieve_partial_encoded_chunk(
 
     Ok(PartialEncodedChunkResponseMsg { chunk_hash: request.chunk_hash.clone(), parts, receipts })
 }
+
+fn retrieve_state_header(
+    chain: &Chain,
+    shard_id: ShardId,
+    sync_hash: CryptoHash,
+) -> StateResponseInfo {
+    let header = chain
+        .get_state_response_header(shard_id, sync_hash)
+        .map_err(|err| {
+            tracing::warn!(?err, shard_id, ?sync_hash, "mock peer can't get the state header");
+        })
+        .ok();
+    match header {
+        Some(ShardStateSyncResponseHeader::V2(header)) => {
+            StateResponseInfo::V2(StateResponseInfoV2 {
+                shard_id,
+                sync_hash,
+                state_response: ShardStateSyncResponse::V2(ShardStateSyncResponseV2 {
+                    header: Some(header),
+                    part: None,
+                }),
+            })
+        }
+        Some(ShardStateSyncResponseHeader::V1(header)) => {
+            StateResponseInfo::V1(StateResponseInfoV1 {
+                shard_id,
+                sync_hash,
+                state_response: ShardStateSyncResponseV1 { header: Some(header), part: None },
+            })
+        }
+        None => StateResponseInfo::V1(StateResponseInfoV1 {
+            shard_id,
+            sync_hash,
+            state_response: ShardStateSyncResponseV1 { header: None, part: None },
+        }),
+    }
+}
+
+fn retrieve_state_part(
+    chain: &mut Chain,
+    shard_id: ShardId,
+    sync_hash: CryptoHash,
+    part_id: u64,
+) -> StateResponseInfo {
+    let part = chain
+        .get_state_response_part(shard_id, part_id, sync_hash)
+        .map_err(|err| {
+            tracing::warn!(
+                ?err,
+                shard_id,
+                ?sync_hash,
+                part_id,
+                "mock peer can't get the state part"
+            );
+        })
+        .ok()
+        .map(|part| (part_id, part));
+    StateResponseInfo::V1(StateResponseInfoV1 {
+        shard_id,
+        sync_hash,
+        state_response: ShardStateSyncResponseV1 { header: None, part },
+    })
+}
@@ -5,6 +5,7 @@
 
 use actix::System;
 use anyhow::Context;
+use mock_node::scenario::Scenario;
 use mock_node::setup::{setup_mock_node, MockNode};
 use mock_node::MockNetworkConfig;
 use near_actix_test_utils::run_actix;
@@ -80,9 +81,13 @@ struct Cli {
     /// If true, use in memory storage instead of rocksdb for the client
     #[clap(short = 'i', long)]
     in_memory_storage: bool,
-    /// port the mock node should listen on
+    /// port the mock node should listen on. With several mock peers in the scenario, they
+    /// listen on consecutive ports starting from this one.
     #[clap(long)]
     mock_port: Option<u16>,
+    /// Scenario file scripting the behavior of the mock peers over time, see the README.
+    #[clap(long)]
+    scenario: Option<PathBuf>,
 }
 
 async fn target_height_reached(client: &JsonRpcClient, target_height: BlockHeight) -> bool {
@@ -139,10 +144,18 @@ fn main() -> anyhow::Result<()> {
 
     let client_height = args.start_height.unwrap_or(args.client_height);
     let network_height = args.start_height.or(args.network_height);
-    let addr = tcp::ListenerAddr::new(SocketAddr::new(
-        "127.0.0.1".parse().unwrap(),
-        args.mock_port.unwrap_or(24566),
-    ));
+    let scenario = match &args.scenario {
+        Some(path) => Scenario::from_file(path)
+            .with_context(|| format!("Error loading scenario from {}", path.display()))?,
+        None => Scenario::default(),
+    };
+    let mock_port = args.mock_port.unwrap_or(24566);
+    let addrs = (0..scenario.peers().len())
+        .map(|i| {
+            let port = mock_port.checked_add(i as u16).expect("mock peer port out of range");
+            tcp::ListenerAddr::new(SocketAddr::new("127.0.0.1".parse().unwrap(), port))
+        })
+        .collect();
 
     run_actix(async move {
         let MockNode { target_height, mut mock_peer, rpc_client } = setup_mock_node(
@@ -154,7 +167,8 @@ fn main() -> anyhow::Result<()> {
             network_height,
             args.target_height,
             args.in_memory_storage,
-            addr,
+            &scenario,
+            addrs,
         );
 
         // TODO: would be nice to be able to somehow quit right after the target block
@@ -0,0 +1,265 @@
+//! Scenario files script the behavior of the mock network over time, so that the client can be
+//! tested against adverse network conditions on top of the recorded chain history.
+//!
+//! Every entry of `peers` starts a separate mock peer. The rules of a peer are checked against
+//! every message it sends, in order, and the first active rule for the type of the message
+//! applies. Messages without a matching rule are sent after the `response_delay` of the network.
+//!
+//! ```json
+//! {
+//!     "seed": 42,
+//!     "peers": [
+//!         {
+//!             "rules": [
+//!                 { "message": "chunk_part", "action": "drop", "probability": 0.3 },
+//!                 { "message": "block", "from_ms": 10000, "until_ms": 20000,
+//!                   "action": "delay", "delay_ms": 2000 }
+//!             ]
+//!         },
+//!         { "fork_from_height": 100 },
+//!         { "rules": [{ "message": "state_part", "action": "drop" }] },
+//!         {
+//!             "claimed_height": 1000000000,
+//!             "rules": [{ "message": "block_headers", "action": "lie" }]
+//!         }
+//!     ]
+//! }
+//! ```
+
+use anyhow::Context;
+use near_primitives::types::BlockHeight;
+use rand::Rng;
+use std::path::Path;
+use std::time::Duration;
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    /// Seed of the random decisions of the peers, e.g. which messages are dropped. Random if not
+    /// set.
+    pub seed: Option<u64>,
+    pub peers: Vec<PeerScenario>,
+}
+
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PeerScenario {
+    #[serde(default)]
+    pub rules: Vec<MessageRule>,
+    /// From this height on, the peer produces the blocks of other forks than the canonical
+    /// chain, at the heights where the recorded history has them.
+    pub fork_from_height: Option<BlockHeight>,
+    /// Height the peer claims in the handshake instead of the height the network starts at,
+    /// e.g. to make the client header sync from a lying peer.
+    pub claimed_height: Option<BlockHeight>,
+}
+
+/// Types of the messages sent by the mock peers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageKind {
+    Block,
+    BlockHeaders,
+    /// Part of a chunk in a `PartialEncodedChunkResponse`. The rules apply to every part of the
+    /// response separately.
+    ChunkPart,
+    StateHeader,
+    StatePart,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MessageRule {
+    pub message: MessageKind,
+    /// The rule is active from this long after the start of the mock network.
+    #[serde(default)]
+    pub from_ms: u64,
+    /// The rule is active until this long after the start of the mock network.
+    pub until_ms: Option<u64>,
+    /// The rule is active once the mock network reaches this height.
+    pub from_height: Option<BlockHeight>,
+    /// The rule is active until the mock network reaches this height.
+    pub until_height: Option<BlockHeight>,
+    pub action: MessageAction,
+    /// Probability of dropping the message, 1 if not set. Only valid for `drop`.
+    pub probability: Option<f64>,
+    /// How much later than usual the message is sent. Required for `delay`, and only valid for
+    /// it.
+    pub delay_ms: Option<u64>,
+}
+
+// The parameters of the actions are fields of the rule rather than of the variants, as serde
+// can't reject unknown fields of a flattened enum, and a typo in a scenario must not go unnoticed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageAction {
+    /// Sends the message as usual, e.g. to exempt a time window from a later rule.
+    Send,
+    /// Drops the message with the given probability.
+    Drop,
+    /// Sends the message `delay_ms` later than usual.
+    Delay,
+    /// Sends block headers with invalid signatures. Only valid for `block_headers`.
+    Lie,
+}
+
+/// What a peer does with a message, see `PeerScenario::decide`.
+#[derive(Debug, PartialEq)]
+pub enum Decision {
+    Send { extra_delay: Duration },
+    Drop,
+    Lie,
+}
+
+impl Scenario {
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let s = std::fs::read_to_string(path)?;
+        let scenario: Self = serde_json::from_str(&s)?;
+        scenario.validate().with_context(|| format!("invalid scenario {}", path.display()))?;
+        Ok(scenario)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        for (i, peer) in self.peers.iter().enumerate() {
+            for rule in &peer.rules {
+                if rule.action == MessageAction::Lie && rule.message != MessageKind::BlockHeaders {
+                    anyhow::bail!("peer {}: only block_headers can lie", i)
+                }
+                match rule.probability {
+                    Some(_) if rule.action != MessageAction::Drop => {
+                        anyhow::bail!("peer {}: probability is only valid for drop", i)
+                    }
+                    Some(probability) if !(0.0..=1.0).contains(&probability) => {
+                        anyhow::bail!("peer {}: drop probability must be within [0, 1]", i)
+                    }
+                    _ => {}
+                }
+                if rule.delay_ms.is_some() != (rule.action == MessageAction::Delay) {
+                    anyhow::bail!(
+                        "peer {}: delay_ms is required for delay, and only valid for it",
+                        i
+                    )
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Behaviors of the mock peers. Without peers in the scenario, the network has a single peer
+    /// which sends every message as usual.
+    pub fn peers(&self) -> Vec<PeerScenario> {
+        if self.peers.is_empty() {
+            vec![PeerScenario::default()]
+        } else {
+            self.peers.clone()
+        }
+    }
+}
+
+impl MessageRule {
+    fn is_active(&self, elapsed: Duration, height: BlockHeight) -> bool {
+        let elapsed_ms = elapsed.as_millis() as u64;
+        elapsed_ms >= self.from_ms
+            && self.until_ms.map_or(true, |until_ms| elapsed_ms < until_ms)
+            && self.from_height.map_or(true, |from_height| height >= from_height)
+            && self.until_height.map_or(true, |until_height| height < until_height)
+    }
+}
+
+impl PeerScenario {
+    /// Decides what to do with a message of the given type, `elapsed` since the start of the
+    /// mock network at the given network height.
+    pub fn decide(
+        &self,
+        message: MessageKind,
+        elapsed: Duration,
+        height: BlockHeight,
+        rng: &mut impl Rng,
+    ) -> Decision {
+        let rule = self
+            .rules
+            .iter()
+            .find(|rule| rule.message == message && rule.is_active(elapsed, height));
+        let Some(rule) = rule else { return Decision::Send { extra_delay: Duration::ZERO } };
+        match rule.action {
+            MessageAction::Send => Decision::Send { extra_delay: Duration::ZERO },
+            MessageAction::Drop => {
+                if rng.gen_bool(rule.probability.unwrap_or(1.0)) {
+                    Decision::Drop
+                } else {
+                    Decision::Send { extra_delay: Duration::ZERO }
+                }
+            }
+            MessageAction::Delay => {
+                Decision::Send { extra_delay: Duration::from_millis(rule.delay_ms.unwrap_or(0)) }
+            }
+            MessageAction::Lie => Decision::Lie,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_scenario_rules() {
+        let scenario: Scenario = serde_json::from_str(
+            r#"{
+                "peers": [{
+                    "rules": [
+                        { "message": "block", "until_ms": 1000, "action": "send" },
+                        { "message": "block", "action": "delay", "delay_ms": 500 },
+                        { "message": "chunk_part", "from_height": 10, "until_height": 20,
+                          "action": "drop" },
+                        { "message": "block_headers", "action": "lie" }
+                    ]
+                }]
+            }"#,
+        )
+        .unwrap();
+        scenario.validate().unwrap();
+        let peer = &scenario.peers()[0];
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let send = Decision::Send { extra_delay: Duration::ZERO };
+
+        let at_ms = |ms| Duration::from_millis(ms);
+        assert_eq!(peer.decide(MessageKind::Block, at_ms(500), 0, &mut rng), send);
+        assert_eq!(
+            peer.decide(MessageKind::Block, at_ms(1000), 0, &mut rng),
+            Decision::Send { extra_delay: at_ms(500) }
+        );
+        assert_eq!(peer.decide(MessageKind::ChunkPart, at_ms(0), 9, &mut rng), send);
+        assert_eq!(peer.decide(MessageKind::ChunkPart, at_ms(0), 10, &mut rng), Decision::Drop);
+        assert_eq!(peer.decide(MessageKind::ChunkPart, at_ms(0), 20, &mut rng), send);
+        assert_eq!(peer.decide(MessageKind::BlockHeaders, at_ms(0), 0, &mut rng), Decision::Lie);
+        assert_eq!(peer.decide(MessageKind::StatePart, at_ms(0), 0, &mut rng), send);
+
+        let invalid: Scenario = serde_json::from_str(
+            r#"{ "peers": [{ "rules": [{ "message": "block", "action": "lie" }] }] }"#,
+        )
+        .unwrap();
+        assert!(invalid.validate().is_err());
+        assert_eq!(Scenario::default().peers().len(), 1);
+
+        // Misspelled or misplaced parameters are rejected rather than ignored.
+        for rule in [
+            r#"{ "message": "block", "action": "delay", "delay_msec": 500 }"#,
+            r#"{ "message": "block", "from_height_": 10, "action": "send" }"#,
+        ] {
+            let json = format!(r#"{{ "peers": [{{ "rules": [{}] }}] }}"#, rule);
+            assert!(serde_json::from_str::<Scenario>(&json).is_err(), "{}", rule);
+        }
+        for rule in [
+            r#"{ "message": "block", "action": "delay" }"#,
+            r#"{ "message": "block", "action": "send", "delay_ms": 500 }"#,
+            r#"{ "message": "block", "action": "delay", "delay_ms": 500, "probability": 0.5 }"#,
+            r#"{ "message": "block", "action": "drop", "probability": 2 }"#,
+        ] {
+            let json = format!(r#"{{ "peers": [{{ "rules": [{}] }}] }}"#, rule);
+            let scenario: Scenario = serde_json::from_str(&json).unwrap();
+            assert!(scenario.validate().is_err(), "{}", rule);
+        }
+    }
+}
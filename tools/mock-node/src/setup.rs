@@ -1,5 +1,6 @@
 //! Provides functions for setting up a mock network from configs and home dirs.
 
+use crate::scenario::Scenario;
 use crate::{MockNetworkConfig, MockPeer};
 use anyhow::Context;
 use near_chain::types::RuntimeAdapter;
@@ -17,6 +18,7 @@ use near_primitives::syncing::get_num_state_parts;
 use near_primitives::types::{BlockHeight, ShardId};
 use near_store::test_utils::create_test_store;
 use nearcore::{NearConfig, NightshadeRuntime};
+use rand::SeedableRng;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use std::cmp::min;
 use std::path::Path;
@@ -43,49 +45,70 @@ fn setup_runtime(
     (epoch_manager, shard_tracker, runtime)
 }
 
-fn setup_mock_peer(
-    chain: Chain,
+/// Starts a mock peer for every peer of the scenario, listening on `mock_listen_addrs`.
+/// The returned task fails as soon as one of the peers fails.
+fn setup_mock_peers(
+    chains: Vec<Chain>,
     config: &mut NearConfig,
     network_start_height: Option<BlockHeight>,
     network_config: MockNetworkConfig,
+    scenario: &Scenario,
     target_height: BlockHeight,
     num_shards: ShardId,
-    mock_listen_addr: tcp::ListenerAddr,
+    mock_listen_addrs: Vec<tcp::ListenerAddr>,
 ) -> tokio::task::JoinHandle<anyhow::Result<()>> {
-    let network_start_height = match network_start_height {
-        None => target_height,
-        Some(0) => chain.genesis_block().header().height(),
-        Some(it) => it,
-    };
-    let secret_key = SecretKey::from_random(KeyType::ED25519);
-    config
-        .network_config
-        .peer_store
-        .boot_nodes
-        .push(PeerInfo::new(PeerId::new(secret_key.public_key()), *mock_listen_addr));
+    let peers = scenario.peers();
+    assert_eq!(peers.len(), mock_listen_addrs.len(), "one listen address is needed per mock peer");
     let chain_id = config.genesis.config.chain_id.clone();
     let block_production_delay = config.client_config.min_block_production_delay;
     let archival = config.client_config.archive;
+    let seed = scenario.seed.unwrap_or_else(rand::random);
+    let mut mocks = vec![];
+    for (i, ((chain, peer), mock_listen_addr)) in
+        chains.into_iter().zip(peers).zip(mock_listen_addrs).enumerate()
+    {
+        let network_start_height = match network_start_height {
+            None => target_height,
+            Some(0) => chain.genesis_block().header().height(),
+            Some(it) => it,
+        };
+        let secret_key = SecretKey::from_random(KeyType::ED25519);
+        config
+            .network_config
+            .peer_store
+            .boot_nodes
+            .push(PeerInfo::new(PeerId::new(secret_key.public_key()), *mock_listen_addr));
+        let chain_id = chain_id.clone();
+        let network_config = network_config.clone();
+        let rng = rand::rngs::StdRng::seed_from_u64(seed.wrapping_add(i as u64));
+        mocks.push(async move {
+            let mock = MockPeer::new(
+                chain,
+                secret_key,
+                mock_listen_addr,
+                chain_id,
+                archival,
+                block_production_delay,
+                num_shards,
+                network_start_height,
+                network_config,
+                peer,
+                rng,
+            )
+            .await?;
+            mock.run(target_height).await
+        });
+    }
     actix::spawn(async move {
-        let mock = MockPeer::new(
-            chain,
-            secret_key,
-            mock_listen_addr,
-            chain_id,
-            archival,
-            block_production_delay,
-            num_shards,
-            network_start_height,
-            network_config,
-        )
-        .await?;
-        mock.run(target_height).await
+        futures::future::try_join_all(mocks).await?;
+        Ok(())
     })
 }
 
 pub struct MockNode {
     // target height actually available to sync to in the chain history database
     pub target_height: BlockHeight,
+    // task running all the mock peers
     pub mock_peer: tokio::task::JoinHandle<anyhow::Result<()>>,
     // client that allows making RPC requests to the node under test
     pub rpc_client: JsonRpcClient,
@@ -103,6 +126,8 @@ pub struct MockNode {
 /// `target_height`: height that the simulated peers will produce blocks until. If None, will
 ///                  use the height from the chain head in storage
 /// `in_memory_storage`: if true, make client use in memory storage instead of rocksdb
+/// `scenario`: scripted behavior of the mock peers, see `crate::scenario`
+/// `mock_listen_addrs`: addresses the mock peers listen on, one per peer of the scenario
 ///
 /// Returns a struct representing the node under test
 pub fn setup_mock_node(
@@ -114,7 +139,8 @@ pub fn setup_mock_node(
     network_start_height: Option<BlockHeight>,
     target_height: Option<BlockHeight>,
     in_memory_storage: bool,
-    mock_listen_addr: tcp::ListenerAddr,
+    scenario: &Scenario,
+    mock_listen_addrs: Vec<tcp::ListenerAddr>,
 ) -> MockNode {
     let parent_span = tracing::debug_span!(target: "mock_node", "setup_mock_node").entered();
     let (mock_network_epoch_manager, mock_network_shard_tracker, mock_network_runtime) =
@@ -238,28 +264,35 @@ pub fn setup_mock_node(
         }
     }
 
-    let chain = Chain::new_for_view_client(
-        mock_network_epoch_manager.clone(),
-        mock_network_shard_tracker,
-        mock_network_runtime,
-        &chain_genesis,
-        DoomslugThresholdMode::NoApprovals,
-        config.client_config.save_trie_changes,
-    )
-    .unwrap();
-    let head = chain.head().unwrap();
+    // Every mock peer reads the chain history on its own.
+    let chains: Vec<Chain> = mock_listen_addrs
+        .iter()
+        .map(|_| {
+            Chain::new_for_view_client(
+                mock_network_epoch_manager.clone(),
+                mock_network_shard_tracker.clone(),
+                mock_network_runtime.clone(),
+                &chain_genesis,
+                DoomslugThresholdMode::NoApprovals,
+                config.client_config.save_trie_changes,
+            )
+            .unwrap()
+        })
+        .collect();
+    let head = chains[0].head().unwrap();
     let target_height = min(target_height.unwrap_or(head.height), head.height);
     let num_shards = mock_network_epoch_manager.num_shards(&head.epoch_id).unwrap();
 
     config.network_config.peer_store.boot_nodes.clear();
-    let mock_peer = setup_mock_peer(
-        chain,
+    let mock_peer = setup_mock_peers(
+        chains,
         &mut config,
         network_start_height,
         network_config.clone(),
+        scenario,
         target_height,
         num_shards,
-        mock_listen_addr,
+        mock_listen_addrs,
     );
 
     let rpc_client = near_jsonrpc_client::new_client(&format!(
@@ -273,6 +306,7 @@ pub fn setup_mock_node(
 
 #[cfg(test)]
 mod tests {
+    use crate::scenario::Scenario;
     use crate::setup::{setup_mock_node, MockNode};
     use crate::MockNetworkConfig;
     use actix::{Actor, System};
@@ -293,20 +327,13 @@ mod tests {
     use nearcore::{load_test_config, start_with_config, NEAR_BASE};
     use rand::thread_rng;
     use std::ops::ControlFlow;
+    use std::path::Path;
     use std::sync::{Arc, RwLock};
     use std::time::Duration;
 
-    // Just a test to test that the basic mocknet setup works
-    // This test first starts a localnet with one validator node that generates 20 blocks
-    // to generate a chain history
-    // then start a mock network with this chain history and test that
-    // the client in the mock network can catch up these 20 blocks
-    #[cfg_attr(not(feature = "mock_node"), ignore)]
-    #[test]
-    fn test_mock_node_basic() {
-        init_integration_logger();
-
-        // first set up a network with only one validator and generate some blocks
+    // Starts a localnet with one validator node that generates a chain history of a bit more
+    // than two epochs, and returns its home dir.
+    fn generate_chain_history() -> (tempfile::TempDir, Genesis) {
         let mut genesis =
             Genesis::test(vec!["test0".parse().unwrap(), "test1".parse().unwrap()], 1);
         let epoch_length = 40;
@@ -387,9 +414,13 @@ mod tests {
             )
             .start();
         });
+        (dir, genesis)
+    }
 
-        // start the mock network to simulate a new node "test1" to sync up
-        // start the client at height 10 (end of the first epoch)
+    // Starts a mock network with the chain history in `dir`, with a new node "test1" starting at
+    // the end of the last epoch of the history. Waits until the client reaches the end of the
+    // history if `sync_to_target` is set, or until it starts otherwise.
+    fn run_mock_node(dir: &Path, genesis: Genesis, scenario: Scenario, sync_to_target: bool) {
         let dir1 = tempfile::Builder::new().prefix("test1").tempdir().unwrap();
         let mut near_config1 = load_test_config("", tcp::ListenerAddr::reserve_for_test(), genesis);
         near_config1.client_config.min_num_peers = 1;
@@ -401,7 +432,7 @@ mod tests {
 
         let client_start_height = {
             let store = near_store::NodeStorage::opener(
-                dir.path(),
+                dir,
                 near_config1.config.archive,
                 &near_config1.config.store,
                 None,
@@ -423,21 +454,25 @@ mod tests {
             last_epoch_start_height - 1
         };
 
+        let mock_listen_addrs =
+            scenario.peers().iter().map(|_| tcp::ListenerAddr::reserve_for_test()).collect();
         run_actix(async {
-            let MockNode { rpc_client, .. } = setup_mock_node(
+            let MockNode { rpc_client, target_height, .. } = setup_mock_node(
                 dir1.path(),
-                dir.path(),
+                dir,
                 near_config1,
                 &network_config,
                 client_start_height,
                 None,
                 None,
                 false,
-                tcp::ListenerAddr::reserve_for_test(),
+                &scenario,
+                mock_listen_addrs,
             );
+            let wait_height = if sync_to_target { target_height } else { client_start_height };
             wait_or_timeout(100, 60000, || async {
                 if let Ok(status) = rpc_client.status().await {
-                    if status.sync_info.latest_block_height >= client_start_height {
+                    if status.sync_info.latest_block_height >= wait_height {
                         System::current().stop();
                         return ControlFlow::Break(());
                     }
@@ -448,4 +483,41 @@ mod tests {
             .unwrap();
         })
     }
+
+    // Just a test to test that the basic mocknet setup works
+    // This test first starts a localnet with one validator node that generates 20 blocks
+    // to generate a chain history
+    // then start a mock network with this chain history and test that
+    // the client in the mock network can catch up these 20 blocks
+    #[cfg_attr(not(feature = "mock_node"), ignore)]
+    #[test]
+    fn test_mock_node_basic() {
+        init_integration_logger();
+        let (dir, genesis) = generate_chain_history();
+        run_mock_node(dir.path(), genesis, Scenario::default(), false);
+    }
+
+    // The client catches up with the network even if the peers drop and delay messages, and one
+    // of them lies about its height and sends invalid headers.
+    #[cfg_attr(not(feature = "mock_node"), ignore)]
+    #[test]
+    fn test_mock_node_adverse_network() {
+        init_integration_logger();
+        let (dir, genesis) = generate_chain_history();
+        let scenario: Scenario = serde_json::from_str(
+            r#"{
+                "seed": 1,
+                "peers": [
+                    { "rules": [{ "message": "chunk_part", "action": "drop", "probability": 0.5 }] },
+                    { "rules": [{ "message": "block", "action": "delay", "delay_ms": 500 }] },
+                    {
+                        "claimed_height": 1000,
+                        "rules": [{ "message": "block_headers", "action": "lie" }]
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+        run_mock_node(dir.path(), genesis, scenario, true);
+    }
 }
@@ -37,10 +37,26 @@ pub struct PingCommand {
     ping_frequency_millis: u64,
     /// line-separated list of accounts to filter on.
     /// We will only try to send pings to these accounts
-    // TODO: add --track-validators or something, that will constantly keep track of
-    // which accounts are validators and ping those
     #[clap(long)]
     account_filter_file: Option<PathBuf>,
+    /// ping the current validators, as learned from the AccountData they broadcast, instead
+    /// of all the accounts announced to us. Validators that leave the validator set are
+    /// no longer pinged.
+    #[clap(long)]
+    track_validators: bool,
+    /// also ping every validator through a connection to one of the proxies it advertises
+    /// for TIER1, to compare with the latency of pings routed over TIER2. These are plain TIER2
+    /// connections to the proxies, so their hop counts are not known
+    #[clap(long, requires = "track_validators")]
+    via_proxy: bool,
+    /// directory shared by several ping instances, to which each of them periodically writes
+    /// a summary of its latencies. `neard ping-latency-matrix` builds a matrix out of them
+    #[clap(long)]
+    upload_dir: Option<PathBuf>,
+    /// name of this instance's summary in --upload-dir, and of its row in the latency
+    /// matrix. Defaults to the public key given with --peer
+    #[clap(long, requires = "upload_dir")]
+    upload_name: Option<String>,
     /// filename to append CSV data to
     #[clap(long)]
     latencies_csv_file: Option<PathBuf>,
@@ -52,24 +68,28 @@ pub struct PingCommand {
     prometheus_addr: String,
 }
 
-fn display_stats(stats: &mut [(crate::PeerIdentifier, crate::PingStats)], peer_id: &PeerId) {
+fn display_stats(
+    stats: &mut [(crate::PeerIdentifier, crate::PingPath, crate::PingStats)],
+    peer_id: &PeerId,
+) {
     let mut acc_width = "account".len();
-    for (peer, _) in stats.iter() {
+    for (peer, _, _) in stats.iter() {
         acc_width = std::cmp::max(acc_width, format!("{}", peer).len());
     }
     // the ones that never responded should end up at the top with this sorting, which is a
     // little weird, but we can fix it later.
-    stats.sort_by(|(_, left), (_, right)| left.average_latency.cmp(&right.average_latency));
+    stats.sort_by(|(_, _, left), (_, _, right)| left.average_latency.cmp(&right.average_latency));
     println!(
-        "{:<acc_width$} | {:<10} | {:<10} | {:<17} | {:<17} | {:<17}",
+        "{:<acc_width$} | {:<5} | {:<10} | {:<10} | {:<17} | {:<17} | {:<17}",
         "account",
+        "path",
         "num pings",
         "num pongs",
         "min ping latency",
         "max ping latency",
         "avg ping latency"
     );
-    for (peer, stats) in stats.iter() {
+    for (peer, path, stats) in stats.iter() {
         if stats.pings_sent == 0 {
             continue;
         }
@@ -77,8 +97,9 @@ fn display_stats(stats: &mut [(crate::PeerIdentifier, crate::PingStats)], peer_i
         let max_latency: Duration = stats.max_latency.try_into().unwrap();
         let average_latency: Duration = stats.average_latency.try_into().unwrap();
         println!(
-            "{:<acc_width$} | {:<10} | {:<10} | {:<17?} | {:<17?} | {:<17?}{}",
+            "{:<acc_width$} | {:<5} | {:<10} | {:<10} | {:<17?} | {:<17?} | {:<17?}{}",
             peer,
+            path.to_string(),
             stats.pings_sent,
             stats.pongs_received,
             min_latency,
@@ -192,6 +213,10 @@ impl PingCommand {
             } else {
                 None
             };
+        let upload_dir = self.upload_dir.as_ref().map(|dir| {
+            let name = self.upload_name.clone().unwrap_or_else(|| peer.id.to_string());
+            (dir.clone(), name)
+        });
         let runtime = tokio::runtime::Runtime::new().unwrap();
         runtime.block_on(async move {
             let mut stats = Vec::new();
@@ -207,6 +232,9 @@ impl PingCommand {
                 self.recv_timeout_seconds.unwrap_or(5),
                 filter,
                 csv,
+                self.track_validators,
+                self.via_proxy,
+                upload_dir,
                 &mut stats,
                 &self.prometheus_addr,
             )
@@ -1,3 +1,4 @@
+use crate::PingPath;
 use near_async::time;
 use near_primitives::network::PeerId;
 use near_primitives::types::AccountId;
@@ -11,7 +12,9 @@ pub struct LatenciesCsv {
     out: BufWriter<File>,
 }
 
-const HEADER: &'static str = "timestamp,ID,latency\n";
+const HEADER: &'static str = "timestamp,ID,path,hops,latency\n";
+// header written by versions of this tool that only sent TIER2 pings.
+const LEGACY_HEADER: &'static str = "timestamp,ID,latency\n";
 
 impl LatenciesCsv {
     pub fn open<P: AsRef<Path>>(filename: P) -> io::Result<Self> {
@@ -25,6 +28,13 @@ impl LatenciesCsv {
             f.read_exact(&mut buf)?;
 
             match std::str::from_utf8(&buf) {
+                Ok(s) if s.starts_with(LEGACY_HEADER) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "Latencies CSV file was written by an older version without the path \
+                        and hops columns. Please use a new file",
+                    ));
+                }
                 Ok(s) => {
                     if s != HEADER {
                         return Err(io::Error::new(
@@ -74,14 +84,18 @@ impl LatenciesCsv {
         &mut self,
         peer_id: &PeerId,
         account_id: Option<&AccountId>,
+        path: PingPath,
+        hops: Option<u32>,
         latency: time::Duration,
     ) -> io::Result<()> {
         let id = account_id.map_or_else(|| format!("{}", peer_id), |a| format!("{}", a));
         write!(
             self.out,
-            "{:?},{},{}\n",
+            "{:?},{},{},{},{}\n",
             chrono::offset::Utc::now(),
             id,
+            path,
+            hops_str(hops),
             latency.whole_microseconds()
         )?;
         Ok(())
@@ -91,9 +105,22 @@ impl LatenciesCsv {
         &mut self,
         peer_id: &PeerId,
         account_id: Option<&AccountId>,
+        path: PingPath,
+        hops: Option<u32>,
     ) -> io::Result<()> {
         let id = account_id.map_or_else(|| format!("{}", peer_id), |a| format!("{}", a));
-        write!(self.out, "{:?},{},TIMEOUT\n", chrono::offset::Utc::now(), id)?;
+        write!(
+            self.out,
+            "{:?},{},{},{},TIMEOUT\n",
+            chrono::offset::Utc::now(),
+            id,
+            path,
+            hops_str(hops)
+        )?;
         Ok(())
     }
 }
+
+fn hops_str(hops: Option<u32>) -> String {
+    hops.map_or_else(String::new, |h| h.to_string())
+}
@@ -1,9 +1,10 @@
 use actix_web::{web, App, HttpServer};
 use anyhow::Context;
 pub use cli::PingCommand;
+pub use matrix::LatencyMatrixCommand;
 use near_async::time;
 use near_network::raw::{ConnectError, Connection, DirectMessage, Message, RoutedMessage};
-use near_network::types::HandshakeFailureReason;
+use near_network::types::{HandshakeFailureReason, SignedAccountData};
 use near_primitives::hash::CryptoHash;
 use near_primitives::network::{AnnounceAccount, PeerId};
 use near_primitives::types::{AccountId, BlockHeight};
@@ -12,11 +13,16 @@ use std::cmp;
 use std::collections::hash_map::Entry;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::mpsc;
 
 pub mod cli;
 mod csv;
+mod matrix;
 mod metrics;
+mod routing;
 
 // TODO: also log number of bytes/other messages (like Blocks) received?
 #[derive(Debug, Default)]
@@ -47,6 +53,26 @@ impl PingStats {
 
 type Nonce = u64;
 
+/// The route a ping takes to reach its target.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum PingPath {
+    /// Routed over TIER2 connections, starting from the node we're connected to.
+    Tier2,
+    /// Sent over a TIER2 connection to one of the proxies the validator advertises for
+    /// TIER1, which routes it to the validator. The proxy may be the validator itself, so the
+    /// number of hops isn't known.
+    Proxy,
+}
+
+impl std::fmt::Display for PingPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Tier2 => "tier2",
+            Self::Proxy => "proxy",
+        })
+    }
+}
+
 #[derive(Debug, Eq, PartialEq)]
 struct PingTarget {
     peer_id: PeerId,
@@ -124,16 +150,18 @@ struct AppInfo {
     timeouts: BTreeSet<PingTimeout>,
     account_filter: Option<HashSet<AccountId>>,
     chain_id: String,
+    path: PingPath,
 }
 
 impl AppInfo {
-    fn new(account_filter: Option<HashSet<AccountId>>, chain_id: &str) -> Self {
+    fn new(account_filter: Option<HashSet<AccountId>>, chain_id: &str, path: PingPath) -> Self {
         Self {
             stats: HashMap::new(),
             requests: BTreeMap::new(),
             timeouts: BTreeSet::new(),
             account_filter,
             chain_id: chain_id.to_owned(),
+            path,
         }
     }
 
@@ -152,7 +180,7 @@ impl AppInfo {
 
         let account_id = self.peer_id_to_account_id(&peer_id);
         crate::metrics::PING_SENT
-            .with_label_values(&[&chain_id, &peer_str(peer_id, account_id)])
+            .with_label_values(&[&chain_id, &peer_str(peer_id, account_id), &self.path.to_string()])
             .inc();
 
         match self.stats.entry(peer_id.clone()) {
@@ -167,7 +195,8 @@ impl AppInfo {
                     .unwrap();
 
                 println!(
-                    "send ping --------------> {}",
+                    "send {} ping --------------> {}",
+                    self.path,
                     peer_str(&peer_id, state.account_id.as_ref())
                 );
 
@@ -205,15 +234,12 @@ impl AppInfo {
     ) -> Option<(time::Duration, Option<&AccountId>)> {
         match self.stats.get_mut(peer_id) {
             Some(state) => {
-                let pending_pings = self
-                    .requests
-                    .get_mut(&PingTarget {
-                        peer_id: peer_id.clone(),
-                        last_pinged: state.last_pinged,
-                    })
-                    .unwrap();
+                let pending_pings = self.requests.get_mut(&PingTarget {
+                    peer_id: peer_id.clone(),
+                    last_pinged: state.last_pinged,
+                });
 
-                match pending_pings.remove(&nonce) {
+                match pending_pings.and_then(|p| p.remove(&nonce)) {
                     Some(times) => {
                         let latency = received_at - times.sent_at;
                         state.stats.pong_received(latency);
@@ -225,7 +251,8 @@ impl AppInfo {
 
                         let l: std::time::Duration = latency.try_into().unwrap();
                         println!(
-                            "recv pong <-------------- {} latency: {:?}",
+                            "recv {} pong <-------------- {} latency: {:?}",
+                            self.path,
                             peer_str(&peer_id, state.account_id.as_ref()),
                             l
                         );
@@ -258,12 +285,22 @@ impl AppInfo {
             .unwrap();
         assert!(pending_pings.remove(&t.nonce).is_some());
         println!(
-            "{} timeout after {} ---------",
+            "{} {} timeout after {} ---------",
+            self.path,
             peer_str(&t.peer_id, state.account_id.as_ref()),
             PING_TIMEOUT
         );
     }
 
+    /// Stops sending pings to this peer, e.g. because it's no longer a validator. Its stats
+    /// are kept, and pinging it resumes if it's passed to add_peer() again.
+    fn stop_pinging(&mut self, peer_id: &PeerId) {
+        let Some(state) = self.stats.get(peer_id) else { return };
+        self.requests
+            .remove(&PingTarget { peer_id: peer_id.clone(), last_pinged: state.last_pinged });
+        self.timeouts.retain(|t| &t.peer_id != peer_id);
+    }
+
     fn add_peer(&mut self, peer_id: PeerId, account_id: Option<AccountId>) {
         if let Some(filter) = self.account_filter.as_ref() {
             if let Some(account_id) = account_id.as_ref() {
@@ -275,6 +312,11 @@ impl AppInfo {
         }
         match self.stats.entry(peer_id.clone()) {
             Entry::Occupied(mut e) => {
+                let target =
+                    PingTarget { peer_id: peer_id.clone(), last_pinged: e.get().last_pinged };
+                if !self.requests.contains_key(&target) {
+                    self.requests.insert(target, HashMap::new());
+                }
                 if let Some(account_id) = account_id {
                     let state = e.get_mut();
                     if let Some(old) = state.account_id.as_ref() {
@@ -312,32 +354,258 @@ impl AppInfo {
     }
 }
 
-fn handle_message(
-    app_info: &mut AppInfo,
-    msg: Message,
-    received_at: time::Instant,
-    latencies_csv: Option<&mut crate::csv::LatenciesCsv>,
-) -> anyhow::Result<()> {
-    match msg {
-        Message::Routed(RoutedMessage::Pong { nonce, source }) => {
-            let chain_id = app_info.chain_id.clone(); // Avoid an immutable borrow during a mutable borrow.
-            if let Some((latency, account_id)) = app_info.pong_received(&source, nonce, received_at)
-            {
-                crate::metrics::PONG_RECEIVED
-                    .with_label_values(&[&chain_id, &peer_str(&source, account_id)])
-                    .observe(latency.as_seconds_f64());
-                if let Some(csv) = latencies_csv {
-                    csv.write(&source, account_id, latency)
-                        .context("Failed writing to CSV file")?;
+/// What we know about a validator from the AccountData it broadcasts.
+struct TrackedValidator {
+    // Proxies the validator advertises for TIER1, as (peer id, address) pairs.
+    proxies: Vec<(PeerId, SocketAddr)>,
+    last_seen: time::Instant,
+}
+
+/// Sent by the tasks running `ping_via_proxy()`.
+enum ProxyEvent {
+    Connected { validator: PeerId },
+    Pong { nonce: Nonce, source: PeerId, received_at: time::Instant },
+    Disconnected { validator: PeerId },
+}
+
+/// A connection to one of the proxies of a validator, owned by a spawned task.
+struct ProxyConnection {
+    pings: mpsc::UnboundedSender<Nonce>,
+}
+
+// how often we ask the node we're connected to for the AccountData of all validators.
+const ACCOUNTS_DATA_REFRESH_INTERVAL: time::Duration = time::Duration::minutes(1);
+// validators whose AccountData we haven't seen for this long are no longer pinged.
+const VALIDATOR_EXPIRY: time::Duration = time::Duration::minutes(5);
+// how often the latency summary is written to --upload-dir.
+const SUMMARY_UPLOAD_INTERVAL: time::Duration = time::Duration::minutes(1);
+
+/// The parameters used for the handshakes of all our connections.
+#[derive(Clone)]
+struct ConnectParams {
+    chain_id: String,
+    genesis_hash: CryptoHash,
+    head_height: BlockHeight,
+    protocol_version: Option<ProtocolVersion>,
+    recv_timeout: time::Duration,
+}
+
+struct Pinger {
+    chain_id: String,
+    // the node we're connected to. TIER2 pings are routed starting from it.
+    peer_id: PeerId,
+    tier2: AppInfo,
+    // Some if pinging validators via their proxies too.
+    proxied: Option<AppInfo>,
+    proxies: HashMap<PeerId, ProxyConnection>,
+    proxy_events: mpsc::UnboundedSender<ProxyEvent>,
+    // Some if only the validators we learn about from AccountData are pinged.
+    validators: Option<HashMap<PeerId, TrackedValidator>>,
+    announced_accounts: HashMap<PeerId, AccountId>,
+    graph: routing::RoutingGraph,
+    latencies_csv: Option<crate::csv::LatenciesCsv>,
+    connect_params: ConnectParams,
+    ttl: u8,
+}
+
+impl Pinger {
+    fn hop_count(&mut self, path: PingPath, target: &PeerId) -> Option<u32> {
+        match path {
+            PingPath::Tier2 => self.graph.hop_count(&self.peer_id, target).map(|h| h + 1),
+            // We don't know the route from the proxy to the validator.
+            PingPath::Proxy => None,
+        }
+    }
+
+    fn handle_message(&mut self, msg: Message, received_at: time::Instant) -> anyhow::Result<()> {
+        match msg {
+            Message::Routed(RoutedMessage::Pong { nonce, source }) => {
+                self.pong_received(PingPath::Tier2, &source, nonce, received_at)?;
+            }
+            Message::Direct(DirectMessage::AnnounceAccounts(a)) => {
+                if self.validators.is_none() {
+                    self.tier2.add_announce_accounts(a);
+                    return Ok(());
+                }
+                for AnnounceAccount { account_id, peer_id, .. } in a {
+                    if self.validators.as_ref().unwrap().contains_key(&peer_id) {
+                        self.tier2.add_peer(peer_id.clone(), Some(account_id.clone()));
+                        // add_peer() would also resume pinging it, which is only done
+                        // once there's a connection to its proxy.
+                        let proxied_state =
+                            self.proxied.as_mut().and_then(|t| t.stats.get_mut(&peer_id));
+                        if let Some(state) = proxied_state {
+                            state.account_id.get_or_insert_with(|| account_id.clone());
+                        }
+                    }
+                    self.announced_accounts.insert(peer_id, account_id);
                 }
             }
+            Message::Direct(DirectMessage::AccountsData(accounts_data)) => {
+                self.add_accounts_data(accounts_data);
+            }
+            Message::Direct(DirectMessage::Edges(edges)) => {
+                self.graph.update(edges);
+            }
+            _ => {}
+        };
+        Ok(())
+    }
+
+    fn add_accounts_data(&mut self, accounts_data: Vec<Arc<SignedAccountData>>) {
+        let Some(validators) = self.validators.as_mut() else { return };
+        let now = time::Instant::now();
+        for data in accounts_data {
+            let proxies = data.proxies.iter().map(|p| (p.peer_id.clone(), p.addr)).collect();
+            if !validators.contains_key(&data.peer_id) {
+                tracing::debug!(
+                    target: "ping", "tracking validator {} with account key {}",
+                    data.peer_id, data.account_key
+                );
+            }
+            validators.insert(data.peer_id.clone(), TrackedValidator { proxies, last_seen: now });
+            let account_id = self.announced_accounts.get(&data.peer_id).cloned();
+            self.tier2.add_peer(data.peer_id.clone(), account_id);
         }
-        Message::Direct(DirectMessage::AnnounceAccounts(a)) => {
-            app_info.add_announce_accounts(a);
+        if self.proxied.is_none() {
+            return;
         }
-        _ => {}
-    };
-    Ok(())
+        for (peer_id, validator) in validators.iter() {
+            if self.proxies.contains_key(peer_id) {
+                continue;
+            }
+            let Some((proxy_id, proxy_addr)) = validator.proxies.first().cloned() else { continue };
+            let (pings_sender, pings) = mpsc::unbounded_channel();
+            tokio::spawn(ping_via_proxy(
+                self.connect_params.clone(),
+                peer_id.clone(),
+                proxy_id.clone(),
+                proxy_addr,
+                self.ttl,
+                pings,
+                self.proxy_events.clone(),
+            ));
+            self.proxies.insert(peer_id.clone(), ProxyConnection { pings: pings_sender });
+        }
+    }
+
+    /// Stops pinging the validators whose AccountData we haven't received in a while,
+    /// which means they're not validators in the current epoch anymore.
+    fn expire_validators(&mut self) {
+        let Some(validators) = self.validators.as_mut() else { return };
+        let now = time::Instant::now();
+        let expired: Vec<_> = validators
+            .iter()
+            .filter(|(_, v)| now - v.last_seen > VALIDATOR_EXPIRY)
+            .map(|(peer_id, _)| peer_id.clone())
+            .collect();
+        for peer_id in expired {
+            tracing::debug!(target: "ping", "validator {} expired", peer_id);
+            validators.remove(&peer_id);
+            self.tier2.stop_pinging(&peer_id);
+            if let Some(proxied) = self.proxied.as_mut() {
+                proxied.stop_pinging(&peer_id);
+            }
+            // dropping the sender ends the task that owns the proxy connection.
+            self.proxies.remove(&peer_id);
+        }
+    }
+
+    fn handle_proxy_event(&mut self, event: ProxyEvent) -> anyhow::Result<()> {
+        match event {
+            ProxyEvent::Connected { validator } => {
+                if self.proxies.contains_key(&validator) {
+                    let account_id = self.announced_accounts.get(&validator).cloned();
+                    self.proxied.as_mut().unwrap().add_peer(validator, account_id);
+                }
+            }
+            ProxyEvent::Pong { nonce, source, received_at } => {
+                self.pong_received(PingPath::Proxy, &source, nonce, received_at)?;
+            }
+            ProxyEvent::Disconnected { validator } => {
+                // We'll connect again the next time we receive its AccountData.
+                self.proxies.remove(&validator);
+                self.proxied.as_mut().unwrap().stop_pinging(&validator);
+            }
+        }
+        Ok(())
+    }
+
+    fn send_proxy_ping(&mut self, target: &PeerId, nonce: Nonce) {
+        let proxied = self.proxied.as_mut().unwrap();
+        let sent = self.proxies.get(target).map_or(false, |proxy| proxy.pings.send(nonce).is_ok());
+        if sent {
+            proxied.ping_sent(target, nonce, &self.chain_id);
+        } else {
+            self.proxies.remove(target);
+            proxied.stop_pinging(target);
+        }
+    }
+
+    fn pong_received(
+        &mut self,
+        path: PingPath,
+        source: &PeerId,
+        nonce: Nonce,
+        received_at: time::Instant,
+    ) -> anyhow::Result<()> {
+        let hops = self.hop_count(path, source);
+        let chain_id = self.chain_id.clone();
+        let app_info = match path {
+            PingPath::Tier2 => &mut self.tier2,
+            PingPath::Proxy => self.proxied.as_mut().unwrap(),
+        };
+        if let Some((latency, account_id)) = app_info.pong_received(source, nonce, received_at) {
+            let account_label = peer_str(source, account_id);
+            let path_label = path.to_string();
+            let labels = [chain_id.as_str(), account_label.as_str(), path_label.as_str()];
+            crate::metrics::PONG_RECEIVED
+                .with_label_values(&labels)
+                .observe(latency.as_seconds_f64());
+            if let Some(hops) = hops {
+                crate::metrics::HOP_COUNT.with_label_values(&labels).set(hops.into());
+            }
+            if let Some(csv) = self.latencies_csv.as_mut() {
+                csv.write(source, account_id, path, hops, latency)
+                    .context("Failed writing to CSV file")?;
+            }
+        }
+        Ok(())
+    }
+
+    fn timeout(&mut self, path: PingPath, t: &PingTimeout) -> anyhow::Result<()> {
+        let hops = self.hop_count(path, &t.peer_id);
+        let chain_id = self.chain_id.clone();
+        let app_info = match path {
+            PingPath::Tier2 => &mut self.tier2,
+            PingPath::Proxy => self.proxied.as_mut().unwrap(),
+        };
+        app_info.pop_timeout(t);
+        let account_id = app_info.peer_id_to_account_id(&t.peer_id);
+        crate::metrics::PONG_TIMEOUTS
+            .with_label_values(&[&chain_id, &peer_str(&t.peer_id, account_id), &path.to_string()])
+            .inc();
+        if let Some(csv) = self.latencies_csv.as_mut() {
+            csv.write_timeout(&t.peer_id, account_id, path, hops)
+                .context("Failed writing to CSV file")?;
+        }
+        Ok(())
+    }
+
+    fn upload_summary(&self, dir: &std::path::Path, name: &str) -> std::io::Result<()> {
+        let stats = self.tier2.stats.iter().map(|(peer_id, s)| (peer_id, s, PingPath::Tier2));
+        let proxy_stats = self
+            .proxied
+            .iter()
+            .flat_map(|t| t.stats.iter().map(|(peer_id, s)| (peer_id, s, PingPath::Proxy)));
+        crate::matrix::upload_summary(
+            dir,
+            name,
+            stats.chain(proxy_stats).map(|(peer_id, state, path)| {
+                (peer_str(peer_id, state.account_id.as_ref()), path, &state.stats)
+            }),
+        )
+    }
 }
 
 #[derive(Debug)]
@@ -355,10 +623,10 @@ impl std::fmt::Display for PeerIdentifier {
     }
 }
 
-fn collect_stats(app_info: AppInfo, ping_stats: &mut Vec<(PeerIdentifier, PingStats)>) {
+fn collect_stats(app_info: AppInfo, ping_stats: &mut Vec<(PeerIdentifier, PingPath, PingStats)>) {
     for (peer_id, state) in app_info.stats {
         let PingState { stats, account_id, .. } = state;
-        ping_stats.push((PeerIdentifier { peer_id, account_id }, stats));
+        ping_stats.push((PeerIdentifier { peer_id, account_id }, app_info.path, stats));
     }
 }
 
@@ -371,35 +639,21 @@ fn prepare_timeout(sleep: Pin<&mut tokio::time::Sleep>, app_info: &AppInfo) -> O
     }
 }
 
-async fn ping_via_node(
-    chain_id: &str,
-    genesis_hash: CryptoHash,
-    head_height: BlockHeight,
-    protocol_version: Option<ProtocolVersion>,
+async fn connect(
+    params: &ConnectParams,
     peer_id: PeerId,
     peer_addr: SocketAddr,
-    ttl: u8,
-    ping_frequency_millis: u64,
-    recv_timeout_seconds: u32,
-    account_filter: Option<HashSet<AccountId>>,
-    mut latencies_csv: Option<crate::csv::LatenciesCsv>,
-    ping_stats: &mut Vec<(PeerIdentifier, PingStats)>,
-    prometheus_addr: &str,
-) -> anyhow::Result<()> {
-    let mut app_info = AppInfo::new(account_filter, chain_id);
-
-    app_info.add_peer(peer_id.clone(), None);
-
-    let mut peer = match Connection::connect(
+) -> anyhow::Result<Connection> {
+    match Connection::connect(
         peer_addr,
         peer_id,
-        protocol_version,
-        chain_id,
-        genesis_hash,
-        head_height,
+        params.protocol_version,
+        &params.chain_id,
+        params.genesis_hash,
+        params.head_height,
         vec![0],
-        time::Duration::seconds(recv_timeout_seconds.into())).await {
-        Ok(p) => p,
+        params.recv_timeout).await {
+        Ok(p) => Ok(p),
         Err(ConnectError::HandshakeFailure(reason)) => {
             match reason {
                 HandshakeFailureReason::ProtocolVersionMismatch { version, oldest_supported_version } => anyhow::bail!(
@@ -419,14 +673,119 @@ async fn ping_via_node(
         Err(e) => {
             anyhow::bail!("Error connecting to {:?}: {}", peer_addr, e);
         }
+    }
+}
+
+/// Connects to a proxy of `validator` and sends it the pings received on `pings`, until either
+/// the connection fails or the sender is dropped. This is a regular TIER2 connection: we don't
+/// have a validator key to perform a TIER1 handshake with.
+async fn ping_via_proxy(
+    params: ConnectParams,
+    validator: PeerId,
+    proxy_id: PeerId,
+    proxy_addr: SocketAddr,
+    ttl: u8,
+    mut pings: mpsc::UnboundedReceiver<Nonce>,
+    events: mpsc::UnboundedSender<ProxyEvent>,
+) {
+    if let Err(e) =
+        proxy_loop(&params, &validator, &proxy_id, proxy_addr, ttl, &mut pings, &events).await
+    {
+        tracing::warn!(
+            target: "ping", "proxy {}@{} of {}: {:#}", proxy_id, proxy_addr, validator, e
+        );
+    }
+    let _ = events.send(ProxyEvent::Disconnected { validator });
+}
+
+async fn proxy_loop(
+    params: &ConnectParams,
+    validator: &PeerId,
+    proxy_id: &PeerId,
+    proxy_addr: SocketAddr,
+    ttl: u8,
+    pings: &mut mpsc::UnboundedReceiver<Nonce>,
+    events: &mpsc::UnboundedSender<ProxyEvent>,
+) -> anyhow::Result<()> {
+    let mut conn = connect(params, proxy_id.clone(), proxy_addr).await?;
+    let _ = events.send(ProxyEvent::Connected { validator: validator.clone() });
+    loop {
+        tokio::select! {
+            nonce = pings.recv() => {
+                let Some(nonce) = nonce else { return Ok(()) };
+                conn.send_routed_message(RoutedMessage::Ping { nonce }, validator.clone(), ttl)
+                    .await
+                    .context("Failed sending ping")?;
+            }
+            res = conn.recv() => {
+                let (msg, received_at) = res.context("Failed receiving messages")?;
+                if let Message::Routed(RoutedMessage::Pong { nonce, source }) = msg {
+                    let _ = events.send(ProxyEvent::Pong { nonce, source, received_at });
+                }
+            }
+        }
+    }
+}
+
+async fn ping_via_node(
+    chain_id: &str,
+    genesis_hash: CryptoHash,
+    head_height: BlockHeight,
+    protocol_version: Option<ProtocolVersion>,
+    peer_id: PeerId,
+    peer_addr: SocketAddr,
+    ttl: u8,
+    ping_frequency_millis: u64,
+    recv_timeout_seconds: u32,
+    account_filter: Option<HashSet<AccountId>>,
+    latencies_csv: Option<crate::csv::LatenciesCsv>,
+    track_validators: bool,
+    via_proxy: bool,
+    upload_dir: Option<(PathBuf, String)>,
+    ping_stats: &mut Vec<(PeerIdentifier, PingPath, PingStats)>,
+    prometheus_addr: &str,
+) -> anyhow::Result<()> {
+    let connect_params = ConnectParams {
+        chain_id: chain_id.to_owned(),
+        genesis_hash,
+        head_height,
+        protocol_version,
+        recv_timeout: time::Duration::seconds(recv_timeout_seconds.into()),
     };
+    let (proxy_events_sender, mut proxy_events) = mpsc::unbounded_channel();
+    let mut pinger = Pinger {
+        chain_id: chain_id.to_owned(),
+        peer_id: peer_id.clone(),
+        tier2: AppInfo::new(account_filter.clone(), chain_id, PingPath::Tier2),
+        proxied: via_proxy.then(|| AppInfo::new(account_filter, chain_id, PingPath::Proxy)),
+        proxies: HashMap::new(),
+        proxy_events: proxy_events_sender,
+        validators: track_validators.then(HashMap::new),
+        announced_accounts: HashMap::new(),
+        graph: routing::RoutingGraph::default(),
+        latencies_csv,
+        connect_params: connect_params.clone(),
+        ttl,
+    };
+
+    pinger.tier2.add_peer(peer_id.clone(), None);
+
+    let mut peer = connect(&connect_params, peer_id, peer_addr).await?;
 
     let mut result = Ok(());
     let mut nonce = 1;
+    let ping_frequency = std::time::Duration::from_millis(ping_frequency_millis);
     let next_ping = tokio::time::sleep(std::time::Duration::ZERO);
     tokio::pin!(next_ping);
+    let next_proxy_ping = tokio::time::sleep(std::time::Duration::ZERO);
+    tokio::pin!(next_proxy_ping);
     let next_timeout = tokio::time::sleep(std::time::Duration::ZERO);
     tokio::pin!(next_timeout);
+    let next_proxy_timeout = tokio::time::sleep(std::time::Duration::ZERO);
+    tokio::pin!(next_proxy_timeout);
+    let mut accounts_data_refresh =
+        tokio::time::interval(ACCOUNTS_DATA_REFRESH_INTERVAL.try_into().unwrap());
+    let mut summary_upload = tokio::time::interval(SUMMARY_UPLOAD_INTERVAL.try_into().unwrap());
 
     let server = HttpServer::new(move || {
         App::new().service(
@@ -442,8 +801,11 @@ async fn ping_via_node(
     tokio::spawn(server);
 
     loop {
-        let target = app_info.pick_next_target();
-        let pending_timeout = prepare_timeout(next_timeout.as_mut(), &app_info);
+        let target = pinger.tier2.pick_next_target();
+        let proxy_target = pinger.proxied.as_ref().and_then(|t| t.pick_next_target());
+        let pending_timeout = prepare_timeout(next_timeout.as_mut(), &pinger.tier2);
+        let pending_proxy_timeout =
+            pinger.proxied.as_ref().and_then(|t| prepare_timeout(next_proxy_timeout.as_mut(), t));
 
         tokio::select! {
             _ = &mut next_ping, if target.is_some() => {
@@ -453,9 +815,14 @@ async fn ping_via_node(
                 if result.is_err() {
                     break;
                 }
-                app_info.ping_sent(&target, nonce, &chain_id);
+                pinger.tier2.ping_sent(&target, nonce, &chain_id);
+                nonce += 1;
+                next_ping.as_mut().reset(tokio::time::Instant::now() + ping_frequency);
+            }
+            _ = &mut next_proxy_ping, if proxy_target.is_some() => {
+                pinger.send_proxy_ping(&proxy_target.unwrap(), nonce);
                 nonce += 1;
-                next_ping.as_mut().reset(tokio::time::Instant::now() + std::time::Duration::from_millis(ping_frequency_millis));
+                next_proxy_ping.as_mut().reset(tokio::time::Instant::now() + ping_frequency);
             }
             res = peer.recv() => {
                 let (msg, first_byte_time) = match res {
@@ -465,30 +832,40 @@ async fn ping_via_node(
                         break;
                     }
                 };
-                result = handle_message(
-                            &mut app_info,
-                            msg,
-                            first_byte_time.try_into().unwrap(),
-                            latencies_csv.as_mut()
-                        );
+                result = pinger.handle_message(msg, first_byte_time.try_into().unwrap());
+                if result.is_err() {
+                    break;
+                }
+            }
+            Some(event) = proxy_events.recv() => {
+                result = pinger.handle_proxy_event(event);
                 if result.is_err() {
                     break;
                 }
             }
             _ = &mut next_timeout, if pending_timeout.is_some() => {
-                let t = pending_timeout.unwrap();
-                app_info.pop_timeout(&t);
-                let account_id = app_info.peer_id_to_account_id(&t.peer_id);
-                crate::metrics::PONG_TIMEOUTS.with_label_values(&[&chain_id, &peer_str(&t.peer_id, account_id)]).inc();
-                if let Some(csv) = latencies_csv.as_mut() {
-                    result = csv.write_timeout(
-                        &t.peer_id,
-                        account_id,
-                    )
-                    .context("Failed writing to CSV file");
-                    if result.is_err() {
-                        break;
-                    }
+                result = pinger.timeout(PingPath::Tier2, &pending_timeout.unwrap());
+                if result.is_err() {
+                    break;
+                }
+            }
+            _ = &mut next_proxy_timeout, if pending_proxy_timeout.is_some() => {
+                result = pinger.timeout(PingPath::Proxy, &pending_proxy_timeout.unwrap());
+                if result.is_err() {
+                    break;
+                }
+            }
+            _ = accounts_data_refresh.tick(), if track_validators => {
+                result = peer.request_accounts_data().await.context("Failed requesting AccountData");
+                if result.is_err() {
+                    break;
+                }
+                pinger.expire_validators();
+            }
+            _ = summary_upload.tick(), if upload_dir.is_some() => {
+                let (dir, name) = upload_dir.as_ref().unwrap();
+                if let Err(e) = pinger.upload_summary(dir, name) {
+                    tracing::warn!(target: "ping", "Failed uploading latency summary to {:?}: {}", dir, e);
                 }
             }
             _ = tokio::signal::ctrl_c() => {
@@ -496,6 +873,14 @@ async fn ping_via_node(
             }
         }
     }
-    collect_stats(app_info, ping_stats);
+    if let Some((dir, name)) = upload_dir.as_ref() {
+        if let Err(e) = pinger.upload_summary(dir, name) {
+            tracing::warn!(target: "ping", "Failed uploading latency summary to {:?}: {}", dir, e);
+        }
+    }
+    collect_stats(pinger.tier2, ping_stats);
+    if let Some(proxied) = pinger.proxied {
+        collect_stats(proxied, ping_stats);
+    }
     result
 }
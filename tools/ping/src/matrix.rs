@@ -0,0 +1,200 @@
+//! Latency summaries uploaded by ping instances to a shared directory, and the
+//! source x target latency matrix built from them.
+
+use crate::{PingPath, PingStats};
+use anyhow::Context;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+const SUMMARY_HEADER: &str =
+    "source,target,path,pings_sent,pongs_received,min_latency,avg_latency,max_latency";
+
+/// Overwrites `{dir}/{source}.csv` with the current stats of every ping target. Latencies
+/// are in microseconds. The file is written to a temporary file first and then renamed,
+/// so that `LatencyMatrixCommand` never reads a partially written summary.
+pub(crate) fn upload_summary<'a>(
+    dir: &Path,
+    source: &str,
+    stats: impl Iterator<Item = (String, PingPath, &'a PingStats)>,
+) -> io::Result<()> {
+    let path = dir.join(format!("{}.csv", source));
+    let tmp_path = dir.join(format!(".{}.csv.tmp", source));
+    let mut out = BufWriter::new(File::create(&tmp_path)?);
+    writeln!(out, "{}", SUMMARY_HEADER)?;
+    for (target, ping_path, stats) in stats {
+        if stats.pings_sent == 0 {
+            continue;
+        }
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{},{}",
+            source,
+            target,
+            ping_path,
+            stats.pings_sent,
+            stats.pongs_received,
+            stats.min_latency.whole_microseconds(),
+            stats.average_latency.whole_microseconds(),
+            stats.max_latency.whole_microseconds(),
+        )?;
+    }
+    out.into_inner()?.sync_all()?;
+    std::fs::rename(tmp_path, path)
+}
+
+/// Average latencies in microseconds, indexed by (source, target).
+type Latencies = BTreeMap<(String, String), u64>;
+
+fn read_summary(filename: &Path, path: PingPath, latencies: &mut Latencies) -> anyhow::Result<()> {
+    let reader = BufReader::new(File::open(filename)?);
+    let mut lines = reader.lines();
+    if lines.next().transpose()?.as_deref() != Some(SUMMARY_HEADER) {
+        anyhow::bail!("not a latency summary file");
+    }
+    for line in lines {
+        let line = line?;
+        let fields: Vec<_> = line.split(',').collect();
+        if fields.len() != 8 {
+            anyhow::bail!("unexpected line {:?}", line);
+        }
+        if fields[2] != path.to_string() || fields[4] == "0" {
+            continue;
+        }
+        let latency = fields[6].parse().with_context(|| format!("bad latency in {:?}", line))?;
+        latencies.insert((fields[0].to_string(), fields[1].to_string()), latency);
+    }
+    Ok(())
+}
+
+#[derive(clap::Parser)]
+pub struct LatencyMatrixCommand {
+    /// directory the ping instances upload their latency summaries to with --upload-dir
+    #[clap(long)]
+    upload_dir: PathBuf,
+    /// which pings to build the matrix from
+    #[clap(long, value_enum, default_value = "tier2")]
+    path: PingPath,
+    /// filename to write the matrix CSV to. Printed to stdout if not given
+    #[clap(long)]
+    out_file: Option<PathBuf>,
+}
+
+impl LatencyMatrixCommand {
+    pub fn run(&self) -> anyhow::Result<()> {
+        let mut latencies = Latencies::new();
+        let entries = std::fs::read_dir(&self.upload_dir)
+            .with_context(|| format!("Couldn't read upload dir {:?}", &self.upload_dir))?;
+        for entry in entries {
+            let filename = entry?.path();
+            let is_summary = filename.extension().map_or(false, |e| e == "csv")
+                && !filename.file_name().unwrap().to_string_lossy().starts_with('.');
+            if !is_summary {
+                continue;
+            }
+            if let Err(e) = read_summary(&filename, self.path, &mut latencies) {
+                tracing::warn!(target: "ping", "skipping {:?}: {:#}", filename, e);
+            }
+        }
+
+        let out: Box<dyn Write> = match &self.out_file {
+            Some(filename) => Box::new(
+                File::create(filename)
+                    .with_context(|| format!("Couldn't create {:?}", filename))?,
+            ),
+            None => Box::new(io::stdout()),
+        };
+        write_matrix(&latencies, BufWriter::new(out)).context("Failed writing the matrix")
+    }
+}
+
+/// Writes a CSV with one row per source and one column per node that is either a source
+/// or a target, so that instances running next to validators give an N x N matrix.
+/// Cells hold average latencies in microseconds, and are empty if there were no pongs.
+fn write_matrix(latencies: &Latencies, mut out: impl Write) -> io::Result<()> {
+    let sources: BTreeSet<_> = latencies.keys().map(|(source, _)| source).collect();
+    let mut nodes = sources.clone();
+    nodes.extend(latencies.keys().map(|(_, target)| target));
+
+    write!(out, "source")?;
+    for node in nodes.iter() {
+        write!(out, ",{}", node)?;
+    }
+    writeln!(out)?;
+    for source in sources {
+        write!(out, "{}", source)?;
+        for &target in nodes.iter() {
+            match latencies.get(&(source.clone(), target.clone())) {
+                Some(latency) => write!(out, ",{}", latency)?,
+                None => write!(out, ",")?,
+            }
+        }
+        writeln!(out)?;
+    }
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn latencies(entries: &[(&str, &str, u64)]) -> Latencies {
+        entries
+            .iter()
+            .map(|&(source, target, latency)| ((source.to_string(), target.to_string()), latency))
+            .collect()
+    }
+
+    #[test]
+    fn test_read_summary() {
+        let dir = tempfile::tempdir().unwrap();
+        let filename = dir.path().join("a.csv");
+        std::fs::write(
+            &filename,
+            format!(
+                "{}\n{}\n{}\n{}\n",
+                SUMMARY_HEADER,
+                "a,b,tier2,10,9,100,150,200",
+                "a,c,tier2,10,0,0,0,0",
+                "a,d,proxy,10,10,300,350,400",
+            ),
+        )
+        .unwrap();
+
+        let mut tier2 = Latencies::new();
+        read_summary(&filename, PingPath::Tier2, &mut tier2).unwrap();
+        // Targets which never answered are left out.
+        assert_eq!(tier2, latencies(&[("a", "b", 150)]));
+        let mut proxy = Latencies::new();
+        read_summary(&filename, PingPath::Proxy, &mut proxy).unwrap();
+        assert_eq!(proxy, latencies(&[("a", "d", 350)]));
+
+        std::fs::write(&filename, "source,target\n").unwrap();
+        assert!(read_summary(&filename, PingPath::Tier2, &mut tier2).is_err());
+        std::fs::write(&filename, format!("{}\na,b,tier2,10\n", SUMMARY_HEADER)).unwrap();
+        assert!(read_summary(&filename, PingPath::Tier2, &mut tier2).is_err());
+    }
+
+    #[test]
+    fn test_write_matrix() {
+        // Every node runs an instance, except d which is only a target.
+        let latencies = latencies(&[
+            ("a", "b", 10),
+            ("a", "d", 40),
+            ("b", "a", 11),
+            ("b", "c", 23),
+            ("c", "a", 31),
+            ("c", "b", 32),
+        ]);
+        let mut out = Vec::new();
+        write_matrix(&latencies, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "source,a,b,c,d\n\
+             a,,10,,40\n\
+             b,11,,23,\n\
+             c,31,32,,\n"
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use near_o11y::metrics::{
-    exponential_buckets, try_create_histogram_vec, try_create_int_counter_vec, HistogramVec,
-    IntCounterVec,
+    exponential_buckets, try_create_histogram_vec, try_create_int_counter_vec,
+    try_create_int_gauge_vec, HistogramVec, IntCounterVec, IntGaugeVec,
 };
 use once_cell::sync::Lazy;
 
@@ -8,7 +8,7 @@ pub(crate) static PONG_RECEIVED: Lazy<HistogramVec> = Lazy::new(|| {
     try_create_histogram_vec(
         "ping_pong_received",
         "Round-trip time of ping-pong",
-        &["chain_id", "account_id"],
+        &["chain_id", "account_id", "path"],
         Some(exponential_buckets(0.00001, 1.6, 40).unwrap()),
     )
     .unwrap()
@@ -18,7 +18,7 @@ pub(crate) static PONG_TIMEOUTS: Lazy<IntCounterVec> = Lazy::new(|| {
     try_create_int_counter_vec(
         "ping_pong_timeout",
         "Number of pongs that were not received",
-        &["chain_id", "account_id"],
+        &["chain_id", "account_id", "path"],
     )
     .unwrap()
 });
@@ -27,7 +27,16 @@ pub(crate) static PING_SENT: Lazy<IntCounterVec> = Lazy::new(|| {
     try_create_int_counter_vec(
         "ping_ping_sent",
         "Number of pings sent",
-        &["chain_id", "account_id"],
+        &["chain_id", "account_id", "path"],
+    )
+    .unwrap()
+});
+
+pub(crate) static HOP_COUNT: Lazy<IntGaugeVec> = Lazy::new(|| {
+    try_create_int_gauge_vec(
+        "ping_hop_count",
+        "Number of hops a ping takes to reach the target, as of the last pong received",
+        &["chain_id", "account_id", "path"],
     )
     .unwrap()
 });
@@ -0,0 +1,88 @@
+use near_network::types::{Edge, EdgeState};
+use near_primitives::network::PeerId;
+use std::collections::{HashMap, VecDeque};
+
+/// The part of the network graph we learn about from the SyncRoutingTable messages
+/// of the node we're connected to. Used to tell how many hops a TIER2 routed ping
+/// takes to reach its target.
+#[derive(Default)]
+pub(crate) struct RoutingGraph {
+    edges: HashMap<(PeerId, PeerId), Edge>,
+    // distances from the node given to hop_count(), computed lazily after the edges change.
+    distances: Option<(PeerId, HashMap<PeerId, u32>)>,
+}
+
+impl RoutingGraph {
+    pub(crate) fn update(&mut self, edges: Vec<Edge>) {
+        for edge in edges {
+            match self.edges.get(edge.key()) {
+                Some(old) if old.nonce() >= edge.nonce() => continue,
+                _ => {}
+            }
+            self.edges.insert(edge.key().clone(), edge);
+            self.distances = None;
+        }
+    }
+
+    /// Number of hops on the shortest path from `source` to `target` over the active edges,
+    /// or None if `target` is not reachable.
+    pub(crate) fn hop_count(&mut self, source: &PeerId, target: &PeerId) -> Option<u32> {
+        if self.distances.as_ref().map_or(true, |(s, _)| s != source) {
+            self.distances = Some((source.clone(), self.compute_distances(source)));
+        }
+        self.distances.as_ref().unwrap().1.get(target).copied()
+    }
+
+    fn compute_distances(&self, source: &PeerId) -> HashMap<PeerId, u32> {
+        let mut adjacency: HashMap<&PeerId, Vec<&PeerId>> = HashMap::new();
+        for ((peer0, peer1), edge) in self.edges.iter() {
+            if edge.edge_type() == EdgeState::Active {
+                adjacency.entry(peer0).or_default().push(peer1);
+                adjacency.entry(peer1).or_default().push(peer0);
+            }
+        }
+        let mut distances = HashMap::from([(source.clone(), 0)]);
+        let mut queue = VecDeque::from([source]);
+        while let Some(peer_id) = queue.pop_front() {
+            let distance = distances[peer_id];
+            for &next in adjacency.get(peer_id).into_iter().flatten() {
+                if !distances.contains_key(next) {
+                    distances.insert(next.clone(), distance + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+        distances
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hop_count() {
+        let peers: Vec<_> = (0..4).map(|_| PeerId::random()).collect();
+        let edge = |a: usize, b: usize, nonce| {
+            Edge::make_fake_edge(peers[a].clone(), peers[b].clone(), nonce)
+        };
+        let mut graph = RoutingGraph::default();
+        // A chain 0 - 1 - 2 - 3, and an edge 0 - 3 which was removed.
+        graph.update(vec![edge(0, 1, 1), edge(1, 2, 1), edge(2, 3, 1), edge(0, 3, 2)]);
+        assert_eq!(graph.hop_count(&peers[0], &peers[0]), Some(0));
+        assert_eq!(graph.hop_count(&peers[0], &peers[3]), Some(3));
+        assert_eq!(graph.hop_count(&peers[3], &peers[1]), Some(2));
+
+        // Updates with a lower or equal nonce than the known edge are ignored.
+        graph.update(vec![edge(0, 3, 1), edge(1, 2, 1)]);
+        assert_eq!(graph.hop_count(&peers[0], &peers[3]), Some(3));
+
+        // Newer edges replace the known ones.
+        graph.update(vec![edge(0, 3, 3)]);
+        assert_eq!(graph.hop_count(&peers[0], &peers[3]), Some(1));
+        assert_eq!(graph.hop_count(&peers[0], &peers[2]), Some(2));
+        graph.update(vec![edge(1, 2, 2), edge(2, 3, 4)]);
+        assert_eq!(graph.hop_count(&peers[0], &peers[2]), None);
+        assert_eq!(graph.hop_count(&peers[0], &peers[3]), Some(1));
+    }
+}
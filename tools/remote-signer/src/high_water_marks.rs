@@ -0,0 +1,149 @@
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::BlockHeight;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Kinds of messages that must never be signed twice for the same height with different content.
+#[derive(
+    serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageKind {
+    Block,
+    Approval,
+}
+
+impl std::fmt::Display for MessageKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Block => "block",
+            Self::Approval => "approval",
+        })
+    }
+}
+
+/// The highest height signed for some kind of message, and the hash of what was signed.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq, Eq)]
+struct HighWaterMark {
+    height: BlockHeight,
+    hash: CryptoHash,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ConflictError {
+    #[error("already signed a {kind} at height {signed_height}, above {height}")]
+    BelowHighWaterMark { kind: MessageKind, height: BlockHeight, signed_height: BlockHeight },
+    #[error("already signed a different {kind} at height {height}")]
+    Conflicting { kind: MessageKind, height: BlockHeight },
+    #[error("failed to persist the high-water mark: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// High-water marks of every kind of message, persisted in a JSON file.
+pub struct HighWaterMarks {
+    path: PathBuf,
+    marks: BTreeMap<MessageKind, HighWaterMark>,
+}
+
+impl HighWaterMarks {
+    /// Loads the marks from `path`, or starts with none if the file doesn't exist yet.
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let marks = match std::fs::read(path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Self { path: path.to_path_buf(), marks })
+    }
+
+    /// Checks that a message with the given hash can be signed at this height: the height is
+    /// above the high-water mark of its kind, or equal to it with the same hash (the same message
+    /// being signed again). The new mark is persisted before returning, so that it holds even if
+    /// the signer crashes right after signing.
+    pub fn check_and_update(
+        &mut self,
+        kind: MessageKind,
+        height: BlockHeight,
+        hash: CryptoHash,
+    ) -> Result<(), ConflictError> {
+        if let Some(mark) = self.marks.get(&kind) {
+            if height < mark.height {
+                return Err(ConflictError::BelowHighWaterMark {
+                    kind,
+                    height,
+                    signed_height: mark.height,
+                });
+            }
+            if height == mark.height {
+                if hash != mark.hash {
+                    return Err(ConflictError::Conflicting { kind, height });
+                }
+                return Ok(());
+            }
+        }
+        let old = self.marks.insert(kind, HighWaterMark { height, hash });
+        if let Err(err) = self.persist() {
+            // Nothing gets signed, so the old mark is still the right one.
+            match old {
+                Some(old) => self.marks.insert(kind, old),
+                None => self.marks.remove(&kind),
+            };
+            return Err(err.into());
+        }
+        Ok(())
+    }
+
+    /// Writes the marks to a temporary file which then replaces the old one, so that a crash
+    /// never leaves a partially written file behind.
+    fn persist(&self) -> io::Result<()> {
+        let mut tmp_path = self.path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        let mut out = BufWriter::new(File::create(&tmp_path)?);
+        serde_json::to_writer_pretty(&mut out, &self.marks)?;
+        out.flush()?;
+        out.into_inner()?.sync_all()?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            File::open(dir)?.sync_all()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_high_water_marks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("marks.json");
+        let mut marks = HighWaterMarks::open(&path).unwrap();
+        let hash = CryptoHash::hash_bytes(b"block");
+        let other_hash = CryptoHash::hash_bytes(b"other block");
+
+        marks.check_and_update(MessageKind::Block, 10, hash).unwrap();
+        // Signing the same block again is fine, a different one at the same height or a lower
+        // one is not.
+        marks.check_and_update(MessageKind::Block, 10, hash).unwrap();
+        assert!(matches!(
+            marks.check_and_update(MessageKind::Block, 10, other_hash),
+            Err(ConflictError::Conflicting { .. })
+        ));
+        assert!(matches!(
+            marks.check_and_update(MessageKind::Block, 9, hash),
+            Err(ConflictError::BelowHighWaterMark { signed_height: 10, .. })
+        ));
+        // Marks are kept per kind.
+        marks.check_and_update(MessageKind::Approval, 10, other_hash).unwrap();
+        marks.check_and_update(MessageKind::Block, 11, other_hash).unwrap();
+
+        // And survive restarts.
+        let mut marks = HighWaterMarks::open(&path).unwrap();
+        assert!(marks.check_and_update(MessageKind::Block, 11, hash).is_err());
+        assert!(marks.check_and_update(MessageKind::Approval, 10, hash).is_err());
+        marks.check_and_update(MessageKind::Approval, 10, other_hash).unwrap();
+    }
+}
@@ -0,0 +1,220 @@
+//! Signer process holding the validator key of a node which uses a `RemoteValidatorSigner`.
+//!
+//! Blocks and approvals are only signed if they don't conflict with the ones signed before,
+//! see `HighWaterMarks`, so that two nodes sharing the signer can't equivocate.
+
+use borsh::BorshDeserialize;
+use near_primitives::block::{Approval, BlockHeader};
+use near_primitives::block_header::BlockHeaderInnerLite;
+use near_primitives::hash::CryptoHash;
+use near_primitives::remote_validator_signer::{
+    read_message, write_message, SignerRequest, SignerResponse,
+};
+use near_primitives::telemetry::TelemetryInfo;
+use near_primitives::validator_signer::{InMemoryValidatorSigner, ValidatorSigner};
+use std::io;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::sync::{Arc, Mutex};
+
+mod high_water_marks;
+
+pub use high_water_marks::{ConflictError, HighWaterMarks, MessageKind};
+
+#[derive(thiserror::Error, Debug)]
+enum SignError {
+    #[error(transparent)]
+    Conflict(#[from] ConflictError),
+    #[error("invalid request: {0}")]
+    Invalid(String),
+}
+
+pub struct Signer {
+    validator_signer: InMemoryValidatorSigner,
+    marks: HighWaterMarks,
+}
+
+impl Signer {
+    pub fn new(validator_signer: InMemoryValidatorSigner, marks: HighWaterMarks) -> Self {
+        Self { validator_signer, marks }
+    }
+
+    pub fn handle(&mut self, request: SignerRequest) -> SignerResponse {
+        let kind = <&'static str>::from(&request);
+        match self.try_handle(request) {
+            Ok(response) => response,
+            Err(err) => {
+                tracing::warn!(target: "remote-signer", kind, %err, "refused to sign");
+                SignerResponse::Refused(err.to_string())
+            }
+        }
+    }
+
+    fn try_handle(&mut self, request: SignerRequest) -> Result<SignerResponse, SignError> {
+        let signer = &self.validator_signer;
+        let signature = match request {
+            SignerRequest::ValidatorInfo => {
+                return Ok(SignerResponse::ValidatorInfo {
+                    account_id: signer.validator_id().clone(),
+                    public_key: signer.public_key(),
+                })
+            }
+            SignerRequest::Telemetry { content } => {
+                let info: TelemetryInfo = serde_json::from_str(&content)
+                    .map_err(|err| SignError::Invalid(err.to_string()))?;
+                let value = signer.sign_telemetry(&info);
+                // The signature is over the content as the signer serializes it, which
+                // must be what the node sends along with the signature.
+                let mut unsigned = value.clone();
+                unsigned.as_object_mut().unwrap().remove("signature");
+                if serde_json::to_string(&unsigned).unwrap() != content {
+                    return Err(SignError::Invalid("telemetry doesn't round-trip".to_string()));
+                }
+                value["signature"].as_str().unwrap().parse().unwrap()
+            }
+            SignerRequest::BlockHeader { prev_hash, inner_lite, inner_rest } => {
+                let height = BlockHeaderInnerLite::try_from_slice(&inner_lite)
+                    .map_err(|err| SignError::Invalid(err.to_string()))?
+                    .height;
+                let hash = BlockHeader::compute_hash(prev_hash, &inner_lite, &inner_rest);
+                self.marks.check_and_update(MessageKind::Block, height, hash)?;
+                signer.sign_block_header_parts(prev_hash, &inner_lite, &inner_rest).1
+            }
+            SignerRequest::Approval { inner, target_height } => {
+                let hash =
+                    CryptoHash::hash_bytes(&Approval::get_data_for_sig(&inner, target_height));
+                self.marks.check_and_update(MessageKind::Approval, target_height, hash)?;
+                signer.sign_approval(&inner, target_height)
+            }
+            SignerRequest::ChunkHash(chunk_hash) => signer.sign_chunk_hash(&chunk_hash),
+            SignerRequest::Challenge(challenge_body) => signer.sign_challenge(&challenge_body).1,
+            SignerRequest::AccountAnnounce { account_id, peer_id, epoch_id } => {
+                signer.sign_account_announce(&account_id, &peer_id, &epoch_id)
+            }
+            SignerRequest::AccountKeyPayload(proto_bytes) => {
+                signer.sign_account_key_payload(&proto_bytes)
+            }
+            SignerRequest::Vrf(data) => {
+                let (value, proof) = signer.compute_vrf_with_proof(&data);
+                return Ok(SignerResponse::Vrf { value, proof });
+            }
+        };
+        Ok(SignerResponse::Signature(signature))
+    }
+}
+
+fn handle_connection(mut stream: UnixStream, signer: &Mutex<Signer>) -> io::Result<()> {
+    loop {
+        let request = match read_message(&mut stream) {
+            Ok(request) => request,
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(err) => return Err(err),
+        };
+        let response = signer.lock().unwrap().handle(request);
+        write_message(&mut stream, &response)?;
+    }
+}
+
+/// Answers the requests of every node connecting to the listener, each on its own thread.
+/// Requests are handled one at a time, so that the high-water marks are checked and updated
+/// atomically.
+pub fn serve(listener: UnixListener, signer: Arc<Mutex<Signer>>) -> io::Result<()> {
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let signer = signer.clone();
+        std::thread::spawn(move || {
+            if let Err(err) = handle_connection(stream, &signer) {
+                tracing::warn!(target: "remote-signer", %err, "connection failed");
+            }
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_crypto::{KeyType, Signature};
+    use near_primitives::block::ApprovalInner;
+    use near_primitives::remote_validator_signer::RemoteValidatorSigner;
+    use near_primitives::types::EpochId;
+    use std::path::Path;
+
+    fn start_signer(dir: &Path, name: &str) -> RemoteValidatorSigner {
+        let validator_signer =
+            InMemoryValidatorSigner::from_seed("test".parse().unwrap(), KeyType::ED25519, "test");
+        let marks = HighWaterMarks::open(&dir.join("marks.json")).unwrap();
+        let signer = Arc::new(Mutex::new(Signer::new(validator_signer, marks)));
+        let socket = dir.join(name);
+        let listener = UnixListener::bind(&socket).unwrap();
+        std::thread::spawn(move || serve(listener, signer));
+        RemoteValidatorSigner::connect(&socket).unwrap()
+    }
+
+    fn inner_lite(height: u64, timestamp: u64) -> Vec<u8> {
+        borsh::BorshSerialize::try_to_vec(&BlockHeaderInnerLite {
+            height,
+            epoch_id: EpochId::default(),
+            next_epoch_id: EpochId::default(),
+            prev_state_root: CryptoHash::default(),
+            outcome_root: CryptoHash::default(),
+            timestamp,
+            next_bp_hash: CryptoHash::default(),
+            block_merkle_root: CryptoHash::default(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_sign_approvals() {
+        let dir = tempfile::tempdir().unwrap();
+        let remote = start_signer(dir.path(), "signer.sock");
+        let local =
+            InMemoryValidatorSigner::from_seed("test".parse().unwrap(), KeyType::ED25519, "test");
+        assert_eq!(remote.validator_id(), local.validator_id());
+        assert_eq!(ValidatorSigner::public_key(&remote), ValidatorSigner::public_key(&local));
+        let empty = Signature::empty(KeyType::ED25519);
+
+        let endorsement = ApprovalInner::Endorsement(CryptoHash::hash_bytes(b"block"));
+        let skip = ApprovalInner::Skip(9);
+        let signature = remote.sign_approval(&endorsement, 10);
+        assert_eq!(signature, local.sign_approval(&endorsement, 10));
+        // The same approval can be signed again, a different or a lower one can't.
+        assert_eq!(remote.sign_approval(&endorsement, 10), signature);
+        assert_eq!(remote.sign_approval(&skip, 10), empty);
+        assert_eq!(remote.sign_approval(&endorsement, 9), empty);
+        assert_eq!(remote.sign_approval(&skip, 11), local.sign_approval(&skip, 11));
+
+        // A restarted signer still refuses the conflicting approval.
+        drop(remote);
+        let remote = start_signer(dir.path(), "restarted.sock");
+        assert_eq!(remote.sign_approval(&endorsement, 11), empty);
+    }
+
+    #[test]
+    fn test_sign_block_headers() {
+        let dir = tempfile::tempdir().unwrap();
+        let remote = start_signer(dir.path(), "signer.sock");
+        let local =
+            InMemoryValidatorSigner::from_seed("test".parse().unwrap(), KeyType::ED25519, "test");
+        let empty = Signature::empty(KeyType::ED25519);
+        let prev_hash = CryptoHash::hash_bytes(b"prev");
+
+        let block = inner_lite(5, 1);
+        let signed = remote.sign_block_header_parts(prev_hash, &block, &[]);
+        assert_eq!(signed, local.sign_block_header_parts(prev_hash, &block, &[]));
+        let conflicting = remote.sign_block_header_parts(prev_hash, &inner_lite(5, 2), &[]);
+        assert_eq!(conflicting.1, empty);
+        let garbage = remote.sign_block_header_parts(prev_hash, &[1, 2, 3], &[]);
+        assert_eq!(garbage.1, empty);
+        // Other messages aren't subject to the high-water marks.
+        let chunk_hash = near_primitives::sharding::ChunkHash(prev_hash);
+        assert_eq!(remote.sign_chunk_hash(&chunk_hash), local.sign_chunk_hash(&chunk_hash));
+        let (value, proof) = remote.compute_vrf_with_proof(b"data");
+        assert_eq!(value.0, local.compute_vrf_with_proof(b"data").0 .0);
+        let vrf_key = near_crypto::key_conversion::convert_public_key(
+            ValidatorSigner::public_key(&local).unwrap_as_ed25519(),
+        )
+        .unwrap();
+        assert!(vrf_key.is_vrf_valid(&&b"data"[..], &value, &proof));
+    }
+}
@@ -0,0 +1,57 @@
+//! Holds a validator key and signs on behalf of the nodes connecting to its Unix socket, see
+//! README.md.
+
+use anyhow::Context;
+use clap::Parser;
+use near_primitives::validator_signer::{InMemoryValidatorSigner, ValidatorSigner};
+use remote_signer::{serve, HighWaterMarks, Signer};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::UnixListener;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+#[derive(Parser)]
+struct Cli {
+    /// The validator key file, in the format of `validator_key.json`.
+    #[clap(long)]
+    validator_key: PathBuf,
+    /// Unix socket to listen on. Point the `remote_validator_signer_socket` of the node's
+    /// `config.json` to it.
+    #[clap(long)]
+    socket: PathBuf,
+    /// File keeping the highest block and approval signed. Must survive restarts and must not
+    /// be shared with another signer for the same key.
+    #[clap(long)]
+    high_water_marks_file: PathBuf,
+}
+
+fn main() -> anyhow::Result<()> {
+    let env_filter = near_o11y::EnvFilterBuilder::from_env().finish()?;
+    let _subscriber = near_o11y::default_subscriber(env_filter, &Default::default()).global();
+    let cli = Cli::parse();
+
+    let validator_signer = InMemoryValidatorSigner::from_file(&cli.validator_key)
+        .with_context(|| format!("Couldn't read the validator key {:?}", cli.validator_key))?;
+    let marks = HighWaterMarks::open(&cli.high_water_marks_file).with_context(|| {
+        format!("Couldn't read the high-water marks {:?}", cli.high_water_marks_file)
+    })?;
+    tracing::info!(
+        target: "remote-signer",
+        account_id = %validator_signer.validator_id(),
+        socket = ?cli.socket,
+        "listening"
+    );
+    let signer = Arc::new(Mutex::new(Signer::new(validator_signer, marks)));
+
+    // A socket left behind by a previous run would make bind() fail.
+    match std::fs::remove_file(&cli.socket) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err.into()),
+        _ => {}
+    }
+    let listener = UnixListener::bind(&cli.socket)
+        .with_context(|| format!("Couldn't bind to {:?}", cli.socket))?;
+    // Anyone who can connect can have blocks signed with the validator key.
+    std::fs::set_permissions(&cli.socket, std::fs::Permissions::from_mode(0o600))?;
+    serve(listener, signer)?;
+    Ok(())
+}
@@ -1,6 +1,8 @@
 use crate::commands::*;
 use crate::contract_accounts::ContractAccountFilter;
+use crate::epoch_info::StakeOverride;
 use crate::rocksdb_stats::get_rocksdb_stats;
+use crate::shard_layout_planner::PlanShardLayoutCmd;
 use crate::trie_iteration_benchmark::TrieIterationBenchmarkCmd;
 
 use near_chain_configs::{GenesisChangeConfig, GenesisValidationMode};
@@ -9,6 +11,7 @@ use near_primitives::account::id::AccountId;
 use near_primitives::hash::CryptoHash;
 use near_primitives::sharding::ChunkHash;
 
+use near_primitives::types::validator_stake::ValidatorStake;
 use near_primitives::types::{BlockHeight, ShardId};
 use near_store::{Mode, NodeStorage, Store, Temperature};
 use nearcore::{load_config, NearConfig};
@@ -70,6 +73,10 @@ pub enum StateViewerSubCommand {
     /// Looks up a certain partial chunk.
     #[clap(alias = "partial_chunks")]
     PartialChunks(PartialChunksCmd),
+    /// Proposes boundaries which split a shard into shards of balanced state size, receipt
+    /// traffic or gas, and writes the resulting shard layout as JSON.
+    #[clap(alias = "plan_shard_layout")]
+    PlanShardLayout(PlanShardLayoutCmd),
     /// Looks up a certain receipt.
     Receipts(ReceiptsCmd),
     /// Replay headers from chain.
@@ -79,6 +86,10 @@ pub enum StateViewerSubCommand {
     RocksDBStats(RocksDBStatsCmd),
     /// Reads all rows of a DB column and deserializes keys and values and prints them.
     ScanDbColumn(ScanDbColumnCmd),
+    /// Shows validators, seat price, kickouts and rewards that would result from the end of the
+    /// current epoch, after overriding proposals, stakes and uptimes of validators.
+    #[clap(alias = "simulate_epoch")]
+    SimulateEpoch(SimulateEpochCmd),
     /// Iterates over a trie and prints the StateRecords.
     State,
     /// Dumps or applies StateChanges.
@@ -140,10 +151,12 @@ impl StateViewerSubCommand {
             StateViewerSubCommand::DumpTx(cmd) => cmd.run(home_dir, near_config, store),
             StateViewerSubCommand::EpochInfo(cmd) => cmd.run(near_config, store),
             StateViewerSubCommand::PartialChunks(cmd) => cmd.run(near_config, store),
+            StateViewerSubCommand::PlanShardLayout(cmd) => cmd.run(near_config, store),
             StateViewerSubCommand::Receipts(cmd) => cmd.run(near_config, store),
             StateViewerSubCommand::Replay(cmd) => cmd.run(near_config, store),
             StateViewerSubCommand::RocksDBStats(cmd) => cmd.run(store_opener.path()),
             StateViewerSubCommand::ScanDbColumn(cmd) => cmd.run(store),
+            StateViewerSubCommand::SimulateEpoch(cmd) => cmd.run(near_config, store),
             StateViewerSubCommand::State => state(home_dir, near_config, store),
             StateViewerSubCommand::StateChanges(cmd) => cmd.run(home_dir, near_config, store),
             StateViewerSubCommand::StateParts(cmd) => cmd.run(home_dir, near_config, store),
@@ -565,6 +578,63 @@ impl ScanDbColumnCmd {
     }
 }
 
+#[derive(clap::Parser)]
+pub struct SimulateEpochCmd {
+    /// Simulate the end of the epoch at this block. Defaults to the head.
+    #[clap(long)]
+    block_hash: Option<CryptoHash>,
+    /// Proposal which replaces the latest proposal of the account in the epoch, in the format
+    /// `<account_id>:<stake>:<public_key>`. Stake is in yoctoNEAR, 0 means unstaking.
+    #[clap(long, value_parser = parse_proposal)]
+    proposal: Vec<ValidatorStake>,
+    /// New stake of an existing validator or proposer, in the format `<account_id>:<stake>`.
+    #[clap(long, value_parser = parse_stake_override)]
+    stake: Vec<StakeOverride>,
+    /// Percentage of the expected blocks and chunks produced by a validator, in the format
+    /// `<account_id>:<percent>`.
+    #[clap(long, value_parser = parse_uptime)]
+    uptime: Vec<(AccountId, u8)>,
+}
+
+fn parse_proposal(s: &str) -> Result<ValidatorStake, String> {
+    let [account_id, stake, public_key]: [&str; 3] = s
+        .splitn(3, ':')
+        .collect::<Vec<_>>()
+        .try_into()
+        .map_err(|_| format!("expected <account_id>:<stake>:<public_key>, got {s}"))?;
+    Ok(ValidatorStake::new(
+        account_id.parse().map_err(|err| format!("invalid account id: {err}"))?,
+        public_key.parse().map_err(|err| format!("invalid public key: {err}"))?,
+        stake.parse().map_err(|err| format!("invalid stake: {err}"))?,
+    ))
+}
+
+fn parse_stake_override(s: &str) -> Result<StakeOverride, String> {
+    let (account_id, stake) =
+        s.split_once(':').ok_or_else(|| format!("expected <account_id>:<stake>, got {s}"))?;
+    Ok(StakeOverride {
+        account_id: account_id.parse().map_err(|err| format!("invalid account id: {err}"))?,
+        stake: stake.parse().map_err(|err| format!("invalid stake: {err}"))?,
+    })
+}
+
+fn parse_uptime(s: &str) -> Result<(AccountId, u8), String> {
+    let (account_id, percent) =
+        s.split_once(':').ok_or_else(|| format!("expected <account_id>:<percent>, got {s}"))?;
+    let percent: u8 = percent.parse().map_err(|err| format!("invalid percent: {err}"))?;
+    if percent > 100 {
+        return Err(format!("uptime can't exceed 100%, got {percent}"));
+    }
+    Ok((account_id.parse().map_err(|err| format!("invalid account id: {err}"))?, percent))
+}
+
+impl SimulateEpochCmd {
+    pub fn run(self, near_config: NearConfig, store: Store) {
+        simulate_epoch(self.block_hash, self.proposal, self.stake, self.uptime, near_config, store)
+            .unwrap();
+    }
+}
+
 #[derive(clap::Parser)]
 pub struct StateChangesCmd {
     #[clap(subcommand)]
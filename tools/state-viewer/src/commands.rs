@@ -24,6 +24,7 @@ use near_primitives::shard_layout::ShardUId;
 use near_primitives::sharding::ChunkHash;
 use near_primitives::state_record::StateRecord;
 use near_primitives::trie_key::TrieKey;
+use near_primitives::types::validator_stake::ValidatorStake;
 use near_primitives::types::{chunk_extra::ChunkExtra, BlockHeight, ShardId, StateRoot};
 use near_primitives_core::types::Gas;
 use near_store::test_utils::create_test_store;
@@ -858,6 +859,31 @@ pub(crate) fn print_epoch_info(
     );
 }
 
+pub(crate) fn simulate_epoch(
+    block_hash: Option<CryptoHash>,
+    proposals: Vec<ValidatorStake>,
+    stake_overrides: Vec<epoch_info::StakeOverride>,
+    uptime_overrides: Vec<(AccountId, u8)>,
+    near_config: NearConfig,
+    store: Store,
+) -> anyhow::Result<()> {
+    let genesis_height = near_config.genesis.config.genesis_height;
+    let mut chain_store =
+        ChainStore::new(store.clone(), genesis_height, near_config.client_config.save_trie_changes);
+    let epoch_manager = EpochManager::new_from_genesis_config(store, &near_config.genesis.config)
+        .expect("Failed to start Epoch Manager")
+        .into_handle();
+
+    epoch_info::simulate_epoch(
+        block_hash,
+        proposals,
+        stake_overrides,
+        uptime_overrides,
+        &mut chain_store,
+        &epoch_manager,
+    )
+}
+
 fn get_trie(store: Store, hash: CryptoHash, shard_id: u32, shard_version: u32) -> Trie {
     let shard_uid = ShardUId { version: shard_version, shard_id };
     let trie_config: TrieConfig = Default::default();
@@ -2,13 +2,17 @@ use borsh::BorshDeserialize;
 use core::ops::Range;
 use itertools::Itertools;
 use near_chain::{ChainStore, ChainStoreAccess};
-use near_epoch_manager::{EpochManagerAdapter, EpochManagerHandle};
+use near_epoch_manager::{EpochManagerAdapter, EpochManagerHandle, EpochSimulationOverrides};
 use near_primitives::account::id::AccountId;
 use near_primitives::epoch_manager::epoch_info::EpochInfo;
 use near_primitives::epoch_manager::AGGREGATOR_KEY;
 use near_primitives::hash::CryptoHash;
-use near_primitives::types::{BlockHeight, EpochHeight, EpochId, ProtocolVersion, ShardId};
+use near_primitives::types::validator_stake::ValidatorStake;
+use near_primitives::types::{
+    Balance, BlockHeight, EpochHeight, EpochId, ProtocolVersion, ShardId, ValidatorId,
+};
 use near_store::{DBCol, Store};
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 use std::sync::Arc;
 
@@ -286,3 +290,107 @@ fn display_validator_info(
     }
     Ok(())
 }
+
+/// A change of stake of an existing validator or proposer.  Turned into a proposal which
+/// keeps the public key the account already uses.
+#[derive(Clone, Debug)]
+pub(crate) struct StakeOverride {
+    pub account_id: AccountId,
+    pub stake: Balance,
+}
+
+pub(crate) fn simulate_epoch(
+    block_hash: Option<CryptoHash>,
+    mut proposals: Vec<ValidatorStake>,
+    stake_overrides: Vec<StakeOverride>,
+    uptime_overrides: Vec<(AccountId, u8)>,
+    chain_store: &mut ChainStore,
+    epoch_manager: &EpochManagerHandle,
+) -> anyhow::Result<()> {
+    let block_hash = match block_hash {
+        Some(block_hash) => block_hash,
+        None => chain_store.head()?.last_block_hash,
+    };
+    let header = chain_store.get_block_header(&block_hash)?;
+    let epoch_manager = epoch_manager.read();
+    let epoch_id = epoch_manager.get_epoch_id(&block_hash)?;
+    let epoch_info = epoch_manager.get_epoch_info(&epoch_id)?;
+    let next_epoch_info =
+        epoch_manager.get_epoch_info(&epoch_manager.get_next_epoch_id(&block_hash)?)?;
+    let recorded_proposals =
+        epoch_manager.get_epoch_info_aggregator_upto_last(&block_hash)?.all_proposals;
+
+    for StakeOverride { account_id, stake } in stake_overrides {
+        let public_key = recorded_proposals
+            .get(&account_id)
+            .map(|proposal| proposal.public_key().clone())
+            .or_else(|| {
+                [&next_epoch_info, &epoch_info].into_iter().find_map(|info| {
+                    let validator_id = info.get_validator_id(&account_id)?;
+                    Some(info.get_validator(*validator_id).public_key().clone())
+                })
+            })
+            .ok_or_else(|| {
+                anyhow::anyhow!("{account_id} is neither a validator nor a proposer, use --proposal to specify its public key")
+            })?;
+        proposals.push(ValidatorStake::new(account_id, public_key, stake));
+    }
+    let overrides = EpochSimulationOverrides {
+        proposals,
+        uptime_percent: uptime_overrides.into_iter().collect(),
+    };
+    let simulated_epoch_info =
+        epoch_manager.simulate_epoch_end(&block_hash, header.random_value().0, &overrides)?;
+
+    println!(
+        "Simulated end of epoch {} at block #{} {}",
+        epoch_info.epoch_height(),
+        header.height(),
+        block_hash
+    );
+    if !epoch_manager.is_next_block_epoch_start(&block_hash)? {
+        println!("The epoch doesn't end at this block, kickouts and rewards only account for the blocks up to it");
+    }
+    println!(
+        "Epoch {} protocol version: {}",
+        simulated_epoch_info.epoch_height(),
+        simulated_epoch_info.protocol_version()
+    );
+    println!("Seat prices:");
+    for info in [&epoch_info, &next_epoch_info, &simulated_epoch_info] {
+        println!("  epoch {}: {}", info.epoch_height(), info.seat_price());
+    }
+
+    let mut shards_of_validator: HashMap<ValidatorId, Vec<ShardId>> = HashMap::new();
+    for (shard_id, validator_ids) in
+        simulated_epoch_info.chunk_producers_settlement().iter().enumerate()
+    {
+        for validator_id in validator_ids {
+            shards_of_validator.entry(*validator_id).or_default().push(shard_id as ShardId);
+        }
+    }
+    let block_producers: HashSet<ValidatorId> =
+        simulated_epoch_info.block_producers_settlement().iter().copied().collect();
+    println!("Validators:");
+    for (validator_id, validator) in simulated_epoch_info.validators_iter().enumerate() {
+        let validator_id = validator_id as ValidatorId;
+        println!(
+            "  {}: stake {}, block producer: {}, chunk producer for shards {:?}",
+            validator.account_id(),
+            validator.stake(),
+            block_producers.contains(&validator_id),
+            shards_of_validator.get(&validator_id).map(Vec::as_slice).unwrap_or_default(),
+        );
+    }
+
+    println!("Kickouts:");
+    display_kickouts(&simulated_epoch_info);
+
+    println!("Rewards (minted amount {}):", simulated_epoch_info.minted_amount());
+    for (account_id, reward) in
+        simulated_epoch_info.validator_reward().iter().sorted_by_key(|&(account_id, _)| account_id)
+    {
+        println!("  {}: {}", account_id, reward);
+    }
+    Ok(())
+}
@@ -8,6 +8,7 @@ mod contract_accounts;
 mod epoch_info;
 mod rocksdb_stats;
 mod scan_db;
+mod shard_layout_planner;
 mod state_changes;
 mod state_dump;
 mod state_parts;
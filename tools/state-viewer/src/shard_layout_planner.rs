@@ -0,0 +1,412 @@
+use anyhow::Context;
+use near_chain::{ChainStore, ChainStoreAccess};
+use near_epoch_manager::{EpochManager, EpochManagerAdapter};
+use near_primitives::shard_layout::{ShardLayout, ShardUId};
+use near_primitives::trie_key::trie_key_parsers::parse_account_id_from_raw_key;
+use near_primitives::types::{AccountId, BlockHeight, Gas, ShardId};
+use near_store::flat::store_helper;
+use near_store::{Store, Trie, TrieDBStorage};
+use nearcore::NearConfig;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum PlannerMetric {
+    /// Bytes of keys and values in the state of the accounts.
+    State,
+    /// Number of transactions and receipts executed by the accounts.
+    Receipts,
+    /// Gas burnt by the accounts.
+    Gas,
+    /// Average of the shares of state, receipts and gas.
+    Combined,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum StateSource {
+    /// Read the state from flat storage, which is faster but must be fully created.
+    Flat,
+    /// Read the state by iterating the trie at the state root of the block.
+    Trie,
+}
+
+#[derive(clap::Parser)]
+pub struct PlanShardLayoutCmd {
+    /// Shard of the current shard layout to split.
+    #[clap(long)]
+    shard_id: ShardId,
+    /// Number of shards the shard will be split into.
+    #[clap(long, default_value = "2")]
+    num_shards: usize,
+    /// Metric to balance between the new shards.
+    #[clap(long, value_enum, default_value = "combined")]
+    metric: PlannerMetric,
+    /// Where to read the state of the shard from.
+    #[clap(long, value_enum, default_value = "flat")]
+    source: StateSource,
+    /// First height of the range in which receipts and gas are measured. Defaults to the
+    /// start of the current epoch.
+    #[clap(long)]
+    start_height: Option<BlockHeight>,
+    /// Last height of the range in which receipts and gas are measured. Defaults to the head.
+    #[clap(long)]
+    end_height: Option<BlockHeight>,
+    /// Number of leading characters of account ids by which the accounts are grouped.
+    /// Boundaries are only placed between groups, so a shorter prefix makes the planner
+    /// faster but less precise.
+    #[clap(long, default_value = "8")]
+    prefix_len: usize,
+    /// File to which the JSON of the proposed shard layout is written.
+    #[clap(long)]
+    output: Option<PathBuf>,
+}
+
+impl PlanShardLayoutCmd {
+    pub fn run(self, near_config: NearConfig, store: Store) {
+        self.plan(near_config, store).unwrap();
+    }
+
+    fn plan(self, near_config: NearConfig, store: Store) -> anyhow::Result<()> {
+        anyhow::ensure!(self.num_shards >= 2, "--num-shards must be at least 2");
+        anyhow::ensure!(self.prefix_len > 0, "--prefix-len must be positive");
+        let chain_store = ChainStore::new(
+            store.clone(),
+            near_config.genesis.config.genesis_height,
+            near_config.client_config.save_trie_changes,
+        );
+        let epoch_manager =
+            EpochManager::new_arc_handle(store.clone(), &near_config.genesis.config);
+
+        let head = chain_store.head()?;
+        let end_height = self.end_height.unwrap_or(head.height);
+        let end_hash = chain_store.get_block_hash_by_height(end_height)?;
+        let end_header = chain_store.get_block_header(&end_hash)?;
+        let shard_layout = epoch_manager.get_shard_layout(end_header.epoch_id())?;
+        anyhow::ensure!(
+            self.shard_id < shard_layout.num_shards(),
+            "shard {} doesn't exist in a layout with {} shards",
+            self.shard_id,
+            shard_layout.num_shards()
+        );
+        let shard_uid = ShardUId::from_shard_id_and_layout(self.shard_id, &shard_layout);
+        let start_height = match self.start_height {
+            Some(height) => height,
+            None => epoch_manager.get_epoch_start_height(&end_hash)?,
+        };
+        anyhow::ensure!(start_height <= end_height, "--start-height is above --end-height");
+
+        let mut buckets = BTreeMap::new();
+        let mut unattributed = LoadStats::default();
+        let mut add_state = |key: &[u8], value_len: u64| -> anyhow::Result<()> {
+            let size = key.len() as u64 + value_len;
+            match parse_account_id_from_raw_key(key)? {
+                Some(account_id) => self.bucket(&mut buckets, account_id).state_bytes += size,
+                None => unattributed.state_bytes += size,
+            }
+            Ok(())
+        };
+        match self.source {
+            StateSource::Flat => {
+                for entry in store_helper::iter_flat_state_entries(shard_uid, &store, None, None) {
+                    let (key, value) = entry
+                        .map_err(|err| anyhow::anyhow!("failed to read flat state: {err:?}"))?;
+                    add_state(&key, value.to_value_ref().length as u64)?;
+                }
+            }
+            StateSource::Trie => {
+                let chunk_extra = chain_store.get_chunk_extra(&end_hash, &shard_uid)?;
+                let storage = TrieDBStorage::new(store.clone(), shard_uid);
+                let trie = Trie::new(Rc::new(storage), *chunk_extra.state_root(), None);
+                for entry in trie.iter()? {
+                    let (key, value) = entry?;
+                    add_state(&key, value.len() as u64)?;
+                }
+            }
+        }
+
+        for height in start_height..=end_height {
+            // Heights without a block on the canonical chain are skipped.
+            let Ok(block_hash) = chain_store.get_block_hash_by_height(height) else { continue };
+            let epoch_id = chain_store.get_block_header(&block_hash)?.epoch_id().clone();
+            anyhow::ensure!(
+                epoch_manager.get_shard_layout(&epoch_id)? == shard_layout,
+                "shard layout changed at height {height}, use a range within a single layout"
+            );
+            for outcome_id in
+                chain_store.get_outcomes_by_block_hash_and_shard_id(&block_hash, self.shard_id)?
+            {
+                let Some(outcome) =
+                    chain_store.get_outcome_by_id_and_block_hash(&outcome_id, &block_hash)?
+                else {
+                    continue;
+                };
+                let stats = self.bucket(&mut buckets, outcome.outcome.executor_id);
+                stats.num_receipts += 1;
+                stats.gas_burnt += outcome.outcome.gas_burnt;
+            }
+        }
+
+        let buckets: Vec<_> = buckets.into_values().collect();
+        anyhow::ensure!(
+            buckets.len() >= self.num_shards,
+            "shard {} has only {} account groups, use a longer --prefix-len",
+            self.shard_id,
+            buckets.len()
+        );
+        let cuts = plan_boundaries(&buckets, self.metric, self.num_shards);
+        let new_boundaries: Vec<AccountId> =
+            cuts.iter().map(|&cut| buckets[cut].first_account.clone()).collect();
+        let new_layout = split_shard(&shard_layout, self.shard_id, new_boundaries.clone())?;
+
+        let total = buckets.iter().fold(LoadStats::default(), |acc, bucket| acc.add(&bucket.stats));
+        println!(
+            "Shard {} between heights {start_height} and {end_height}: {} state bytes ({} without an account), {} receipts, {} gas",
+            self.shard_id,
+            total.state_bytes + unattributed.state_bytes,
+            unattributed.state_bytes,
+            total.num_receipts,
+            total.gas_burnt,
+        );
+        let mut ranges = vec![0];
+        ranges.extend(&cuts);
+        ranges.push(buckets.len());
+        for (i, range) in ranges.windows(2).enumerate() {
+            let stats = buckets[range[0]..range[1]]
+                .iter()
+                .fold(LoadStats::default(), |acc, bucket| acc.add(&bucket.stats));
+            let from = if i == 0 { "start".to_string() } else { new_boundaries[i - 1].to_string() };
+            let to = new_boundaries
+                .get(i)
+                .map_or_else(|| "end".to_string(), |account| account.to_string());
+            println!(
+                "New shard {}: [{from}, {to}) state {} bytes ({:.1}%), receipts {} ({:.1}%), gas {} ({:.1}%)",
+                self.shard_id as usize + i,
+                stats.state_bytes,
+                share(stats.state_bytes as u128, total.state_bytes as u128),
+                stats.num_receipts,
+                share(stats.num_receipts as u128, total.num_receipts as u128),
+                stats.gas_burnt,
+                share(stats.gas_burnt as u128, total.gas_burnt as u128),
+            );
+        }
+
+        let json = serde_json::to_string_pretty(&new_layout)?;
+        match &self.output {
+            Some(path) => {
+                std::fs::write(path, json)
+                    .with_context(|| format!("failed to write {}", path.display()))?;
+                println!("Shard layout written to {}", path.display());
+            }
+            None => println!("{json}"),
+        }
+        Ok(())
+    }
+
+    fn bucket<'a>(
+        &self,
+        buckets: &'a mut BTreeMap<String, AccountBucket>,
+        account_id: AccountId,
+    ) -> &'a mut LoadStats {
+        let prefix: String = account_id.as_str().chars().take(self.prefix_len).collect();
+        let bucket = buckets.entry(prefix).or_insert_with(|| AccountBucket {
+            first_account: account_id.clone(),
+            stats: LoadStats::default(),
+        });
+        if account_id < bucket.first_account {
+            bucket.first_account = account_id;
+        }
+        &mut bucket.stats
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct LoadStats {
+    state_bytes: u64,
+    num_receipts: u64,
+    gas_burnt: Gas,
+}
+
+impl LoadStats {
+    fn add(self, other: &LoadStats) -> LoadStats {
+        LoadStats {
+            state_bytes: self.state_bytes + other.state_bytes,
+            num_receipts: self.num_receipts + other.num_receipts,
+            gas_burnt: self.gas_burnt + other.gas_burnt,
+        }
+    }
+}
+
+/// Load of the accounts sharing a prefix.  Accounts are ordered the same way as their
+/// prefixes, so the smallest account of a group is a valid boundary right before it.
+#[derive(Debug)]
+struct AccountBucket {
+    first_account: AccountId,
+    stats: LoadStats,
+}
+
+fn share(part: u128, total: u128) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 * 100.0 / total as f64
+    }
+}
+
+/// Returns the weight of every bucket for the metric, scaled so that the combined metric
+/// gives each of its components the same influence.
+fn bucket_weights(buckets: &[AccountBucket], metric: PlannerMetric) -> Vec<f64> {
+    let total = buckets.iter().fold(LoadStats::default(), |acc, bucket| acc.add(&bucket.stats));
+    buckets
+        .iter()
+        .map(|bucket| {
+            let state = share(bucket.stats.state_bytes as u128, total.state_bytes as u128);
+            let receipts = share(bucket.stats.num_receipts as u128, total.num_receipts as u128);
+            let gas = share(bucket.stats.gas_burnt as u128, total.gas_burnt as u128);
+            match metric {
+                PlannerMetric::State => state,
+                PlannerMetric::Receipts => receipts,
+                PlannerMetric::Gas => gas,
+                PlannerMetric::Combined => (state + receipts + gas) / 3.0,
+            }
+        })
+        .collect()
+}
+
+/// Splits the buckets into `num_shards` contiguous ranges of roughly equal weight and returns
+/// the indices of the first bucket of every range but the first one.  Every range contains at
+/// least one bucket, so there must be at least `num_shards` buckets.
+fn plan_boundaries(
+    buckets: &[AccountBucket],
+    metric: PlannerMetric,
+    num_shards: usize,
+) -> Vec<usize> {
+    assert!(buckets.len() >= num_shards);
+    let weights = bucket_weights(buckets, metric);
+    let total: f64 = weights.iter().sum();
+    let mut cuts = Vec::with_capacity(num_shards - 1);
+    let mut prefix_sum = 0.0;
+    let mut next = 0;
+    for k in 1..num_shards {
+        let target = total * k as f64 / num_shards as f64;
+        // Leave at least one bucket for this shard and for each of the remaining ones.
+        let min_cut = cuts.last().map_or(1, |&cut| cut + 1);
+        let max_cut = buckets.len() - (num_shards - k);
+        let mut cut = min_cut;
+        while next < cut {
+            prefix_sum += weights[next];
+            next += 1;
+        }
+        while cut < max_cut && prefix_sum + weights[cut] / 2.0 < target {
+            prefix_sum += weights[cut];
+            cut += 1;
+            next = cut;
+        }
+        cuts.push(cut);
+    }
+    cuts
+}
+
+/// Returns the layout in which `shard_id` of `layout` is split at `new_boundaries` and the
+/// other shards are kept.
+fn split_shard(
+    layout: &ShardLayout,
+    shard_id: ShardId,
+    new_boundaries: Vec<AccountId>,
+) -> anyhow::Result<ShardLayout> {
+    let shard = shard_id as usize;
+    let num_children = new_boundaries.len() + 1;
+    let (boundaries, num_parent_shards) = match layout.boundary_accounts() {
+        Some(boundaries) => (boundaries.to_vec(), layout.num_shards() as usize),
+        None => {
+            anyhow::ensure!(
+                layout.num_shards() == 1,
+                "only layouts with a single V0 shard can be split by account boundaries"
+            );
+            (vec![], 1)
+        }
+    };
+    if shard > 0 {
+        anyhow::ensure!(
+            new_boundaries[0] > boundaries[shard - 1],
+            "boundary {} is not inside shard {shard_id}",
+            new_boundaries[0]
+        );
+    }
+    if shard < boundaries.len() {
+        anyhow::ensure!(
+            new_boundaries[new_boundaries.len() - 1] < boundaries[shard],
+            "boundary {} is not inside shard {shard_id}",
+            new_boundaries[new_boundaries.len() - 1]
+        );
+    }
+    let mut new_layout_boundaries = boundaries[..shard].to_vec();
+    new_layout_boundaries.extend(new_boundaries);
+    new_layout_boundaries.extend_from_slice(&boundaries[shard..]);
+
+    let shards_split_map = (0..num_parent_shards)
+        .map(|parent| match parent.cmp(&shard) {
+            std::cmp::Ordering::Less => vec![parent as ShardId],
+            std::cmp::Ordering::Equal => {
+                (shard..shard + num_children).map(|child| child as ShardId).collect()
+            }
+            std::cmp::Ordering::Greater => vec![(parent + num_children - 1) as ShardId],
+        })
+        .collect();
+    Ok(ShardLayout::v1(new_layout_boundaries, Some(shards_split_map), layout.version() + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_primitives::shard_layout::account_id_to_shard_id;
+
+    fn buckets(stats: &[(&str, u64, u64)]) -> Vec<AccountBucket> {
+        stats
+            .iter()
+            .map(|&(account, state_bytes, gas_burnt)| AccountBucket {
+                first_account: account.parse().unwrap(),
+                stats: LoadStats { state_bytes, num_receipts: gas_burnt, gas_burnt },
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_plan_boundaries() {
+        let buckets = buckets(&[("a", 10, 0), ("b", 10, 0), ("c", 10, 100), ("d", 10, 0)]);
+        assert_eq!(plan_boundaries(&buckets, PlannerMetric::State, 2), vec![2]);
+        assert_eq!(plan_boundaries(&buckets, PlannerMetric::State, 4), vec![1, 2, 3]);
+        // All the gas is in a single bucket, which can't be split further.
+        assert_eq!(plan_boundaries(&buckets, PlannerMetric::Gas, 2), vec![2]);
+        assert_eq!(plan_boundaries(&buckets, PlannerMetric::Gas, 3), vec![2, 3]);
+
+        let buckets = buckets(&[("a", 100, 0), ("b", 1, 0), ("c", 1, 0), ("d", 1, 0)]);
+        assert_eq!(plan_boundaries(&buckets, PlannerMetric::State, 2), vec![1]);
+        assert_eq!(plan_boundaries(&buckets, PlannerMetric::State, 3), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_split_shard() {
+        let layout = ShardLayout::v1(vec!["b".parse().unwrap(), "m".parse().unwrap()], None, 1);
+        let new_layout =
+            split_shard(&layout, 1, vec!["d".parse().unwrap(), "f".parse().unwrap()]).unwrap();
+        assert_eq!(new_layout.num_shards(), 5);
+        assert_eq!(new_layout.version(), 2);
+        assert_eq!(new_layout.get_split_shard_ids(0), Some(vec![0]));
+        assert_eq!(new_layout.get_split_shard_ids(1), Some(vec![1, 2, 3]));
+        assert_eq!(new_layout.get_split_shard_ids(2), Some(vec![4]));
+        assert_eq!(account_id_to_shard_id(&"e".parse().unwrap(), &new_layout), 2);
+        assert_eq!(account_id_to_shard_id(&"z".parse().unwrap(), &new_layout), 4);
+
+        assert!(split_shard(&layout, 1, vec!["a".parse().unwrap()]).is_err());
+        assert!(split_shard(&layout, 1, vec!["n".parse().unwrap()]).is_err());
+
+        let new_layout =
+            split_shard(&ShardLayout::v0_single_shard(), 0, vec!["m".parse().unwrap()]).unwrap();
+        assert_eq!(new_layout.get_split_shard_ids(0), Some(vec![0, 1]));
+        let boundaries: &[AccountId] = &["m".parse().unwrap()];
+        assert_eq!(new_layout.boundary_accounts(), Some(boundaries));
+        // V0 layouts with more shards map accounts by hash and have no boundaries to split at.
+        assert!(split_shard(&ShardLayout::v0(4, 0), 1, vec!["m".parse().unwrap()]).is_err());
+    }
+}
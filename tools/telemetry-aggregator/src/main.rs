@@ -0,0 +1,174 @@
+//! Receives the telemetry reports of the nodes, checks their signatures and serves an overview of
+//! the latest report of every node.
+
+use actix_web::{web, App, HttpResponse, HttpServer};
+use chrono::{DateTime, Utc};
+use clap::Parser;
+use near_primitives::telemetry::{verify_telemetry, TelemetryInfo};
+use near_primitives::types::{AccountId, BlockHeight};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+
+#[derive(Parser)]
+struct Cli {
+    /// Address to listen on. Point the `telemetry.endpoints` of the nodes to
+    /// `http://<addr>/nodes`.
+    #[clap(long, default_value = "0.0.0.0:8080")]
+    addr: SocketAddr,
+    /// Reports older than this are dropped from the overview.
+    #[clap(long, default_value = "3600")]
+    forget_after_secs: i64,
+}
+
+struct NodeReport {
+    info: TelemetryInfo,
+    received_at: DateTime<Utc>,
+}
+
+/// Latest verified report of every node, by `node_id`.
+struct Reports {
+    nodes: Mutex<HashMap<String, NodeReport>>,
+    forget_after: chrono::Duration,
+}
+
+#[derive(serde::Serialize)]
+struct NodeOverview {
+    node_id: String,
+    /// Set only if the report is signed by the validator key in `validator_public_key`.
+    account_id: Option<AccountId>,
+    validator_public_key: Option<String>,
+    is_validator: bool,
+    version: String,
+    build: String,
+    status: String,
+    latest_block_height: BlockHeight,
+    num_peers: usize,
+    received_at: DateTime<Utc>,
+}
+
+impl Reports {
+    /// Keeps the report, unless a report for a higher block was already received from the node,
+    /// so that old reports can't be replayed over newer ones.
+    fn insert(&self, info: TelemetryInfo) -> bool {
+        let mut nodes = self.nodes.lock().unwrap();
+        let received_at = Utc::now();
+        nodes.retain(|_, report| received_at - report.received_at < self.forget_after);
+        if let Some(report) = nodes.get(&info.chain.node_id) {
+            if report.info.chain.latest_block_height > info.chain.latest_block_height {
+                return false;
+            }
+        }
+        nodes.insert(info.chain.node_id.clone(), NodeReport { info, received_at });
+        true
+    }
+
+    fn overview(&self) -> Vec<NodeOverview> {
+        let nodes = self.nodes.lock().unwrap();
+        let mut overview: Vec<_> = nodes
+            .values()
+            .map(|report| {
+                let chain = &report.info.chain;
+                NodeOverview {
+                    node_id: chain.node_id.clone(),
+                    account_id: chain.account_id.clone(),
+                    validator_public_key: chain
+                        .validator_public_key
+                        .as_ref()
+                        .map(|key| key.to_string()),
+                    is_validator: chain.is_validator,
+                    version: report.info.agent.version.clone(),
+                    build: report.info.agent.build.clone(),
+                    status: chain.status.clone(),
+                    latest_block_height: chain.latest_block_height,
+                    num_peers: chain.num_peers,
+                    received_at: report.received_at,
+                }
+            })
+            .collect();
+        overview.sort_by(|a, b| (&a.account_id, &a.node_id).cmp(&(&b.account_id, &b.node_id)));
+        overview
+    }
+}
+
+async fn receive_report(
+    reports: web::Data<Reports>,
+    report: web::Json<serde_json::Value>,
+) -> HttpResponse {
+    let info = match verify_telemetry(&report) {
+        Ok(info) => info,
+        Err(err) => {
+            tracing::debug!(target: "telemetry-aggregator", ?err, "rejected a telemetry report");
+            return HttpResponse::BadRequest().body(err.to_string());
+        }
+    };
+    if reports.insert(info) {
+        HttpResponse::Ok().finish()
+    } else {
+        HttpResponse::Conflict().body("a report for a higher block was already received")
+    }
+}
+
+async fn nodes(reports: web::Data<Reports>) -> HttpResponse {
+    HttpResponse::Ok().json(reports.overview())
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+async fn fleet_overview(reports: web::Data<Reports>) -> HttpResponse {
+    let overview = reports.overview();
+    let max_height = overview.iter().map(|node| node.latest_block_height).max().unwrap_or(0);
+    let mut rows = String::new();
+    for node in &overview {
+        rows += &format!(
+            "<tr><td>{}</td><td>{}</td><td>{} ({})</td><td>{}</td><td>{} (-{})</td><td>{}</td><td>{}</td></tr>\n",
+            escape_html(&node.node_id),
+            escape_html(node.account_id.as_ref().map_or("", |account_id| account_id.as_str())),
+            escape_html(&node.version),
+            escape_html(&node.build),
+            escape_html(&node.status),
+            node.latest_block_height,
+            max_height.saturating_sub(node.latest_block_height),
+            node.num_peers,
+            node.received_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        );
+    }
+    let body = format!(
+        "<!DOCTYPE html>\n<html><head><title>Fleet overview</title></head><body>\n\
+         <h1>{} nodes</h1>\n<table border=\"1\">\n\
+         <tr><th>Node</th><th>Validator</th><th>Version</th><th>Sync status</th><th>Height</th>\
+         <th>Peers</th><th>Last report</th></tr>\n{}</table>\n</body></html>\n",
+        overview.len(),
+        rows
+    );
+    HttpResponse::Ok().content_type("text/html; charset=utf-8").body(body)
+}
+
+#[actix_web::main]
+async fn main() -> anyhow::Result<()> {
+    let env_filter = near_o11y::EnvFilterBuilder::from_env().finish()?;
+    let _subscriber = near_o11y::default_subscriber(env_filter, &Default::default()).global();
+    let cli = Cli::parse();
+
+    let reports = web::Data::new(Reports {
+        nodes: Mutex::new(HashMap::new()),
+        forget_after: chrono::Duration::seconds(cli.forget_after_secs),
+    });
+    tracing::info!(target: "telemetry-aggregator", addr = %cli.addr, "listening");
+    HttpServer::new(move || {
+        App::new()
+            .app_data(reports.clone())
+            .service(
+                web::resource("/nodes")
+                    .route(web::post().to(receive_report))
+                    .route(web::get().to(nodes)),
+            )
+            .service(web::resource("/").route(web::get().to(fleet_overview)))
+    })
+    .bind(cli.addr)?
+    .run()
+    .await?;
+    Ok(())
+}
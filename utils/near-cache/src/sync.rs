@@ -68,6 +68,11 @@ where
         self.inner.lock().unwrap().put(key, value);
     }
 
+    /// Removes all key-value pairs from the cache.
+    pub fn clear(&self) {
+        self.inner.lock().unwrap().clear();
+    }
+
     /// Returns the value of the key in the cache or None if it is not present in the cache.
     /// Moves the key to the head of the LRU list if it exists.
     pub fn get(&self, key: &K) -> Option<V> {